@@ -0,0 +1,125 @@
+/* generated by `cbindgen --config cbindgen.toml --output include/dbless.h`, don't edit it by hand */
+
+#ifndef DBLESS_H
+#define DBLESS_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * The call succeeded
+ */
+#define DBLESS_OK 0
+
+/**
+ * The key doesn't exist
+ */
+#define DBLESS_NOT_FOUND 1
+
+/**
+ * The call failed, see `dbless_last_error()`
+ */
+#define DBLESS_ERROR -1
+
+/**
+ * An open database, only ever handled through a pointer
+ */
+typedef struct DblessDb DblessDb;
+
+/**
+ * Bytes allocated by dbless, free them with `dbless_buf_free()`
+ */
+typedef struct DblessBuf {
+  /**
+   * The bytes, null if nothing was returned
+   */
+  uint8_t *data;
+  /**
+   * The number of bytes
+   */
+  size_t len;
+} DblessBuf;
+
+/**
+ * Opens (or creates) the database file at `path`, \
+ * returns null on error.
+ *
+ * # Safety
+ * `path` must be a valid NUL-terminated string.
+ */
+struct DblessDb *dbless_open(const char *path);
+
+/**
+ * Closes a database opened with `dbless_open()`, \
+ * does nothing if `db` is null.
+ *
+ * # Safety
+ * `db` must come from `dbless_open()`, and must not be used after this call.
+ */
+void dbless_close(struct DblessDb *db);
+
+/**
+ * Gets the value of `key` in `table` as plain MessagePack into `out`, \
+ * or as it was given for bytes stored with `set_raw()` from rust, \
+ * `out` is left empty if the key doesn't exist.
+ *
+ * # Safety
+ * `db` must come from `dbless_open()`, `table` must be null or a valid NUL-terminated string,
+ * `key` must be a valid NUL-terminated string, and `out` must be valid for writes.
+ */
+int32_t dbless_get_raw(struct DblessDb *db,
+                       const char *table,
+                       const char *key,
+                       struct DblessBuf *out);
+
+/**
+ * Sets the value of `key` in `table` to the `len` bytes at `value`, \
+ * which must be exactly one MessagePack value.
+ *
+ * # Safety
+ * `db` must come from `dbless_open()`, `table` must be null or a valid NUL-terminated string,
+ * `key` must be a valid NUL-terminated string, and `value` must be valid for reads of `len` bytes.
+ */
+int32_t dbless_set_raw(struct DblessDb *db,
+                       const char *table,
+                       const char *key,
+                       const uint8_t *value,
+                       size_t len);
+
+/**
+ * Removes `key` from `table`, removing a key that doesn't exist succeeds.
+ *
+ * # Safety
+ * `db` must come from `dbless_open()`, `table` must be null or a valid NUL-terminated string,
+ * and `key` must be a valid NUL-terminated string.
+ */
+int32_t dbless_remove(struct DblessDb *db, const char *table, const char *key);
+
+/**
+ * Lists the tables of the database into `out`, \
+ * as the names one after the other, each followed by a NUL byte.
+ *
+ * # Safety
+ * `db` must come from `dbless_open()`, and `out` must be valid for writes.
+ */
+int32_t dbless_list_tables(struct DblessDb *db, struct DblessBuf *out);
+
+/**
+ * Frees bytes returned by dbless, \
+ * does nothing if `buf.data` is null.
+ *
+ * # Safety
+ * `buf` must have been filled by a dbless function, and must not be used after this call.
+ */
+void dbless_buf_free(struct DblessBuf buf);
+
+/**
+ * Returns the message of the last error on the calling thread, or null if there was none. \
+ * the message stays valid until the next failing call on the same thread.
+ */
+const char *dbless_last_error(void);
+
+#endif  /* DBLESS_H */
@@ -0,0 +1,238 @@
+//! a C ABI for [dbless](https://docs.rs/dbless) over raw MessagePack values, so programs in other languages can share a database file. \
+//! `cargo build --release -p dbless-ffi` builds it as a shared library (`libdbless_ffi.so`, `dbless_ffi.dll`, ...). \
+//! the matching header is `include/dbless.h`, generated from this file with the cbindgen CLI, so it's never edited by hand:
+//! ```sh
+//! cbindgen --config cbindgen.toml --output include/dbless.h
+//! ```
+//!
+//! every function returning an `int32_t` returns [`DBLESS_OK`], [`DBLESS_NOT_FOUND`], or [`DBLESS_ERROR`], \
+//! in which case [`dbless_last_error()`] describes what went wrong. \
+//! a null table name means the default table. \
+//! rust panics are caught and reported as errors, they never unwind into the caller.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+use anyhow::{ensure, Context, Result};
+
+use dbless::{Database, TableReadInterface, TableWriteInterface};
+
+/// The call succeeded
+pub const DBLESS_OK: i32 = 0;
+/// The key doesn't exist
+pub const DBLESS_NOT_FOUND: i32 = 1;
+/// The call failed, see `dbless_last_error()`
+pub const DBLESS_ERROR: i32 = -1;
+
+/// An open database, only ever handled through a pointer
+pub struct DblessDb(Database);
+
+/// Bytes allocated by dbless, free them with `dbless_buf_free()`
+#[repr(C)]
+pub struct DblessBuf {
+    /// The bytes, null if nothing was returned
+    pub data: *mut u8,
+    /// The number of bytes
+    pub len: usize,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// runs `f`, turning errors and panics into `DBLESS_ERROR` with their message saved for `dbless_last_error()`
+fn call(f: impl FnOnce() -> Result<i32>) -> i32 {
+    let message = match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(code)) => return code,
+        Ok(Err(e)) => format!("{:#}", e),
+        Err(_) => "dbless panicked".to_string(),
+    };
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+    DBLESS_ERROR
+}
+
+unsafe fn db_arg<'a>(db: *mut DblessDb) -> Result<&'a mut Database> {
+    ensure!(!db.is_null(), "`db` is null");
+    Ok(&mut (*db).0)
+}
+
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str> {
+    ensure!(!s.is_null(), "`{}` is null", name);
+    CStr::from_ptr(s)
+        .to_str()
+        .with_context(|| format!("`{}` isn't valid UTF-8", name))
+}
+
+unsafe fn table_arg<'a>(table: *const c_char) -> Result<Option<&'a str>> {
+    match table.is_null() {
+        true => Ok(None),
+        false => str_arg(table, "table").map(Some),
+    }
+}
+
+fn into_buf(bytes: Vec<u8>) -> DblessBuf {
+    let len = bytes.len();
+    let data = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+    DblessBuf { data, len }
+}
+
+/// Opens (or creates) the database file at `path`, \
+/// returns null on error.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_open(path: *const c_char) -> *mut DblessDb {
+    let mut db = ptr::null_mut();
+    call(|| {
+        let path = str_arg(path, "path")?;
+        db = Box::into_raw(Box::new(DblessDb(Database::open(path)?)));
+        Ok(DBLESS_OK)
+    });
+    db
+}
+
+/// Closes a database opened with `dbless_open()`, \
+/// does nothing if `db` is null.
+///
+/// # Safety
+/// `db` must come from `dbless_open()`, and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_close(db: *mut DblessDb) {
+    if !db.is_null() {
+        drop(Box::from_raw(db));
+    }
+}
+
+/// Gets the value of `key` in `table` as plain MessagePack into `out`, \
+/// or as it was given for bytes stored with `set_raw()` from rust, \
+/// `out` is left empty if the key doesn't exist.
+///
+/// # Safety
+/// `db` must come from `dbless_open()`, `table` must be null or a valid NUL-terminated string,
+/// `key` must be a valid NUL-terminated string, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_get_raw(
+    db: *mut DblessDb,
+    table: *const c_char,
+    key: *const c_char,
+    out: *mut DblessBuf,
+) -> i32 {
+    call(|| {
+        let db = db_arg(db)?;
+        let key = str_arg(key, "key")?;
+        ensure!(!out.is_null(), "`out` is null");
+        out.write(DblessBuf {
+            data: ptr::null_mut(),
+            len: 0,
+        });
+        let value = match table_arg(table)? {
+            Some(table) => db.table(table).get_raw(key)?,
+            None => db.default_table().get_raw(key)?,
+        };
+        let Some(value) = value else {
+            return Ok(DBLESS_NOT_FOUND);
+        };
+        out.write(into_buf(value));
+        Ok(DBLESS_OK)
+    })
+}
+
+/// Sets the value of `key` in `table` to the `len` bytes at `value`, \
+/// which must be exactly one MessagePack value.
+///
+/// # Safety
+/// `db` must come from `dbless_open()`, `table` must be null or a valid NUL-terminated string,
+/// `key` must be a valid NUL-terminated string, and `value` must be valid for reads of `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_set_raw(
+    db: *mut DblessDb,
+    table: *const c_char,
+    key: *const c_char,
+    value: *const u8,
+    len: usize,
+) -> i32 {
+    call(|| {
+        let db = db_arg(db)?;
+        let key = str_arg(key, "key")?;
+        ensure!(!value.is_null(), "`value` is null");
+        let value = std::slice::from_raw_parts(value, len);
+        match table_arg(table)? {
+            Some(table) => db.table_mut(table).set_msgpack(key, value)?,
+            None => db.default_table_mut().set_msgpack(key, value)?,
+        }
+        Ok(DBLESS_OK)
+    })
+}
+
+/// Removes `key` from `table`, removing a key that doesn't exist succeeds.
+///
+/// # Safety
+/// `db` must come from `dbless_open()`, `table` must be null or a valid NUL-terminated string,
+/// and `key` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_remove(
+    db: *mut DblessDb,
+    table: *const c_char,
+    key: *const c_char,
+) -> i32 {
+    call(|| {
+        let db = db_arg(db)?;
+        let key = str_arg(key, "key")?;
+        match table_arg(table)? {
+            Some(table) => db.table_mut(table).remove(key)?,
+            None => db.default_table_mut().remove(key)?,
+        }
+        Ok(DBLESS_OK)
+    })
+}
+
+/// Lists the tables of the database into `out`, \
+/// as the names one after the other, each followed by a NUL byte.
+///
+/// # Safety
+/// `db` must come from `dbless_open()`, and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_list_tables(db: *mut DblessDb, out: *mut DblessBuf) -> i32 {
+    call(|| {
+        let db = db_arg(db)?;
+        ensure!(!out.is_null(), "`out` is null");
+        let mut names = vec![];
+        for table in db.list_tables()? {
+            names.extend_from_slice(table.as_bytes());
+            names.push(0);
+        }
+        out.write(into_buf(names));
+        Ok(DBLESS_OK)
+    })
+}
+
+/// Frees bytes returned by dbless, \
+/// does nothing if `buf.data` is null.
+///
+/// # Safety
+/// `buf` must have been filled by a dbless function, and must not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn dbless_buf_free(buf: DblessBuf) {
+    if !buf.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buf.data, buf.len,
+        )));
+    }
+}
+
+/// Returns the message of the last error on the calling thread, or null if there was none. \
+/// the message stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn dbless_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| match &*last.borrow() {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,75 @@
+use std::ffi::{CStr, CString};
+use std::ptr;
+
+use anyhow::Result;
+use dbless::{Database, TableReadInterface};
+
+use crate::*;
+
+type TestResult = Result<()>;
+
+#[test]
+fn ffi_round_trip() -> TestResult {
+    let path = std::env::temp_dir().join(format!("dbless_ffi_{}", std::process::id()));
+    let c_path = CString::new(path.to_str().unwrap())?;
+    let table = CString::new("users")?;
+    let key = CString::new("alice")?;
+    let value = rmp_serde::to_vec("Alice")?;
+    let empty = || DblessBuf {
+        data: ptr::null_mut(),
+        len: 0,
+    };
+
+    unsafe {
+        let db = dbless_open(c_path.as_ptr());
+        assert!(!db.is_null());
+
+        let mut out = empty();
+        let code = dbless_get_raw(db, table.as_ptr(), key.as_ptr(), &mut out);
+        assert_eq!(code, DBLESS_NOT_FOUND);
+        assert!(out.data.is_null());
+
+        let code = dbless_set_raw(
+            db,
+            table.as_ptr(),
+            key.as_ptr(),
+            value.as_ptr(),
+            value.len(),
+        );
+        assert_eq!(code, DBLESS_OK);
+        let code = dbless_set_raw(db, ptr::null(), key.as_ptr(), value.as_ptr(), value.len());
+        assert_eq!(code, DBLESS_OK);
+
+        let code = dbless_get_raw(db, table.as_ptr(), key.as_ptr(), &mut out);
+        assert_eq!(code, DBLESS_OK);
+        assert_eq!(std::slice::from_raw_parts(out.data, out.len), value);
+        dbless_buf_free(out);
+
+        let invalid = [0xc1];
+        let code = dbless_set_raw(db, table.as_ptr(), key.as_ptr(), invalid.as_ptr(), 1);
+        assert_eq!(code, DBLESS_ERROR);
+        assert!(!dbless_last_error().is_null());
+        assert!(!CStr::from_ptr(dbless_last_error()).to_bytes().is_empty());
+
+        let mut out = empty();
+        assert_eq!(dbless_list_tables(db, &mut out), DBLESS_OK);
+        assert_eq!(std::slice::from_raw_parts(out.data, out.len), b"users\0");
+        dbless_buf_free(out);
+
+        assert_eq!(dbless_remove(db, ptr::null(), key.as_ptr()), DBLESS_OK);
+        dbless_close(db);
+    }
+
+    let db = Database::open(&path)?;
+    assert_eq!(
+        db.table("users").get::<String>("alice")?.as_deref(),
+        Some("Alice")
+    );
+    assert_eq!(db.get::<String>("alice")?, None);
+    drop(db);
+    std::fs::remove_file(&path)?;
+
+    assert!(unsafe { dbless_open(ptr::null()) }.is_null());
+
+    Ok(())
+}
@@ -0,0 +1,38 @@
+#![no_main]
+
+use std::collections::{BTreeMap, HashMap};
+
+use dbless::fuzzing::deserialize;
+use libfuzzer_sys::fuzz_target;
+use serde::Deserialize;
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+enum Shape {
+    Point,
+    Circle(f32),
+    Rect { width: u32, height: u32 },
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize)]
+struct Record {
+    name: String,
+    age: u8,
+    score: Option<f64>,
+    tags: Vec<String>,
+    shapes: Vec<Shape>,
+    extra: BTreeMap<String, i64>,
+    nested: Option<Box<Record>>,
+}
+
+fuzz_target!(|data: &[u8]| {
+    for strict in [false, true] {
+        let _ = deserialize::<Record>(data, strict);
+        let _ = deserialize::<HashMap<String, Vec<u64>>>(data, strict);
+        let _ = deserialize::<(i32, String, Vec<u8>)>(data, strict);
+        let _ = deserialize::<Vec<Shape>>(data, strict);
+        let _ = deserialize::<String>(data, strict);
+        let _ = deserialize::<f32>(data, strict);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+
+use dbless::fuzzing::inspect;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    // the first byte picks the field path, the rest is the stored value
+    let Some((path, bytes)) = data.split_first() else {
+        return;
+    };
+    let path = ["a", "0", "a.b", "0.a.1", "name.0.x"][*path as usize % 5];
+    inspect(bytes, path);
+});
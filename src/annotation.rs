@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// the description and annotations of a table, saved in the annotations table under the table's name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableNotes {
+    pub description: Option<String>,
+    pub annotations: BTreeMap<String, String>,
+}
+
+impl TableNotes {
+    pub fn is_empty(&self) -> bool {
+        self.description.is_none() && self.annotations.is_empty()
+    }
+}
+
+/// What a table found in the database file is, \
+/// see [`Database::list_tables_detailed()`](struct.Database.html#method.list_tables_detailed)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TableKind {
+    /// A table created through dbless, listed by [`Database::list_tables()`](struct.Database.html#method.list_tables)
+    User,
+    /// A table dbless uses for itself
+    Reserved(ReservedKind),
+    /// A table dbless can't read, created in the same file by another program using redb directly
+    Foreign,
+}
+
+/// What dbless uses a reserved table for, \
+/// see [`TableKind::Reserved`](enum.TableKind.html#variant.Reserved)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReservedKind {
+    /// The default table, either the one set with [`Database::set_default_table()`](struct.Database.html#method.set_default_table) \
+    /// or the original one under a custom [`Options::reserved_prefix()`](struct.Options.html#method.reserved_prefix)
+    Default,
+    /// The marker written by [`Database::health_check()`](struct.Database.html#method.health_check)
+    Health,
+    /// The table aliases
+    Aliases,
+    /// The size limits of the tables
+    Limits,
+    /// The tables whose keys are escaped
+    Escaped,
+    /// The tables with a hash chain
+    Chained,
+    /// The tables whose values are deduplicated
+    Deduplicated,
+    /// The descriptions and annotations of the tables
+    Annotations,
+    /// The tables whose keys can't be overwritten
+    WriteOnce,
+    /// The settings of the database itself, like the format of its values
+    Metadata,
+    /// The insertion order of a table with a size limit
+    EvictionOrder,
+    /// The last access of a table with a size limit
+    EvictionStamps,
+    /// The hash chain of a table
+    Chain,
+    /// The deduplicated values of a table
+    Blobs,
+    /// Any other table starting with a custom [`Options::reserved_prefix()`](struct.Options.html#method.reserved_prefix)
+    Other,
+}
+
+/// A table listed by [`Database::list_tables_detailed()`](struct.Database.html#method.list_tables_detailed)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableInfo {
+    /// The name of the table
+    pub name: String,
+    /// Whether the table is a user, reserved or foreign table
+    pub kind: TableKind,
+    /// The number of entries in the table, `None` if it's a multimap table
+    pub entries: Option<usize>,
+    /// The description set with [`Database::set_table_description()`](struct.Database.html#method.set_table_description)
+    pub description: Option<String>,
+    /// The annotations set with [`Database::set_table_annotation()`](struct.Database.html#method.set_table_annotation)
+    pub annotations: BTreeMap<String, String>,
+}
@@ -0,0 +1,434 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+
+use anyhow::{bail, ensure, Context, Result};
+use sha2::{Digest, Sha256};
+
+use crate::manifest::FORMAT_VERSION;
+use crate::store::Store;
+use crate::{temp_path, Manifest, TableManifest, TempFile};
+
+// the first bytes of every backup stream
+const MAGIC: &[u8; 8] = b"DBLESSBK";
+// bumped whenever the framing changes
+const BACKUP_VERSION: u32 = 1;
+// a table is split into sections of about this many bytes of records, before compression
+const SECTION_BYTES: usize = 1 << 20;
+
+const TAG_FOOTER: u8 = 0;
+const TAG_SECTION: u8 = 1;
+
+const COMPRESSION_NONE: u8 = 0;
+const COMPRESSION_ZSTD: u8 = 1;
+
+/// How the sections of a backup stream are compressed, see [`BackupOpts::compression()`](struct.BackupOpts.html#method.compression)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackupCompression {
+    /// Sections are written as they are
+    #[default]
+    None,
+    /// Every section is compressed with zstd at the given level (1 to 22, or 0 for zstd's default), \
+    /// needs the `zstd` feature, to write the backup and to restore it
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+/// The options of [`Database::backup_stream()`](struct.Database.html#method.backup_stream)
+/// ```no_run
+/// # use dbless::{BackupOpts, Database};
+/// let db = Database::open("my_database.db")?;
+/// let file = std::fs::File::create("my_database.backup")?;
+/// db.backup_stream(std::io::BufWriter::new(file), BackupOpts::new())?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct BackupOpts {
+    pub(crate) compression: BackupCompression,
+}
+
+impl BackupOpts {
+    /// Creates a new set of backup options with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets how the sections of the stream are compressed. \
+    /// default: [`BackupCompression::None`](enum.BackupCompression.html#variant.None)
+    pub fn compression(mut self, compression: BackupCompression) -> Self {
+        self.compression = compression;
+        self
+    }
+}
+
+/// What happens to the tables already in the database, see [`Database::restore_stream()`](struct.Database.html#method.restore_stream)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// The entries of the backup are written over the tables, \
+    /// keys that aren't in the backup are kept
+    Merge,
+    /// Every table in the backup is cleared before its entries are written, \
+    /// tables that aren't in the backup are kept
+    Replace,
+}
+
+/// What a backup stream holds, \
+/// returned by [`Database::backup_stream()`](struct.Database.html#method.backup_stream) and [`Database::restore_stream()`](struct.Database.html#method.restore_stream)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackupSummary {
+    /// The number of tables
+    pub tables: usize,
+    /// The number of entries in all tables
+    pub entries: u64,
+    /// The size of the stream, in bytes
+    pub bytes: u64,
+    /// The manifest of the database the backup was taken from, when it was taken
+    pub manifest: Manifest,
+}
+
+/// hashes everything written through it
+struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+    count: u64,
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// hashes everything read through it
+struct HashingReader<R> {
+    inner: R,
+    hasher: Sha256,
+    count: u64,
+}
+
+impl<R: Read> Read for HashingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..read]);
+        self.count += read as u64;
+        Ok(read)
+    }
+}
+
+fn write_field(writer: &mut impl Write, bytes: &[u8]) -> Result<()> {
+    let len = u32::try_from(bytes.len()).context("a field of the backup is larger than 4 GiB")?;
+    writer.write_all(&len.to_be_bytes())?;
+    writer.write_all(bytes)?;
+    Ok(())
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N]> {
+    let mut bytes = [0; N];
+    match reader.read_exact(&mut bytes) {
+        Ok(()) => Ok(bytes),
+        Err(e) if e.kind() == ErrorKind::UnexpectedEof => bail!("the backup stream is truncated"),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    Ok(u32::from_be_bytes(read_array(reader)?))
+}
+
+fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    Ok(u64::from_be_bytes(read_array(reader)?))
+}
+
+/// reads `len` bytes, growing the buffer as they come in, \
+/// so a corrupted length can't allocate more than the stream holds
+fn read_exactly(reader: &mut impl Read, len: u64) -> Result<Vec<u8>> {
+    let mut bytes = vec![];
+    reader.take(len).read_to_end(&mut bytes)?;
+    ensure!(bytes.len() as u64 == len, "the backup stream is truncated");
+    Ok(bytes)
+}
+
+fn read_field(reader: &mut impl Read) -> Result<Vec<u8>> {
+    let len = read_u32(reader)?;
+    read_exactly(reader, len as u64)
+}
+
+/// takes a length-prefixed field from the front of `records`
+fn take_field<'r>(records: &mut &'r [u8]) -> Result<&'r [u8]> {
+    let corrupted = || anyhow::anyhow!("a section of the backup stream is corrupted");
+    let (len, rest) = records.split_first_chunk::<4>().ok_or_else(corrupted)?;
+    let len = u32::from_be_bytes(*len) as usize;
+    ensure!(rest.len() >= len, corrupted());
+    let (field, rest) = rest.split_at(len);
+    *records = rest;
+    Ok(field)
+}
+
+fn write_section(
+    writer: &mut impl Write,
+    name: &str,
+    entries: u32,
+    records: &[u8],
+    compression: BackupCompression,
+) -> Result<()> {
+    let (method, payload) = match compression {
+        BackupCompression::None => (COMPRESSION_NONE, Cow::Borrowed(records)),
+        #[cfg(feature = "zstd")]
+        BackupCompression::Zstd(level) => (
+            COMPRESSION_ZSTD,
+            Cow::Owned(zstd::stream::encode_all(records, level)?),
+        ),
+    };
+    writer.write_all(&[TAG_SECTION])?;
+    write_field(writer, name.as_bytes())?;
+    writer.write_all(&[method])?;
+    writer.write_all(&entries.to_be_bytes())?;
+    writer.write_all(&(records.len() as u64).to_be_bytes())?;
+    writer.write_all(&(payload.len() as u64).to_be_bytes())?;
+    writer.write_all(&payload)?;
+    Ok(())
+}
+
+fn decompress(method: u8, payload: Vec<u8>, len: u64) -> Result<Vec<u8>> {
+    let records = match method {
+        COMPRESSION_NONE => payload,
+        #[cfg(feature = "zstd")]
+        COMPRESSION_ZSTD => {
+            let mut records = vec![];
+            zstd::stream::read::Decoder::new(payload.as_slice())?
+                .take(len.saturating_add(1))
+                .read_to_end(&mut records)
+                .context("a section of the backup stream can't be decompressed")?;
+            records
+        }
+        #[cfg(not(feature = "zstd"))]
+        COMPRESSION_ZSTD => {
+            bail!("the backup stream is compressed with zstd, which needs the `zstd` feature")
+        }
+        method => bail!(
+            "the backup stream uses an unknown compression method ({})",
+            method
+        ),
+    };
+    ensure!(
+        records.len() as u64 == len,
+        "a section of the backup stream is corrupted"
+    );
+    Ok(records)
+}
+
+/// writes every table of the database, read under a single snapshot, \
+/// `manifest` building the manifest of the database from the summaries of its tables
+pub fn write_backup(
+    store: &Store,
+    default_table: &str,
+    writer: impl Write,
+    opts: &BackupOpts,
+    manifest: impl FnOnce(BTreeMap<String, TableManifest>) -> Manifest,
+) -> Result<BackupSummary> {
+    let tnx = store.begin_read()?;
+    let manifest = manifest(store.summarize_at(&tnx, default_table)?);
+    let mut writer = HashingWriter {
+        inner: writer,
+        hasher: Sha256::new(),
+        count: 0,
+    };
+    writer.write_all(MAGIC)?;
+    writer.write_all(&BACKUP_VERSION.to_be_bytes())?;
+    write_field(&mut writer, &rmp_serde::to_vec_named(&manifest)?)?;
+
+    let (mut sections, mut total) = (0u64, 0u64);
+    let mut records = Vec::with_capacity(SECTION_BYTES);
+    for name in manifest.tables.keys() {
+        let (mut entries, mut written) = (0u32, false);
+        store.for_each_resolved_at(&tnx, name, |key, value| {
+            write_field(&mut records, key.as_bytes())?;
+            write_field(&mut records, value)?;
+            entries += 1;
+            if records.len() >= SECTION_BYTES || entries == u32::MAX {
+                write_section(&mut writer, name, entries, &records, opts.compression)?;
+                (sections, total, written) = (sections + 1, total + entries as u64, true);
+                records.clear();
+                entries = 0;
+            }
+            Ok(())
+        })?;
+        // an empty table still gets a section, so it's restored too
+        if entries > 0 || !written {
+            write_section(&mut writer, name, entries, &records, opts.compression)?;
+            (sections, total) = (sections + 1, total + entries as u64);
+            records.clear();
+        }
+    }
+
+    writer.write_all(&[TAG_FOOTER])?;
+    writer.write_all(&sections.to_be_bytes())?;
+    writer.write_all(&total.to_be_bytes())?;
+    let digest = writer.hasher.clone().finalize();
+    writer.write_all(&digest)?;
+    writer.flush()?;
+    Ok(BackupSummary {
+        tables: manifest.tables.len(),
+        entries: total,
+        bytes: writer.count,
+        manifest,
+    })
+}
+
+/// a backup stream read to the end and validated, \
+/// with its entries copied to a temporary file to be applied from
+pub struct StagedBackup {
+    pub summary: BackupSummary,
+    /// every table and its number of entries, in the order of the staged entries
+    pub tables: Vec<(String, u64)>,
+    file: TempFile,
+}
+
+impl StagedBackup {
+    /// reads the whole stream, checking its framing, its manifest and its checksum, \
+    /// `codec` being the codec of the database it will be restored into
+    pub fn read(reader: impl Read, codec: &str) -> Result<Self> {
+        let mut reader = HashingReader {
+            inner: BufReader::new(reader),
+            hasher: Sha256::new(),
+            count: 0,
+        };
+        ensure!(
+            read_array(&mut reader).ok() == Some(*MAGIC),
+            "not a dbless backup stream"
+        );
+        let version = read_u32(&mut reader)?;
+        ensure!(
+            version == BACKUP_VERSION,
+            "the backup stream has version {}, only version {} is supported",
+            version,
+            BACKUP_VERSION
+        );
+        let manifest: Manifest = rmp_serde::from_slice(&read_field(&mut reader)?)
+            .context("the manifest of the backup stream can't be read")?;
+        ensure!(
+            manifest.codec == codec,
+            "the backup was taken from a database storing values as {}, this one stores them as {}",
+            manifest.codec,
+            codec
+        );
+        ensure!(
+            manifest.format_version <= FORMAT_VERSION,
+            "the backup was taken from a newer version of dbless (value format {})",
+            manifest.format_version
+        );
+
+        let file = TempFile(temp_path()?);
+        let mut staged = BufWriter::new(File::create(&file.0)?);
+        let mut tables: Vec<(String, u64)> = vec![];
+        let mut sections = 0u64;
+        loop {
+            let [tag] = read_array(&mut reader)?;
+            match tag {
+                TAG_SECTION => {}
+                TAG_FOOTER => break,
+                tag => bail!(
+                    "the backup stream is corrupted (unknown section tag {})",
+                    tag
+                ),
+            }
+            let name = String::from_utf8(read_field(&mut reader)?)
+                .context("the backup stream is corrupted (a table name isn't UTF-8)")?;
+            let [method] = read_array(&mut reader)?;
+            let entries = read_u32(&mut reader)?;
+            let len = read_u64(&mut reader)?;
+            let payload_len = read_u64(&mut reader)?;
+            let records = decompress(method, read_exactly(&mut reader, payload_len)?, len)?;
+
+            match tables.last_mut() {
+                Some((last, count)) if *last == name => *count += entries as u64,
+                _ => {
+                    ensure!(
+                        manifest.tables.contains_key(&name),
+                        "the backup stream holds table `{}`, which isn't in its manifest",
+                        name
+                    );
+                    ensure!(
+                        tables.iter().all(|(table, _)| *table != name),
+                        "the backup stream is corrupted (table `{}` is split)",
+                        name
+                    );
+                    tables.push((name, entries as u64));
+                }
+            }
+            let mut records = records.as_slice();
+            for _ in 0..entries {
+                let key = take_field(&mut records)?;
+                std::str::from_utf8(key)
+                    .context("the backup stream is corrupted (a key isn't UTF-8)")?;
+                write_field(&mut staged, key)?;
+                write_field(&mut staged, take_field(&mut records)?)?;
+            }
+            ensure!(
+                records.is_empty(),
+                "a section of the backup stream is corrupted"
+            );
+            sections += 1;
+        }
+
+        let declared_sections = read_u64(&mut reader)?;
+        let declared_entries = read_u64(&mut reader)?;
+        let digest = reader.hasher.clone().finalize();
+        ensure!(
+            read_array::<32>(&mut reader)? == *digest,
+            "the checksum of the backup stream doesn't match, it's corrupted"
+        );
+        ensure!(
+            reader.read(&mut [0])? == 0,
+            "the backup stream has data after its footer"
+        );
+        let entries = tables.iter().map(|(_, entries)| entries).sum();
+        ensure!(
+            declared_sections == sections && declared_entries == entries,
+            "the footer of the backup stream doesn't match its sections"
+        );
+        ensure!(
+            tables.len() == manifest.tables.len(),
+            "the backup stream is missing tables listed in its manifest"
+        );
+        for (name, entries) in &tables {
+            let expected = manifest.tables[name].entries;
+            ensure!(
+                *entries == expected,
+                "table `{}` holds {} entries in the backup stream, its manifest says {}",
+                name,
+                entries,
+                expected
+            );
+        }
+        staged.flush()?;
+
+        Ok(StagedBackup {
+            summary: BackupSummary {
+                tables: tables.len(),
+                entries,
+                bytes: reader.count,
+                manifest,
+            },
+            tables,
+            file,
+        })
+    }
+
+    /// writes the staged entries into the database
+    pub fn apply(&self, store: &mut Store, mode: RestoreMode) -> Result<()> {
+        let mut staged = BufReader::new(File::open(&self.file.0)?);
+        store.restore_entries(&self.tables, mode == RestoreMode::Replace, || {
+            let key = String::from_utf8(read_field(&mut staged)?)?;
+            Ok((key, read_field(&mut staged)?))
+        })
+    }
+}
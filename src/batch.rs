@@ -0,0 +1,66 @@
+use anyhow::Result;
+use serde::Serialize;
+
+use crate::serde::serialize_for_key;
+
+pub(crate) enum BatchOp {
+    Insert(String, Vec<u8>),
+    Remove(String),
+    Clear,
+}
+
+/// Inserts and removes staged against a table, \
+/// applied all at once in a single write transaction by [`TableMut::apply()`](struct.TableMut.html#method.apply) \
+/// or [`Database::apply()`](struct.Database.html#method.apply). \
+/// operations are applied in the order they're staged, \
+/// so inserting a key and then removing it leaves it absent. \
+/// values are serialized as they're staged, so a value that can't be serialized fails here, not when the batch is applied.
+/// ```no_run
+/// # use dbless::{Database, WriteBatch};
+/// let mut db = Database::open("my_database.db")?;
+/// let mut batch = WriteBatch::new();
+/// batch.insert("alice", &30)?;
+/// batch.insert("bob", &25)?;
+/// batch.remove("carol");
+/// db.table_mut("users").apply(batch)?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Default)]
+pub struct WriteBatch {
+    pub(crate) ops: Vec<BatchOp>,
+}
+
+impl WriteBatch {
+    /// Creates an empty batch
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stages inserting a value with the given key, \
+    /// errors if the value fails to serialize, in which case nothing is staged.
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        let bytes = serialize_for_key(key, value)?;
+        self.ops.push(BatchOp::Insert(key.to_string(), bytes));
+        Ok(())
+    }
+
+    /// Stages removing the given key, removing a key that doesn't exist does nothing
+    pub fn remove(&mut self, key: &str) {
+        self.ops.push(BatchOp::Remove(key.to_string()));
+    }
+
+    /// Stages removing every entry of the table, including the ones staged before
+    pub fn clear_table(&mut self) {
+        self.ops.push(BatchOp::Clear);
+    }
+
+    /// The number of staged operations
+    pub fn len(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Whether nothing is staged
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+}
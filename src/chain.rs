@@ -0,0 +1,90 @@
+use std::fmt;
+
+/// The result of [`Table::verify_chain()`](struct.Table.html#method.verify_chain)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChainReport {
+    /// The number of entries in the chain
+    pub entries: u64,
+    /// The hash at the end of the chain (all zeros for an empty chain), \
+    /// store it elsewhere to also detect a chain that was rewritten as a whole
+    pub head: [u8; 32],
+    /// The first key, in insertion order, whose value doesn't match the chain, \
+    /// or a key in the table that was never appended to the chain
+    pub first_divergent_key: Option<String>,
+}
+
+impl ChainReport {
+    /// Checks if every entry matched the chain
+    pub fn is_intact(&self) -> bool {
+        self.first_divergent_key.is_none()
+    }
+}
+
+/// The error returned when overwriting or removing entries of a table made append-only with \
+/// [`TableMut::append_only_chained()`](struct.TableMut.html#method.append_only_chained), \
+/// or when removing entries of a table made append-only with [`TableMut::append_only()`](struct.TableMut.html#method.append_only).
+/// ```no_run
+/// # use dbless::{AppendOnlyViolation, Database, TableWriteInterface};
+/// let mut db = Database::open("my_database.db")?;
+/// if let Err(e) = db.table_mut("audit").remove("entry-1") {
+///     if let Some(e) = e.downcast_ref::<AppendOnlyViolation>() {
+///         println!("refused: {}", e);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppendOnlyViolation {
+    /// The append-only table
+    pub table: String,
+    /// The key that was about to be overwritten or removed, \
+    /// `None` for operations on the whole table, like clearing it
+    pub key: Option<String>,
+}
+
+impl fmt::Display for AppendOnlyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.key {
+            Some(key) => write!(
+                f,
+                "key `{}` of append-only table `{}` can't be overwritten or removed",
+                key, self.table
+            ),
+            None => write!(f, "append-only table `{}` can't be cleared", self.table),
+        }
+    }
+}
+
+impl std::error::Error for AppendOnlyViolation {}
+
+/// The error returned when overwriting a key of a table made insert-only with \
+/// [`TableMut::insert_only()`](struct.TableMut.html#method.insert_only) or [`TableMut::append_only()`](struct.TableMut.html#method.append_only).
+/// ```no_run
+/// # use dbless::{Database, ImmutableKey, TableWriteInterface};
+/// let mut db = Database::open("my_database.db")?;
+/// if let Err(e) = db.table_mut("blobs").set("9f86d081", &"test") {
+///     if let Some(e) = e.downcast_ref::<ImmutableKey>() {
+///         println!("already stored: {}", e.key);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImmutableKey {
+    /// The insert-only table
+    pub table: String,
+    /// The key that was about to be overwritten
+    pub key: String,
+}
+
+impl fmt::Display for ImmutableKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key `{}` of insert-only table `{}` can't be overwritten",
+            self.key, self.table
+        )
+    }
+}
+
+impl std::error::Error for ImmutableKey {}
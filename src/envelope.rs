@@ -0,0 +1,236 @@
+//! values that carry metadata (like a version or a type tag) are stored wrapped in an envelope, \
+//! a MessagePack ext value of type [`EXT_TYPE`] whose payload is:
+//! - one byte of flags, telling which metadata fields follow.
+//! - the metadata fields, in the order of their flags.
+//! - the serialized value itself.
+//!
+//! a value written with a time to live carries its expiry as a big-endian u64 of milliseconds since the unix epoch, \
+//! it's read as missing once that time has passed.
+//!
+//! values without metadata are stored as plain MessagePack, \
+//! and rmp-serde never produces ext values for regular types, so both can live in the same table.
+//!
+//! counters are stored as a MessagePack fixext 8 of type [`COUNTER_EXT_TYPE`], \
+//! whose payload is the value as a big-endian u64, so counters compare the same as their bytes.
+//!
+//! in deduplicated tables, values longer than a reference are stored once in the table's blobs, \
+//! and replaced by a MessagePack ext 8 of type [`BLOB_EXT_TYPE`] whose payload is the SHA-256 of the stored bytes.
+//!
+//! bytes written with [`set_raw()`](crate::TableWriteInterface::set_raw) are stored as they are, \
+//! in a MessagePack ext 32 of type [`RAW_EXT_TYPE`], so they're never mistaken for a serialized value. \
+//! like any other value, they're wrapped in an envelope when they carry metadata.
+//!
+//! values written through a [transform chain](crate::ValueTransform) are wrapped in a MessagePack ext value of type [`TRANSFORM_EXT_TYPE`] \
+//! whose payload is one byte with the number of stages, the id of each stage in the order they were applied, \
+//! then the transformed bytes. the stages are applied to the whole stored value, envelope included, \
+//! and blob references point to the transformed bytes.
+
+use anyhow::{ensure, Result};
+
+const EXT_TYPE: i8 = 0x64;
+const COUNTER_EXT_TYPE: i8 = 0x65;
+const BLOB_EXT_TYPE: i8 = 0x66;
+const TRANSFORM_EXT_TYPE: i8 = 0x67;
+const RAW_EXT_TYPE: i8 = 0x68;
+
+/// the length of an encoded blob reference
+pub const BLOB_REF_LEN: usize = 35;
+
+const FLAG_VERSION: u8 = 1 << 0;
+const FLAG_TAG: u8 = 1 << 1;
+const FLAG_EXPIRY: u8 = 1 << 2;
+const KNOWN_FLAGS: u8 = FLAG_VERSION | FLAG_TAG | FLAG_EXPIRY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Envelope<'a> {
+    pub version: Option<u64>,
+    pub tag: Option<&'a str>,
+    /// milliseconds since the unix epoch
+    pub expires_at: Option<u64>,
+    pub value: &'a [u8],
+}
+
+impl<'a> Envelope<'a> {
+    pub fn plain(value: &'a [u8]) -> Self {
+        Envelope {
+            version: None,
+            tag: None,
+            expires_at: None,
+            value,
+        }
+    }
+
+    pub fn is_plain(&self) -> bool {
+        self.version.is_none() && self.tag.is_none() && self.expires_at.is_none()
+    }
+
+    /// checks if the value expired at or before `now`, in milliseconds since the unix epoch
+    pub fn is_expired(&self, now: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    pub fn decode(bytes: &'a [u8]) -> Result<Self> {
+        let Some((ext_type, mut payload)) = split_ext(bytes) else {
+            return Ok(Self::plain(bytes));
+        };
+        if ext_type != EXT_TYPE {
+            return Ok(Self::plain(bytes));
+        }
+
+        let flags = take(&mut payload, 1)?[0];
+        ensure!(
+            flags & !KNOWN_FLAGS == 0,
+            "unsupported value envelope flags: {:#04x}",
+            flags
+        );
+        let mut envelope = Self::plain(&[]);
+        if flags & FLAG_VERSION != 0 {
+            envelope.version = Some(u64::from_be_bytes(take(&mut payload, 8)?.try_into()?));
+        }
+        if flags & FLAG_TAG != 0 {
+            let len = u16::from_be_bytes(take(&mut payload, 2)?.try_into()?);
+            envelope.tag = Some(std::str::from_utf8(take(&mut payload, len as usize)?)?);
+        }
+        if flags & FLAG_EXPIRY != 0 {
+            envelope.expires_at = Some(u64::from_be_bytes(take(&mut payload, 8)?.try_into()?));
+        }
+        envelope.value = payload;
+        Ok(envelope)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        if self.is_plain() {
+            return self.value.to_vec();
+        }
+
+        let mut flags = 0;
+        let mut payload = vec![0];
+        if let Some(version) = self.version {
+            flags |= FLAG_VERSION;
+            payload.extend_from_slice(&version.to_be_bytes());
+        }
+        if let Some(tag) = self.tag {
+            flags |= FLAG_TAG;
+            payload.extend_from_slice(&(tag.len() as u16).to_be_bytes());
+            payload.extend_from_slice(tag.as_bytes());
+        }
+        if let Some(expires_at) = self.expires_at {
+            flags |= FLAG_EXPIRY;
+            payload.extend_from_slice(&expires_at.to_be_bytes());
+        }
+        payload[0] = flags;
+        payload.extend_from_slice(self.value);
+
+        let mut bytes = Vec::with_capacity(payload.len() + 6);
+        bytes.push(0xc9);
+        bytes.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        bytes.push(EXT_TYPE as u8);
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+}
+
+pub fn encode_counter(value: u64) -> [u8; 10] {
+    let mut bytes = [0; 10];
+    bytes[0] = 0xd7;
+    bytes[1] = COUNTER_EXT_TYPE as u8;
+    bytes[2..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+/// returns `None` if the bytes are not a counter
+pub fn decode_counter(bytes: &[u8]) -> Option<u64> {
+    match split_ext(bytes)? {
+        (COUNTER_EXT_TYPE, payload) => Some(u64::from_be_bytes(payload.try_into().ok()?)),
+        _ => None,
+    }
+}
+
+pub fn encode_blob_ref(hash: &[u8; 32]) -> [u8; BLOB_REF_LEN] {
+    let mut bytes = [0; BLOB_REF_LEN];
+    bytes[0] = 0xc7;
+    bytes[1] = 32;
+    bytes[2] = BLOB_EXT_TYPE as u8;
+    bytes[3..].copy_from_slice(hash);
+    bytes
+}
+
+/// returns `None` if the bytes are not a blob reference
+pub fn decode_blob_ref(bytes: &[u8]) -> Option<[u8; 32]> {
+    match split_ext(bytes)? {
+        (BLOB_EXT_TYPE, payload) => payload.try_into().ok(),
+        _ => None,
+    }
+}
+
+pub fn encode_raw(bytes: &[u8]) -> Vec<u8> {
+    let mut encoded = Vec::with_capacity(bytes.len() + 6);
+    encoded.push(0xc9);
+    encoded.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+    encoded.push(RAW_EXT_TYPE as u8);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+/// returns `None` if the bytes are not raw bytes
+pub fn decode_raw(bytes: &[u8]) -> Option<&[u8]> {
+    match split_ext(bytes)? {
+        (RAW_EXT_TYPE, payload) => Some(payload),
+        _ => None,
+    }
+}
+
+pub fn encode_transformed(ids: &[u8], bytes: &[u8]) -> Vec<u8> {
+    let len = 1 + ids.len() + bytes.len();
+    let mut encoded = Vec::with_capacity(len + 6);
+    encoded.push(0xc9);
+    encoded.extend_from_slice(&(len as u32).to_be_bytes());
+    encoded.push(TRANSFORM_EXT_TYPE as u8);
+    encoded.push(ids.len() as u8);
+    encoded.extend_from_slice(ids);
+    encoded.extend_from_slice(bytes);
+    encoded
+}
+
+/// returns the ids of the applied stages and the transformed bytes, \
+/// or `None` if the bytes were not transformed
+pub fn decode_transformed(bytes: &[u8]) -> Result<Option<(&[u8], &[u8])>> {
+    let Some((TRANSFORM_EXT_TYPE, mut payload)) = split_ext(bytes) else {
+        return Ok(None);
+    };
+    let count = take(&mut payload, 1)?[0];
+    let ids = take(&mut payload, count as usize)?;
+    Ok(Some((ids, payload)))
+}
+
+fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8]> {
+    ensure!(bytes.len() >= n, "truncated value envelope");
+    let (head, tail) = bytes.split_at(n);
+    *bytes = tail;
+    Ok(head)
+}
+
+/// splits a MessagePack ext value into its type and payload, \
+/// returns `None` if the bytes are not a (well formed) ext value.
+fn split_ext(bytes: &[u8]) -> Option<(i8, &[u8])> {
+    let (&marker, rest) = bytes.split_first()?;
+    let (len, rest) = match marker {
+        0xd4 => (1, rest),
+        0xd5 => (2, rest),
+        0xd6 => (4, rest),
+        0xd7 => (8, rest),
+        0xd8 => (16, rest),
+        0xc7 => (*rest.first()? as usize, &rest[1..]),
+        0xc8 => (
+            u16::from_be_bytes(rest.get(..2)?.try_into().ok()?) as usize,
+            &rest[2..],
+        ),
+        0xc9 => (
+            u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize,
+            &rest[4..],
+        ),
+        _ => return None,
+    };
+    let (&ext_type, payload) = rest.split_first()?;
+    (payload.len() == len).then_some((ext_type as i8, payload))
+}
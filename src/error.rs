@@ -0,0 +1,182 @@
+use std::fmt;
+
+use redb::{
+    CommitError, CompactionError, DatabaseError, SavepointError, StorageError, TableError,
+    TransactionError,
+};
+
+use crate::LossyNumberError;
+
+/// The error returned by dbless, telling what went wrong to match on. \
+/// every method of [`Database`](struct.Database.html), of the tables and of the transactions returns it, \
+/// and each variant holds the original error, as an [`anyhow::Error`](https://docs.rs/anyhow/latest/anyhow/struct.Error.html) \
+/// the error types of dbless (like [`Frozen`](struct.Frozen.html)) can be downcast from. \
+/// it converts to and from `anyhow::Error` without losing its kind, \
+/// so `?` keeps working in closures and in apps using anyhow.
+/// ```no_run
+/// # use dbless::{Database, DblessError, TableReadInterface};
+/// let db = Database::open("my_database.db")?;
+/// match db.get::<u32>("name") {
+///     Ok(name) => println!("{:?}", name),
+///     Err(DblessError::Deserialize(e)) => println!("`name` isn't a number: {:#}", e),
+///     Err(e) => return Err(e.into()),
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug)]
+pub enum DblessError {
+    /// A value couldn't be serialized
+    Serialize(anyhow::Error),
+    /// A stored value couldn't be deserialized into the requested type, \
+    /// including a [`LossyNumberError`](struct.LossyNumberError.html)
+    Deserialize(anyhow::Error),
+    /// redb or the file system failed, like an I/O error or a corrupted file
+    Backend(anyhow::Error),
+    /// A table that had to exist doesn't
+    NotFound(anyhow::Error),
+    /// Any other error, like a write refused by the settings of a table
+    Other(anyhow::Error),
+}
+
+impl DblessError {
+    /// Gets the original error
+    pub fn inner(&self) -> &anyhow::Error {
+        match self {
+            DblessError::Serialize(e)
+            | DblessError::Deserialize(e)
+            | DblessError::Backend(e)
+            | DblessError::NotFound(e)
+            | DblessError::Other(e) => e,
+        }
+    }
+
+    /// Gets the original error, by value
+    pub fn into_inner(self) -> anyhow::Error {
+        match self {
+            DblessError::Serialize(e)
+            | DblessError::Deserialize(e)
+            | DblessError::Backend(e)
+            | DblessError::NotFound(e)
+            | DblessError::Other(e) => e,
+        }
+    }
+
+    /// Checks if the original error, or any context added to it, is an `E`, \
+    /// like [`anyhow::Error::is()`](https://docs.rs/anyhow/latest/anyhow/struct.Error.html#method.is)
+    pub fn is<E: fmt::Display + fmt::Debug + Send + Sync + 'static>(&self) -> bool {
+        self.inner().is::<E>()
+    }
+
+    /// Downcasts the original error, or any context added to it, to an `E`, \
+    /// like [`anyhow::Error::downcast_ref()`](https://docs.rs/anyhow/latest/anyhow/struct.Error.html#method.downcast_ref)
+    pub fn downcast_ref<E: fmt::Display + fmt::Debug + Send + Sync + 'static>(&self) -> Option<&E> {
+        self.inner().downcast_ref::<E>()
+    }
+}
+
+/// The result of the methods of dbless, see [`DblessError`](enum.DblessError.html)
+pub type Result<T, E = DblessError> = std::result::Result<T, E>;
+
+impl From<anyhow::Error> for DblessError {
+    fn from(e: anyhow::Error) -> Self {
+        // a `DblessError` that went through `?` in a closure, or through an app using anyhow, \
+        // taken back as it was, or keeping its kind under the context added to it since
+        if e.chain().next().is_some_and(|top| top.is::<DblessError>()) {
+            return e.downcast().unwrap_or_else(DblessError::Other);
+        }
+        if let Some(original) = e.downcast_ref::<DblessError>() {
+            let kind: fn(anyhow::Error) -> Self = match original {
+                DblessError::Serialize(_) => DblessError::Serialize,
+                DblessError::Deserialize(_) => DblessError::Deserialize,
+                DblessError::Backend(_) => DblessError::Backend,
+                DblessError::NotFound(_) => DblessError::NotFound,
+                DblessError::Other(_) => DblessError::Other,
+            };
+            return kind(e);
+        }
+        // downcasting looks through every context added on the way up
+        if e.is::<SerializeFailed>() {
+            return DblessError::Serialize(e);
+        }
+        if e.is::<rmp_serde::encode::Error>() {
+            return DblessError::Serialize(e);
+        }
+        if e.is::<DeserializeFailed>()
+            || e.is::<LossyNumberError>()
+            || e.is::<rmp_serde::decode::Error>()
+        {
+            return DblessError::Deserialize(e);
+        }
+        #[cfg(feature = "json")]
+        if let Some(json) = e.downcast_ref::<serde_json::Error>() {
+            if json.is_io() {
+                return DblessError::Backend(e);
+            }
+            return DblessError::Deserialize(e);
+        }
+        let missing_table = matches!(
+            e.downcast_ref::<TableError>(),
+            Some(TableError::TableDoesNotExist(_))
+        );
+        if missing_table || e.is::<Missing>() {
+            return DblessError::NotFound(e);
+        }
+        if e.is::<redb::Error>()
+            || e.is::<StorageError>()
+            || e.is::<TableError>()
+            || e.is::<TransactionError>()
+            || e.is::<CommitError>()
+            || e.is::<DatabaseError>()
+            || e.is::<CompactionError>()
+            || e.is::<SavepointError>()
+            || e.is::<std::io::Error>()
+        {
+            return DblessError::Backend(e);
+        }
+        DblessError::Other(e)
+    }
+}
+
+impl fmt::Display for DblessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.inner(), f)
+    }
+}
+
+impl std::error::Error for DblessError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.inner().source()
+    }
+}
+
+/// the context of an error serializing a value
+#[derive(Debug)]
+pub struct SerializeFailed(pub String);
+
+impl fmt::Display for SerializeFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// the context of an error deserializing a stored value
+#[derive(Debug)]
+pub struct DeserializeFailed(pub String);
+
+impl fmt::Display for DeserializeFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// an error about a table that had to exist
+#[derive(Debug)]
+pub struct Missing(pub String);
+
+impl fmt::Display for Missing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Missing {}
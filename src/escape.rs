@@ -0,0 +1,95 @@
+//! the key escaping scheme: \
+//! every `%` and every ASCII control character (`U+0000` to `U+001F` and `U+007F`) \
+//! is replaced by `%` followed by its code as two uppercase hex digits, \
+//! so `"a\nb%"` becomes `"a%0Ab%25"`, and every other character is kept as is.
+//!
+//! each character is escaped on its own, so the escaped form of a prefix \
+//! is always a prefix of the escaped form of the key.
+
+use anyhow::{bail, Context, Result};
+
+fn needs_escape(c: char) -> bool {
+    c == '%' || c.is_ascii_control()
+}
+
+/// Escapes `%` and ASCII control characters (like newlines) in a key as `%XX`, \
+/// leaving every other character untouched. \
+/// the escaping is reversible with [`unescape_key()`](fn.unescape_key.html), \
+/// and prefix preserving: if `key` starts with `prefix`, \
+/// `escape_key(key)` starts with `escape_key(prefix)`.
+/// ```
+/// # use dbless::{escape_key, unescape_key};
+/// assert_eq!(escape_key("line 1\nline 2"), "line 1%0Aline 2");
+/// assert_eq!(escape_key("100%"), "100%25");
+/// assert_eq!(unescape_key("line 1%0Aline 2")?, "line 1\nline 2");
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn escape_key(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for c in key.chars() {
+        if needs_escape(c) {
+            escaped.push_str(&format!("%{:02X}", c as u32));
+        } else {
+            escaped.push(c);
+        }
+    }
+    escaped
+}
+
+/// escapes a key that may not be valid UTF-8: \
+/// the valid parts are escaped like [`escape_key()`](fn.escape_key.html) does, \
+/// and every invalid byte is replaced by `%` followed by its two hex digits, \
+/// which [`unescape_key()`](fn.unescape_key.html) refuses since it isn't a character
+#[cfg(feature = "lmdb-import")]
+pub(crate) fn escape_bytes(key: &[u8]) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for chunk in key.utf8_chunks() {
+        escaped.push_str(&escape_key(chunk.valid()));
+        for b in chunk.invalid() {
+            escaped.push_str(&format!("%{:02X}", b));
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape_key()`](fn.escape_key.html). \
+/// errors if the key contains a `%` that isn't followed by two hex digits, \
+/// or anything that [`escape_key()`](fn.escape_key.html) would never produce, \
+/// like an escaped character that didn't need escaping or an unescaped control character.
+/// ```
+/// # use dbless::unescape_key;
+/// assert_eq!(unescape_key("tab%09separated")?, "tab\tseparated");
+/// assert!(unescape_key("50%").is_err());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn unescape_key(key: &str) -> Result<String> {
+    let mut unescaped = String::with_capacity(key.len());
+    let mut chars = key.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c != '%' {
+            if needs_escape(c) {
+                bail!(
+                    "key `{}` has an unescaped control character",
+                    key.escape_debug()
+                );
+            }
+            unescaped.push(c);
+            continue;
+        }
+        let code = key
+            .get(i + 1..i + 3)
+            .filter(|hex| {
+                hex.bytes()
+                    .all(|b| b.is_ascii_digit() || (b'A'..=b'F').contains(&b))
+            })
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            .with_context(|| format!("key `{}` has an invalid escape at byte {}", key, i))?;
+        let c = code as char;
+        if !needs_escape(c) {
+            bail!("key `{}` escapes `{}`, which doesn't need escaping", key, c);
+        }
+        unescaped.push(c);
+        chars.nth(1);
+    }
+    Ok(unescaped)
+}
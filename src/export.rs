@@ -0,0 +1,413 @@
+use std::borrow::Cow;
+use std::io::Write;
+
+use anyhow::{bail, Result};
+#[cfg(feature = "json")]
+use anyhow::{ensure, Context};
+
+use crate::envelope::{decode_counter, decode_raw, Envelope};
+use crate::msgpack::{self, Limits, Reader, Token};
+
+/// The output format of [`Database::stream_table_to()`](struct.Database.html#method.stream_table_to)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A single MessagePack map from key to value. \
+    /// values are copied as stored, without their version or type tag, \
+    /// and [`u64` counters](trait.TableWriteInterface.html#method.set_u64) are written as plain integers.
+    MessagePack,
+    /// A single JSON object from key to value, transcoded from MessagePack. \
+    /// binary values become arrays of bytes, non-string map keys become strings holding their JSON, \
+    /// and non-finite floats become `null`.
+    Json,
+}
+
+/// counts the bytes written through it
+pub struct CountingWriter<W> {
+    inner: W,
+    pub count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CountingWriter { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// the value without any dbless metadata, as plain MessagePack
+pub fn plain_value(bytes: &[u8]) -> Result<Cow<'_, [u8]>> {
+    if let Some(counter) = decode_counter(bytes) {
+        let mut plain = vec![0xcf];
+        plain.extend_from_slice(&counter.to_be_bytes());
+        return Ok(Cow::Owned(plain));
+    }
+    let value = Envelope::decode(bytes)?.value;
+    match decode_raw(value) {
+        Some(raw) => Ok(Cow::Owned(msgpack_bin(raw))),
+        None => Ok(Cow::Borrowed(value)),
+    }
+}
+
+/// raw bytes as a MessagePack bin
+pub fn msgpack_bin(bytes: &[u8]) -> Vec<u8> {
+    let len = bytes.len();
+    let mut bin = Vec::with_capacity(len + 5);
+    match len {
+        0..=0xff => bin.extend_from_slice(&[0xc4, len as u8]),
+        0x100..=0xffff => {
+            bin.push(0xc5);
+            bin.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        _ => {
+            bin.push(0xc6);
+            bin.extend_from_slice(&(len as u32).to_be_bytes());
+        }
+    }
+    bin.extend_from_slice(bytes);
+    bin
+}
+
+pub fn write_msgpack_map_len(writer: &mut impl Write, len: u32) -> Result<()> {
+    match len {
+        0..=15 => writer.write_all(&[0x80 | len as u8])?,
+        16..=0xffff => {
+            writer.write_all(&[0xde])?;
+            writer.write_all(&(len as u16).to_be_bytes())?;
+        }
+        _ => {
+            writer.write_all(&[0xdf])?;
+            writer.write_all(&len.to_be_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn write_msgpack_str(writer: &mut impl Write, s: &str) -> Result<()> {
+    let len = s.len();
+    match len {
+        0..=31 => writer.write_all(&[0xa0 | len as u8])?,
+        32..=0xff => writer.write_all(&[0xd9, len as u8])?,
+        0x100..=0xffff => {
+            writer.write_all(&[0xda])?;
+            writer.write_all(&(len as u16).to_be_bytes())?;
+        }
+        _ => {
+            writer.write_all(&[0xdb])?;
+            writer.write_all(&(len as u32).to_be_bytes())?;
+        }
+    }
+    writer.write_all(s.as_bytes())?;
+    Ok(())
+}
+
+pub fn write_json_str(writer: &mut impl Write, s: &str) -> Result<()> {
+    writer.write_all(b"\"")?;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '"' => Cow::Borrowed("\\\""),
+            '\\' => Cow::Borrowed("\\\\"),
+            '\n' => Cow::Borrowed("\\n"),
+            '\r' => Cow::Borrowed("\\r"),
+            '\t' => Cow::Borrowed("\\t"),
+            c if (c as u32) < 0x20 => Cow::Owned(format!("\\u{:04x}", c as u32)),
+            _ => continue,
+        };
+        writer.write_all(&s.as_bytes()[start..i])?;
+        writer.write_all(escaped.as_bytes())?;
+        start = i + c.len_utf8();
+    }
+    writer.write_all(&s.as_bytes()[start..])?;
+    writer.write_all(b"\"")?;
+    Ok(())
+}
+
+/// transcodes the next MessagePack value of `reader` into JSON
+pub fn write_json(writer: &mut impl Write, reader: &mut Reader) -> Result<()> {
+    match reader.read()? {
+        Token::Nil => writer.write_all(b"null")?,
+        Token::Bool(b) => write!(writer, "{}", b)?,
+        Token::Int(n) => write!(writer, "{}", n)?,
+        Token::UInt(n) => write!(writer, "{}", n)?,
+        Token::F32(f) if f.is_finite() => write!(writer, "{}", f)?,
+        Token::F64(f) if f.is_finite() => write!(writer, "{}", f)?,
+        Token::F32(_) | Token::F64(_) => writer.write_all(b"null")?,
+        Token::Str(s) => write_json_str(writer, s)?,
+        Token::Bin(bytes) => {
+            writer.write_all(b"[")?;
+            for (i, byte) in bytes.iter().enumerate() {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                write!(writer, "{}", byte)?;
+            }
+            writer.write_all(b"]")?;
+        }
+        Token::Array(len) => {
+            writer.write_all(b"[")?;
+            for i in 0..len {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                write_json(writer, reader)?;
+            }
+            writer.write_all(b"]")?;
+        }
+        Token::Map(len) => {
+            writer.write_all(b"{")?;
+            for i in 0..len {
+                if i > 0 {
+                    writer.write_all(b",")?;
+                }
+                let mut key_reader = Reader::new(reader.remaining());
+                match key_reader.read()? {
+                    Token::Str(key) => write_json_str(writer, key)?,
+                    _ => {
+                        let mut key = vec![];
+                        write_json(&mut key, &mut Reader::new(reader.remaining()))?;
+                        write_json_str(writer, &String::from_utf8(key)?)?;
+                    }
+                }
+                reader.skip()?;
+                writer.write_all(b":")?;
+                write_json(writer, reader)?;
+            }
+            writer.write_all(b"}")?;
+        }
+        Token::Ext(ext_type, _) => bail!("can't convert MessagePack ext type {} to JSON", ext_type),
+    }
+    Ok(())
+}
+
+/// the fields of the objects a JSON dump wraps values carrying metadata in
+const DUMP_FIELDS: [&str; 6] = [
+    "$value",
+    "$binary",
+    "$raw",
+    "$counter",
+    "$tag",
+    "$expires_at",
+];
+
+/// checks if a plain MessagePack value is a non-empty map with only dump fields as keys, \
+/// which would be read back as a wrapper if it wasn't wrapped itself
+fn looks_like_wrapper(plain: &[u8]) -> Result<bool> {
+    let mut reader = Reader::new(plain);
+    let Token::Map(len @ 1..) = reader.read()? else {
+        return Ok(false);
+    };
+    for _ in 0..len {
+        match reader.read()? {
+            Token::Str(key) if DUMP_FIELDS.contains(&key) => reader.skip()?,
+            _ => return Ok(false),
+        }
+    }
+    Ok(true)
+}
+
+/// writes a value of a JSON dump from its `stored` bytes and their `plain` MessagePack form. \
+/// values without metadata are written as they are, the others are wrapped in an object holding: \
+/// the value as `$value`, a top-level binary value or raw bytes as hex in `$binary` or `$raw`, \
+/// or a counter as `$counter`, along with `$tag` and `$expires_at` if they're set. \
+/// versions aren't written
+pub fn write_dump_value(
+    writer: &mut impl Write,
+    stored: &[u8],
+    plain: &[u8],
+    limits: &Limits,
+) -> Result<()> {
+    if let Some(counter) = decode_counter(stored) {
+        write!(writer, "{{\"$counter\":{}}}", counter)?;
+        return Ok(());
+    }
+    let envelope = Envelope::decode(stored)?;
+    let (field, bytes) = match (decode_raw(envelope.value), Reader::new(plain).read()?) {
+        (Some(raw), _) => ("$raw", Some(raw)),
+        (None, Token::Bin(bin)) => ("$binary", Some(bin)),
+        (None, _) => ("$value", None),
+    };
+    let wrapped = bytes.is_some()
+        || envelope.tag.is_some()
+        || envelope.expires_at.is_some()
+        || looks_like_wrapper(plain)?;
+    if !wrapped {
+        msgpack::check(plain, limits)?;
+        return write_json(writer, &mut Reader::new(plain));
+    }
+    write!(writer, "{{\"{}\":", field)?;
+    match bytes {
+        Some(bytes) => write_json_str(writer, &to_hex(bytes))?,
+        None => {
+            msgpack::check(plain, limits)?;
+            write_json(writer, &mut Reader::new(plain))?;
+        }
+    }
+    if let Some(tag) = envelope.tag {
+        writer.write_all(b",\"$tag\":")?;
+        write_json_str(writer, tag)?;
+    }
+    if let Some(expires_at) = envelope.expires_at {
+        write!(writer, ",\"$expires_at\":{}", expires_at)?;
+    }
+    writer.write_all(b"}")?;
+    Ok(())
+}
+
+/// A value read from a JSON dump, see [`write_dump_value()`]
+#[cfg(feature = "json")]
+pub enum DumpValue {
+    Json(serde_json::Value),
+    Binary(Vec<u8>),
+    Raw(Vec<u8>),
+    Counter(u64),
+}
+
+/// a value read from a JSON dump along with its metadata
+#[cfg(feature = "json")]
+pub struct DumpEntry {
+    pub value: DumpValue,
+    pub tag: Option<String>,
+    /// milliseconds since the unix epoch
+    pub expires_at: Option<u64>,
+}
+
+#[cfg(feature = "json")]
+impl DumpEntry {
+    /// unwraps a value written by [`write_dump_value()`]
+    pub fn parse(value: serde_json::Value) -> Result<Self> {
+        use serde_json::Value;
+
+        let mut fields = match value {
+            Value::Object(fields)
+                if !fields.is_empty()
+                    && fields.keys().all(|key| DUMP_FIELDS.contains(&key.as_str())) =>
+            {
+                fields
+            }
+            value => {
+                return Ok(DumpEntry {
+                    value: DumpValue::Json(value),
+                    tag: None,
+                    expires_at: None,
+                })
+            }
+        };
+        let tag = match fields.remove("$tag") {
+            Some(Value::String(tag)) => Some(tag),
+            None => None,
+            Some(_) => bail!("`$tag` isn't a string"),
+        };
+        let expires_at = match fields.remove("$expires_at") {
+            Some(expires_at) => Some(
+                expires_at
+                    .as_u64()
+                    .context("`$expires_at` isn't a number of milliseconds")?,
+            ),
+            None => None,
+        };
+        let hex = |value: Value, field: &str| match value {
+            Value::String(hex) => from_hex(&hex).with_context(|| format!("`{}` isn't hex", field)),
+            _ => bail!("`{}` isn't a hex string", field),
+        };
+        let single = fields.len() == 1;
+        let Some((field, value)) = fields.into_iter().next().filter(|_| single) else {
+            bail!("expected one of `$value`, `$binary`, `$raw` or `$counter`");
+        };
+        let value = match field.as_str() {
+            "$value" => DumpValue::Json(value),
+            "$binary" => DumpValue::Binary(hex(value, "$binary")?),
+            "$raw" => DumpValue::Raw(hex(value, "$raw")?),
+            _ => {
+                ensure!(
+                    tag.is_none() && expires_at.is_none(),
+                    "counters can't have a `$tag` or an `$expires_at`"
+                );
+                DumpValue::Counter(value.as_u64().context("`$counter` isn't a u64")?)
+            }
+        };
+        Ok(DumpEntry {
+            value,
+            tag,
+            expires_at,
+        })
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "json")]
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    ensure!(hex.len().is_multiple_of(2), "odd number of hex digits");
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .context("invalid hex digit")
+        })
+        .collect()
+}
+
+/// writes a CSV field, quoted if it holds a separator, a quote or a line break
+#[cfg(feature = "csv")]
+pub fn write_csv_field(writer: &mut impl Write, field: &str) -> Result<()> {
+    if !field.contains([',', '"', '\n', '\r']) {
+        writer.write_all(field.as_bytes())?;
+        return Ok(());
+    }
+    writer.write_all(b"\"")?;
+    writer.write_all(field.replace('"', "\"\"").as_bytes())?;
+    writer.write_all(b"\"")?;
+    Ok(())
+}
+
+/// flattens a MessagePack value into `(column, cell)` pairs: \
+/// a map (a struct) gives a column per field, anything else a single `value` column
+#[cfg(feature = "csv")]
+pub fn csv_columns(bytes: &[u8]) -> Result<Vec<(String, String)>> {
+    let mut reader = Reader::new(bytes);
+    let len = match Reader::new(bytes).read()? {
+        Token::Map(len) => len,
+        _ => return Ok(vec![("value".to_string(), csv_cell(&mut reader, "value")?)]),
+    };
+    reader.read()?;
+    let mut columns = Vec::with_capacity(len.min(reader.remaining().len() / 2));
+    for _ in 0..len {
+        let Token::Str(column) = reader.read()? else {
+            bail!("only structs and maps with string keys can be split into CSV columns");
+        };
+        columns.push((column.to_string(), csv_cell(&mut reader, column)?));
+    }
+    Ok(columns)
+}
+
+#[cfg(feature = "csv")]
+fn csv_cell(reader: &mut Reader, column: &str) -> Result<String> {
+    Ok(match reader.read()? {
+        Token::Nil => String::new(),
+        Token::Bool(b) => b.to_string(),
+        Token::Int(n) => n.to_string(),
+        Token::UInt(n) => n.to_string(),
+        Token::F32(f) => f.to_string(),
+        Token::F64(f) => f.to_string(),
+        Token::Str(s) => s.to_string(),
+        Token::Bin(_) | Token::Array(_) | Token::Map(_) | Token::Ext(..) => bail!(
+            "column `{}` holds a nested value (a list, map, struct or bytes) that doesn't fit in a CSV cell, \
+             flatten it into its parent with #[serde(flatten)], or leave it out with #[serde(skip)]",
+            column
+        ),
+    })
+}
@@ -0,0 +1,115 @@
+use std::cmp::Ordering;
+
+use anyhow::Result;
+
+use crate::envelope::Envelope;
+use crate::msgpack::{Reader, Token};
+
+/// A scalar a field is compared to by a [`FieldPredicate`](enum.FieldPredicate.html). \
+/// integers and floats compare with each other by value, strings only compare with strings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldValue {
+    /// A signed or unsigned integer
+    Int(i128),
+    /// A floating point number
+    Float(f64),
+    /// A string, compared byte by byte
+    Str(String),
+}
+
+/// A condition on a single field of a stored value, \
+/// see [`TableMut::remove_where_field()`](struct.TableMut.html#method.remove_where_field). \
+/// a field that is missing, isn't a scalar, or has a type that can't be compared to the given value never matches.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldPredicate {
+    /// The field is less than the value
+    LessThan(FieldValue),
+    /// The field is greater than the value
+    GreaterThan(FieldValue),
+    /// The field is equal to the value
+    Equals(FieldValue),
+}
+
+macro_rules! field_value_from {
+    ($variant:ident: $($ty:ty),*) => {
+        $(
+            impl From<$ty> for FieldValue {
+                fn from(value: $ty) -> Self {
+                    FieldValue::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+field_value_from!(Int: i8, i16, i32, i64, u8, u16, u32, u64);
+field_value_from!(Float: f32, f64);
+field_value_from!(Str: &str, String);
+
+impl FieldValue {
+    fn compare(&self, token: Token) -> Option<Ordering> {
+        let int = match token {
+            Token::Int(n) => Some(n as i128),
+            Token::UInt(n) => Some(n as i128),
+            _ => None,
+        };
+        let float = match token {
+            Token::F32(f) => Some(f as f64),
+            Token::F64(f) => Some(f),
+            _ => int.map(|n| n as f64),
+        };
+        match (self, token) {
+            (FieldValue::Int(value), _) if int.is_some() => int?.partial_cmp(value),
+            (FieldValue::Int(value), _) => float?.partial_cmp(&(*value as f64)),
+            (FieldValue::Float(value), _) => float?.partial_cmp(value),
+            (FieldValue::Str(value), Token::Str(s)) => Some(s.cmp(value.as_str())),
+            (FieldValue::Str(_), _) => None,
+        }
+    }
+}
+
+impl FieldPredicate {
+    fn matches(&self, token: Token) -> bool {
+        match self {
+            FieldPredicate::LessThan(value) => value.compare(token) == Some(Ordering::Less),
+            FieldPredicate::GreaterThan(value) => value.compare(token) == Some(Ordering::Greater),
+            FieldPredicate::Equals(value) => value.compare(token) == Some(Ordering::Equal),
+        }
+    }
+
+    /// checks the field at the dotted `path` of a stored value, without deserializing the value. \
+    /// a path segment selects a map entry by key, or an array element by index.
+    pub(crate) fn matches_value(&self, bytes: &[u8], path: &str) -> Result<bool> {
+        let mut reader = Reader::new(Envelope::decode(bytes)?.value);
+        let segments = path.split('.').filter(|segment| !segment.is_empty());
+        for segment in segments {
+            match reader.read()? {
+                Token::Map(len) => {
+                    let mut found = false;
+                    for _ in 0..len {
+                        let key = Reader::new(reader.remaining()).read()?;
+                        reader.skip()?;
+                        if key == Token::Str(segment) {
+                            found = true;
+                            break;
+                        }
+                        reader.skip()?;
+                    }
+                    if !found {
+                        return Ok(false);
+                    }
+                }
+                Token::Array(len) => match segment.parse::<usize>() {
+                    Ok(index) if index < len => {
+                        for _ in 0..index {
+                            reader.skip()?;
+                        }
+                    }
+                    _ => return Ok(false),
+                },
+                _ => return Ok(false),
+            }
+        }
+        Ok(self.matches(reader.read()?))
+    }
+}
@@ -0,0 +1,37 @@
+//! entry points for the targets in `fuzz/`, not part of the public API. \
+//! they only exist with the `fuzzing` feature, and must never panic whatever the bytes.
+
+use std::io::sink;
+
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+
+use crate::envelope::{decode_blob_ref, decode_counter, decode_transformed, Envelope};
+use crate::export::{plain_value, write_json};
+use crate::msgpack::{self, Limits, Reader};
+use crate::{FieldPredicate, Value};
+
+/// deserializes `bytes` as a stored value the way a read does, with the default limits
+pub fn deserialize<T: DeserializeOwned>(bytes: &[u8], strict: bool) -> Result<T> {
+    crate::serde::deserialize("fuzz", bytes, strict)
+}
+
+/// runs every helper that looks at stored bytes without deserializing them
+pub fn inspect(bytes: &[u8], path: &str) {
+    let _ = decode_counter(bytes);
+    let _ = decode_blob_ref(bytes);
+    let _ = decode_transformed(bytes);
+    let _ = Envelope::decode(bytes);
+    let _ = Reader::new(bytes).skip();
+    let _ = FieldPredicate::Equals(0.into()).matches_value(bytes, path);
+    let _ = FieldPredicate::LessThan("m".into()).matches_value(bytes, path);
+    let Ok(value) = plain_value(bytes) else {
+        return;
+    };
+    let _ = Value::read(&value, &Limits::default());
+    if msgpack::check(&value, &Limits::default()).is_ok() {
+        let _ = write_json(&mut sink(), &mut Reader::new(&value));
+    }
+    #[cfg(feature = "csv")]
+    let _ = crate::export::csv_columns(&value);
+}
@@ -0,0 +1,76 @@
+use crate::store::Store;
+use crate::{Table, TableMut};
+
+/// A read-only view of the tables whose names start with a prefix, \
+/// created with [`Database::group()`](struct.Database.html#method.group). \
+/// tables are opened by their name without the prefix, \
+/// so code handed a group doesn't need to know the tables are namespaced.
+pub struct TableGroup<'a> {
+    pub(crate) store: &'a Store,
+    pub(crate) prefix: &'a str,
+}
+
+/// A read-write view of the tables whose names start with a prefix, \
+/// created with [`Database::group_mut()`](struct.Database.html#method.group_mut). \
+/// see [`TableGroup`](struct.TableGroup.html)
+pub struct TableGroupMut<'a> {
+    pub(crate) store: &'a mut Store,
+    pub(crate) prefix: &'a str,
+}
+
+impl<'a> TableGroup<'a> {
+    /// Gets the prefix of the group
+    pub fn prefix(&self) -> &str {
+        self.prefix
+    }
+
+    /// Get a read-only handle to the table named `name` within the group, \
+    /// that is, the table named `prefix + name`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let user = db.group("user.42.");
+    /// let unread = user.table("inbox").len()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn table(&self, name: &str) -> Table<'_> {
+        Table {
+            store: self.store,
+            name: format!("{}{}", self.prefix, name).into(),
+        }
+    }
+}
+
+impl<'a> TableGroupMut<'a> {
+    /// Gets the prefix of the group
+    pub fn prefix(&self) -> &str {
+        self.prefix
+    }
+
+    /// Get a read-only handle to the table named `prefix + name`, \
+    /// see [`TableGroup::table()`](struct.TableGroup.html#method.table)
+    pub fn table(&self, name: &str) -> Table<'_> {
+        Table {
+            store: self.store,
+            name: format!("{}{}", self.prefix, name).into(),
+        }
+    }
+
+    /// Get a read-write handle to the table named `name` within the group, \
+    /// that is, the table named `prefix + name`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut user = db.group_mut("user.42.");
+    /// user.table_mut("inbox").set("message-1", &"hello")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn table_mut(&mut self, name: &str) -> TableMut<'_> {
+        TableMut {
+            store: self.store,
+            name: format!("{}{}", self.prefix, name).into(),
+        }
+    }
+}
@@ -110,40 +110,277 @@
 //! - [`TableReadInterface`](https://docs.rs/dbless/latest/dbless/trait.TableReadInterface.html).
 //! - [`TableWriteInterface`](https://docs.rs/dbless/latest/dbless/trait.TableWriteInterface.html).
 //!
+//! ## Performance
+//! every method runs in its own redb transaction, so:
+//! - [`get()`](trait.TableReadInterface.html#method.get), [`contains_key()`](trait.TableReadInterface.html#method.contains_key)
+//!   are a single B-tree lookup, and [`len()`](trait.TableReadInterface.html#method.len) reads a stored count, without touching any value.
+//! - [`keys()`](trait.TableReadInterface.html#method.keys), [`values()`](trait.TableReadInterface.html#method.values)
+//!   and [`entries()`](trait.TableReadInterface.html#method.entries) walk the whole table, and the last two deserialize every value.
+//!   values are deserialized as the table is walked, so their peak memory is the returned list plus one raw value,
+//!   on top of the pages redb keeps in its page cache (a cold read of a large table can briefly cache as much as it returns).
+//!   [`iter_keys()`](trait.TableReadInterface.html#method.iter_keys) and [`iter_entries()`](trait.TableReadInterface.html#method.iter_entries) \
+//!   walk a table without collecting it, in bounded memory, and only as far as they're advanced.
+//! - [`len_all_tables()`](struct.Database.html#method.len_all_tables) opens every table in the database (in parallel with the `rayon` feature),
+//!   [`len_all_tables_capped()`](struct.Database.html#method.len_all_tables_capped) stops once it counted enough entries.
+//! - on the file backend every write is a durable commit, prefer
+//!   [`insert_many()`](trait.TableWriteInterface.html#method.insert_many) or
+//!   [`insert_batch()`](trait.TableWriteInterface.html#method.insert_batch) over calling `set()` in a loop.
+//! - [`transaction()`](struct.Database.html#method.transaction) runs several reads and writes across tables in a single transaction,
+//!   when they must happen together.
+//!
+//! benchmarks for these live in `benches/`, run them with `cargo bench`.
+//!
+//! ## Timestamps
+//! with the `chrono` feature enabled, `chrono::DateTime<Utc>` values can be stored and read like any other value, \
+//! they're stored as their RFC 3339 string (chrono's serde representation). \
+//! for keys, [`TimestampKey`](struct.TimestampKey.html) turns a timestamp into a string that sorts in time order,
+//! which [`TableMut::insert_at()`](struct.TableMut.html#method.insert_at) and
+//! [`Table::range_between()`](struct.Table.html#method.range_between) use to store and query entries by time.
+//!
+//! ## Services
+//! with the `service` feature enabled, [`DbHandle`](struct.DbHandle.html) wraps a database in a cloneable handle for multi-threaded apps, \
+//! like the state of a web service: reads run concurrently, writes run one at a time, \
+//! and [`shutdown()`](struct.DbHandle.html#method.shutdown) waits for in-flight writes before closing the database.
+//! during maintenance, [`freeze()`](struct.DbHandle.html#method.freeze) makes it read-only for every clone of the handle, without closing it.
+//! [`start_expiry_sweeper()`](struct.DbHandle.html#method.start_expiry_sweeper) removes the entries set with a time to live in the background.
+//! [`lock_table()`](struct.DbHandle.html#method.lock_table) keeps the other writers of the process away from a table across several writes.
+//! it doesn't depend on any framework.
+//!
+//! without it, a `Database` can still be shared between threads by reference: \
+//! reads, [`delete_table()`](struct.Database.html#method.delete_table), [`delete_tables_with_prefix()`](struct.Database.html#method.delete_tables_with_prefix), \
+//! [`delete_all_tables()`](struct.Database.html#method.delete_all_tables) and [`clear_table()`](struct.Database.html#method.clear_table) only need `&self`. \
+//! inserting and removing entries need `&mut self`, as do changes to table settings, and compacting the file (which redb requires).
+//!
+//! ## C API
+//! the `dbless-ffi` crate, in the `ffi` directory of the repository, exports a small C ABI working on raw MessagePack values \
+//! (`dbless_open`, `dbless_get_raw`, `dbless_set_raw`, `dbless_remove`, `dbless_list_tables`, ...), declared in `ffi/include/dbless.h`. \
+//! `cargo build --release -p dbless-ffi` builds it as a shared library, \
+//! so apps using dbless from rust don't build one.
+//!
+//! ## CSV
+//! with the `csv` feature enabled, [`export_table_csv()`](struct.Database.html#method.export_table_csv) \
+//! writes a table as CSV, a row per entry and a column per field of the stored struct.
+//!
+//! ## JSON
+//! values are stored as MessagePack by default. with the `json` feature enabled, \
+//! [`Options::format()`](struct.Options.html#method.format) can store them as JSON instead, \
+//! so the file can be inspected with other tools, at the cost of larger values and slower reads and writes. \
+//! the format is saved in the database, which is then read in that format even when opened without setting it. \
+//! [`export_json()`](struct.Database.html#method.export_json) dumps the whole database as a single JSON object, \
+//! and with the feature enabled, [`import_json()`](struct.Database.html#method.import_json) reads it back.
+//!
+//! ## Recovery
+//! with the `recovery` feature enabled, [`salvage()`](fn.salvage.html) copies whatever can still be read \
+//! from a damaged database file into another database, letting redb repair it first if needed, \
+//! and reports what was salvaged and lost table by table.
+//!
+//! ## Migrating from LMDB
+//! with the `lmdb-import` feature enabled, [`import_lmdb()`](struct.Database.html#method.import_lmdb) \
+//! copies the databases of an LMDB environment (like one managed with heed) into tables, \
+//! storing the values as raw bytes or as MessagePack, see [`ValueMode`](enum.ValueMode.html).
+//!
+//! ## Backups
+//! [`Database::backup_stream()`](struct.Database.html#method.backup_stream) writes the whole database as a single stream, \
+//! ready to be piped to a file or object storage, and [`Database::restore_stream()`](struct.Database.html#method.restore_stream) reads it back, \
+//! checking it's complete and intact before writing anything. \
+//! with the `zstd` feature enabled, the stream can be compressed with [`BackupCompression::Zstd`](enum.BackupCompression.html#variant.Zstd).
+//!
+//! ## Untrusted files
+//! reading a corrupted or hostile value returns an error, it never panics, overflows the stack, or allocates without bound: \
+//! values are checked against [`Options::max_depth()`](struct.Options.html#method.max_depth) \
+//! and [`Options::max_collection_len()`](struct.Options.html#method.max_collection_len) before they're deserialized or exported. \
+//! the fuzz targets in `fuzz/` check this, run them with `cargo +nightly fuzz run deserialize` (or `inspect`).
+//!
+//! ## Errors
+//! every method returns a [`dbless::Result`](type.Result.html), whose error is a [`DblessError`](enum.DblessError.html). \
+//! match on its variants to tell serialization, deserialization, storage and missing-table failures apart, \
+//! and use [`downcast_ref()`](enum.DblessError.html#method.downcast_ref) for specific failures like [`Frozen`](struct.Frozen.html) or [`ImmutableKey`](struct.ImmutableKey.html). \
+//! it converts into an `anyhow::Error` (or a `Box<dyn Error>`), so `?` works in apps using either.
+//! ```no_run
+//! # use dbless::{Database, DblessError, Frozen, TableWriteInterface};
+//! let mut db = Database::open("my_database.db")?;
+//! match db.set("key", &"value") {
+//!     Ok(()) => {}
+//!     Err(e) if e.downcast_ref::<Frozen>().is_some() => println!("read-only for now: {}", e),
+//!     Err(DblessError::Serialize(e)) => println!("can't store the value: {:#}", e),
+//!     Err(e) => return Err(e.into()),
+//! }
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+//!
+//! ## Value transforms
+//! values can go through a chain of [`ValueTransform`](trait.ValueTransform.html) stages before they're stored, \
+//! like compression, encryption, or signing, added with [`Options::transform()`](struct.Options.html#method.transform). \
+//! every value records which stages were applied to it, so databases written with different chains over time stay readable.
+//!
 //! ## About the default table
 //! Using methods from [`TableReadInterface`](trait.TableReadInterface.html) and [`TableWriteInterface`](trait.TableWriteInterface.html) directly on [`Database`](struct.Database.html) \
-//! uses a default table named `#_#_main_dbless_table_#_#`.
+//! uses a default table named `#_#_main_dbless_table_#_#`, [`Database::DEFAULT_TABLE_NAME`](struct.Database.html#associatedconstant.DEFAULT_TABLE_NAME) \
+//! (or `<prefix>main` when opened with [`Options::reserved_prefix()`](struct.Options.html#method.reserved_prefix)).
+//!
+//! these methods behave exactly like the same methods on [`default_table()`](struct.Database.html#method.default_table) \
+//! and [`default_table_mut()`](struct.Database.html#method.default_table_mut). \
+//! on every backend, [`keys()`](trait.TableReadInterface.html#method.keys), [`values()`](trait.TableReadInterface.html#method.values)
+//! and [`entries()`](trait.TableReadInterface.html#method.entries) are sorted by key, byte by byte, whichever table they're called on.
 //!
 //! calling [`clear()`](struct.Database.html#method.clear) or [`reset()`](struct.Database.html#method.reset) will only clear this table, not the entire database, \
 //! to clear the entire database, use [`delete_all_tables()`](struct.Database.html#method.delete_all_tables)
 //!
 //! similarly, calling [`len()`](struct.Database.html#method.len) or [`size()`](struct.Database.html#method.size) will only count the number of entries in this table, \
 //! to count the number of entries in the entire database, use [`len_all_tables()`](struct.Database.html#method.len_all_tables) or [`size_all_tables()`](struct.Database.html#method.size_all_tables).
+//!
+//! to move an app from the default table to named tables, check for old data with [`has_default_table_data()`](struct.Database.html#method.has_default_table_data) \
+//! and move it with [`migrate_default_table_to()`](struct.Database.html#method.migrate_default_table_to), \
+//! or spread it over several tables by key prefix with [`split_default_table_by_prefix()`](struct.Database.html#method.split_default_table_by_prefix).
 
 mod store;
-use std::path::Path;
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::ops::RangeBounds;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use store::Store;
 
+mod annotation;
+mod backup;
+mod batch;
+mod chain;
+mod envelope;
+mod error;
+mod escape;
+mod export;
+mod field;
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod fuzzing;
+mod group;
+#[cfg(feature = "lmdb-import")]
+mod lmdb;
+mod manifest;
+mod msgpack;
+mod options;
+#[cfg(feature = "recovery")]
+mod recovery;
+mod reserved;
+mod serde;
+#[cfg(feature = "service")]
+mod service;
 mod table;
+#[cfg(feature = "chrono")]
+mod timestamp;
+mod transaction;
+mod transform;
+mod typed;
+mod value;
 
 #[cfg(test)]
 mod tests;
 
-pub use table::{Table, TableMut, TableReadInterface, TableWriteInterface};
+pub use annotation::{ReservedKind, TableInfo, TableKind};
+pub use backup::{BackupCompression, BackupOpts, BackupSummary, RestoreMode};
+pub use batch::WriteBatch;
+pub use chain::{AppendOnlyViolation, ChainReport, ImmutableKey};
+pub use error::{DblessError, Result};
+pub use escape::{escape_key, unescape_key};
+pub use export::ExportFormat;
+pub use field::{FieldPredicate, FieldValue};
+pub use group::{TableGroup, TableGroupMut};
+#[cfg(feature = "lmdb-import")]
+pub use lmdb::{ImportReport, ImportedDatabase, ValueMode};
+pub use manifest::{Manifest, ManifestDiff, TableManifest, MANIFEST_VERSION};
+pub use options::{Options, SerializationFormat, SerializeErrorPolicy};
+#[cfg(feature = "recovery")]
+pub use recovery::{salvage, SalvageReport, SalvageStrategy, SalvagedTable};
+pub use redb::{StorageBackend, TableStats};
+pub use serde::{serialized_size, LossyNumberError};
+#[cfg(feature = "service")]
+pub use service::{DbHandle, HandleShutDown, SweeperHandle, TableLockGuard, TableLocked};
+pub use store::{EntriesIter, Frozen, KeysIter, WriteVerificationFailed};
+pub use table::{
+    CappedLen, ConvertProgress, ConvertReport, EvictionPolicy, KeyedResult, LoadTiming, Lookup,
+    RemoveReport, SplitReport, Table, TableLimit, TableMut, TableReadInterface,
+    TableWriteInterface, Versioned, VersionedOutcome,
+};
+#[cfg(feature = "chrono")]
+pub use timestamp::TimestampKey;
+pub use transaction::{ReadTransaction, ReadTransactionTable, Transaction, TransactionTable};
+pub use transform::ValueTransform;
+pub use typed::{TypedTable, TypedTableMut};
+pub use value::Value;
 
-use anyhow::Result;
-use serde::{de::DeserializeOwned, Serialize};
+use ::serde::{de::DeserializeOwned, Serialize};
+use anyhow::ensure;
+#[cfg(feature = "json")]
+use anyhow::Context;
+#[cfg(feature = "json")]
+use export::DumpEntry;
 
 const DEFAULT_DEFAULT_TABLE: &str = "#_#_main_dbless_table_#_#";
+const HEALTH_TABLE: &str = "#_#_health_dbless_table_#_#";
+const ALIASES_TABLE: &str = "#_#_aliases_dbless_table_#_#";
+const LIMITS_TABLE: &str = "#_#_limits_dbless_table_#_#";
+const ESCAPED_TABLE: &str = "#_#_escaped_dbless_table_#_#";
+const CHAINED_TABLE: &str = "#_#_chained_dbless_table_#_#";
+const DEDUPLICATED_TABLE: &str = "#_#_deduplicated_dbless_table_#_#";
+const ANNOTATIONS_TABLE: &str = "#_#_annotations_dbless_table_#_#";
+const WRITE_ONCE_TABLE: &str = "#_#_write_once_dbless_table_#_#";
+const METADATA_TABLE: &str = "#_#_metadata_dbless_table_#_#";
+// followed by the name of the limited table
+const ORDER_TABLE_PREFIX: &str = "#_#_order_dbless_table_#_#:";
+const STAMPS_TABLE_PREFIX: &str = "#_#_stamps_dbless_table_#_#:";
+// followed by the name of the append-only table
+const CHAIN_TABLE_PREFIX: &str = "#_#_chain_dbless_table_#_#:";
+// followed by the name of the deduplicated table
+const BLOBS_TABLE_PREFIX: &str = "#_#_blobs_dbless_table_#_#:";
+
+const MOVE_CHUNK_SIZE: usize = 10_000;
 
 /// A Database
 pub struct Database {
     store: Store,
     default_table: String,
+    // declared after `store`, so the file is closed before it gets deleted
+    temp_file: Option<TempFile>,
+}
+
+// deletes the file of a database opened with `Database::open_temp()` when dropped
+struct TempFile(PathBuf);
+
+/// a unique path in the system's temporary directory
+fn temp_path() -> anyhow::Result<PathBuf> {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    let name = format!(
+        "dbless-{}-{}-{}.redb",
+        std::process::id(),
+        nanos,
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    );
+    Ok(std::env::temp_dir().join(name))
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
 }
 
 impl Database {
+    /// The name of the table used by [`default_table()`](#method.default_table) \
+    /// until [`set_default_table()`](#method.set_default_table) is called, \
+    /// unless [`Options::reserved_prefix()`](struct.Options.html#method.reserved_prefix) is set, which names it `<prefix>main` instead.
+    pub const DEFAULT_TABLE_NAME: &'static str = DEFAULT_DEFAULT_TABLE;
+
+    fn from_store(store: Store) -> Self {
+        Database {
+            default_table: store.reserved().default_table.clone(),
+            store,
+            temp_file: None,
+        }
+    }
+
     /// Opens a file at the given path and uses it as the database. \
     /// If the file doesn't exist, it will be created.
     /// ```no_run
@@ -152,10 +389,18 @@ impl Database {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn open(path: impl AsRef<Path>) -> Result<Self> {
-        Ok(Database {
-            store: Store::file(path)?,
-            default_table: String::from(DEFAULT_DEFAULT_TABLE),
-        })
+        Self::open_with_options(path, Options::default())
+    }
+
+    /// Opens a file at the given path and uses it as the database, with the given options. \
+    /// If the file doesn't exist, it will be created.
+    /// ```no_run
+    /// # use dbless::{Database, Options};
+    /// let db = Database::open_with_options("my_database.db", Options::new())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_with_options(path: impl AsRef<Path>, options: Options) -> Result<Self> {
+        Ok(Self::from_store(Store::file(path, options)?))
     }
 
     /// Opens an in-memory database. \
@@ -166,10 +411,102 @@ impl Database {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn in_memory() -> Result<Self> {
-        Ok(Database {
-            store: Store::in_memory()?,
-            default_table: String::from(DEFAULT_DEFAULT_TABLE),
-        })
+        Self::in_memory_with_options(Options::default())
+    }
+
+    /// Opens an in-memory database that lists keys in a shuffled order, the same for the same `seed`, instead of sorted. \
+    /// a testing tool, to catch code that assumes the order of [`keys()`](trait.TableReadInterface.html#method.keys), \
+    /// [`values()`](trait.TableReadInterface.html#method.values), [`entries()`](trait.TableReadInterface.html#method.entries) \
+    /// and the other methods listing keys or values of a whole table (or all the keys with a prefix), while keeping test runs reproducible. \
+    /// methods walking a range of keys in order, like [`keys_after()`](trait.TableReadInterface.html#method.keys_after) \
+    /// and [`keys_page()`](trait.TableReadInterface.html#method.keys_page), stay sorted.
+    /// ```
+    /// # use dbless::{Database, TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::in_memory_seeded(42)?;
+    /// db.set_many(&[("a", &1), ("b", &2), ("c", &3)])?;
+    /// let mut keys = db.keys()?;
+    /// keys.sort(); // don't rely on the order
+    /// assert_eq!(keys, ["a", "b", "c"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn in_memory_seeded(seed: u64) -> Result<Self> {
+        let options = Options {
+            shuffle_seed: Some(seed),
+            ..Options::default()
+        };
+        Self::in_memory_with_options(options)
+    }
+
+    /// Opens an in-memory database with the given options.
+    /// ```no_run
+    /// # use dbless::{Database, Options};
+    /// let db = Database::in_memory_with_options(Options::new())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn in_memory_with_options(options: Options) -> Result<Self> {
+        Ok(Self::from_store(Store::in_memory(options)?))
+    }
+
+    /// Opens a new database in a uniquely named file in the system's temporary directory, \
+    /// the file is deleted when the database is dropped. \
+    /// Useful for tests and scratch work that should go through the same file backed path as a real database.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open_temp()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_temp() -> Result<Self> {
+        Self::open_temp_with_options(Options::default())
+    }
+
+    /// Opens a new temporary database with the given options, see [`open_temp()`](#method.open_temp)
+    /// ```no_run
+    /// # use dbless::{Database, Options};
+    /// let db = Database::open_temp_with_options(Options::new())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_temp_with_options(options: Options) -> Result<Self> {
+        let path = temp_path()?;
+        let temp_file = TempFile(path.clone());
+        let mut db = Self::open_with_options(&path, options)?;
+        db.temp_file = Some(temp_file);
+        Ok(db)
+    }
+
+    /// Opens a database stored in a custom redb [`StorageBackend`](trait.StorageBackend.html), \
+    /// like an encrypted volume, or a test harness.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let backend = redb::backends::InMemoryBackend::new();
+    /// let db = Database::open_with_backend(backend)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_with_backend(backend: impl StorageBackend) -> Result<Self> {
+        Self::open_with_backend_and_options(backend, Options::default())
+    }
+
+    /// Opens a database stored in a custom redb [`StorageBackend`](trait.StorageBackend.html), with the given options.
+    /// ```no_run
+    /// # use dbless::{Database, Options};
+    /// let backend = redb::backends::InMemoryBackend::new();
+    /// let db = Database::open_with_backend_and_options(backend, Options::new())?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn open_with_backend_and_options(
+        backend: impl StorageBackend,
+        options: Options,
+    ) -> Result<Self> {
+        Ok(Self::from_store(Store::with_backend(backend, options)?))
+    }
+
+    /// Returns the default [`Options`](struct.Options.html), to open a database with custom options.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::options().open("my_database.db")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn options() -> Options {
+        Options::default()
     }
 
     /// Closes the database
@@ -184,6 +521,61 @@ impl Database {
         drop(self);
     }
 
+    /// Closes the database, first compacting it if it was opened with \
+    /// [`Options::auto_compact_on_close()`](struct.Options.html#method.auto_compact_on_close) and is fragmented enough. \
+    /// unlike [`close()`](#method.close), it returns the errors of the work done on close. \
+    /// the database is closed even if compacting fails.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::options().auto_compact_on_close(0.25).open("my_database.db")?;
+    /// db.close_checked()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn close_checked(mut self) -> Result<()> {
+        let result = self.store.compact_on_close();
+        self.store.context("", result)
+    }
+
+    /// Rewrites the database file to release the space lost to fragmentation, \
+    /// and returns whether anything was released. \
+    /// takes about as long as copying the file, see [`fragmentation()`](#method.fragmentation) to decide when it's worth it.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// if db.fragmentation()? > 0.5 {
+    ///     db.compact()?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn compact(&mut self) -> Result<bool> {
+        let result = self.store.compact();
+        self.store.context("", result)
+    }
+
+    /// Returns the share of the database's allocated space that's lost to fragmentation, from 0 to 1.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// println!("{:.0}% fragmented", db.fragmentation()? * 100.0);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn fragmentation(&self) -> Result<f64> {
+        self.store.context("", self.store.fragmentation())
+    }
+
+    /// Sets a function that builds a context message attached (with [`anyhow::Context`]) to every error returned by the database, \
+    /// to tell apart errors from different databases in the same process. \
+    /// it's called with the name of the table being accessed, or an empty string for operations on the whole database.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("users.db")?;
+    /// db.with_error_context(|table| format!("while accessing user-db (table `{}`)", table));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn with_error_context(&mut self, f: fn(&str) -> String) {
+        self.store.set_error_context(f);
+    }
+
     /// Get a read-only handle to a table with the given name.
     /// ```no_run
     /// # use dbless::Database;
@@ -196,7 +588,7 @@ impl Database {
     pub fn table<'a>(&'a self, name: &'a str) -> Table<'a> {
         Table {
             store: &self.store,
-            name,
+            name: name.into(),
         }
     }
 
@@ -211,12 +603,40 @@ impl Database {
     pub fn table_mut<'a>(&'a mut self, name: &'a str) -> TableMut<'a> {
         TableMut {
             store: &mut self.store,
-            name,
+            name: name.into(),
         }
     }
 
+    /// Get a read-only handle to a table whose values are all of type `T`, \
+    /// see [`TypedTable`](struct.TypedTable.html).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let scores = db.typed_table::<u32>("scores");
+    /// let best = scores.values()?.into_iter().max();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn typed_table<'a, T>(&'a self, name: &'a str) -> TypedTable<'a, T> {
+        TypedTable::new(self.table(name))
+    }
+
+    /// Get a read-write handle to a table whose values are all of type `T`, \
+    /// see [`TypedTableMut`](struct.TypedTableMut.html).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut scores = db.typed_table_mut::<u32>("scores");
+    /// scores.update("alice", |score| score.unwrap_or(0) + 10)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn typed_table_mut<'a, T>(&'a mut self, name: &'a str) -> TypedTableMut<'a, T> {
+        TypedTableMut::new(self.table_mut(name))
+    }
+
     /// Returns a list of the names of all tables in the database. \
-    /// This list does not include the default table.
+    /// This list does not include the default table, the tables dbless uses internally \
+    /// or the ones created in the same file by other programs, \
+    /// see [`list_tables_detailed()`](#method.list_tables_detailed) to get them too.
     /// ```no_run
     /// # use dbless::Database;
     /// let db = Database::open("my_database.db")?;
@@ -227,26 +647,593 @@ impl Database {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn list_tables(&self) -> Result<Vec<String>> {
-        Ok(self
+        self.store
+            .context("", self.store.user_tables(&self.default_table))
+    }
+
+    /// Drops the table names and lengths cached with [`Options::cache_table_names()`](struct.Options.html#method.cache_table_names) \
+    /// and [`Options::cache_table_lengths()`](struct.Options.html#method.cache_table_lengths), \
+    /// so the next reads see the changes made by another process (or another `Database` opened on the same file). \
+    /// writes through this database already drop them, and this does nothing if neither cache is enabled.
+    /// ```no_run
+    /// # use dbless::{Database, TableReadInterface};
+    /// let db = Database::options().cache_table_lengths(true).open("my_database.db")?;
+    /// // ... the file is written by another process
+    /// db.drop_caches();
+    /// println!("{} entries", db.len()?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn drop_caches(&self) {
+        self.store.drop_caches();
+    }
+
+    /// Checks if a table is listed by [`list_tables()`](#method.list_tables), \
+    /// without collecting the list: the default table, aliases and internal tables are never found. \
+    /// see [`Options::cache_table_names()`](struct.Options.html#method.cache_table_names) to answer repeated checks from memory.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// if db.contains_table("drafts")? {
+    ///     println!("you have drafts");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn contains_table(&self, name: &str) -> Result<bool> {
+        if name == self.default_table || self.store.reserved().is_reserved(name) {
+            return Ok(false);
+        }
+        let result = self.store.contains_table(name, &self.default_table);
+        self.store.context(name, result)
+    }
+
+    /// Returns the names of all tables starting with `prefix`, \
+    /// with the prefix removed if `strip_prefix` is set. \
+    /// like [`list_tables()`](#method.list_tables), this list does not include the default table.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// for session in db.list_tables_with_prefix("session:", true)? {
+    ///     println!("session {}", session);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn list_tables_with_prefix(&self, prefix: &str, strip_prefix: bool) -> Result<Vec<String>> {
+        let result = self
             .store
-            .list_tables()?
+            .user_tables_with_prefix(prefix, &self.default_table);
+        let tables = self.store.context("", result)?;
+        if !strip_prefix {
+            return Ok(tables);
+        }
+        Ok(tables
             .into_iter()
-            .filter(|t| t != &self.default_table)
+            .map(|t| t[prefix.len()..].to_string())
             .collect())
     }
 
-    /// Deletes a table from the database.
+    /// Writes every entry of the table `name` to `writer` in the given format, \
+    /// straight from the stored bytes without deserializing values into rust types, \
+    /// and returns the number of bytes written. \
+    /// [`MessagePack`](enum.ExportFormat.html#variant.MessagePack) is close to a plain copy, \
+    /// [`Json`](enum.ExportFormat.html#variant.Json) transcodes every value. \
+    /// the whole table is read in a single read transaction.
+    /// ```no_run
+    /// # use dbless::{Database, ExportFormat};
+    /// let db = Database::open("my_database.db")?;
+    /// let file = std::fs::File::create("users.json")?;
+    /// let written = db.stream_table_to("users", std::io::BufWriter::new(file), ExportFormat::Json)?;
+    /// println!("wrote {} bytes", written);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn stream_table_to<W: Write>(
+        &self,
+        name: &str,
+        writer: W,
+        format: ExportFormat,
+    ) -> Result<u64> {
+        self.store
+            .context(name, self.store.stream_table_to(name, writer, format))
+    }
+
+    /// Writes every entry of the table `name` to `writer` as CSV, for spreadsheets and other tools. \
+    /// each value is read as a `T`, and becomes a row with its key in the first column (`key`), \
+    /// followed by a column per field of the struct, or a single `value` column for anything else. \
+    /// the header row is taken from the first entry, and an empty table only gets a `key` header.
+    ///
+    /// fields must hold plain values (numbers, strings, booleans, or `None` which becomes an empty cell), \
+    /// a nested list, map or struct is an error: flatten it with `#[serde(flatten)]`, or skip it with `#[serde(skip)]`. \
+    /// a value that isn't a `T` is an error too, and so is a value with other fields than the first one. \
+    /// the whole table is read in a single read transaction.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// #[derive(serde::Serialize, serde::Deserialize)]
+    /// struct User {
+    ///     name: String,
+    ///     age: u32,
+    /// }
+    ///
+    /// let db = Database::open("my_database.db")?;
+    /// let file = std::fs::File::create("users.csv")?;
+    /// db.export_table_csv::<User, _>("users", std::io::BufWriter::new(file))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "csv")]
+    pub fn export_table_csv<T: Serialize + DeserializeOwned, W: Write>(
+        &self,
+        name: &str,
+        writer: W,
+    ) -> Result<()> {
+        self.store
+            .context(name, self.store.export_table_csv::<T>(name, writer))
+    }
+
+    /// Writes every table of the database (including the default table) to `writer` as a single JSON object, \
+    /// mapping each table name to an object of its keys and values, all under a single snapshot, \
+    /// to be read back with [`import_json()`](#method.import_json). \
+    /// the tables dbless uses internally and the foreign ones (see [`TableKind`](enum.TableKind.html)) are left out. \
+    /// values are transcoded straight from the stored bytes, like [`stream_table_to()`](#method.stream_table_to) does, \
+    /// and a key or a value that can't be decoded is an error, not skipped.
+    ///
+    /// values carrying metadata are written as an object of `$` fields, so [`import_json()`](#method.import_json) can restore it: \
+    /// `{"$value": .., "$tag": "..", "$expires_at": ..}` for a value with a [type tag](trait.TableWriteInterface.html#method.set_tagged) \
+    /// or a [time to live](trait.TableWriteInterface.html#method.set_with_ttl) (its expiry, in milliseconds since the unix epoch), \
+    /// `{"$counter": ..}` for a [`u64` counter](trait.TableWriteInterface.html#method.set_u64), \
+    /// and `{"$binary": ".."}` or `{"$raw": ".."}` with the bytes as hex for a binary value \
+    /// or [raw bytes](trait.TableWriteInterface.html#method.set_raw). \
+    /// a value that is itself an object of only such fields is wrapped in `{"$value": ..}`. \
+    /// what the dump loses: versions (imported values start over at version 1 if versions are tracked), \
+    /// binary values nested in a map or an array (written as arrays of bytes), \
+    /// non-string map keys (written as strings holding their JSON), and non-finite floats (written as `null`).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let file = std::fs::File::create("dump.json")?;
+    /// db.export_json(std::io::BufWriter::new(file))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn export_json(&self, writer: impl Write) -> Result<()> {
+        let result = self.store.export_json(writer, &self.default_table);
+        self.store.context("", result)
+    }
+
+    /// Reads a JSON dump written by [`export_json()`](#method.export_json) from `reader`, \
+    /// and inserts its entries into their tables in a single write transaction, creating the tables that don't exist. \
+    /// entries already in the database under other keys are kept, and nothing is written if any of the tables is \
+    /// one dbless uses internally or a foreign one. \
+    /// values are stored as they were parsed, so a number is read back as any number type it fits in, \
+    /// along with the metadata the dump kept for them (see [`export_json()`](#method.export_json)).
     /// ```no_run
     /// # use dbless::Database;
     /// let mut db = Database::open("my_database.db")?;
-    /// db.delete_table("my_table")?;
+    /// let file = std::fs::File::open("dump.json")?;
+    /// db.import_json(std::io::BufReader::new(file))?;
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn delete_table(&mut self, name: &str) -> Result<()> {
-        self.store.delete_table(name)
+    #[cfg(feature = "json")]
+    pub fn import_json(&mut self, reader: impl Read) -> Result<()> {
+        type Dump = BTreeMap<String, BTreeMap<String, serde_json::Value>>;
+        let tables: anyhow::Result<Dump> = serde_json::from_reader(reader).map_err(Into::into);
+        let tables = self.store.context("", tables)?;
+        for name in tables.keys() {
+            let check = || -> anyhow::Result<()> {
+                let kind = self.store.table_kind_of(name, &self.default_table)?;
+                ensure!(
+                    matches!(
+                        kind,
+                        TableKind::User | TableKind::Reserved(ReservedKind::Default)
+                    ),
+                    "table `{}` is reserved by dbless or foreign, it can't be imported",
+                    name
+                );
+                Ok(())
+            };
+            self.store.context(name, check())?;
+        }
+        let tnx = self.store.begin_transaction()?;
+        for (name, entries) in tables {
+            for (key, value) in entries {
+                let result = DumpEntry::parse(value)
+                    .with_context(|| format!("invalid value for key `{}`", key))
+                    .and_then(|entry| self.store.insert_dump_in(&tnx, &name, &key, entry));
+                self.store.context(&name, result)?;
+            }
+        }
+        self.store.commit_transaction(tnx)
     }
 
-    /// Returns the number of entries in all tables in the database. \
+    /// Copies the named databases of the LMDB environment at `env_path` into the tables of the same names, \
+    /// creating the tables that don't exist and overwriting the keys already in them. \
+    /// the environment is opened read-only and read under a single snapshot, \
+    /// and the entries are written in chunks of 10 000, each in its own write transaction, \
+    /// so on an error, the chunks written before it are kept. \
+    /// `value_mode` tells how the values are stored, see [`ValueMode`](enum.ValueMode.html). \
+    /// keys that aren't valid UTF-8 are escaped (see [`ImportedDatabase::escaped_keys`](struct.ImportedDatabase.html#structfield.escaped_keys)), \
+    /// and nothing is imported if any of the tables is one dbless uses internally or a foreign one. \
+    /// requires the `lmdb-import` feature, which builds the LMDB C library.
+    /// ```no_run
+    /// # use dbless::{Database, ValueMode};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let report = db.import_lmdb("data.mdb", &["users", "sessions"], ValueMode::RawBytes)?;
+    /// for imported in &report.databases {
+    ///     println!("{}: {} entries", imported.name, imported.imported);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    #[cfg(feature = "lmdb-import")]
+    pub fn import_lmdb(
+        &mut self,
+        env_path: impl AsRef<Path>,
+        db_names: &[&str],
+        value_mode: ValueMode,
+    ) -> Result<ImportReport> {
+        let path = env_path.as_ref();
+        let result = lmdb::import(self, path, db_names, value_mode, MOVE_CHUNK_SIZE);
+        self.store.context("", result)
+    }
+
+    /// Converts every value of the database to the format `to`, then saves `to` as the format of the database, \
+    /// see [`Options::format()`](struct.Options.html#method.format). \
+    /// values are transcoded straight from the stored bytes, without going through a Rust type, \
+    /// and keep their versions, type tags and expiry. counters and [raw bytes](trait.TableWriteInterface.html#method.set_raw) are stored the same way in every format and are left as they are. \
+    /// the values are converted in chunks of 10 000, each in its own write transaction, \
+    /// and `progress` is called after each of them. \
+    /// an interrupted conversion (by an error or a crash) is resumed by calling this again with the same format, \
+    /// until then, the values already converted can't be read correctly. \
+    /// append-only tables can't be converted, since their hash chain covers the stored values.
+    /// ```no_run
+    /// # #[cfg(feature = "json")] {
+    /// # use dbless::{Database, SerializationFormat};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let report = db.convert_codec(
+    ///     SerializationFormat::Json,
+    ///     Some(&mut |progress| println!("{} values converted", progress.converted)),
+    /// )?;
+    /// println!("converted {} values", report.converted);
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn convert_codec(
+        &mut self,
+        to: SerializationFormat,
+        progress: Option<&mut dyn FnMut(ConvertProgress)>,
+    ) -> Result<ConvertReport> {
+        let result = match progress {
+            Some(progress) => {
+                self.store
+                    .convert_format(to, &self.default_table, MOVE_CHUNK_SIZE, progress)
+            }
+            None => self
+                .store
+                .convert_format(to, &self.default_table, MOVE_CHUNK_SIZE, |_| {}),
+        };
+        self.store.context("", result)
+    }
+
+    /// Deletes every table whose name starts with `prefix` in a single write transaction, \
+    /// and returns the number of deleted tables. \
+    /// only the tables listed by [`list_tables_with_prefix()`](#method.list_tables_with_prefix) are deleted, \
+    /// so the default table (including the original one, even after [`set_default_table()`](#method.set_default_table)) \
+    /// and the tables dbless uses internally are never touched, even if their names match, \
+    /// which makes even an empty prefix safe: it deletes every other table. \
+    /// if any of them still has aliases (see [`alias_table()`](#method.alias_table)), nothing is deleted and an error is returned.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let deleted = db.delete_tables_with_prefix("user.42.")?;
+    /// println!("deleted {} tables", deleted);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn delete_tables_with_prefix(&self, prefix: &str) -> Result<usize> {
+        let mut tables = self.list_tables_with_prefix(prefix, false)?;
+        // listed once another default table is set, but still reserved
+        tables.retain(|t| t != &self.store.reserved().default_table);
+        self.store.context("", self.store.delete_tables(&tables))?;
+        Ok(tables.len())
+    }
+
+    /// Returns the number of entries in all tables whose name starts with `prefix`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let len = db.len_tables_with_prefix("user.42.")?;
+    /// println!("user 42 has {} entries", len);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn len_tables_with_prefix(&self, prefix: &str) -> Result<usize> {
+        let mut len = 0;
+        for table in self.list_tables_with_prefix(prefix, false)? {
+            len += self.store.context(&table, self.store.len(&table))?;
+        }
+        Ok(len)
+    }
+
+    /// Get a read-only view of the tables whose names start with `prefix`, \
+    /// see [`TableGroup`](struct.TableGroup.html).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let inbox = db.group("user.42.").table("inbox").keys()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn group<'a>(&'a self, prefix: &'a str) -> TableGroup<'a> {
+        TableGroup {
+            store: &self.store,
+            prefix,
+        }
+    }
+
+    /// Get a read-write view of the tables whose names start with `prefix`, \
+    /// see [`TableGroupMut`](struct.TableGroupMut.html).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.group_mut("user.42.").table_mut("inbox").set("message-1", &"hello")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn group_mut<'a>(&'a mut self, prefix: &'a str) -> TableGroupMut<'a> {
+        TableGroupMut {
+            store: &mut self.store,
+            prefix,
+        }
+    }
+
+    /// Renames the table `from` to `to`, in a single write transaction, \
+    /// so readers see either the old name or the new one, never both or neither. \
+    /// redb can't rename tables, so every entry is copied to the new name before the old table is deleted, \
+    /// taking time proportional to the size of the table. \
+    /// its settings (limits, key escaping, append-only chain, deduplication), description and annotations move with it.
+    ///
+    /// returns an error if `from` doesn't exist, if `to` already exists (see [`rename_table_overwriting()`](#method.rename_table_overwriting)), \
+    /// or if either of them is an alias or has aliases (see [`alias_table()`](#method.alias_table)). \
+    /// renaming a table to its own name does nothing.
+    /// ```no_run
+    /// # use dbless::{Database, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("users_tmp").set("alice", &"Alice")?;
+    /// db.rename_table("users_tmp", "users")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rename_table(&mut self, from: &str, to: &str) -> Result<()> {
+        let result = self.store.rename_table(from, to, false);
+        self.store.context(from, result)
+    }
+
+    /// Renames the table `from` to `to` like [`rename_table()`](#method.rename_table), \
+    /// replacing `to` (along with its settings, description and annotations) if it already exists, \
+    /// in the same write transaction, so readers never see `to` missing.
+    /// ```no_run
+    /// # use dbless::{Database, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("users_tmp").set("alice", &"Alice")?;
+    /// db.rename_table_overwriting("users_tmp", "users")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn rename_table_overwriting(&mut self, from: &str, to: &str) -> Result<()> {
+        let result = self.store.rename_table(from, to, true);
+        self.store.context(from, result)
+    }
+
+    /// Deletes a table from the database. \
+    /// deleting an alias (see [`alias_table()`](#method.alias_table)) only removes the alias, not the data, \
+    /// and deleting a table that still has aliases is an error. \
+    /// like the other ways to delete or clear tables, it only needs `&self`, \
+    /// so another thread can delete stale tables while this one keeps reading.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// std::thread::scope(|s| {
+    ///     s.spawn(|| db.delete_table("stale_sessions"));
+    ///     db.table("users").keys()
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn delete_table(&self, name: &str) -> Result<()> {
+        self.store.context(name, self.store.delete_table(name))
+    }
+
+    /// Removes every entry of a table, keeping its settings. \
+    /// same as [`TableMut::clear()`](struct.TableMut.html#method.clear), but only needs `&self`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// db.clear_table("cache")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn clear_table(&self, name: &str) -> Result<()> {
+        self.store.context(name, self.store.clear(name))
+    }
+
+    /// Makes `alias` another name for the table `target`, \
+    /// reads and writes through either name go to the same table. \
+    /// the alias is saved in the database, and shows up in [`list_aliases()`](#method.list_aliases) \
+    /// but not in [`list_tables()`](#method.list_tables). \
+    /// an alias can point to another alias, but not to itself (directly or through other aliases), \
+    /// and can't take the name of an existing table. \
+    /// remove it with [`delete_table()`](#method.delete_table).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("users").set("key", &"value")?;
+    /// db.alias_table("old_users", "users")?;
+    /// assert_eq!(db.table("old_users").get::<String>("key")?, Some("value".to_owned()));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn alias_table(&mut self, alias: &str, target: &str) -> Result<()> {
+        let check = || -> anyhow::Result<()> {
+            let reserved = self.store.reserved();
+            ensure!(
+                !reserved.is_reserved(alias) && !reserved.is_reserved(target),
+                "reserved tables can't be aliased"
+            );
+            Ok(())
+        };
+        let result = check().and_then(|_| self.store.alias_table(alias, target));
+        self.store.context(alias, result)
+    }
+
+    /// Returns every alias created with [`alias_table()`](#method.alias_table), mapped to its target.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// for (alias, target) in db.list_aliases()? {
+    ///     println!("{} -> {}", alias, target);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn list_aliases(&self) -> Result<BTreeMap<String, String>> {
+        Ok(self.store.list_aliases())
+    }
+
+    /// Returns every table in the database file, sorted by name, \
+    /// each with its [`TableKind`](enum.TableKind.html), number of entries, description and annotations, all under a single snapshot. \
+    /// the [`TableKind::User`](enum.TableKind.html#variant.User) tables are the ones [`list_tables()`](#method.list_tables) returns, \
+    /// the default table and the tables dbless uses internally are [`TableKind::Reserved`](enum.TableKind.html#variant.Reserved), \
+    /// and the tables created in the same file by other programs using redb are [`TableKind::Foreign`](enum.TableKind.html#variant.Foreign), \
+    /// with no entry count.
+    /// ```no_run
+    /// # use dbless::{Database, TableKind};
+    /// let db = Database::open("my_database.db")?;
+    /// for table in db.list_tables_detailed()? {
+    ///     if table.kind == TableKind::User {
+    ///         println!("{} ({:?} entries): {}", table.name, table.entries, table.description.unwrap_or_default());
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn list_tables_detailed(&self) -> Result<Vec<TableInfo>> {
+        let result = self.store.list_tables_detailed(&self.default_table);
+        self.store.context("", result)
+    }
+
+    /// Describes what the table is for, replacing its previous description. \
+    /// the description is saved in the database, shows up in [`list_tables_detailed()`](#method.list_tables_detailed) \
+    /// and the [`manifest()`](#method.manifest), and is removed along with the table. \
+    /// describing a table that doesn't exist yet creates it, empty.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_table_description("misc2", "leftover import batches, safe to delete after june")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_table_description(&mut self, table: &str, text: &str) -> Result<()> {
+        self.ensure_annotatable(table)?;
+        let result = self
+            .store
+            .update_notes(table, |notes| notes.description = Some(text.to_string()));
+        self.store.context(table, result)
+    }
+
+    /// Removes the description of the table, if it has one.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.remove_table_description("misc2")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_table_description(&mut self, table: &str) -> Result<()> {
+        self.ensure_annotatable(table)?;
+        let result = self
+            .store
+            .update_notes(table, |notes| notes.description = None);
+        self.store.context(table, result)
+    }
+
+    /// Gets the description set with [`set_table_description()`](#method.set_table_description), \
+    /// or `None` if the table has none.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// if let Some(description) = db.table_description("misc2")? {
+    ///     println!("misc2: {}", description);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn table_description(&self, table: &str) -> Result<Option<String>> {
+        let notes = self.store.context(table, self.store.table_notes(table))?;
+        Ok(notes.description)
+    }
+
+    /// Sets the annotation `key` of the table to `value`, \
+    /// free-form strings saved along with the [description](#method.set_table_description) \
+    /// and listed and removed the same way.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_table_annotation("events", "owner", "ingest-service")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_table_annotation(&mut self, table: &str, key: &str, value: &str) -> Result<()> {
+        self.ensure_annotatable(table)?;
+        let result = self.store.update_notes(table, |notes| {
+            notes.annotations.insert(key.to_string(), value.to_string());
+        });
+        self.store.context(table, result)
+    }
+
+    /// Removes the annotation `key` of the table, if it's set.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.remove_table_annotation("events", "owner")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_table_annotation(&mut self, table: &str, key: &str) -> Result<()> {
+        self.ensure_annotatable(table)?;
+        let result = self.store.update_notes(table, |notes| {
+            notes.annotations.remove(key);
+        });
+        self.store.context(table, result)
+    }
+
+    /// Gets every annotation of the table set with [`set_table_annotation()`](#method.set_table_annotation).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let owner = db.table_annotations("events")?.remove("owner");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn table_annotations(&self, table: &str) -> Result<BTreeMap<String, String>> {
+        let notes = self.store.context(table, self.store.table_notes(table))?;
+        Ok(notes.annotations)
+    }
+
+    fn ensure_annotatable(&self, table: &str) -> Result<()> {
+        let check = || -> anyhow::Result<()> {
+            let reserved = self.store.reserved();
+            ensure!(
+                table == reserved.default_table || !reserved.is_reserved(table),
+                "reserved table `{}` can't be annotated",
+                table
+            );
+            Ok(())
+        };
+        self.store.context(table, check())
+    }
+
+    /// Returns every `(table, key)` pair in the database (including the default table), \
+    /// sorted by table then key, all under a single snapshot. \
+    /// the tables dbless uses internally are left out, \
+    /// and so are [foreign](enum.TableKind.html#variant.Foreign) ones, like every method reading the whole database.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// for (table, key) in db.keys_all_tables()? {
+    ///     println!("{}/{}", table, key);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn keys_all_tables(&self) -> Result<Vec<(String, String)>> {
+        self.store
+            .context("", self.store.keys_all_tables(&self.default_table))
+    }
+
+    /// Returns the number of entries in all tables in the database, \
+    /// the user tables and the default table, as classified by [`list_tables_detailed()`](#method.list_tables_detailed) \
+    /// (with a custom [`Options::reserved_prefix()`](struct.Options.html#method.reserved_prefix), only the user tables). \
     /// aliases: [`size_all_tables()`](#method.size_all_tables)
     /// ```no_run
     /// # use dbless::Database;
@@ -256,7 +1243,25 @@ impl Database {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn len_all_tables(&self) -> Result<usize> {
-        self.store.len_all_tables()
+        self.store
+            .context("", self.store.len_all_tables(&self.default_table))
+    }
+
+    /// Counts the entries in all tables like [`len_all_tables()`](#method.len_all_tables), \
+    /// but stops as soon as `cap` entries were counted, without opening the remaining tables. \
+    /// returns [`CappedLen::AtLeast(cap)`](enum.CappedLen.html#variant.AtLeast) then, \
+    /// and [`CappedLen::Exact`](enum.CappedLen.html#variant.Exact) if the database holds fewer entries than that.
+    /// ```no_run
+    /// # use dbless::{CappedLen, Database};
+    /// let db = Database::open("my_database.db")?;
+    /// if db.len_all_tables_capped(1)? == CappedLen::Exact(0) {
+    ///     println!("the database is empty");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn len_all_tables_capped(&self, cap: usize) -> Result<CappedLen> {
+        let result = self.store.len_all_tables_capped(&self.default_table, cap);
+        self.store.context("", result)
     }
 
     /// Returns the number of entries in all tables in the database. \
@@ -272,16 +1277,243 @@ impl Database {
         self.len_all_tables()
     }
 
-    /// Deletes all tables in the database. \
+    /// Computes a digest of every table in the database (including the default table, but not the foreign ones), \
+    /// all under a single snapshot, see [`Table::digest()`](struct.Table.html#method.digest). \
+    /// two databases with equal digests for every table have identical content.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// for (table, digest) in db.digest_all()? {
+    ///     println!("{}: {:02x?}", table, digest);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn digest_all(&self) -> Result<BTreeMap<String, [u8; 32]>> {
+        self.store
+            .context("", self.store.digest_all(&self.default_table))
+    }
+
+    /// Checks if the tables `a` and `b` hold the same keys with byte-identical raw values, \
+    /// both read under a single snapshot. \
+    /// keys are compared as stored, so a table with [escaped keys](struct.TableMut.html#method.set_escape_keys) \
+    /// only equals another one with escaped keys. \
+    /// a table that doesn't exist is equal to an empty one.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// assert!(db.tables_equal("users", "users_backup")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn tables_equal(&self, a: &str, b: &str) -> Result<bool> {
+        self.store.context(a, self.store.tables_equal(a, b))
+    }
+
+    /// Returns the table, key and size of every value larger than `bytes` in the database (including the default table, but not the foreign ones), \
+    /// sorted by table then key, all under a single snapshot. \
+    /// see [`Table::keys_larger_than()`](struct.Table.html#method.keys_larger_than) for how sizes are measured.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// for (table, key, size) in db.large_values_report(1 << 20)? {
+    ///     println!("{}/{}: {} bytes", table, key, size);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn large_values_report(&self, bytes: usize) -> Result<Vec<(String, String, usize)>> {
+        self.store.context(
+            "",
+            self.store.large_values_report(bytes, &self.default_table),
+        )
+    }
+
+    /// Moves every entry of the table `from` for which `f` returns `true` to the table `to`, \
+    /// in a single write transaction, so an entry is never in both tables or in neither. \
+    /// values are moved as stored, `T` is only used to evaluate `f`, \
+    /// and entries that fail to deserialize as `T` stay where they are. \
+    /// returns the number of entries moved.
     /// ```no_run
     /// # use dbless::Database;
     /// let mut db = Database::open("my_database.db")?;
+    /// let moved = db.archive::<u64, _>("events", "old_events", |_, timestamp| *timestamp < 1_700_000_000)?;
+    /// println!("archived {} events", moved);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn archive<T, F>(&mut self, from: &str, to: &str, f: F) -> Result<usize>
+    where
+        T: DeserializeOwned,
+        F: FnMut(&str, &T) -> bool,
+    {
+        let result = self.store.archive(from, to, f);
+        self.store.context(from, result)
+    }
+
+    /// Builds a [`Manifest`](struct.Manifest.html) of the database: every table (including the default table, but not the foreign ones) \
+    /// with its entry count, size and digest, along with the storage format, codec, and enabled options. \
+    /// all tables are read under a single snapshot.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let manifest = db.manifest()?;
+    /// for (name, table) in &manifest.tables {
+    ///     println!("{}: {} entries, {} bytes", name, table.entries, table.bytes);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn manifest(&self) -> Result<Manifest> {
+        let tables = self
+            .store
+            .context("", self.store.summarize_all(&self.default_table))?;
+        Ok(self.manifest_of(tables))
+    }
+
+    /// the manifest of the database, given the summaries of its tables
+    fn manifest_of(&self, tables: BTreeMap<String, TableManifest>) -> Manifest {
+        let options = self.store.options();
+        let mut features = vec![];
+        if options.track_versions {
+            features.push("track_versions".to_string());
+        }
+        if options.strict_numbers {
+            features.push("strict_numbers".to_string());
+        }
+        for id in options.transforms.ids() {
+            features.push(format!("transform:{}", id));
+        }
+        Manifest {
+            manifest_version: MANIFEST_VERSION,
+            format_version: manifest::FORMAT_VERSION,
+            codec: self.codec().to_string(),
+            features,
+            tables,
+        }
+    }
+
+    /// the name of the codec values are serialized with, as written in the manifest
+    fn codec(&self) -> &'static str {
+        self.store.format().name()
+    }
+
+    /// Writes a backup of the whole database to `writer`, as a single stream, \
+    /// to be restored with [`restore_stream()`](#method.restore_stream). \
+    /// the stream starts with a header holding the [`manifest()`](#method.manifest) of the database, \
+    /// followed by sections of length-prefixed keys and values (each table split into sections of about 1 MiB, \
+    /// optionally compressed, see [`BackupOpts`](struct.BackupOpts.html)), \
+    /// and ends with a footer holding a SHA-256 checksum of everything before it. \
+    /// every table is read under a single snapshot, and only a section is held in memory at a time.
+    ///
+    /// values are written with their transforms reversed and their deduplicated blobs resolved, \
+    /// keys are written as stored (escaped in tables that escape keys). \
+    /// table settings (aliases, limits, key escaping, append-only chains, deduplication) aren't part of the backup, \
+    /// and neither are the [foreign](enum.TableKind.html#variant.Foreign) tables.
+    /// ```no_run
+    /// # use dbless::{BackupOpts, Database};
+    /// let db = Database::open("my_database.db")?;
+    /// let file = std::fs::File::create("my_database.backup")?;
+    /// let summary = db.backup_stream(std::io::BufWriter::new(file), BackupOpts::new())?;
+    /// println!("{} entries in {} bytes", summary.entries, summary.bytes);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn backup_stream(&self, writer: impl Write, opts: BackupOpts) -> Result<BackupSummary> {
+        let result =
+            backup::write_backup(&self.store, &self.default_table, writer, &opts, |tables| {
+                self.manifest_of(tables)
+            });
+        self.store.context("", result)
+    }
+
+    /// Restores a backup written by [`backup_stream()`](#method.backup_stream) from `reader`. \
+    /// the whole stream is read and validated before anything is written: \
+    /// its framing, its checksum, and the number of entries of each table against its manifest. \
+    /// a truncated or corrupted stream returns an error and leaves the database untouched. \
+    /// meanwhile, the entries are staged in a file in the system's temporary directory, \
+    /// then written in a single write transaction, going through the settings and transforms of this database.
+    ///
+    /// `mode` decides what happens to the tables already in the database, see [`RestoreMode`](enum.RestoreMode.html). \
+    /// the database must store values in the same format as the one the backup was taken from.
+    /// ```no_run
+    /// # use dbless::{Database, RestoreMode};
+    /// let mut db = Database::open("restored.db")?;
+    /// let file = std::fs::File::open("my_database.backup")?;
+    /// let summary = db.restore_stream(file, RestoreMode::Replace)?;
+    /// println!("restored {} tables", summary.tables);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn restore_stream(
+        &mut self,
+        reader: impl Read,
+        mode: RestoreMode,
+    ) -> Result<BackupSummary> {
+        let staged = backup::StagedBackup::read(reader, self.codec());
+        let staged = self.store.context("", staged)?;
+        let result = staged.apply(&mut self.store, mode);
+        self.store.context("", result)?;
+        Ok(staged.summary)
+    }
+
+    /// Deletes all tables in the database, along with the tables dbless uses internally. \
+    /// the [`TableKind::Foreign`](enum.TableKind.html#variant.Foreign) tables created in the same file by other programs are left alone.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
     /// db.delete_all_tables()?;
     /// assert!(db.list_tables()?.is_empty());
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn delete_all_tables(&mut self) -> Result<()> {
-        self.store.delete_all_tables()
+    pub fn delete_all_tables(&self) -> Result<()> {
+        self.store
+            .context("", self.store.delete_all_tables(&self.default_table))
+    }
+
+    /// Checks that the database is readable and writable, \
+    /// by writing a sentinel value to a reserved table, reading it back, and removing it. \
+    /// returns an error if any of these steps fails, or if the value read back doesn't match, \
+    /// including a [`Frozen`](struct.Frozen.html) error if the database is [frozen](#method.freeze).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.health_check()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn health_check(&mut self) -> Result<()> {
+        let result = self.store.health_check();
+        self.store.context("", result)
+    }
+
+    /// Makes the database read-only until [`unfreeze()`](#method.unfreeze) is called, without closing it. \
+    /// every write after this (setting, removing, clearing or deleting tables, changing table settings, compacting...) \
+    /// returns a [`Frozen`](struct.Frozen.html) error and writes nothing, reads keep working. \
+    /// reads of a table limited with [`EvictionPolicy::Lru`](enum.EvictionPolicy.html#variant.Lru) don't count as a use while frozen. \
+    /// a write already running when it's called finishes. \
+    /// see [`freeze_with_reason()`](#method.freeze_with_reason) to tell the callers why.
+    /// ```no_run
+    /// # use dbless::{Database, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.freeze();
+    /// assert!(db.set("key", &"value").is_err());
+    /// db.unfreeze();
+    /// db.set("key", &"value")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn freeze(&mut self) {
+        self.store.freeze(None);
+    }
+
+    /// Makes the database read-only like [`freeze()`](#method.freeze), \
+    /// with a reason carried by the [`Frozen`](struct.Frozen.html) errors, like "maintenance in progress". \
+    /// freezing a frozen database replaces the reason.
+    pub fn freeze_with_reason(&mut self, reason: &str) {
+        self.store.freeze(Some(reason.to_string()));
+    }
+
+    /// Makes a database frozen with [`freeze()`](#method.freeze) writable again, \
+    /// does nothing if it isn't frozen.
+    pub fn unfreeze(&mut self) {
+        self.store.unfreeze();
+    }
+
+    /// Checks if the database is frozen, see [`freeze()`](#method.freeze)
+    pub fn is_frozen(&self) -> bool {
+        self.store.is_frozen()
     }
 
     /// Get a read-only handle to the default table.
@@ -296,10 +1528,10 @@ impl Database {
     /// # let tmp: Option<String> = also_value;
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn default_table(&self) -> Table {
+    pub fn default_table(&self) -> Table<'_> {
         Table {
             store: &self.store,
-            name: &self.default_table,
+            name: self.default_table.as_str().into(),
         }
     }
 
@@ -311,10 +1543,94 @@ impl Database {
     /// db.default_table_mut().set("key", &"value")?;
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
-    pub fn default_table_mut(&mut self) -> TableMut {
+    pub fn default_table_mut(&mut self) -> TableMut<'_> {
         TableMut {
             store: &mut self.store,
-            name: &self.default_table,
+            name: self.default_table.as_str().into(),
+        }
+    }
+
+    /// Applies the operations staged in `batch` to the default table, in order, in a single write transaction. \
+    /// see [`TableMut::apply()`](struct.TableMut.html#method.apply)
+    /// ```no_run
+    /// # use dbless::{Database, WriteBatch};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut batch = WriteBatch::new();
+    /// batch.insert("session", &"a1b2")?;
+    /// batch.remove("old_session");
+    /// db.apply(batch)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn apply(&mut self, batch: WriteBatch) -> Result<()> {
+        self.default_table_mut().apply(batch)
+    }
+
+    /// Runs `f` with a consistent snapshot of the database, \
+    /// see [`ReadTransaction`](struct.ReadTransaction.html). \
+    /// all the reads made through it come from a single redb read transaction, \
+    /// so writes made in the meantime (by another thread, or a `DbHandle`) can't land between them, \
+    /// and only one transaction is opened for all of them. \
+    /// nothing is written either: the keys read from a table limited with [`EvictionPolicy::Lru`](enum.EvictionPolicy.html#variant.Lru) \
+    /// don't count as a use, so they keep their place in the eviction order.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let sessions = db.read_transaction(|snapshot| {
+    ///     let sessions = snapshot.table("sessions");
+    ///     let mut found = vec![];
+    ///     for key in sessions.keys()? {
+    ///         found.push((key.clone(), sessions.get::<String>(&key)?));
+    ///     }
+    ///     Ok(found)
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn read_transaction<R>(&self, f: impl FnOnce(&ReadTransaction) -> Result<R>) -> Result<R> {
+        let tnx = self.store.begin_snapshot()?;
+        f(&ReadTransaction {
+            store: &self.store,
+            tnx,
+        })
+    }
+
+    /// Runs `f` inside a single write transaction spanning any number of tables, \
+    /// see [`Transaction`](struct.Transaction.html). \
+    /// the transaction is committed if `f` returns `Ok`, and rolled back if it returns an error (which is then returned) or panics, \
+    /// in which case none of the tables are changed. \
+    /// it's also rolled back, returning an error, if any write through its tables failed, even if `f` ignored the error. \
+    /// other writes wait for the transaction to end, so `f` should be quick.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let order = db.transaction(|tx| {
+    ///     let mut counters = tx.table_mut("counters");
+    ///     let order = counters.get::<u64>("orders")?.unwrap_or(0) + 1;
+    ///     counters.set("orders", &order)?;
+    ///     tx.table_mut("log").set(&format!("order-{}", order), &"created")?;
+    ///     Ok(order)
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn transaction<R>(&mut self, f: impl FnOnce(&mut Transaction) -> Result<R>) -> Result<R> {
+        let tnx = self.store.begin_transaction()?;
+        let mut transaction = Transaction {
+            store: &self.store,
+            tnx,
+            failed: false,
+        };
+        match f(&mut transaction) {
+            Ok(_) if transaction.failed => {
+                self.store.abort_transaction(transaction.tnx)?;
+                Err(anyhow::anyhow!("a write of the transaction failed, it was rolled back").into())
+            }
+            Ok(result) => {
+                self.store.commit_transaction(transaction.tnx)?;
+                Ok(result)
+            }
+            Err(e) => {
+                self.store.abort_transaction(transaction.tnx)?;
+                Err(e)
+            }
         }
     }
 
@@ -327,26 +1643,146 @@ impl Database {
     /// ```
     pub fn set_default_table(&mut self, name: &str) {
         self.default_table = String::from(name);
+        // the cached table names leave out the default table
+        self.store.drop_caches();
+    }
+
+    /// Checks if the original default table holds any entries, \
+    /// even after [`set_default_table()`](#method.set_default_table) was called. \
+    /// useful to detect data written before an app moved to named tables.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// if db.has_default_table_data()? {
+    ///     println!("found data in the default table");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn has_default_table_data(&self) -> Result<bool> {
+        let name = &self.store.reserved().default_table;
+        Ok(self.store.context(name, self.store.len(name))? > 0)
+    }
+
+    /// Moves every entry of the original default table into the table `name`, \
+    /// in a single write transaction, leaving the default table empty. \
+    /// values are moved as stored, and entries already in `name` with the same keys are overwritten. \
+    /// returns the number of entries moved.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let moved = db.migrate_default_table_to("settings")?;
+    /// println!("moved {} entries out of the default table", moved);
+    /// db.set_default_table("settings");
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn migrate_default_table_to(&mut self, name: &str) -> Result<usize> {
+        let default_table = self.store.reserved().default_table.clone();
+        let check = || -> anyhow::Result<()> {
+            ensure!(
+                !self.store.reserved().is_reserved(name),
+                "can't migrate the default table into the reserved table `{}`",
+                name
+            );
+            Ok(())
+        };
+        let result = check().and_then(|_| self.store.move_all(&default_table, name));
+        self.store.context(&default_table, result)
+    }
+
+    /// Moves the entries of the original default table into tables picked by key prefix, \
+    /// each rule `(prefix, table)` sending the keys starting with `prefix` to `table`, \
+    /// the first matching rule winning. \
+    /// with `strip_prefix`, the prefix is removed from the moved keys. \
+    /// values are moved as stored, and entries already in a table with the same keys are overwritten.
+    ///
+    /// entries are moved in chunks, each chunk in its own write transaction, \
+    /// so the database stays usable during the migration, \
+    /// and running it again after an interruption picks up the entries that weren't moved yet.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let report = db.split_default_table_by_prefix(&[("cfg:", "config"), ("user:", "users")], true)?;
+    /// println!("moved {:?}, {} entries left in the default table", report.moved, report.unmatched);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn split_default_table_by_prefix(
+        &mut self,
+        rules: &[(&str, &str)],
+        strip_prefix: bool,
+    ) -> Result<SplitReport> {
+        let default_table = self.store.reserved().default_table.clone();
+        let check = || -> anyhow::Result<()> {
+            for (_, table) in rules {
+                ensure!(
+                    !self.store.reserved().is_reserved(table),
+                    "can't move entries into the reserved table `{}`",
+                    table
+                );
+            }
+            Ok(())
+        };
+        let result = check().and_then(|_| {
+            self.store
+                .move_routed(&default_table, MOVE_CHUNK_SIZE, false, |key| {
+                    let (prefix, table) =
+                        rules.iter().find(|(prefix, _)| key.starts_with(prefix))?;
+                    let key = match strip_prefix {
+                        true => &key[prefix.len()..],
+                        false => key,
+                    };
+                    Some((table.to_string(), key.to_string()))
+                })
+        });
+        let (moved, unmatched) = self.store.context(&default_table, result)?;
+        Ok(SplitReport { moved, unmatched })
+    }
+
+    /// Moves every entry of the table `name` into the original default table, with `prefix` added to its key, \
+    /// the inverse of [`split_default_table_by_prefix()`](#method.split_default_table_by_prefix). \
+    /// values are moved as stored, and entries of the default table with the same keys are overwritten. \
+    /// entries are moved in chunks, each chunk in its own write transaction, and the emptied table is deleted with the last one. \
+    /// returns the number of entries moved.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let moved = db.flatten_table_into_default("config", "cfg:")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn flatten_table_into_default(&mut self, name: &str, prefix: &str) -> Result<u64> {
+        let default_table = self.store.reserved().default_table.clone();
+        let check = || -> anyhow::Result<()> {
+            ensure!(
+                !self.store.reserved().is_reserved(name),
+                "can't flatten the reserved table `{}`",
+                name
+            );
+            Ok(())
+        };
+        let result = check().and_then(|_| {
+            self.store.move_routed(name, MOVE_CHUNK_SIZE, true, |key| {
+                Some((default_table.clone(), format!("{}{}", prefix, key)))
+            })
+        });
+        let (moved, _) = self.store.context(name, result)?;
+        Ok(moved.values().sum())
     }
 }
 
 macro_rules! mirror_methods_with {
-    {with .$fn:ident(...); $(fn $name:ident$(<$($gname:ident: $gty1:ident $(+$gtyr:ident)*),+>)?(&self $(,$pname:ident: $pty:ty)*) -> $ret:ty;)*} => {
+    {with .$fn:ident(); $(fn $name:ident$(<$($gname:ident: $gty1:ident $(+$gtyr:ident)*),+>)?(&self $(,$pname:ident: $pty:ty)*) -> $ret:ty;)*} => {
         $(
             fn $name$(<$($gname: $gty1$(+$gtyr)*),+>)?(&self, $($pname: $pty),*) -> $ret {
-                let table = &self.default_table;
-                self.$fn(table).$name($($pname),*)
+                self.$fn().$name$(::<$($gname),+>)?($($pname),*)
             }
         )*
     }
 }
 
 macro_rules! mirror_methods_mut_with {
-    {with .$fn:ident(...); $(fn $name:ident$(<$($gname:ident: $gty1:ident $(+$gtyr:ident)*),+>)?(&mut self $(,$pname:ident: $pty:ty)*) -> $ret:ty;)*} => {
+    {with .$fn:ident(); $(fn $name:ident$(<$($gname:ident: $gty1:ident $(+$gtyr:ident)*),+>)?(&mut self $(,$pname:ident: $pty:ty)*) -> $ret:ty;)*} => {
         $(
             fn $name$(<$($gname: $gty1$(+$gtyr)*),+>)?(&mut self, $($pname: $pty),*) -> $ret {
-                let table = &self.default_table.clone();
-                self.$fn(table).$name($($pname),*)
+                self.$fn().$name($($pname),*)
             }
         )*
     }
@@ -354,12 +1790,18 @@ macro_rules! mirror_methods_mut_with {
 
 impl TableReadInterface for Database {
     mirror_methods_with! {
-        with .table(...);
+        with .default_table();
         fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
+        fn get_many<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<Option<T>>> ;
+        fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> ;
+        fn get_all<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<KeyedResult<T>>> ;
         fn keys(&self) -> Result<Vec<String>> ;
+        fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> ;
+        fn entries_with_prefix<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<(String, T)>> ;
         fn values<T: DeserializeOwned>(&self) -> Result<Vec<T>> ;
         fn entries<T: DeserializeOwned>(&self) -> Result<Vec<(String, T)>> ;
         fn len(&self) -> Result<usize> ;
+        fn count_values<T: DeserializeOwned>(&self) -> Result<usize> ;
         fn is_empty(&self) -> Result<bool> ;
         fn contains_key(&self, key: &str) -> Result<bool> ;
         fn size(&self) -> Result<usize> ;
@@ -367,6 +1809,15 @@ impl TableReadInterface for Database {
         fn has(&self, key: &str) -> Result<bool> ;
         fn get_or<T: DeserializeOwned>(&self, key: &str, default: T) -> Result<T> ;
         fn get_or_default<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T> ;
+        fn version_of(&self, key: &str) -> Result<Option<u64>> ;
+        fn keys_after(&self, after: Option<&str>, limit: usize) -> Result<Vec<String>> ;
+        fn keys_page(&self, offset: usize, limit: usize) -> Result<Vec<String>> ;
+        fn entries_page<T: DeserializeOwned>(&self, offset: usize, limit: usize) -> Result<Vec<(String, T)>> ;
+        fn get_tagged(&self, key: &str) -> Result<Option<(String, Vec<u8>)>> ;
+        fn get_as<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> ;
+        fn get_escaped<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> ;
+        fn get_u64(&self, key: &str) -> Result<Option<u64>> ;
+        fn get_opt<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> ;
     }
 
     // current macro can't handle FnOnce() -> T
@@ -375,22 +1826,71 @@ impl TableReadInterface for Database {
         key: &str,
         default: F,
     ) -> Result<T> {
-        let table = &self.default_table;
-        self.table(table).get_or_else(key, default)
+        self.default_table().get_or_else(key, default)
+    }
+
+    fn find_key<F: FnMut(&str) -> bool>(&self, f: F) -> Result<Option<String>> {
+        self.default_table().find_key(f)
+    }
+
+    fn get_range<'r, T: DeserializeOwned>(
+        &self,
+        range: impl RangeBounds<&'r str>,
+    ) -> Result<Vec<(String, T)>> {
+        self.default_table().get_range(range)
+    }
+
+    // the iterators borrow the store, not the temporary `Table`
+    fn iter_keys(&self) -> Result<KeysIter<'_>> {
+        self.store.context(
+            &self.default_table,
+            self.store.iter_keys(&self.default_table),
+        )
+    }
+
+    fn iter_entries<T: DeserializeOwned>(&self) -> Result<EntriesIter<'_, T>> {
+        self.store.context(
+            &self.default_table,
+            self.store.iter_entries(&self.default_table),
+        )
+    }
+
+    fn entries_by_key_filter<T: DeserializeOwned, F: FnMut(&str) -> bool>(
+        &self,
+        f: F,
+    ) -> Result<Vec<(String, T)>> {
+        self.default_table().entries_by_key_filter(f)
     }
 }
 
 impl TableWriteInterface for Database {
     mirror_methods_mut_with! {
-        with .table_mut(...);
+        with .default_table_mut();
         fn insert<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()>;
         fn remove(&mut self, key: &str) -> Result<()>;
         fn clear(&mut self) -> Result<()>;
         fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()>;
         fn delete(&mut self, key: &str) -> Result<()>;
+        fn remove_many(&mut self, keys: &[&str]) -> Result<()>;
+        fn delete_many(&mut self, keys: &[&str]) -> Result<()>;
         fn reset(&mut self) -> Result<()>;
         fn get_or_insert<T: Serialize + DeserializeOwned>(&mut self, key: &str, default: T) -> Result<T>;
         fn get_or_insert_default<T: Serialize + DeserializeOwned + Default>(&mut self, key: &str) -> Result<T>;
+        fn insert_batch<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<Vec<String>>;
+        fn insert_many<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<()>;
+        fn set_many<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<()>;
+        fn set_raw(&mut self, key: &str, bytes: &[u8]) -> Result<()>;
+        fn set_if_version<T: Serialize>(&mut self, key: &str, value: &T, expected_version: u64) -> Result<bool>;
+        fn set_tagged<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()>;
+        fn set_with_ttl<T: Serialize>(&mut self, key: &str, value: &T, ttl: Duration) -> Result<()>;
+        fn purge_expired(&mut self) -> Result<usize>;
+        fn set_many_timed<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<(Vec<String>, LoadTiming)>;
+        fn update_versioned<T: Serialize + DeserializeOwned + Versioned + Clone>(&mut self, key: &str, expected_version: u64, new_value: &T) -> Result<VersionedOutcome>;
+        fn remove_if<T: DeserializeOwned + PartialEq>(&mut self, key: &str, expected: &T) -> Result<bool>;
+        fn insert_escaped<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()>;
+        fn set_u64(&mut self, key: &str, value: u64) -> Result<()>;
+        fn add_u64(&mut self, key: &str, delta: u64) -> Result<u64>;
+        fn set_opt<T: Serialize>(&mut self, key: &str, value: &Option<T>) -> Result<()>;
     }
 
     // current macro can't handle FnOnce() -> T
@@ -399,7 +1899,23 @@ impl TableWriteInterface for Database {
         key: &str,
         default: F,
     ) -> Result<T> {
-        let table = &self.default_table.clone();
-        self.table_mut(table).get_or_insert_with(key, default)
+        self.default_table_mut().get_or_insert_with(key, default)
+    }
+
+    fn get_or_insert_with_ttl<T: Serialize + DeserializeOwned, F: FnOnce() -> T>(
+        &mut self,
+        key: &str,
+        ttl: Duration,
+        f: F,
+    ) -> Result<T> {
+        self.default_table_mut().get_or_insert_with_ttl(key, ttl, f)
+    }
+
+    fn update<T, F>(&mut self, key: &str, f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<T>) -> T,
+    {
+        self.default_table_mut().update(key, f)
     }
 }
@@ -0,0 +1,124 @@
+use std::path::Path;
+
+use anyhow::{ensure, Context, Result};
+use heed::types::Bytes;
+use heed::{EnvFlags, EnvOpenOptions};
+
+use crate::error::Missing;
+use crate::escape::escape_bytes;
+use crate::{Database, ReservedKind, TableKind};
+
+/// How [`Database::import_lmdb()`](struct.Database.html#method.import_lmdb) stores the values it reads
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueMode {
+    /// The values are stored as they are, \
+    /// to be read back with [`get_raw()`](trait.TableReadInterface.html#method.get_raw)
+    RawBytes,
+    /// The values are already serialized as MessagePack, \
+    /// each of them is checked like [`TableMut::set_msgpack()`](struct.TableMut.html#method.set_msgpack) does, \
+    /// and they can be read back with [`get()`](trait.TableReadInterface.html#method.get)
+    MsgpackPassthrough,
+}
+
+/// What [`Database::import_lmdb()`](struct.Database.html#method.import_lmdb) imported from a single LMDB database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportedDatabase {
+    /// The name of the LMDB database, and of the table it was imported into
+    pub name: String,
+    /// The number of entries imported
+    pub imported: u64,
+    /// The keys that weren't valid UTF-8, as they were stored: \
+    /// escaped like [`escape_key()`](fn.escape_key.html) does, with every invalid byte written as `%XX`
+    pub escaped_keys: Vec<String>,
+}
+
+/// The result of [`Database::import_lmdb()`](struct.Database.html#method.import_lmdb)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportReport {
+    /// Every imported LMDB database, in the order they were asked for
+    pub databases: Vec<ImportedDatabase>,
+}
+
+impl ImportReport {
+    /// The number of entries imported from all databases
+    pub fn imported(&self) -> u64 {
+        self.databases.iter().map(|db| db.imported).sum()
+    }
+}
+
+/// copies the named databases of the LMDB environment at `path` into the tables of the same names, \
+/// `chunk_size` entries per write transaction
+pub(crate) fn import(
+    db: &mut Database,
+    path: &Path,
+    names: &[&str],
+    mode: ValueMode,
+    chunk_size: usize,
+) -> Result<ImportReport> {
+    for name in names {
+        let kind = db.store.table_kind_of(name, &db.default_table)?;
+        ensure!(
+            matches!(
+                kind,
+                TableKind::User | TableKind::Reserved(ReservedKind::Default)
+            ),
+            "table `{}` is reserved by dbless or foreign, it can't be imported into",
+            name
+        );
+    }
+
+    let mut options = EnvOpenOptions::new();
+    options.max_dbs(u32::try_from(names.len()).context("too many LMDB databases")?);
+    // SAFETY: the environment is only read, and nothing else of it is kept once the import is done
+    let env = unsafe { options.flags(EnvFlags::READ_ONLY).open(path) }
+        .with_context(|| format!("failed to open the LMDB environment `{}`", path.display()))?;
+    let rtxn = env.read_txn()?;
+    let mut databases = Vec::with_capacity(names.len());
+    for name in names {
+        let source = env
+            .open_database::<Bytes, Bytes>(&rtxn, Some(name))?
+            .with_context(|| Missing(format!("the LMDB database `{}` doesn't exist", name)))?;
+        let mut imported = ImportedDatabase {
+            name: name.to_string(),
+            imported: 0,
+            escaped_keys: vec![],
+        };
+        let mut chunk = Vec::with_capacity(chunk_size.min(source.len(&rtxn)? as usize));
+        for entry in source.iter(&rtxn)? {
+            let (key, value) = entry?;
+            let key = match std::str::from_utf8(key) {
+                Ok(key) => key.to_string(),
+                Err(_) => {
+                    let key = escape_bytes(key);
+                    imported.escaped_keys.push(key.clone());
+                    key
+                }
+            };
+            chunk.push((key, value.to_vec()));
+            if chunk.len() == chunk_size {
+                write_chunk(db, name, &chunk, mode)?;
+                imported.imported += chunk.len() as u64;
+                chunk.clear();
+            }
+        }
+        write_chunk(db, name, &chunk, mode)?;
+        imported.imported += chunk.len() as u64;
+        databases.push(imported);
+    }
+    Ok(ImportReport { databases })
+}
+
+fn write_chunk(
+    db: &mut Database,
+    name: &str,
+    chunk: &[(String, Vec<u8>)],
+    mode: ValueMode,
+) -> Result<()> {
+    if chunk.is_empty() {
+        return Ok(());
+    }
+    let raw = mode == ValueMode::RawBytes;
+    db.store
+        .insert_serialized(name, chunk, raw)
+        .with_context(|| format!("failed to import the LMDB database `{}`", name))
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+/// The version of the [`Manifest`] layout, bumped whenever a field is added, removed, or changes meaning.
+pub const MANIFEST_VERSION: u32 = 2;
+
+/// The version of the on-disk value format (plain MessagePack, or wrapped in a metadata envelope).
+pub(crate) const FORMAT_VERSION: u32 = 1;
+
+/// A machine-readable overview of a database, \
+/// see [`Database::manifest()`](struct.Database.html#method.manifest). \
+/// it can be serialized (to JSON for example) and stored, then compared with [`diff()`](#method.diff).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The version of the manifest layout, see [`MANIFEST_VERSION`]
+    pub manifest_version: u32,
+    /// The version of the on-disk value format
+    pub format_version: u32,
+    /// The codec values are serialized with
+    pub codec: String,
+    /// The options the database was opened with that change how values are stored or read
+    pub features: Vec<String>,
+    /// Every table in the database (including the default table), by name
+    pub tables: BTreeMap<String, TableManifest>,
+}
+
+/// The overview of a single table in a [`Manifest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableManifest {
+    /// The number of entries
+    pub entries: u64,
+    /// The total size of all keys and raw values, in bytes
+    pub bytes: u64,
+    /// The hex encoded digest of the table, see [`Table::digest()`](struct.Table.html#method.digest)
+    pub digest: String,
+    /// The description of the table, see [`Database::set_table_description()`](struct.Database.html#method.set_table_description)
+    #[serde(default)]
+    pub description: Option<String>,
+    /// The annotations of the table, see [`Database::set_table_annotation()`](struct.Database.html#method.set_table_annotation)
+    #[serde(default)]
+    pub annotations: BTreeMap<String, String>,
+}
+
+/// The differences between two manifests, see [`Manifest::diff()`](struct.Manifest.html#method.diff)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestDiff {
+    /// The names of the top level fields that differ (like `codec` or `features`)
+    pub changed_fields: Vec<String>,
+    /// Tables only in the other manifest
+    pub added_tables: Vec<String>,
+    /// Tables only in this manifest
+    pub removed_tables: Vec<String>,
+    /// Tables in both manifests with different content, as `(name, this, other)`
+    pub changed_tables: Vec<(String, TableManifest, TableManifest)>,
+}
+
+impl Manifest {
+    /// Compares this manifest with `other`, \
+    /// describing what changed going from this one to the other.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let staging = Database::open("staging.db")?;
+    /// let production = Database::open("production.db")?;
+    /// let diff = staging.manifest()?.diff(&production.manifest()?);
+    /// assert!(diff.is_empty(), "environments differ: {:?}", diff);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn diff(&self, other: &Manifest) -> ManifestDiff {
+        let mut diff = ManifestDiff::default();
+        if self.manifest_version != other.manifest_version {
+            diff.changed_fields.push("manifest_version".to_string());
+        }
+        if self.format_version != other.format_version {
+            diff.changed_fields.push("format_version".to_string());
+        }
+        if self.codec != other.codec {
+            diff.changed_fields.push("codec".to_string());
+        }
+        if self.features != other.features {
+            diff.changed_fields.push("features".to_string());
+        }
+        for (name, table) in &self.tables {
+            match other.tables.get(name) {
+                None => diff.removed_tables.push(name.clone()),
+                Some(other_table) if other_table != table => {
+                    diff.changed_tables
+                        .push((name.clone(), table.clone(), other_table.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        diff.added_tables = other
+            .tables
+            .keys()
+            .filter(|name| !self.tables.contains_key(*name))
+            .cloned()
+            .collect();
+        diff
+    }
+}
+
+impl ManifestDiff {
+    /// Whether the two manifests are identical
+    pub fn is_empty(&self) -> bool {
+        self.changed_fields.is_empty()
+            && self.added_tables.is_empty()
+            && self.removed_tables.is_empty()
+            && self.changed_tables.is_empty()
+    }
+}
@@ -0,0 +1,179 @@
+//! a minimal reader for raw MessagePack, \
+//! used to look at stored values without deserializing them into a rust type.
+
+use anyhow::{bail, ensure, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Token<'a> {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    F32(f32),
+    F64(f64),
+    Str(&'a str),
+    Bin(&'a [u8]),
+    /// followed by this many values
+    Array(usize),
+    /// followed by this many key-value pairs
+    Map(usize),
+    Ext(i8, &'a [u8]),
+}
+
+pub struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Reader { bytes }
+    }
+
+    /// the bytes that haven't been read yet
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        ensure!(self.bytes.len() >= n, "truncated MessagePack value");
+        let (head, tail) = self.bytes.split_at(n);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    fn take_len(&mut self, width: usize) -> Result<usize> {
+        Ok(match width {
+            1 => self.take_array::<1>()?[0] as usize,
+            2 => u16::from_be_bytes(self.take_array()?) as usize,
+            _ => u32::from_be_bytes(self.take_array()?) as usize,
+        })
+    }
+
+    fn take_str(&mut self, len: usize) -> Result<Token<'a>> {
+        Ok(Token::Str(std::str::from_utf8(self.take(len)?)?))
+    }
+
+    fn take_ext(&mut self, len: usize) -> Result<Token<'a>> {
+        let ext_type = self.take_array::<1>()?[0] as i8;
+        Ok(Token::Ext(ext_type, self.take(len)?))
+    }
+
+    /// reads the next token, arrays and maps are only read up to their header
+    pub fn read(&mut self) -> Result<Token<'a>> {
+        let marker = self.take_array::<1>()?[0];
+        Ok(match marker {
+            0x00..=0x7f => Token::UInt(marker as u64),
+            0x80..=0x8f => Token::Map((marker & 0x0f) as usize),
+            0x90..=0x9f => Token::Array((marker & 0x0f) as usize),
+            0xa0..=0xbf => self.take_str((marker & 0x1f) as usize)?,
+            0xc0 => Token::Nil,
+            0xc2 => Token::Bool(false),
+            0xc3 => Token::Bool(true),
+            0xc4..=0xc6 => {
+                let len = self.take_len(1 << (marker - 0xc4))?;
+                Token::Bin(self.take(len)?)
+            }
+            0xc7..=0xc9 => {
+                let len = self.take_len(1 << (marker - 0xc7))?;
+                self.take_ext(len)?
+            }
+            0xca => Token::F32(f32::from_be_bytes(self.take_array()?)),
+            0xcb => Token::F64(f64::from_be_bytes(self.take_array()?)),
+            0xcc => Token::UInt(self.take_array::<1>()?[0] as u64),
+            0xcd => Token::UInt(u16::from_be_bytes(self.take_array()?) as u64),
+            0xce => Token::UInt(u32::from_be_bytes(self.take_array()?) as u64),
+            0xcf => Token::UInt(u64::from_be_bytes(self.take_array()?)),
+            0xd0 => Token::Int(i8::from_be_bytes(self.take_array()?) as i64),
+            0xd1 => Token::Int(i16::from_be_bytes(self.take_array()?) as i64),
+            0xd2 => Token::Int(i32::from_be_bytes(self.take_array()?) as i64),
+            0xd3 => Token::Int(i64::from_be_bytes(self.take_array()?)),
+            0xd4..=0xd8 => self.take_ext(1 << (marker - 0xd4))?,
+            0xd9..=0xdb => {
+                let len = self.take_len(1 << (marker - 0xd9))?;
+                self.take_str(len)?
+            }
+            0xdc | 0xdd => Token::Array(self.take_len(2 << (marker - 0xdc))?),
+            0xde | 0xdf => Token::Map(self.take_len(2 << (marker - 0xde))?),
+            0xe0..=0xff => Token::Int(marker as i8 as i64),
+            0xc1 => bail!("invalid MessagePack marker 0xc1"),
+        })
+    }
+
+    /// skips the next value, including everything inside it
+    pub fn skip(&mut self) -> Result<()> {
+        let mut remaining = 1usize;
+        while remaining > 0 {
+            remaining -= 1;
+            match self.read()? {
+                Token::Array(len) => remaining = remaining.saturating_add(len),
+                Token::Map(len) => remaining = remaining.saturating_add(len.saturating_mul(2)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// bounds on the shape of a value read from untrusted bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// the most arrays and maps nested inside each other, a flat array being at depth 1
+    pub max_depth: usize,
+    /// the most elements (or key-value pairs) a single array or map can claim
+    pub max_collection_len: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            max_depth: 128,
+            max_collection_len: None,
+        }
+    }
+}
+
+/// checks the next value of `bytes` against `limits` without recursing, \
+/// and that no array or map claims more elements than there are bytes left to hold them, \
+/// so deserializing it afterwards can't overflow the stack or preallocate unbounded memory
+pub fn check(bytes: &[u8], limits: &Limits) -> Result<()> {
+    let mut reader = Reader::new(bytes);
+    // the number of values left to read at each open level
+    let mut levels = vec![1usize];
+    while let Some(left) = levels.last_mut() {
+        if *left == 0 {
+            levels.pop();
+            continue;
+        }
+        *left -= 1;
+        let (len, values) = match reader.read()? {
+            Token::Array(len) => (len, len),
+            Token::Map(len) => (len, len.saturating_mul(2)),
+            _ => continue,
+        };
+        if let Some(max) = limits.max_collection_len {
+            ensure!(
+                len <= max,
+                "MessagePack collection of {} elements exceeds the limit of {}",
+                len,
+                max
+            );
+        }
+        // every value takes at least one byte
+        ensure!(
+            values <= reader.remaining().len(),
+            "truncated MessagePack value: a collection claims {} elements",
+            len
+        );
+        ensure!(
+            levels.len() <= limits.max_depth,
+            "MessagePack value is nested deeper than the limit of {}",
+            limits.max_depth
+        );
+        levels.push(values);
+    }
+    Ok(())
+}
@@ -0,0 +1,303 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+
+use crate::msgpack::Limits;
+use crate::transform::TransformChain;
+use crate::{Database, StorageBackend, ValueTransform};
+
+/// What to do when a value in a batch fails to serialize.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SerializeErrorPolicy {
+    /// Abort the whole batch, nothing is written.
+    #[default]
+    Abort,
+    /// Skip the offending entry and write the rest, the skipped keys are returned.
+    Skip,
+}
+
+/// The format values are serialized in, see [`Options::format()`](struct.Options.html#method.format)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SerializationFormat {
+    /// MessagePack, compact and fast to read and write
+    #[default]
+    MessagePack,
+    /// JSON, readable and editable with other tools, needs the `json` feature
+    #[cfg(feature = "json")]
+    Json,
+}
+
+impl SerializationFormat {
+    /// the name of the format, as saved in the database and written in manifests
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            SerializationFormat::MessagePack => "messagepack",
+            #[cfg(feature = "json")]
+            SerializationFormat::Json => "json",
+        }
+    }
+
+    /// the format saved under `name` in a database
+    pub(crate) fn from_name(name: &str) -> Result<Self> {
+        match name {
+            "messagepack" => Ok(SerializationFormat::MessagePack),
+            #[cfg(feature = "json")]
+            "json" => Ok(SerializationFormat::Json),
+            #[cfg(not(feature = "json"))]
+            "json" => {
+                bail!("the database stores its values as JSON, which needs the `json` feature")
+            }
+            _ => bail!(
+                "the database stores its values in an unknown format `{}`",
+                name
+            ),
+        }
+    }
+}
+
+/// Options used to open a [`Database`](struct.Database.html).
+/// ```no_run
+/// # use dbless::{Database, SerializeErrorPolicy};
+/// let db = Database::options()
+///     .serialize_error_policy(SerializeErrorPolicy::Skip)
+///     .open("my_database.db")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub(crate) serialize_error_policy: SerializeErrorPolicy,
+    // `None` to use the format saved in the database
+    pub(crate) format: Option<SerializationFormat>,
+    pub(crate) track_versions: bool,
+    pub(crate) strict_numbers: bool,
+    pub(crate) verify_writes: bool,
+    pub(crate) reserved_prefix: Option<String>,
+    pub(crate) cache_table_names: bool,
+    pub(crate) cache_table_lengths: bool,
+    pub(crate) transforms: TransformChain,
+    pub(crate) auto_compact_on_close: Option<f64>,
+    pub(crate) read_limits: Limits,
+    // only set by `Database::in_memory_seeded()`
+    pub(crate) shuffle_seed: Option<u64>,
+}
+
+impl Options {
+    /// Creates a new set of options with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets what happens when a value in a batch fails to serialize. \
+    /// default: [`SerializeErrorPolicy::Abort`](enum.SerializeErrorPolicy.html#variant.Abort)
+    pub fn serialize_error_policy(mut self, policy: SerializeErrorPolicy) -> Self {
+        self.serialize_error_policy = policy;
+        self
+    }
+
+    /// Sets the format values are serialized in. \
+    /// only values are affected: keys, the metadata dbless stores alongside values (versions, type tags), \
+    /// counters, and the tables dbless uses internally are stored the same way in every format. \
+    /// [`get_raw()`](trait.TableReadInterface.html#method.get_raw), [`TableMut::set_msgpack()`](struct.TableMut.html#method.set_msgpack), \
+    /// field filters and exports still take and give MessagePack, converting from and to the stored format, \
+    /// and [raw bytes](trait.TableWriteInterface.html#method.set_raw) are kept as they are. \
+    /// the format is saved in the database when it's created, \
+    /// opening it with another format fails, and opening it without setting one uses the saved format. \
+    /// [`Database::convert_codec()`](struct.Database.html#method.convert_codec) converts an existing database to another format. \
+    /// default: the format of the database, [`SerializationFormat::MessagePack`](enum.SerializationFormat.html#variant.MessagePack) for a new one
+    /// ```no_run
+    /// # #[cfg(feature = "json")] {
+    /// # use dbless::{Database, SerializationFormat, TableWriteInterface};
+    /// let mut db = Database::options().format(SerializationFormat::Json).open("my_database.db")?;
+    /// db.set("user", &("alice", 30))?; // stored as `["alice",30]`
+    /// # }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn format(mut self, format: SerializationFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Sets whether every write stores a version alongside the value, \
+    /// incremented each time the key is written. \
+    /// without it, only [`set_if_version()`](trait.TableWriteInterface.html#method.set_if_version) stores versions, \
+    /// and a regular write resets the key back to version 0. \
+    /// this costs an extra read per write. \
+    /// default: `false`
+    pub fn track_versions(mut self, track_versions: bool) -> Self {
+        self.track_versions = track_versions;
+        self
+    }
+
+    /// Sets whether reading a number into a type that can't represent it exactly \
+    /// (like a large integer into a float, or a precise `f64` into an `f32`) \
+    /// returns a [`LossyNumberError`](struct.LossyNumberError.html) instead of silently losing precision. \
+    /// out of range integers are always rejected, regardless of this option. \
+    /// default: `false`
+    pub fn strict_numbers(mut self, strict_numbers: bool) -> Self {
+        self.strict_numbers = strict_numbers;
+        self
+    }
+
+    /// Sets whether every written value is read back within the same write transaction and compared byte by byte, \
+    /// failing the write with a [`WriteVerificationFailed`](struct.WriteVerificationFailed.html) error on mismatch. \
+    /// this catches storage bugs as soon as they happen, but every write does an extra lookup, \
+    /// roughly doubling the cost of writes, so it's best kept for small, critical databases. \
+    /// default: `false`
+    pub fn verify_writes(mut self, verify_writes: bool) -> Self {
+        self.verify_writes = verify_writes;
+        self
+    }
+
+    /// Sets the prefix of every table dbless uses internally, \
+    /// including the default table (named `<prefix>main`) and the tables holding aliases, limits and other metadata. \
+    /// tables starting with the prefix are never listed by [`list_tables()`](struct.Database.html#method.list_tables), \
+    /// so dbless can share a redb file with tables created by other code, as long as their names don't start with it. \
+    /// a database must always be opened with the prefix it was created with, \
+    /// otherwise its default table and metadata won't be found. \
+    /// opening fails if the prefix is empty. \
+    /// default: the `#_#_<name>_dbless_table_#_#` markers
+    /// ```no_run
+    /// # use dbless::{Database, TableWriteInterface};
+    /// let mut db = Database::options().reserved_prefix("dbless/").open("shared.redb")?;
+    /// db.set("key", &"value")?; // stored in the `dbless/main` table
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn reserved_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.reserved_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Sets whether [`contains_table()`](struct.Database.html#method.contains_table) caches the names of all tables, \
+    /// loading them on the first call and answering from memory after that. \
+    /// tables are created by their first write, so the cache is dropped by every write through this database, \
+    /// which makes it pay off when checks are much more frequent than writes. \
+    /// tables created or deleted by another process (or another `Database` opened on the same file) \
+    /// aren't seen until the next write through this one, \
+    /// or until [`Database::drop_caches()`](struct.Database.html#method.drop_caches) is called. \
+    /// default: `false`
+    pub fn cache_table_names(mut self, cache_table_names: bool) -> Self {
+        self.cache_table_names = cache_table_names;
+        self
+    }
+
+    /// Sets whether [`len()`](trait.TableReadInterface.html#method.len) and [`is_empty()`](trait.TableReadInterface.html#method.is_empty) \
+    /// remember the number of entries of each table they count, answering from memory until the next write. \
+    /// every write through this database drops all the remembered lengths, \
+    /// which makes it pay off when tables are counted much more often than they're written, like a UI refreshing badges every frame. \
+    /// writes made by another process (or another `Database` opened on the same file) aren't seen until the next write through this one, \
+    /// or until [`Database::drop_caches()`](struct.Database.html#method.drop_caches) is called. \
+    /// default: `false`
+    pub fn cache_table_lengths(mut self, cache_table_lengths: bool) -> Self {
+        self.cache_table_lengths = cache_table_lengths;
+        self
+    }
+
+    /// Adds a stage to the chain every value goes through before it's stored, \
+    /// stages are applied in the order they're added, and reversed in the opposite order on read. \
+    /// values keep the ids of the stages applied to them, so a database can hold values written with different chains, \
+    /// but reading a value fails if one of its stages was removed from the chain. \
+    /// values that skipped a stage are read as they are, unless [`require_transforms()`](#method.require_transforms) is set. \
+    /// [`u64` counters](trait.TableWriteInterface.html#method.set_u64) are stored as they are, \
+    /// and in deduplicated tables, only values that transform to the same bytes are stored once. \
+    /// opening fails if two stages have the same id. \
+    /// see [`ValueTransform`](trait.ValueTransform.html)
+    pub fn transform(mut self, stage: impl ValueTransform + 'static) -> Self {
+        self.transforms.push(stage);
+        self
+    }
+
+    /// Sets whether every value has to go through every stage of the [transform chain](#method.transform). \
+    /// by default, values without the header of the stages are read as they are (like the ones written before a stage was added), \
+    /// so whoever can write to the file can bypass a signing stage by storing a plain value. \
+    /// when required, reading a value that skipped a stage of the chain fails, \
+    /// and [`u64` counters](trait.TableWriteInterface.html#method.set_u64), which are never transformed, can't be written nor read. \
+    /// does nothing without stages. \
+    /// default: `false`
+    /// ```no_run
+    /// # use dbless::{Database, ValueTransform};
+    /// # struct Signature;
+    /// # impl ValueTransform for Signature {
+    /// #     fn id(&self) -> u8 { 1 }
+    /// #     fn encode(&self, bytes: &[u8]) -> Vec<u8> { bytes.to_vec() }
+    /// #     fn decode(&self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> { Ok(bytes.to_vec()) }
+    /// # }
+    /// let db = Database::options()
+    ///     .transform(Signature)
+    ///     .require_transforms(true)
+    ///     .open("my_database.db")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn require_transforms(mut self, require_transforms: bool) -> Self {
+        self.transforms.set_required(require_transforms);
+        self
+    }
+
+    /// Sets [`Database::close_checked()`](struct.Database.html#method.close_checked) to compact the database \
+    /// when at least `threshold` (from 0 to 1) of its allocated space is lost to fragmentation, \
+    /// which keeps a file with a lot of churn from growing across restarts. \
+    /// compacting rewrites the file, so closing can take about as long as copying it. \
+    /// [`close()`](struct.Database.html#method.close) and dropping the database never compact. \
+    /// default: never compact on close
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::options().auto_compact_on_close(0.25).open("my_database.db")?;
+    /// // ... churn keys
+    /// db.close_checked()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn auto_compact_on_close(mut self, threshold: f64) -> Self {
+        self.auto_compact_on_close = Some(threshold);
+        self
+    }
+
+    /// Sets the most arrays and maps a stored value can nest inside each other (a flat list being 1) \
+    /// before reading it fails with an error, \
+    /// which keeps a corrupted or hostile value from overflowing the stack while it's deserialized or exported. \
+    /// default: `128`
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.read_limits.max_depth = depth;
+        self
+    }
+
+    /// Sets the most elements (or key-value pairs) a single list or map in a stored value can hold \
+    /// before reading it fails with an error. \
+    /// a collection claiming more elements than its value has bytes left is always rejected, \
+    /// so this is only needed to bound values that really are large. \
+    /// default: no limit
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::options()
+    ///     .max_depth(16)
+    ///     .max_collection_len(100_000)
+    ///     .open("untrusted.db")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn max_collection_len(mut self, len: usize) -> Self {
+        self.read_limits.max_collection_len = Some(len);
+        self
+    }
+
+    /// Opens a file at the given path with these options. \
+    /// see [`Database::open()`](struct.Database.html#method.open)
+    pub fn open(self, path: impl AsRef<Path>) -> crate::Result<Database> {
+        Database::open_with_options(path, self)
+    }
+
+    /// Opens an in-memory database with these options. \
+    /// see [`Database::in_memory()`](struct.Database.html#method.in_memory)
+    pub fn in_memory(self) -> crate::Result<Database> {
+        Database::in_memory_with_options(self)
+    }
+
+    /// Opens a self-deleting temporary database with these options. \
+    /// see [`Database::open_temp()`](struct.Database.html#method.open_temp)
+    pub fn open_temp(self) -> crate::Result<Database> {
+        Database::open_temp_with_options(self)
+    }
+
+    /// Opens a database stored in a custom redb backend with these options. \
+    /// see [`Database::open_with_backend()`](struct.Database.html#method.open_with_backend)
+    pub fn open_with_backend(self, backend: impl StorageBackend) -> crate::Result<Database> {
+        Database::open_with_backend_and_options(backend, self)
+    }
+}
@@ -0,0 +1,118 @@
+use std::panic::catch_unwind;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+
+use crate::{temp_path, Database, TempFile};
+
+/// How [`salvage()`](fn.salvage.html) got the damaged file open
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SalvageStrategy {
+    /// The file opened normally
+    Normal,
+    /// The file was left in an inconsistent state (after a crash or a power failure for example), \
+    /// and opened after redb repaired it
+    Repaired,
+}
+
+/// What [`salvage()`](fn.salvage.html) recovered from a single table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SalvagedTable {
+    /// The name of the table
+    pub name: String,
+    /// The number of entries copied into the destination database
+    pub salvaged: u64,
+    /// The number of entries the table says it holds, `None` if even that couldn't be read
+    pub expected: Option<u64>,
+    /// The error that stopped the copy of the table, `None` if the whole table was copied
+    pub error: Option<String>,
+}
+
+impl SalvagedTable {
+    /// The number of entries that couldn't be copied, `None` if the expected number is unknown
+    pub fn lost(&self) -> Option<u64> {
+        self.expected
+            .map(|expected| expected.saturating_sub(self.salvaged))
+    }
+}
+
+/// The result of [`salvage()`](fn.salvage.html)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SalvageReport {
+    /// How the file was opened
+    pub strategy: SalvageStrategy,
+    /// Every table found in the file (including the default table), sorted by name
+    pub tables: Vec<SalvagedTable>,
+}
+
+impl SalvageReport {
+    /// The number of entries copied from all tables
+    pub fn salvaged(&self) -> u64 {
+        self.tables.iter().map(|table| table.salvaged).sum()
+    }
+
+    /// Whether every entry of every table was copied
+    pub fn is_complete(&self) -> bool {
+        self.tables
+            .iter()
+            .all(|table| table.error.is_none() && table.lost() == Some(0))
+    }
+}
+
+/// Copies whatever entries can still be read from the damaged database file at `path` into `dest`, \
+/// table by table, and reports what was salvaged and what was lost. \
+/// the file is first opened normally, and if it wasn't closed cleanly, after letting redb repair it. \
+/// once open, every table is copied on its own, so a table that can't be read doesn't stop the others, \
+/// and the entries of a table read before an error are kept.
+///
+/// the file at `path` is never modified, the repair runs on a copy in the system's temporary directory. \
+/// values are read and stored again with the transforms of `dest`, so it must be opened with the same transforms as the damaged database. \
+/// table settings (limits, key escaping, append-only chains, deduplication) aren't salvaged, \
+/// values of deduplicated tables are copied in full, and keys of escaping tables are copied escaped. \
+/// a file that redb can't open at all, even after repairing it (a truncated file for example), returns an error: \
+/// scanning its pages for recognizable entries isn't supported.
+/// ```no_run
+/// # use dbless::{salvage, Database};
+/// let mut rescued = Database::open("rescued.db")?;
+/// let report = salvage("customer.db", &mut rescued)?;
+/// for table in &report.tables {
+///     println!("{}: {} salvaged, {:?} lost", table.name, table.salvaged, table.lost());
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn salvage(path: impl AsRef<Path>, dest: &mut Database) -> Result<SalvageReport> {
+    let path = path.as_ref();
+    let copy = TempFile(temp_path()?);
+    std::fs::copy(path, &copy.0)
+        .with_context(|| format!("failed to copy `{}` to salvage it", path.display()))?;
+
+    let repaired = Arc::new(AtomicBool::new(false));
+    let open = {
+        let repaired = repaired.clone();
+        let copy = &copy.0;
+        // redb panics on some damage, like a truncated file
+        catch_unwind(move || {
+            redb::Builder::new()
+                .set_repair_callback(move |_| repaired.store(true, Ordering::Relaxed))
+                .open(copy)
+        })
+    };
+    let source = match open {
+        Ok(source) => source
+            .with_context(|| format!("`{}` can't be opened, even repaired", path.display()))?,
+        Err(_) => bail!(
+            "`{}` can't be opened, redb panicked reading it (is the file truncated?)",
+            path.display()
+        ),
+    };
+    let strategy = match repaired.load(Ordering::Relaxed) {
+        true => SalvageStrategy::Repaired,
+        false => SalvageStrategy::Normal,
+    };
+
+    let tables = dest.store.salvage_from(&source)?;
+    drop(source);
+    Ok(SalvageReport { strategy, tables })
+}
@@ -0,0 +1,122 @@
+use anyhow::{ensure, Result};
+
+use crate::{
+    annotation::ReservedKind, ALIASES_TABLE, ANNOTATIONS_TABLE, BLOBS_TABLE_PREFIX, CHAINED_TABLE,
+    CHAIN_TABLE_PREFIX, DEDUPLICATED_TABLE, DEFAULT_DEFAULT_TABLE, ESCAPED_TABLE, HEALTH_TABLE,
+    LIMITS_TABLE, METADATA_TABLE, ORDER_TABLE_PREFIX, STAMPS_TABLE_PREFIX, WRITE_ONCE_TABLE,
+};
+
+/// the names of the tables dbless uses internally, \
+/// either the `#_#_..._dbless_table_#_#` markers, or names starting with `Options::reserved_prefix()`
+#[derive(Debug, Clone)]
+pub struct ReservedNames {
+    prefix: Option<String>,
+    pub default_table: String,
+    pub health: String,
+    pub aliases: String,
+    pub limits: String,
+    pub escaped: String,
+    pub chained: String,
+    pub deduplicated: String,
+    pub annotations: String,
+    pub write_once: String,
+    pub metadata: String,
+    pub order_prefix: String,
+    pub stamps_prefix: String,
+    pub chain_prefix: String,
+    pub blobs_prefix: String,
+}
+
+impl ReservedNames {
+    pub fn new(prefix: Option<&str>) -> Result<Self> {
+        let prefix = match prefix {
+            Some(prefix) => prefix,
+            None => {
+                return Ok(ReservedNames {
+                    prefix: None,
+                    default_table: DEFAULT_DEFAULT_TABLE.to_string(),
+                    health: HEALTH_TABLE.to_string(),
+                    aliases: ALIASES_TABLE.to_string(),
+                    limits: LIMITS_TABLE.to_string(),
+                    escaped: ESCAPED_TABLE.to_string(),
+                    chained: CHAINED_TABLE.to_string(),
+                    deduplicated: DEDUPLICATED_TABLE.to_string(),
+                    annotations: ANNOTATIONS_TABLE.to_string(),
+                    write_once: WRITE_ONCE_TABLE.to_string(),
+                    metadata: METADATA_TABLE.to_string(),
+                    order_prefix: ORDER_TABLE_PREFIX.to_string(),
+                    stamps_prefix: STAMPS_TABLE_PREFIX.to_string(),
+                    chain_prefix: CHAIN_TABLE_PREFIX.to_string(),
+                    blobs_prefix: BLOBS_TABLE_PREFIX.to_string(),
+                })
+            }
+        };
+        ensure!(
+            !prefix.is_empty(),
+            "the reserved table prefix can't be empty"
+        );
+        let name = |kind: &str| format!("{}{}", prefix, kind);
+        Ok(ReservedNames {
+            prefix: Some(prefix.to_string()),
+            default_table: name("main"),
+            health: name("health"),
+            aliases: name("aliases"),
+            limits: name("limits"),
+            escaped: name("escaped"),
+            chained: name("chained"),
+            deduplicated: name("deduplicated"),
+            annotations: name("annotations"),
+            write_once: name("write_once"),
+            metadata: name("metadata"),
+            order_prefix: name("order:"),
+            stamps_prefix: name("stamps:"),
+            chain_prefix: name("chain:"),
+            blobs_prefix: name("blobs:"),
+        })
+    }
+
+    /// what the table is used for by dbless, `None` if it isn't one of its internal tables. \
+    /// with a custom prefix that's every table starting with it, \
+    /// otherwise the default table is left out, since it can be used as a regular table after changing the default
+    pub fn kind(&self, name: &str) -> Option<ReservedKind> {
+        let names = [
+            (&self.health, ReservedKind::Health),
+            (&self.aliases, ReservedKind::Aliases),
+            (&self.limits, ReservedKind::Limits),
+            (&self.escaped, ReservedKind::Escaped),
+            (&self.chained, ReservedKind::Chained),
+            (&self.deduplicated, ReservedKind::Deduplicated),
+            (&self.annotations, ReservedKind::Annotations),
+            (&self.write_once, ReservedKind::WriteOnce),
+            (&self.metadata, ReservedKind::Metadata),
+        ];
+        let prefixes = [
+            (&self.order_prefix, ReservedKind::EvictionOrder),
+            (&self.stamps_prefix, ReservedKind::EvictionStamps),
+            (&self.chain_prefix, ReservedKind::Chain),
+            (&self.blobs_prefix, ReservedKind::Blobs),
+        ];
+        if let Some((_, kind)) = names.iter().find(|(reserved, _)| name == reserved.as_str()) {
+            return Some(*kind);
+        }
+        if let Some((_, kind)) = prefixes
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix.as_str()))
+        {
+            return Some(*kind);
+        }
+        let prefix = self.prefix.as_ref()?;
+        match name == self.default_table {
+            true => Some(ReservedKind::Default),
+            false => name
+                .starts_with(prefix.as_str())
+                .then_some(ReservedKind::Other),
+        }
+    }
+
+    /// checks if the table is used internally by dbless, and never listed by `Database::list_tables()`, \
+    /// see [`kind()`](#method.kind)
+    pub fn is_reserved(&self, name: &str) -> bool {
+        self.kind(name).is_some()
+    }
+}
@@ -0,0 +1,573 @@
+use std::any::type_name;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::fmt;
+use std::io;
+
+use anyhow::{Context, Error, Result};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Deserializer, Visitor};
+use serde::Serialize;
+
+use crate::envelope::{decode_raw, Envelope};
+use crate::error::{DeserializeFailed, SerializeFailed};
+use crate::export::CountingWriter;
+use crate::msgpack::{self, Limits};
+use crate::options::SerializationFormat;
+#[cfg(feature = "json")]
+use crate::value::Value;
+
+pub fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut serializer = rmp_serde::Serializer::new(vec![]).with_struct_map();
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner())
+}
+
+pub fn serialize_for_key<T: Serialize>(key: &str, value: &T) -> Result<Vec<u8>> {
+    serialize_for_key_as(SerializationFormat::MessagePack, key, value)
+}
+
+/// [`serialize_for_key()`] in `format`
+pub fn serialize_for_key_as<T: Serialize>(
+    format: SerializationFormat,
+    key: &str,
+    value: &T,
+) -> Result<Vec<u8>> {
+    let bytes = match format {
+        SerializationFormat::MessagePack => serialize(value),
+        #[cfg(feature = "json")]
+        SerializationFormat::Json => serde_json::to_vec(value).map_err(Into::into),
+    };
+    bytes.with_context(|| {
+        SerializeFailed(format!(
+            "failed to serialize value of type `{}` for key `{}`",
+            type_name::<T>(),
+            key
+        ))
+    })
+}
+
+/// Gets the number of bytes `value` takes once serialized, without allocating them, \
+/// as MessagePack, the default [`SerializationFormat`](enum.SerializationFormat.html). \
+/// this is the size of the value before the [transforms](struct.Options.html#method.transform) of the database (like compression), \
+/// and without the metadata dbless stores along with some values (versions, expiry times).
+/// ```no_run
+/// # #[derive(serde::Serialize)]
+/// # struct Upload { data: Vec<u8> }
+/// # let upload = Upload { data: vec![] };
+/// if dbless::serialized_size(&upload)? > 1024 * 1024 {
+///     println!("uploads are limited to 1MiB");
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub fn serialized_size<T: Serialize>(value: &T) -> Result<u64> {
+    serialized_size_as(SerializationFormat::MessagePack, value)
+}
+
+/// [`serialized_size()`] in `format`
+pub fn serialized_size_as<T: Serialize>(format: SerializationFormat, value: &T) -> Result<u64> {
+    let mut writer = CountingWriter::new(io::sink());
+    match format {
+        SerializationFormat::MessagePack => {
+            value.serialize(&mut rmp_serde::Serializer::new(&mut writer).with_struct_map())?
+        }
+        #[cfg(feature = "json")]
+        SerializationFormat::Json => serde_json::to_writer(&mut writer, value)?,
+    }
+    Ok(writer.count)
+}
+
+/// deserializes a stored value, rejecting lossy numeric coercions if `strict` is set
+pub fn deserialize<T: DeserializeOwned>(key: &str, value: &[u8], strict: bool) -> Result<T> {
+    deserialize_with_limits(key, value, strict, &Limits::default())
+}
+
+/// [`deserialize()`] after checking the shape of the value against `limits`
+pub fn deserialize_with_limits<T: DeserializeOwned>(
+    key: &str,
+    value: &[u8],
+    strict: bool,
+    limits: &Limits,
+) -> Result<T> {
+    deserialize_as(SerializationFormat::MessagePack, key, value, strict, limits)
+}
+
+/// [`deserialize_with_limits()`] for a value serialized in `format`. \
+/// `limits` only apply to MessagePack, serde_json has its own nesting limit, \
+/// and JSON collections don't announce their length up front
+pub fn deserialize_as<T: DeserializeOwned>(
+    format: SerializationFormat,
+    key: &str,
+    value: &[u8],
+    strict: bool,
+    limits: &Limits,
+) -> Result<T> {
+    let value = Envelope::decode(value)
+        .with_context(|| DeserializeFailed(format!("value of key `{}` is corrupted", key)))?
+        .value;
+    if decode_raw(value).is_some() {
+        return Err(Error::msg(DeserializeFailed(format!(
+            "value of key `{}` holds raw bytes, read them with `get_raw()`",
+            key
+        ))));
+    }
+    match format {
+        SerializationFormat::MessagePack => {
+            msgpack::check(value, limits).with_context(|| {
+                DeserializeFailed(format!("value of key `{}` can't be read safely", key))
+            })?;
+            let mut deserializer = rmp_serde::Deserializer::from_read_ref(value);
+            deserialize_from(key, &mut deserializer, strict)
+        }
+        #[cfg(feature = "json")]
+        SerializationFormat::Json => {
+            let mut deserializer = serde_json::Deserializer::from_slice(value);
+            let value = deserialize_from(key, &mut deserializer, strict)?;
+            deserializer.end()?;
+            Ok(value)
+        }
+    }
+}
+
+fn deserialize_from<'de, T, D>(key: &str, deserializer: D, strict: bool) -> Result<T>
+where
+    T: DeserializeOwned,
+    D: Deserializer<'de>,
+    D::Error: std::error::Error + Send + Sync + 'static,
+{
+    if !strict {
+        return Ok(T::deserialize(deserializer)?);
+    }
+
+    let lossy = RefCell::new(None);
+    let result = T::deserialize(Strict {
+        inner: deserializer,
+        lossy: &lossy,
+    });
+    match (result, lossy.into_inner()) {
+        (Ok(value), _) => Ok(value),
+        (Err(_), Some((value, target))) => Err(LossyNumberError {
+            key: key.to_string(),
+            value,
+            target,
+        }
+        .into()),
+        (Err(e), None) => Err(e.into()),
+    }
+}
+
+/// the MessagePack form of a value stored in `format`, \
+/// for the APIs that work on raw MessagePack whatever the format
+pub fn to_msgpack(format: SerializationFormat, value: &[u8]) -> Result<Cow<'_, [u8]>> {
+    match format {
+        SerializationFormat::MessagePack => Ok(Cow::Borrowed(value)),
+        #[cfg(feature = "json")]
+        SerializationFormat::Json => {
+            let value: Value = serde_json::from_slice(value)?;
+            Ok(Cow::Owned(serialize(&value)?))
+        }
+    }
+}
+
+/// the form a MessagePack value is stored in with `format`, the reverse of [`to_msgpack()`]
+#[cfg_attr(not(feature = "json"), allow(unused_variables))]
+pub fn from_msgpack<'v>(
+    format: SerializationFormat,
+    value: &'v [u8],
+    limits: &Limits,
+) -> Result<Cow<'v, [u8]>> {
+    match format {
+        SerializationFormat::MessagePack => Ok(Cow::Borrowed(value)),
+        #[cfg(feature = "json")]
+        SerializationFormat::Json => {
+            let value = Value::read(value, limits)?;
+            Ok(Cow::Owned(serde_json::to_vec(&value)?))
+        }
+    }
+}
+
+/// The error returned when a stored number can't be represented exactly in the requested type, \
+/// only returned by databases opened with [`Options::strict_numbers()`](struct.Options.html#method.strict_numbers).
+/// ```no_run
+/// # use dbless::{Database, LossyNumberError, TableReadInterface};
+/// let db = Database::options().strict_numbers(true).open("my_database.db")?;
+/// if let Err(e) = db.get::<f32>("key") {
+///     if let Some(e) = e.downcast_ref::<LossyNumberError>() {
+///         println!("{} holds {}, which doesn't fit in {}", e.key, e.value, e.target);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LossyNumberError {
+    /// The key of the offending value
+    pub key: String,
+    /// The stored number
+    pub value: String,
+    /// The type it was read into
+    pub target: &'static str,
+}
+
+impl fmt::Display for LossyNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value `{}` of key `{}` can't be represented exactly as {}",
+            self.value, self.key, self.target
+        )
+    }
+}
+
+impl std::error::Error for LossyNumberError {}
+
+type Lossy = RefCell<Option<(String, &'static str)>>;
+
+/// a deserializer wrapper that checks every number visited on the way, \
+/// it only has to care about floats, as serde already rejects out of range integers.
+struct Strict<'a, D> {
+    inner: D,
+    lossy: &'a Lossy,
+}
+
+#[derive(Clone, Copy)]
+enum Target {
+    F32,
+    F64,
+    Other,
+}
+
+struct StrictVisitor<'a, V> {
+    inner: V,
+    target: Target,
+    lossy: &'a Lossy,
+}
+
+struct StrictSeed<'a, S> {
+    inner: S,
+    lossy: &'a Lossy,
+}
+
+impl<'a, V> StrictVisitor<'a, V> {
+    fn check_int<E: de::Error>(&self, v: i128) -> Result<(), E> {
+        let exact = match self.target {
+            Target::F32 => v as f32 as i128 == v,
+            Target::F64 => v as f64 as i128 == v,
+            Target::Other => true,
+        };
+        self.check(exact, v)
+    }
+
+    fn check_float<E: de::Error>(&self, v: f64) -> Result<(), E> {
+        let exact = match self.target {
+            Target::F32 => v.is_nan() || v as f32 as f64 == v,
+            Target::F64 | Target::Other => true,
+        };
+        self.check(exact, v)
+    }
+
+    fn check<E: de::Error>(&self, exact: bool, v: impl fmt::Display) -> Result<(), E> {
+        if exact {
+            return Ok(());
+        }
+        let target = match self.target {
+            Target::F32 => "f32",
+            _ => "f64",
+        };
+        *self.lossy.borrow_mut() = Some((v.to_string(), target));
+        Err(E::custom(format!(
+            "{} can't be represented exactly as {}",
+            v, target
+        )))
+    }
+}
+
+macro_rules! forward_deserialize {
+    ($($method:ident($($arg:ident: $ty:ty),*) => $target:ident;)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, $($arg: $ty,)* visitor: V) -> Result<V::Value, Self::Error> {
+                self.inner.$method($($arg,)* StrictVisitor {
+                    inner: visitor,
+                    target: Target::$target,
+                    lossy: self.lossy,
+                })
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, D: Deserializer<'de>> Deserializer<'de> for Strict<'a, D> {
+    type Error = D::Error;
+
+    forward_deserialize! {
+        deserialize_any() => Other;
+        deserialize_bool() => Other;
+        deserialize_i8() => Other;
+        deserialize_i16() => Other;
+        deserialize_i32() => Other;
+        deserialize_i64() => Other;
+        deserialize_i128() => Other;
+        deserialize_u8() => Other;
+        deserialize_u16() => Other;
+        deserialize_u32() => Other;
+        deserialize_u64() => Other;
+        deserialize_u128() => Other;
+        deserialize_f32() => F32;
+        deserialize_f64() => F64;
+        deserialize_char() => Other;
+        deserialize_str() => Other;
+        deserialize_string() => Other;
+        deserialize_bytes() => Other;
+        deserialize_byte_buf() => Other;
+        deserialize_option() => Other;
+        deserialize_unit() => Other;
+        deserialize_unit_struct(name: &'static str) => Other;
+        deserialize_newtype_struct(name: &'static str) => Other;
+        deserialize_seq() => Other;
+        deserialize_tuple(len: usize) => Other;
+        deserialize_tuple_struct(name: &'static str, len: usize) => Other;
+        deserialize_map() => Other;
+        deserialize_struct(name: &'static str, fields: &'static [&'static str]) => Other;
+        deserialize_enum(name: &'static str, variants: &'static [&'static str]) => Other;
+        deserialize_identifier() => Other;
+        deserialize_ignored_any() => Other;
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+macro_rules! visit_number {
+    ($($method:ident($ty:ty) => $check:ident as $as:ty;)*) => {
+        $(
+            fn $method<E: de::Error>(self, v: $ty) -> Result<Self::Value, E> {
+                self.$check(v as $as)?;
+                self.inner.$method(v)
+            }
+        )*
+    };
+}
+
+macro_rules! visit_nested {
+    ($($method:ident($trait:ident);)*) => {
+        $(
+            fn $method<A: de::$trait<'de>>(self, access: A) -> Result<Self::Value, A::Error> {
+                self.inner.$method(Strict {
+                    inner: access,
+                    lossy: self.lossy,
+                })
+            }
+        )*
+    };
+}
+
+impl<'de, 'a, V: Visitor<'de>> Visitor<'de> for StrictVisitor<'a, V> {
+    type Value = V::Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.expecting(f)
+    }
+
+    visit_number! {
+        visit_i8(i8) => check_int as i128;
+        visit_i16(i16) => check_int as i128;
+        visit_i32(i32) => check_int as i128;
+        visit_i64(i64) => check_int as i128;
+        visit_i128(i128) => check_int as i128;
+        visit_u8(u8) => check_int as i128;
+        visit_u16(u16) => check_int as i128;
+        visit_u32(u32) => check_int as i128;
+        visit_u64(u64) => check_int as i128;
+        visit_f32(f32) => check_float as f64;
+        visit_f64(f64) => check_float as f64;
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        self.check_int(i128::try_from(v).unwrap_or(i128::MAX))?;
+        self.inner.visit_u128(v)
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        self.inner.visit_bool(v)
+    }
+
+    fn visit_char<E: de::Error>(self, v: char) -> Result<Self::Value, E> {
+        self.inner.visit_char(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        self.inner.visit_str(v)
+    }
+
+    fn visit_borrowed_str<E: de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
+        self.inner.visit_borrowed_str(v)
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        self.inner.visit_string(v)
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        self.inner.visit_bytes(v)
+    }
+
+    fn visit_borrowed_bytes<E: de::Error>(self, v: &'de [u8]) -> Result<Self::Value, E> {
+        self.inner.visit_borrowed_bytes(v)
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        self.inner.visit_byte_buf(v)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_none()
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        self.inner.visit_unit()
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        self.inner.visit_some(Strict {
+            inner: d,
+            lossy: self.lossy,
+        })
+    }
+
+    fn visit_newtype_struct<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        self.inner.visit_newtype_struct(Strict {
+            inner: d,
+            lossy: self.lossy,
+        })
+    }
+
+    visit_nested! {
+        visit_seq(SeqAccess);
+        visit_map(MapAccess);
+        visit_enum(EnumAccess);
+    }
+}
+
+impl<'de, 'a, S: DeserializeSeed<'de>> DeserializeSeed<'de> for StrictSeed<'a, S> {
+    type Value = S::Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+        self.inner.deserialize(Strict {
+            inner: d,
+            lossy: self.lossy,
+        })
+    }
+}
+
+impl<'de, 'a, A: de::SeqAccess<'de>> de::SeqAccess<'de> for Strict<'a, A> {
+    type Error = A::Error;
+
+    fn next_element_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        self.inner.next_element_seed(StrictSeed {
+            inner: seed,
+            lossy: self.lossy,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 'a, A: de::MapAccess<'de>> de::MapAccess<'de> for Strict<'a, A> {
+    type Error = A::Error;
+
+    fn next_key_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<Option<S::Value>, Self::Error> {
+        self.inner.next_key_seed(StrictSeed {
+            inner: seed,
+            lossy: self.lossy,
+        })
+    }
+
+    fn next_value_seed<S: DeserializeSeed<'de>>(
+        &mut self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        self.inner.next_value_seed(StrictSeed {
+            inner: seed,
+            lossy: self.lossy,
+        })
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.inner.size_hint()
+    }
+}
+
+impl<'de, 'a, A: de::EnumAccess<'de>> de::EnumAccess<'de> for Strict<'a, A> {
+    type Error = A::Error;
+    type Variant = Strict<'a, A::Variant>;
+
+    fn variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<(S::Value, Self::Variant), Self::Error> {
+        let lossy = self.lossy;
+        let (value, variant) = self.inner.variant_seed(StrictSeed { inner: seed, lossy })?;
+        Ok((
+            value,
+            Strict {
+                inner: variant,
+                lossy,
+            },
+        ))
+    }
+}
+
+impl<'de, 'a, A: de::VariantAccess<'de>> de::VariantAccess<'de> for Strict<'a, A> {
+    type Error = A::Error;
+
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        self.inner.unit_variant()
+    }
+
+    fn newtype_variant_seed<S: DeserializeSeed<'de>>(
+        self,
+        seed: S,
+    ) -> Result<S::Value, Self::Error> {
+        self.inner.newtype_variant_seed(StrictSeed {
+            inner: seed,
+            lossy: self.lossy,
+        })
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(
+        self,
+        len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.inner.tuple_variant(
+            len,
+            StrictVisitor {
+                inner: visitor,
+                target: Target::Other,
+                lossy: self.lossy,
+            },
+        )
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.inner.struct_variant(
+            fields,
+            StrictVisitor {
+                inner: visitor,
+                target: Target::Other,
+                lossy: self.lossy,
+            },
+        )
+    }
+}
@@ -0,0 +1,577 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crate::{Database, Result, TableReadInterface, TableWriteInterface};
+
+/// A cloneable handle to a database shared between threads, \
+/// meant to be stored in the state of a web service (or any other multi-threaded app). \
+/// reads run concurrently with each other, writes run one at a time.
+///
+/// the closures passed to [`read()`](#method.read) and [`write()`](#method.write) must not call back into the handle, \
+/// doing so deadlocks.
+/// ```no_run
+/// # use dbless::{Database, DbHandle, TableReadInterface, TableWriteInterface};
+/// let handle = DbHandle::new(Database::open("my_database.db")?);
+///
+/// let worker = handle.clone();
+/// std::thread::spawn(move || worker.write(|db| db.set("visits", &1)));
+///
+/// let visits = handle.read(|db| db.get::<u32>("visits"))?;
+/// handle.shutdown();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Clone)]
+pub struct DbHandle {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    db: RwLock<Option<Database>>,
+    state: Mutex<State>,
+    drained: Condvar,
+    // the same locks as the store's, so they can be waited on without holding the database
+    locks: Arc<TableLocks>,
+}
+
+#[derive(Default)]
+struct State {
+    shutting_down: bool,
+    pending_writes: usize,
+}
+
+/// The error returned by a [`DbHandle`](struct.DbHandle.html) after [`shutdown()`](struct.DbHandle.html#method.shutdown) was called.
+/// ```no_run
+/// # use dbless::{Database, DbHandle, HandleShutDown, TableWriteInterface};
+/// let handle = DbHandle::new(Database::open("my_database.db")?);
+/// handle.shutdown();
+/// let result = handle.write(|db| db.set("key", &"value"));
+/// assert!(result.unwrap_err().downcast_ref::<HandleShutDown>().is_some());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HandleShutDown;
+
+impl fmt::Display for HandleShutDown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the database handle was shut down")
+    }
+}
+
+impl std::error::Error for HandleShutDown {}
+
+/// The error returned when writing to a table locked by another [`TableLockGuard`](struct.TableLockGuard.html), \
+/// either by [`DbHandle::try_lock_table()`](struct.DbHandle.html#method.try_lock_table), \
+/// or by a write made outside of the guard's [`write()`](struct.TableLockGuard.html#method.write). \
+/// nothing was written when it's returned.
+/// ```no_run
+/// # use dbless::{Database, DbHandle, TableLocked, TableWriteInterface};
+/// let handle = DbHandle::new(Database::open("my_database.db")?);
+/// let guard = handle.lock_table("users")?;
+/// let result = handle.write(|db| db.table_mut("users").set("alice", &"Alice"));
+/// assert!(result.unwrap_err().downcast_ref::<TableLocked>().is_some());
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableLocked {
+    /// The locked table, aliases resolved
+    pub table: String,
+}
+
+impl fmt::Display for TableLocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "table `{}` is locked by another writer", self.table)
+    }
+}
+
+impl std::error::Error for TableLocked {}
+
+// the ids of the lock guards, unique across handles
+static NEXT_LOCK_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    // the id of the lock guard whose `write()` is running on this thread, if any
+    static WRITING_GUARD: Cell<Option<u64>> = const { Cell::new(None) };
+}
+
+/// the tables locked with [`DbHandle::lock_table()`](struct.DbHandle.html#method.lock_table), \
+/// owned by the store which checks them before every write, and shared with the handles wrapping it
+#[derive(Default)]
+pub(crate) struct TableLocks {
+    // table -> the id of the guard holding it
+    held: Mutex<HashMap<String, u64>>,
+    released: Condvar,
+}
+
+impl TableLocks {
+    /// refuses to write to `table` while it's locked by a guard that isn't writing on this thread
+    pub(crate) fn ensure_unlocked(&self, table: &str) -> anyhow::Result<()> {
+        let held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        match held.get(table) {
+            Some(&id) if WRITING_GUARD.get() != Some(id) => Err(TableLocked {
+                table: table.to_string(),
+            }
+            .into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// refuses to write to every table at once while one of them is locked by a guard that isn't writing on this thread
+    pub(crate) fn ensure_none_locked(&self) -> anyhow::Result<()> {
+        let held = self.held.lock().unwrap_or_else(PoisonError::into_inner);
+        let writing = WRITING_GUARD.get();
+        match held.iter().find(|(_, &id)| writing != Some(id)) {
+            Some((table, _)) => Err(TableLocked {
+                table: table.clone(),
+            }
+            .into()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A lock on a table of a [`DbHandle`](struct.DbHandle.html), \
+/// taken with [`lock_table()`](struct.DbHandle.html#method.lock_table) and released when this is dropped. \
+/// while it's held, only the writes made through its [`write()`](#method.write) can change the table, \
+/// the other writes to it return a [`TableLocked`](struct.TableLocked.html) error before writing anything.
+///
+/// it's `Send`, so it can be held across an `.await` and dropped on another thread.
+pub struct TableLockGuard {
+    handle: DbHandle,
+    table: String,
+    id: u64,
+}
+
+impl TableLockGuard {
+    /// Gets the name of the locked table, aliases resolved
+    pub fn table(&self) -> &str {
+        &self.table
+    }
+
+    /// Runs `f` with write access to the database like [`DbHandle::write()`](struct.DbHandle.html#method.write), \
+    /// with the writes to the locked table allowed.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle, TableReadInterface, TableWriteInterface};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// let guard = handle.lock_table("counters")?;
+    /// let visits = guard.write(|db| db.table("counters").get::<u32>("visits"))?.unwrap_or(0);
+    /// guard.write(|db| db.table_mut("counters").set("visits", &(visits + 1)))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write<R>(&self, f: impl FnOnce(&mut Database) -> Result<R>) -> Result<R> {
+        self.handle.write(|db| {
+            // restored even if `f` panics, so the thread doesn't keep writing as this guard
+            struct Writing(Option<u64>);
+            impl Drop for Writing {
+                fn drop(&mut self) {
+                    WRITING_GUARD.set(self.0);
+                }
+            }
+            let _writing = Writing(WRITING_GUARD.replace(Some(self.id)));
+            f(db)
+        })
+    }
+}
+
+impl fmt::Debug for TableLockGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TableLockGuard")
+            .field("table", &self.table)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for TableLockGuard {
+    fn drop(&mut self) {
+        let locks = &self.handle.shared.locks;
+        locks
+            .held
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&self.table);
+        locks.released.notify_all();
+    }
+}
+
+/// A background thread removing expired entries from every table, \
+/// started with [`DbHandle::start_expiry_sweeper()`](struct.DbHandle.html#method.start_expiry_sweeper). \
+/// the thread is stopped when this is dropped.
+pub struct SweeperHandle {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+    purged: Arc<AtomicU64>,
+    last_error: Arc<Mutex<Option<String>>>,
+}
+
+impl SweeperHandle {
+    /// Gets how many entries the sweeper removed so far
+    pub fn purged(&self) -> u64 {
+        self.purged.load(Ordering::Relaxed)
+    }
+
+    /// Gets the last error the sweeper ran into while purging a table, if any. \
+    /// a table that fails is skipped, and tried again on the next sweep.
+    pub fn last_error(&self) -> Option<String> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Stops the sweeper, waiting for the running sweep (if any) to finish
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for SweeperHandle {
+    fn drop(&mut self) {
+        let (stopped, wake) = &*self.stop;
+        *stopped.lock().unwrap_or_else(PoisonError::into_inner) = true;
+        wake.notify_all();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// counts a write as pending until it's dropped, even if the closure panics
+struct PendingWrite<'a>(&'a Shared);
+
+impl Drop for PendingWrite<'_> {
+    fn drop(&mut self) {
+        let mut state = self.0.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.pending_writes -= 1;
+        if state.pending_writes == 0 {
+            self.0.drained.notify_all();
+        }
+    }
+}
+
+impl DbHandle {
+    /// Wraps a database in a handle
+    pub fn new(db: Database) -> Self {
+        let locks = Arc::clone(db.store.table_locks());
+        DbHandle {
+            shared: Arc::new(Shared {
+                db: RwLock::new(Some(db)),
+                state: Mutex::new(State::default()),
+                drained: Condvar::new(),
+                locks,
+            }),
+        }
+    }
+
+    /// Runs `f` with read access to the database, \
+    /// concurrently with other reads. \
+    /// returns a [`HandleShutDown`](struct.HandleShutDown.html) error if the handle was shut down.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle, TableReadInterface};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// let name = handle.read(|db| db.table("users").get::<String>("alice"))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn read<R>(&self, f: impl FnOnce(&Database) -> Result<R>) -> Result<R> {
+        // each operation commits on its own, so a panic in another closure can't leave the database half written
+        let db = self
+            .shared
+            .db
+            .read()
+            .unwrap_or_else(PoisonError::into_inner);
+        match db.as_ref() {
+            Some(db) => f(db),
+            None => Err(anyhow::Error::from(HandleShutDown).into()),
+        }
+    }
+
+    /// Runs `f` with write access to the database, \
+    /// after every other read and write running at the same time finished. \
+    /// returns a [`HandleShutDown`](struct.HandleShutDown.html) error if the handle is shutting down, \
+    /// a write that started before that is always run to the end.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle, TableWriteInterface};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// handle.write(|db| db.table_mut("users").set("alice", &"Alice"))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn write<R>(&self, f: impl FnOnce(&mut Database) -> Result<R>) -> Result<R> {
+        let _pending = {
+            let mut state = self
+                .shared
+                .state
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if state.shutting_down {
+                return Err(anyhow::Error::from(HandleShutDown).into());
+            }
+            state.pending_writes += 1;
+            PendingWrite(&self.shared)
+        };
+        let mut db = self
+            .shared
+            .db
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        match db.as_mut() {
+            Some(db) => f(db),
+            None => Err(anyhow::Error::from(HandleShutDown).into()),
+        }
+    }
+
+    /// Locks a table, waiting for the guard holding it (if any) to be dropped. \
+    /// while the returned guard is held, the table can only be written to through its [`write()`](struct.TableLockGuard.html#method.write), \
+    /// any other write to it, through this handle or its clones, returns a [`TableLocked`](struct.TableLocked.html) error before writing anything, \
+    /// as do the writes to every table at once, like [`delete_all_tables()`](struct.Database.html#method.delete_all_tables). \
+    /// the writers that want to wait for the table instead lock it first.
+    ///
+    /// it's purely in-process coordination: another process opening the same file doesn't see the lock. \
+    /// the lock isn't reentrant, locking a table again while holding its guard deadlocks, \
+    /// and waiting blocks the thread, async code should use [`try_lock_table()`](#method.try_lock_table) \
+    /// or call this from a blocking task. \
+    /// returns a [`HandleShutDown`](struct.HandleShutDown.html) error if the handle was shut down.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle, TableReadInterface, TableWriteInterface};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// let guard = handle.lock_table("accounts")?;
+    /// let balance = guard.write(|db| db.table("accounts").get::<i64>("alice"))?.unwrap_or(0);
+    /// // ... talk to a payment provider, the other writers can't change alice's balance meanwhile
+    /// guard.write(|db| db.table_mut("accounts").set("alice", &(balance - 10)))?;
+    /// drop(guard);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn lock_table(&self, name: &str) -> Result<TableLockGuard> {
+        let table = self.read(|db| Ok(db.store.resolve(name).into_owned()))?;
+        let locks = &self.shared.locks;
+        let mut held = locks.held.lock().unwrap_or_else(PoisonError::into_inner);
+        while held.contains_key(&table) {
+            held = locks
+                .released
+                .wait(held)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        Ok(self.hold(&mut held, table))
+    }
+
+    /// Locks a table like [`lock_table()`](#method.lock_table), \
+    /// returning a [`TableLocked`](struct.TableLocked.html) error instead of waiting if it's already locked.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle, TableLocked};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// let guard = handle.try_lock_table("accounts")?;
+    /// let result = handle.try_lock_table("accounts");
+    /// assert!(result.unwrap_err().downcast_ref::<TableLocked>().is_some());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn try_lock_table(&self, name: &str) -> Result<TableLockGuard> {
+        let table = self.read(|db| Ok(db.store.resolve(name).into_owned()))?;
+        let mut held = self
+            .shared
+            .locks
+            .held
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if held.contains_key(&table) {
+            return Err(anyhow::Error::from(TableLocked { table }).into());
+        }
+        Ok(self.hold(&mut held, table))
+    }
+
+    fn hold(&self, held: &mut HashMap<String, u64>, table: String) -> TableLockGuard {
+        let id = NEXT_LOCK_ID.fetch_add(1, Ordering::Relaxed);
+        held.insert(table.clone(), id);
+        TableLockGuard {
+            handle: self.clone(),
+            table,
+            id,
+        }
+    }
+
+    /// Checks that the database can be read, with a single cheap read. \
+    /// see [`health_check()`](#method.health_check) to also check that it's writable.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// let healthy = handle.ping().is_ok();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn ping(&self) -> Result<()> {
+        self.read(|db| db.len().map(drop))
+    }
+
+    /// Checks that the database is readable and writable, \
+    /// see [`Database::health_check()`](struct.Database.html#method.health_check).
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// handle.health_check()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn health_check(&self) -> Result<()> {
+        self.write(|db| db.health_check())
+    }
+
+    /// Makes the database read-only, see [`Database::freeze()`](struct.Database.html#method.freeze). \
+    /// it runs like a read, alongside other reads and after the running write (if any), \
+    /// then every write returns a [`Frozen`](struct.Frozen.html) error, through this handle and all of its clones.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// handle.freeze_with_reason("maintenance in progress")?;
+    /// // ... back up the database
+    /// handle.unfreeze()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn freeze(&self) -> Result<()> {
+        self.read(|db| {
+            db.store.freeze(None);
+            Ok(())
+        })
+    }
+
+    /// Makes the database read-only with a reason carried by the [`Frozen`](struct.Frozen.html) errors, \
+    /// see [`freeze()`](#method.freeze)
+    pub fn freeze_with_reason(&self, reason: &str) -> Result<()> {
+        self.read(|db| {
+            db.store.freeze(Some(reason.to_string()));
+            Ok(())
+        })
+    }
+
+    /// Makes the database writable again, see [`Database::unfreeze()`](struct.Database.html#method.unfreeze)
+    pub fn unfreeze(&self) -> Result<()> {
+        self.read(|db| {
+            db.store.unfreeze();
+            Ok(())
+        })
+    }
+
+    /// Checks if the database is frozen, see [`freeze()`](#method.freeze)
+    pub fn is_frozen(&self) -> Result<bool> {
+        self.read(|db| Ok(db.is_frozen()))
+    }
+
+    /// Starts a thread that calls [`purge_expired()`](trait.TableWriteInterface.html#method.purge_expired) \
+    /// on every table (the default one included) every `interval`, until the returned handle is dropped. \
+    /// each table is purged in its own [`write()`](#method.write), so reads and other writes get to run in between. \
+    /// a table that fails to purge (a frozen database, an append-only table, ...) is skipped, \
+    /// see [`SweeperHandle::last_error()`](struct.SweeperHandle.html#method.last_error). \
+    /// the thread also stops once the handle is [shut down](#method.shutdown).
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle, TableWriteInterface};
+    /// # use std::time::Duration;
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// let sweeper = handle.start_expiry_sweeper(Duration::from_secs(60));
+    /// handle.write(|db| db.table_mut("sessions").set_with_ttl("abc", &"alice", Duration::from_secs(3600)))?;
+    /// // ... serve requests
+    /// sweeper.stop();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn start_expiry_sweeper(&self, interval: Duration) -> SweeperHandle {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let purged = Arc::new(AtomicU64::new(0));
+        let last_error = Arc::new(Mutex::new(None));
+        let thread = {
+            let handle = self.clone();
+            let stop = Arc::clone(&stop);
+            let purged = Arc::clone(&purged);
+            let last_error = Arc::clone(&last_error);
+            std::thread::spawn(move || loop {
+                {
+                    let (stopped, wake) = &*stop;
+                    let stopped = stopped.lock().unwrap_or_else(PoisonError::into_inner);
+                    let (stopped, _) = wake
+                        .wait_timeout_while(stopped, interval, |stopped| !*stopped)
+                        .unwrap_or_else(PoisonError::into_inner);
+                    if *stopped {
+                        return;
+                    }
+                }
+                let tables = handle.read(|db| {
+                    let mut tables = db.list_tables()?;
+                    tables.push(db.default_table().name().to_string());
+                    Ok(tables)
+                });
+                let tables = match tables {
+                    Ok(tables) => tables,
+                    Err(e) if e.is::<HandleShutDown>() => return,
+                    Err(e) => {
+                        *last_error.lock().unwrap_or_else(PoisonError::into_inner) =
+                            Some(format!("{:#}", e));
+                        continue;
+                    }
+                };
+                for table in tables {
+                    match handle.write(|db| db.table_mut(&table).purge_expired()) {
+                        Ok(removed) => {
+                            purged.fetch_add(removed as u64, Ordering::Relaxed);
+                        }
+                        Err(e) if e.is::<HandleShutDown>() => return,
+                        Err(e) => {
+                            *last_error.lock().unwrap_or_else(PoisonError::into_inner) =
+                                Some(format!("{:#}", e));
+                        }
+                    }
+                }
+            })
+        };
+        SweeperHandle {
+            stop,
+            thread: Some(thread),
+            purged,
+            last_error,
+        }
+    }
+
+    /// Checks if [`shutdown()`](#method.shutdown) was called on this handle or one of its clones
+    pub fn is_shut_down(&self) -> bool {
+        let state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.shutting_down
+    }
+
+    /// Stops accepting writes, waits for the writes that already started to finish, then closes the database. \
+    /// every read and write after this returns a [`HandleShutDown`](struct.HandleShutDown.html) error, \
+    /// through this handle and all of its clones. \
+    /// calling it again does nothing.
+    ///
+    /// calling this from inside a [`write()`](#method.write) closure deadlocks.
+    /// ```no_run
+    /// # use dbless::{Database, DbHandle};
+    /// let handle = DbHandle::new(Database::open("my_database.db")?);
+    /// // ... serve requests
+    /// handle.shutdown();
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn shutdown(&self) {
+        let mut state = self
+            .shared
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        state.shutting_down = true;
+        while state.pending_writes > 0 {
+            state = self
+                .shared
+                .drained
+                .wait(state)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        drop(state);
+        let db = self
+            .shared
+            .db
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(db) = db {
+            db.close();
+        }
+    }
+}
@@ -1,178 +1,4282 @@
+use std::any::type_name;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::ops::Bound;
+#[cfg(feature = "recovery")]
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "service")]
+use std::sync::Arc;
+use std::sync::{Mutex, MutexGuard, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-use anyhow::Result;
-use redb::{backends::InMemoryBackend, Builder, Database, TableError, TableHandle};
-use redb::{ReadableTable, ReadableTableMetadata, TableDefinition};
-use serde::{de::DeserializeOwned, Serialize};
+use anyhow::{bail, ensure, Context, Result};
+use redb::MultimapTableHandle;
+use redb::{backends::InMemoryBackend, Builder, Database, StorageBackend, TableError, TableHandle};
+use redb::{ReadOnlyTable, ReadTransaction, ReadableTable, ReadableTableMetadata};
+use redb::{TableDefinition, TableStats, WriteTransaction};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
-pub struct Store(Database);
+use crate::annotation::{ReservedKind, TableKind, TableNotes};
+use crate::batch::BatchOp;
+use crate::envelope::{
+    decode_blob_ref, decode_counter, decode_raw, encode_blob_ref, encode_counter, encode_raw,
+    Envelope, BLOB_REF_LEN,
+};
+use crate::error::{Missing, SerializeFailed};
+use crate::escape::{escape_key, unescape_key};
+#[cfg(feature = "csv")]
+use crate::export::{csv_columns, write_csv_field};
+#[cfg(feature = "json")]
+use crate::export::{msgpack_bin, DumpEntry, DumpValue};
+use crate::export::{
+    plain_value, write_dump_value, write_json, write_json_str, write_msgpack_map_len,
+    write_msgpack_str, CountingWriter,
+};
+use crate::msgpack::{self, Reader, Token};
+#[cfg(feature = "recovery")]
+use crate::recovery::SalvagedTable;
+use crate::reserved::ReservedNames;
+#[cfg(feature = "csv")]
+use crate::serde::serialize_for_key;
+use crate::serde::{
+    deserialize, deserialize_as, from_msgpack, serialize, serialize_for_key_as, serialized_size_as,
+    to_msgpack,
+};
+#[cfg(feature = "service")]
+use crate::service::TableLocks;
+use crate::transform::TransformChain;
+use crate::{
+    AppendOnlyViolation, CappedLen, ChainReport, ConvertProgress, ConvertReport, DblessError,
+    EvictionPolicy, ExportFormat, FieldPredicate, ImmutableKey, KeyedResult, LoadTiming, Lookup,
+    Options, RemoveReport, SerializationFormat, SerializeErrorPolicy, TableInfo, TableLimit,
+    TableManifest, Value, Versioned, VersionedOutcome,
+};
 
-fn serialize<T: Serialize>(value: &T) -> Result<Vec<u8>> {
-    let mut serializer = rmp_serde::Serializer::new(vec![]).with_struct_map();
-    value.serialize(&mut serializer)?;
-    Ok(serializer.into_inner())
+/// The error returned when a value doesn't read back as it was just written, \
+/// only returned by databases opened with [`Options::verify_writes()`](struct.Options.html#method.verify_writes). \
+/// the write transaction is aborted, so the value isn't stored.
+/// ```no_run
+/// # use dbless::{Database, TableWriteInterface, WriteVerificationFailed};
+/// let mut db = Database::options().verify_writes(true).open("my_database.db")?;
+/// if let Err(e) = db.set("key", &"value") {
+///     if let Some(e) = e.downcast_ref::<WriteVerificationFailed>() {
+///         eprintln!("storage is misbehaving: {}", e);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteVerificationFailed {
+    /// The table the value was written to
+    pub table: String,
+    /// The key of the value
+    pub key: String,
 }
 
-fn deserialize<T: DeserializeOwned>(value: &[u8]) -> Result<T> {
-    Ok(rmp_serde::from_slice(value)?)
+impl fmt::Display for WriteVerificationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "value of key `{}` in table `{}` didn't read back as written",
+            self.key, self.table
+        )
+    }
+}
+
+impl std::error::Error for WriteVerificationFailed {}
+
+/// The error returned by every write to a database frozen with \
+/// [`Database::freeze()`](struct.Database.html#method.freeze), until it's unfrozen. \
+/// nothing is written.
+/// ```no_run
+/// # use dbless::{Database, Frozen, TableWriteInterface};
+/// let mut db = Database::open("my_database.db")?;
+/// db.freeze_with_reason("maintenance in progress");
+/// if let Err(e) = db.set("key", &"value") {
+///     if let Some(e) = e.downcast_ref::<Frozen>() {
+///         println!("try again later: {}", e);
+///     }
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frozen {
+    /// The reason given when the database was frozen, if any
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for Frozen {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.reason {
+            Some(reason) => write!(f, "the database is read-only: {}", reason),
+            None => write!(f, "the database is read-only"),
+        }
+    }
+}
+
+impl std::error::Error for Frozen {}
+
+/// A lazy iterator over the entries of a table, \
+/// returned by [`iter_entries()`](trait.TableReadInterface.html#method.iter_entries). \
+/// it holds a read transaction open until it's dropped, so it sees the table as it was when it was created.
+pub struct EntriesIter<'a, T> {
+    store: &'a Store,
+    name: String,
+    // both keep the read transaction alive
+    range: Option<redb::Range<'static, &'static str, &'static [u8]>>,
+    blobs: Option<ReadOnlyTable<&'static [u8], &'static [u8]>>,
+    _value: PhantomData<T>,
+}
+
+impl<'a, T: DeserializeOwned> EntriesIter<'a, T> {
+    /// the entry, or `None` if it expired
+    fn entry(&self, key: &str, value: &[u8]) -> Result<Option<(String, T)>> {
+        let value = resolve_value(self.blobs.as_ref(), &self.store.options.transforms, value)?;
+        if is_expired(&value, now_millis())? {
+            return Ok(None);
+        }
+        let value = self
+            .store
+            .deserialize(key, &value)
+            .with_context(|| format!("failed to deserialize value of key `{}`", key))?;
+        Ok(Some((self.store.user_key(&self.name, key)?, value)))
+    }
+}
+
+impl<'a, T: DeserializeOwned> Iterator for EntriesIter<'a, T> {
+    type Item = Result<(String, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (key, value) = match self.range.as_mut()?.next()? {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e.into())),
+            };
+            match self.entry(key.value(), value.value()) {
+                Ok(None) => continue,
+                entry => return entry.transpose(),
+            }
+        }
+    }
+}
+
+/// A lazy iterator over the keys of a table, \
+/// returned by [`iter_keys()`](trait.TableReadInterface.html#method.iter_keys). \
+/// it holds a read transaction open until it's dropped, so it sees the table as it was when it was created.
+pub struct KeysIter<'a> {
+    store: &'a Store,
+    name: String,
+    // keeps the read transaction alive
+    range: Option<redb::Range<'static, &'static str, &'static [u8]>>,
+}
+
+impl Iterator for KeysIter<'_> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (key, _) = match self.range.as_mut()?.next()? {
+            Ok(entry) => entry,
+            Err(e) => return Some(Err(e.into())),
+        };
+        Some(self.store.user_key(&self.name, key.value()))
+    }
+}
+
+pub struct Store {
+    db: Database,
+    options: Options,
+    // the format set in the options, or saved in the database
+    format: SerializationFormat,
+    // behind a lock so tables can be deleted and cleared through `&self`
+    settings: RwLock<Settings>,
+    // held by the writes made through `&self`, so their settings changes don't interleave
+    maintenance: Mutex<()>,
+    error_context: Option<fn(&str) -> String>,
+    reserved: ReservedNames,
+    // the names of the user tables, if cached with `Options::cache_table_names()`, dropped by every write transaction
+    table_names: Mutex<Option<HashSet<String>>>,
+    // the number of entries of every table counted since the last write, if cached with `Options::cache_table_lengths()`, \
+    // dropped by every write transaction
+    table_lengths: Mutex<HashMap<String, usize>>,
+    // opened with `Store::in_memory()`, whose page layout says nothing about a file
+    in_memory: bool,
+    // set by `Database::freeze()`, checked before every write transaction, \
+    // atomic so it can be flipped while the database is shared
+    frozen: AtomicBool,
+    frozen_reason: Mutex<Option<String>>,
+    // the tables locked through a `DbHandle`, checked before writing to a table
+    #[cfg(feature = "service")]
+    table_locks: Arc<TableLocks>,
+}
+
+/// the settings of every table, mirroring the reserved tables they're saved in
+struct Settings {
+    // alias -> target, mirrors the aliases table
+    aliases: HashMap<String, String>,
+    // table -> limit, mirrors the limits table
+    limits: HashMap<String, TableLimit>,
+    // tables whose keys are escaped transparently, mirrors the escaped table
+    escaped: HashSet<String>,
+    // append-only chained tables, mirrors the chained table
+    chained: HashSet<String>,
+    // tables storing each distinct value once, mirrors the deduplicated table
+    deduplicated: HashSet<String>,
+    // tables with a description or annotations, mirrors the keys of the annotations table
+    annotated: HashSet<String>,
+    // table -> how its keys are protected, mirrors the write once table
+    write_once: HashMap<String, WriteOnce>,
+}
+
+/// how the keys of a table are protected, saved in the write once table under the table's name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+enum WriteOnce {
+    /// keys can't be overwritten
+    InsertOnly,
+    /// keys can't be overwritten or removed
+    AppendOnly,
+}
+
+impl Settings {
+    /// drops the settings of a deleted table
+    fn forget(&mut self, name: &str) {
+        self.limits.remove(name);
+        self.escaped.remove(name);
+        self.chained.remove(name);
+        self.deduplicated.remove(name);
+        self.annotated.remove(name);
+        self.write_once.remove(name);
+    }
+
+    /// moves the settings of a renamed table to its new name
+    fn rename(&mut self, from: &str, to: &str) {
+        if let Some(limit) = self.limits.remove(from) {
+            self.limits.insert(to.to_string(), limit);
+        }
+        if let Some(write_once) = self.write_once.remove(from) {
+            self.write_once.insert(to.to_string(), write_once);
+        }
+        for names in [
+            &mut self.escaped,
+            &mut self.chained,
+            &mut self.deduplicated,
+            &mut self.annotated,
+        ] {
+            if names.remove(from) {
+                names.insert(to.to_string());
+            }
+        }
+    }
+}
+
+/// copies every entry of `from` into `to` as stored, then deletes `from`, \
+/// for the tables of any key and value types
+fn rename_in<K: redb::Key + 'static, V: redb::Value + 'static>(
+    tnx: &WriteTransaction,
+    from: TableDefinition<K, V>,
+    to: TableDefinition<K, V>,
+) -> Result<()> {
+    {
+        let source = match tnx.open_table(from) {
+            Ok(source) => source,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut target = tnx.open_table(to)?;
+        for entry in source.iter()? {
+            let (key, value) = entry?;
+            target.insert(key.value(), value.value())?;
+        }
+    }
+    tnx.delete_table(from)?;
+    Ok(())
+}
+
+/// the smallest string greater than every string starting with `prefix`, \
+/// `None` if there's none (the prefix is empty or only made of `char::MAX`), \
+/// redb compares `&str` keys by their utf-8 bytes, which sorts them the same as their chars
+fn prefix_end(prefix: &str) -> Option<String> {
+    let mut end = prefix.to_string();
+    while let Some(last) = end.pop() {
+        let next = match last {
+            '\u{D7FF}' => Some('\u{E000}'),
+            last => char::from_u32(last as u32 + 1),
+        };
+        if let Some(next) = next {
+            end.push(next);
+            return Some(end);
+        }
+    }
+    None
+}
+
+/// the range of the keys starting with `prefix`
+fn prefix_range<'p>(prefix: &'p str, end: &'p Option<String>) -> (Bound<&'p str>, Bound<&'p str>) {
+    let end = match end {
+        Some(end) => Bound::Excluded(end.as_str()),
+        None => Bound::Unbounded,
+    };
+    (Bound::Included(prefix), end)
+}
+
+fn version_of_value(bytes: &[u8]) -> Result<u64> {
+    Ok(Envelope::decode(bytes)?.version.unwrap_or(0))
+}
+
+fn counter_in(key: &str, bytes: &[u8]) -> Result<u64> {
+    decode_counter(bytes).with_context(|| format!("value of key `{}` is not a u64 counter", key))
+}
+
+fn digest_table(
+    table: &impl ReadableTable<&'static str, &'static [u8]>,
+    blobs: Option<&impl ReadableTable<&'static [u8], &'static [u8]>>,
+    transforms: &TransformChain,
+) -> Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    for entry in table.iter()? {
+        let (key, value) = entry?;
+        let value = resolve_value(blobs, transforms, value.value())?;
+        hash_entry(&mut hasher, key.value().as_bytes(), &value);
+    }
+    Ok(hasher.finalize().into())
+}
+
+fn hash_entry(hasher: &mut Sha256, key: &[u8], value: &[u8]) {
+    hasher.update((key.len() as u64).to_be_bytes());
+    hasher.update(key);
+    hasher.update((value.len() as u64).to_be_bytes());
+    hasher.update(value);
+}
+
+fn summarize_table(
+    table: &impl ReadableTable<&'static str, &'static [u8]>,
+    blobs: Option<&impl ReadableTable<&'static [u8], &'static [u8]>>,
+    transforms: &TransformChain,
+) -> Result<TableManifest> {
+    let mut hasher = Sha256::new();
+    let (mut entries, mut bytes) = (0, 0);
+    for entry in table.iter()? {
+        let (key, value) = entry?;
+        let value = resolve_value(blobs, transforms, value.value())?;
+        let (key, value) = (key.value().as_bytes(), &*value);
+        hash_entry(&mut hasher, key, value);
+        entries += 1;
+        bytes += (key.len() + value.len()) as u64;
+    }
+    let digest = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    Ok(TableManifest {
+        entries,
+        bytes,
+        digest,
+        description: None,
+        annotations: BTreeMap::new(),
+    })
+}
+
+macro_rules! open_table_read_or {
+    ($tnx:expr, $table:expr, $or:expr) => {
+        match $tnx.open_table(TableDefinition::<&str, &[u8]>::new($table)) {
+            Ok(table) => table,
+            Err(e) => match e {
+                TableError::TableDoesNotExist(_) => return Ok($or),
+                _ => return Err(e.into()),
+            },
+        }
+    };
+}
+
+fn load_aliases(db: &Database, reserved: &ReservedNames) -> Result<HashMap<String, String>> {
+    let tnx = db.begin_read()?;
+    let table = open_table_read_or!(tnx, &reserved.aliases, HashMap::new());
+    let mut aliases = HashMap::new();
+    for entry in table.iter()? {
+        let (alias, target) = entry?;
+        aliases.insert(
+            alias.value().to_string(),
+            deserialize(alias.value(), target.value(), false)?,
+        );
+    }
+    Ok(aliases)
+}
+
+// the key of the metadata table the format of the values is saved under
+const FORMAT_KEY: &str = "format";
+// the keys of the metadata table a running `convert_format()` keeps its target format and last converted entry under
+const CONVERT_TO_KEY: &str = "convert_to";
+const CONVERT_CURSOR_KEY: &str = "convert_cursor";
+
+/// the name of the format saved in the metadata table, if any
+fn saved_format(db: &Database, reserved: &ReservedNames) -> Result<Option<String>> {
+    let tnx = db.begin_read()?;
+    let table = open_table_read_or!(tnx, &reserved.metadata, None);
+    let Some(name) = table.get(FORMAT_KEY)? else {
+        return Ok(None);
+    };
+    Ok(Some(deserialize(FORMAT_KEY, name.value(), false)?))
+}
+
+/// the format the values of the database are stored in, \
+/// saved in the metadata table the first time the database is opened, \
+/// `requested` being the format set in the options, if any
+fn load_format(
+    db: &Database,
+    reserved: &ReservedNames,
+    requested: Option<SerializationFormat>,
+) -> Result<SerializationFormat> {
+    if let Some(saved) = saved_format(db, reserved)? {
+        let saved = SerializationFormat::from_name(&saved)?;
+        if let Some(requested) = requested {
+            ensure!(
+                requested == saved,
+                "the database stores its values as {}, it can't be opened as {}",
+                saved.name(),
+                requested.name()
+            );
+        }
+        return Ok(saved);
+    }
+    let format = requested.unwrap_or_default();
+    let tnx = db.begin_write()?;
+    {
+        let mut metadata =
+            tnx.open_table(TableDefinition::<&str, &[u8]>::new(&reserved.metadata))?;
+        metadata.insert(FORMAT_KEY, serialize(&format.name())?.as_slice())?;
+    }
+    tnx.commit()?;
+    Ok(format)
+}
+
+/// loads the settings saved under the table names in a metadata table
+fn load_settings<T: DeserializeOwned>(
+    db: &Database,
+    metadata_table: &str,
+) -> Result<HashMap<String, T>> {
+    let tnx = db.begin_read()?;
+    let table = open_table_read_or!(tnx, metadata_table, HashMap::new());
+    let mut settings = HashMap::new();
+    for entry in table.iter()? {
+        let (name, setting) = entry?;
+        settings.insert(
+            name.value().to_string(),
+            deserialize(name.value(), setting.value(), false)?,
+        );
+    }
+    Ok(settings)
+}
+
+/// loads the table names saved as keys of a metadata table
+fn load_name_set(db: &Database, metadata_table: &str) -> Result<HashSet<String>> {
+    let tnx = db.begin_read()?;
+    let table = open_table_read_or!(tnx, metadata_table, HashSet::new());
+    let mut names = HashSet::new();
+    for entry in table.iter()? {
+        names.insert(entry?.0.value().to_string());
+    }
+    Ok(names)
 }
 
-macro_rules! open_table_read_or {
-    ($tnx:expr, $table:expr, $or:expr) => {
-        match $tnx.open_table(TableDefinition::<&str, &[u8]>::new($table)) {
-            Ok(table) => table,
-            Err(e) => match e {
-                TableError::TableDoesNotExist(_) => return Ok($or),
-                _ => return Err(e.into()),
-            },
+// the blobs table of a deduplicated table maps the SHA-256 of a value's stored bytes
+// to the number of keys referencing it (a big-endian u64), followed by the bytes.
+fn blob_refs(blob: &[u8]) -> Result<u64> {
+    let refs = blob.get(..8).context("a deduplicated value is corrupted")?;
+    Ok(u64::from_be_bytes(refs.try_into()?))
+}
+
+/// the bytes a stored value stands for, \
+/// looked up in `blobs` if it's a reference to a value of a deduplicated table
+fn resolve_blob<'v>(
+    blobs: Option<&impl ReadableTable<&'static [u8], &'static [u8]>>,
+    bytes: &'v [u8],
+) -> Result<Cow<'v, [u8]>> {
+    let (Some(blobs), Some(hash)) = (blobs, decode_blob_ref(bytes)) else {
+        return Ok(Cow::Borrowed(bytes));
+    };
+    let blob = blobs
+        .get(hash.as_slice())?
+        .context("a deduplicated value is missing from the blobs of its table")?;
+    blob_refs(blob.value())?;
+    Ok(Cow::Owned(blob.value()[8..].to_vec()))
+}
+
+/// the size of a value as stored, counting the blob of a deduplicated value instead of its reference
+fn stored_len(
+    blobs: Option<&impl ReadableTable<&'static [u8], &'static [u8]>>,
+    bytes: &[u8],
+) -> Result<usize> {
+    let (Some(blobs), Some(hash)) = (blobs, decode_blob_ref(bytes)) else {
+        return Ok(bytes.len());
+    };
+    let blob = blobs
+        .get(hash.as_slice())?
+        .context("a deduplicated value is missing from the blobs of its table")?;
+    Ok(blob.value().len().saturating_sub(8))
+}
+
+/// the bytes a stored value stands for, with its blob resolved and its transforms reversed
+fn resolve_value<'v>(
+    blobs: Option<&impl ReadableTable<&'static [u8], &'static [u8]>>,
+    transforms: &TransformChain,
+    bytes: &'v [u8],
+) -> Result<Cow<'v, [u8]>> {
+    match resolve_blob(blobs, bytes)? {
+        Cow::Borrowed(bytes) => transforms.reverse(bytes),
+        Cow::Owned(bytes) => Ok(Cow::Owned(transforms.reverse(&bytes)?.into_owned())),
+    }
+}
+
+/// the current time in milliseconds since the unix epoch, what expiries are compared to
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| u64::try_from(now.as_millis()).unwrap_or(u64::MAX))
+}
+
+/// checks if a resolved value was written with a time to live that ended at or before `now`
+fn is_expired(bytes: &[u8], now: u64) -> Result<bool> {
+    Ok(Envelope::decode(bytes)?.is_expired(now))
+}
+
+fn chain_hash(previous: &[u8], key: &str, value: &[u8]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    hasher.update(previous);
+    hash_entry(&mut hasher, key.as_bytes(), value);
+    hasher.finalize().into()
+}
+
+/// moves the key to the end of the order
+fn touch(
+    order: &mut redb::Table<u64, &'static str>,
+    stamps: &mut redb::Table<&'static str, u64>,
+    key: &str,
+) -> Result<()> {
+    let next = order
+        .last()?
+        .map(|(stamp, _)| stamp.value() + 1)
+        .unwrap_or(0);
+    let old = stamps.insert(key, next)?.map(|stamp| stamp.value());
+    if let Some(old) = old {
+        order.remove(old)?;
+    }
+    order.insert(next, key)?;
+    Ok(())
+}
+
+/// removes the oldest entry of the table that is still in the order
+fn evict_oldest(
+    table: &mut redb::Table<&'static str, &'static [u8]>,
+    order: &mut redb::Table<u64, &'static str>,
+    stamps: &mut redb::Table<&'static str, u64>,
+) -> Result<()> {
+    loop {
+        let (stamp, key) = match order.pop_first()? {
+            Some((stamp, key)) => (stamp.value(), key.value().to_string()),
+            None => bail!("no entry left to evict, the order of the table is out of sync"),
+        };
+        if stamps.get(key.as_str())?.map(|s| s.value()) != Some(stamp) {
+            continue;
+        }
+        stamps.remove(key.as_str())?;
+        if table.remove(key.as_str())?.is_some() {
+            return Ok(());
+        }
+    }
+}
+
+impl Store {
+    // the chain table maps an insertion number to the hash of the chain up to that entry (32 bytes),
+    // followed by the entry's key. each hash covers the previous hash, the key and the stored value.
+    fn chain_table_name(&self, name: &str) -> String {
+        format!("{}{}", self.reserved.chain_prefix, name)
+    }
+
+    // the order table maps a stamp to its key (oldest first), the stamps table maps a key to its stamp.
+    // removing a key doesn't touch them, their stale entries are skipped (and dropped) when evicting.
+    fn order_table_names(&self, name: &str) -> (String, String) {
+        (
+            format!("{}{}", self.reserved.order_prefix, name),
+            format!("{}{}", self.reserved.stamps_prefix, name),
+        )
+    }
+
+    fn blobs_table_name(&self, name: &str) -> String {
+        format!("{}{}", self.reserved.blobs_prefix, name)
+    }
+
+    pub fn reserved(&self) -> &ReservedNames {
+        &self.reserved
+    }
+
+    pub fn file(path: impl AsRef<Path>, options: Options) -> Result<Self> {
+        Self::with_db(Database::create(path)?, options)
+    }
+
+    pub fn in_memory(options: Options) -> Result<Self> {
+        let mut store = Self::with_backend(InMemoryBackend::new(), options)?;
+        store.in_memory = true;
+        Ok(store)
+    }
+
+    pub fn with_backend(backend: impl StorageBackend, options: Options) -> Result<Self> {
+        Self::with_db(Builder::new().create_with_backend(backend)?, options)
+    }
+
+    fn with_db(db: Database, options: Options) -> Result<Self> {
+        let reserved = ReservedNames::new(options.reserved_prefix.as_deref())?;
+        options.transforms.validate()?;
+        let format = load_format(&db, &reserved, options.format)?;
+        let aliases = load_aliases(&db, &reserved)?;
+        let limits = load_settings(&db, &reserved.limits)?;
+        let escaped = load_name_set(&db, &reserved.escaped)?;
+        let chained = load_name_set(&db, &reserved.chained)?;
+        let deduplicated = load_name_set(&db, &reserved.deduplicated)?;
+        let annotated = load_name_set(&db, &reserved.annotations)?;
+        let write_once = load_settings(&db, &reserved.write_once)?;
+        Ok(Store {
+            db,
+            options,
+            format,
+            settings: RwLock::new(Settings {
+                aliases,
+                limits,
+                escaped,
+                chained,
+                deduplicated,
+                annotated,
+                write_once,
+            }),
+            maintenance: Mutex::new(()),
+            error_context: None,
+            reserved,
+            table_names: Mutex::new(None),
+            table_lengths: Mutex::new(HashMap::new()),
+            in_memory: false,
+            frozen: AtomicBool::new(false),
+            frozen_reason: Mutex::new(None),
+            #[cfg(feature = "service")]
+            table_locks: Arc::default(),
+        })
+    }
+
+    /// begins a write transaction, which may create, delete or resize tables, so the cached table names and lengths are dropped
+    fn begin_write(&self) -> Result<WriteTransaction> {
+        self.ensure_not_frozen()?;
+        self.drop_caches();
+        Ok(self.db.begin_write()?)
+    }
+
+    /// begins a write transaction changing the table `name`, see `ensure_unlocked()`
+    fn begin_write_to(&self, name: &str) -> Result<WriteTransaction> {
+        self.ensure_unlocked(name)?;
+        self.begin_write()
+    }
+
+    /// refuses to write to a table locked by another writer with `DbHandle::lock_table()`
+    fn ensure_unlocked(&self, name: &str) -> Result<()> {
+        #[cfg(feature = "service")]
+        self.table_locks.ensure_unlocked(name)?;
+        #[cfg(not(feature = "service"))]
+        let _ = name;
+        Ok(())
+    }
+
+    /// refuses to write to every table at once while another writer locked one with `DbHandle::lock_table()`
+    fn ensure_none_locked(&self) -> Result<()> {
+        #[cfg(feature = "service")]
+        self.table_locks.ensure_none_locked()?;
+        Ok(())
+    }
+
+    #[cfg(feature = "service")]
+    pub fn table_locks(&self) -> &Arc<TableLocks> {
+        &self.table_locks
+    }
+
+    fn ensure_not_frozen(&self) -> Result<()> {
+        match self.frozen.load(Ordering::Acquire) {
+            true => Err(Frozen {
+                reason: self.frozen_reason(),
+            }
+            .into()),
+            false => Ok(()),
+        }
+    }
+
+    pub fn freeze(&self, reason: Option<String>) {
+        *self
+            .frozen_reason
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = reason;
+        self.frozen.store(true, Ordering::Release);
+    }
+
+    pub fn unfreeze(&self) {
+        self.frozen.store(false, Ordering::Release);
+        *self
+            .frozen_reason
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::Acquire)
+    }
+
+    pub fn frozen_reason(&self) -> Option<String> {
+        self.frozen_reason
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    pub fn drop_caches(&self) {
+        if self.options.cache_table_names {
+            *self
+                .table_names
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = None;
+        }
+        if self.options.cache_table_lengths {
+            self.table_lengths
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .clear();
+        }
+    }
+
+    /// serializes the writes made through `&self` (deleting and clearing tables), \
+    /// which would otherwise read and change the settings of a table while another one deletes it
+    fn lock_maintenance(&self) -> MutexGuard<'_, ()> {
+        self.maintenance
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// commits a write made through `&self`, then drops the caches again: \
+    /// reads may run alongside it, and cache what they saw before the commit
+    fn commit_shared(&self, tnx: WriteTransaction) -> Result<()> {
+        tnx.commit()?;
+        self.drop_caches();
+        Ok(())
+    }
+
+    pub fn set_error_context(&mut self, f: fn(&str) -> String) {
+        self.error_context = Some(f);
+    }
+
+    /// attaches the user's context (if any) to an error returned while accessing `table`, \
+    /// and turns it into the [`DblessError`](../enum.DblessError.html) the public API returns
+    pub fn context<T>(&self, table: &str, result: Result<T>) -> Result<T, DblessError> {
+        let result = match self.error_context {
+            Some(f) => result.with_context(|| f(table)),
+            None => result,
+        };
+        result.map_err(DblessError::from)
+    }
+
+    fn settings(&self) -> RwLockReadGuard<'_, Settings> {
+        self.settings.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn settings_mut(&self) -> RwLockWriteGuard<'_, Settings> {
+        self.settings
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// follows aliases until reaching a table name that isn't one
+    pub fn resolve<'n>(&self, name: &'n str) -> Cow<'n, str> {
+        let settings = self.settings();
+        let Some(mut target) = settings.aliases.get(name) else {
+            return Cow::Borrowed(name);
+        };
+        while let Some(next) = settings.aliases.get(target) {
+            target = next;
+        }
+        Cow::Owned(target.clone())
+    }
+
+    pub fn alias_table(&mut self, alias: &str, target: &str) -> Result<()> {
+        {
+            let settings = self.settings();
+            let mut next = Some(target);
+            while let Some(name) = next {
+                ensure!(
+                    name != alias,
+                    "aliasing `{}` to `{}` would create a cycle",
+                    alias,
+                    target
+                );
+                next = settings.aliases.get(name).map(String::as_str);
+            }
+        }
+        let tnx = self.begin_write()?;
+        ensure!(
+            !tnx.list_tables()?.any(|t| t.name() == alias),
+            "table `{}` already exists",
+            alias
+        );
+        {
+            let mut table =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.aliases))?;
+            table.insert(alias, serialize(&target)?.as_slice())?;
+        }
+        tnx.commit()?;
+        self.settings_mut()
+            .aliases
+            .insert(alias.to_string(), target.to_string());
+        Ok(())
+    }
+
+    pub fn limit(&self, table: &str) -> Option<TableLimit> {
+        let name = self.resolve(table);
+        self.settings().limits.get(&*name).copied()
+    }
+
+    pub fn set_limit(&mut self, table: &str, limit: TableLimit) -> Result<()> {
+        ensure!(
+            limit.max_entries > 0,
+            "a table limit must allow at least one entry"
+        );
+        let name = self.resolve(table).to_string();
+        ensure!(
+            !self.settings().chained.contains(&name)
+                && self.settings().write_once.get(&name) != Some(&WriteOnce::AppendOnly),
+            "append-only table `{}` can't have a limit",
+            name
+        );
+        ensure!(
+            !self.settings().deduplicated.contains(&name),
+            "deduplicated table `{}` can't have a limit",
+            name
+        );
+        let (order_name, stamps_name) = self.order_table_names(&name);
+        let tnx = self.begin_write_to(&name)?;
+        {
+            let mut limits =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.limits))?;
+            limits.insert(name.as_str(), serialize(&limit)?.as_slice())?;
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            let mut order = tnx.open_table(TableDefinition::<u64, &str>::new(&order_name))?;
+            let mut stamps = tnx.open_table(TableDefinition::<&str, u64>::new(&stamps_name))?;
+            if !self.settings().limits.contains_key(&name) {
+                order.retain(|_, _| false)?;
+                stamps.retain(|_, _| false)?;
+                for (stamp, entry) in table.iter()?.enumerate() {
+                    let (key, _) = entry?;
+                    order.insert(stamp as u64, key.value())?;
+                    stamps.insert(key.value(), stamp as u64)?;
+                }
+            }
+            if limit.policy != EvictionPolicy::RejectNew {
+                while table.len()? > limit.max_entries as u64 {
+                    evict_oldest(&mut table, &mut order, &mut stamps)?;
+                }
+            }
+        }
+        tnx.commit()?;
+        self.settings_mut().limits.insert(name, limit);
+        Ok(())
+    }
+
+    pub fn remove_limit(&mut self, table: &str) -> Result<()> {
+        let name = self.resolve(table).to_string();
+        let tnx = self.begin_write_to(&name)?;
+        self.delete_limit(&tnx, &name)?;
+        tnx.commit()?;
+        self.settings_mut().limits.remove(&name);
+        Ok(())
+    }
+
+    fn delete_limit(&self, tnx: &WriteTransaction, name: &str) -> Result<()> {
+        let (order_name, stamps_name) = self.order_table_names(name);
+        {
+            let mut limits =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.limits))?;
+            limits.remove(name)?;
+        }
+        tnx.delete_table(TableDefinition::<u64, &str>::new(&order_name))?;
+        tnx.delete_table(TableDefinition::<&str, u64>::new(&stamps_name))?;
+        Ok(())
+    }
+
+    /// inserts the value and returns whether the key existed before, \
+    /// reading it back right away if writes are verified, and appending it to the chain if the table is chained. \
+    /// every write goes through here, so this is where keys of insert-only and chained tables are kept from being overwritten, \
+    /// and new keys from being added to a full table rejecting them. \
+    /// those are checked before anything is written, so a refused write leaves the transaction as it was
+    fn write_value(
+        &self,
+        tnx: &WriteTransaction,
+        table: &mut redb::Table<&'static str, &'static [u8]>,
+        name: &str,
+        key: &str,
+        bytes: &[u8],
+    ) -> Result<bool> {
+        self.ensure_unlocked(name)?;
+        // counters stay as they are, so they can still be read and added to in place
+        let transformed = match decode_counter(bytes) {
+            Some(_) => {
+                ensure!(
+                    !self.options.transforms.is_required(),
+                    "counters aren't transformed, and transforms are required"
+                );
+                Cow::Borrowed(bytes)
+            }
+            None => self.options.transforms.apply(bytes),
+        };
+        let (write_once, chained, max_entries) = {
+            let settings = self.settings();
+            let max_entries = settings
+                .limits
+                .get(name)
+                .filter(|limit| limit.policy == EvictionPolicy::RejectNew)
+                .map(|limit| limit.max_entries);
+            (
+                settings.write_once.contains_key(name),
+                settings.chained.contains(name),
+                max_entries,
+            )
+        };
+        if write_once || chained || max_entries.is_some() {
+            if table.get(key)?.is_some() {
+                if write_once {
+                    return Err(ImmutableKey {
+                        table: name.to_string(),
+                        key: self.user_key(name, key).unwrap_or_else(|_| key.to_string()),
+                    }
+                    .into());
+                }
+                if chained {
+                    return Err(self.append_only_violation(name, Some(key)));
+                }
+            } else if let Some(max_entries) = max_entries {
+                ensure!(
+                    table.len()? < max_entries as u64,
+                    "table `{}` is full ({} entries), can't insert key `{}`",
+                    name,
+                    max_entries,
+                    key
+                );
+            }
+        }
+        let stored = self.store_blob(tnx, name, &transformed)?;
+        let existed = match table.insert(key, &*stored)? {
+            Some(old) => {
+                self.release_blob(tnx, name, old.value())?;
+                true
+            }
+            None => false,
+        };
+        if self.options.verify_writes {
+            let read_back = table.get(key)?;
+            if read_back.as_ref().map(|b| b.value()) != Some(&*stored) {
+                return Err(WriteVerificationFailed {
+                    table: name.to_string(),
+                    key: key.to_string(),
+                }
+                .into());
+            }
+        }
+        if chained {
+            let mut chain = tnx.open_table(TableDefinition::<u64, &[u8]>::new(
+                &self.chain_table_name(name),
+            ))?;
+            let (next, previous) = match chain.last()? {
+                Some((n, link)) => (n.value() + 1, link.value()[..32].to_vec()),
+                None => (0, vec![0; 32]),
+            };
+            let mut link = chain_hash(&previous, key, &transformed).to_vec();
+            link.extend_from_slice(key.as_bytes());
+            chain.insert(next, link.as_slice())?;
+        }
+        Ok(existed)
+    }
+
+    /// stores the bytes of a value in the blobs of a deduplicated table (or adds a reference to them), \
+    /// and returns what to store under its key: a reference, or the bytes themselves if they're no longer than one
+    fn store_blob<'b>(
+        &self,
+        tnx: &WriteTransaction,
+        name: &str,
+        bytes: &'b [u8],
+    ) -> Result<Cow<'b, [u8]>> {
+        if !self.settings().deduplicated.contains(name) || bytes.len() <= BLOB_REF_LEN {
+            return Ok(Cow::Borrowed(bytes));
+        }
+        let hash: [u8; 32] = Sha256::digest(bytes).into();
+        let mut blobs = tnx.open_table(TableDefinition::<&[u8], &[u8]>::new(
+            &self.blobs_table_name(name),
+        ))?;
+        let existing = blobs
+            .get(hash.as_slice())?
+            .map(|blob| blob.value().to_vec());
+        let blob = match existing {
+            Some(mut blob) => {
+                let refs = blob_refs(&blob)? + 1;
+                blob[..8].copy_from_slice(&refs.to_be_bytes());
+                blob
+            }
+            None => [&1u64.to_be_bytes(), bytes].concat(),
+        };
+        blobs.insert(hash.as_slice(), blob.as_slice())?;
+        Ok(Cow::Owned(encode_blob_ref(&hash).to_vec()))
+    }
+
+    /// drops the reference held by a removed or overwritten value of a deduplicated table, \
+    /// removing the referenced bytes along with the last one
+    fn release_blob(&self, tnx: &WriteTransaction, name: &str, stored: &[u8]) -> Result<()> {
+        if !self.settings().deduplicated.contains(name) {
+            return Ok(());
+        }
+        let Some(hash) = decode_blob_ref(stored) else {
+            return Ok(());
+        };
+        let mut blobs = tnx.open_table(TableDefinition::<&[u8], &[u8]>::new(
+            &self.blobs_table_name(name),
+        ))?;
+        let Some(mut blob) = blobs
+            .get(hash.as_slice())?
+            .map(|blob| blob.value().to_vec())
+        else {
+            return Ok(());
+        };
+        match blob_refs(&blob)? {
+            0 | 1 => {
+                blobs.remove(hash.as_slice())?;
+            }
+            refs => {
+                blob[..8].copy_from_slice(&(refs - 1).to_be_bytes());
+                blobs.insert(hash.as_slice(), blob.as_slice())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// opens the blobs of a deduplicated table for reading, `None` for other tables
+    fn read_blobs(
+        &self,
+        tnx: &ReadTransaction,
+        name: &str,
+    ) -> Result<Option<ReadOnlyTable<&'static [u8], &'static [u8]>>> {
+        if !self.settings().deduplicated.contains(name) {
+            return Ok(None);
+        }
+        let blobs_name = self.blobs_table_name(name);
+        match tnx.open_table(TableDefinition::<&[u8], &[u8]>::new(&blobs_name)) {
+            Ok(blobs) => Ok(Some(blobs)),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// the size of a stored value, within a write transaction
+    fn stored_len_in(&self, tnx: &WriteTransaction, name: &str, bytes: &[u8]) -> Result<usize> {
+        if !self.settings().deduplicated.contains(name) || decode_blob_ref(bytes).is_none() {
+            return Ok(bytes.len());
+        }
+        let blobs = tnx.open_table(TableDefinition::<&[u8], &[u8]>::new(
+            &self.blobs_table_name(name),
+        ))?;
+        stored_len(Some(&blobs), bytes)
+    }
+
+    /// the bytes a stored value stands for, within a write transaction
+    fn resolve_in<'v>(
+        &self,
+        tnx: &WriteTransaction,
+        name: &str,
+        bytes: &'v [u8],
+    ) -> Result<Cow<'v, [u8]>> {
+        if !self.settings().deduplicated.contains(name) || decode_blob_ref(bytes).is_none() {
+            return self.options.transforms.reverse(bytes);
+        }
+        let blobs = tnx.open_table(TableDefinition::<&[u8], &[u8]>::new(
+            &self.blobs_table_name(name),
+        ))?;
+        let value = resolve_value(Some(&blobs), &self.options.transforms, bytes)?;
+        Ok(Cow::Owned(value.into_owned()))
+    }
+
+    /// the version of `key` within a write transaction, `None` if it doesn't exist
+    fn version_in(
+        &self,
+        tnx: &WriteTransaction,
+        table: &impl ReadableTable<&'static str, &'static [u8]>,
+        name: &str,
+        key: &str,
+    ) -> Result<Option<u64>> {
+        match table.get(key)? {
+            Some(bytes) => Ok(Some(version_of_value(&self.resolve_in(
+                tnx,
+                name,
+                bytes.value(),
+            )?)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn append_only_violation(&self, name: &str, key: Option<&str>) -> anyhow::Error {
+        AppendOnlyViolation {
+            table: name.to_string(),
+            key: key.map(|key| self.user_key(name, key).unwrap_or_else(|_| key.to_string())),
+        }
+        .into()
+    }
+
+    /// rejects removing `key` (or every key) from an append-only table
+    fn ensure_removable(&self, name: &str, key: Option<&str>) -> Result<()> {
+        let settings = self.settings();
+        if settings.chained.contains(name)
+            || settings.write_once.get(name) == Some(&WriteOnce::AppendOnly)
+        {
+            drop(settings);
+            return Err(self.append_only_violation(name, key));
+        }
+        Ok(())
+    }
+
+    pub fn is_insert_only(&self, table: &str) -> bool {
+        let name = self.resolve(table);
+        self.settings().write_once.contains_key(&*name)
+    }
+
+    pub fn is_append_only(&self, table: &str) -> bool {
+        let name = self.resolve(table);
+        self.settings().write_once.get(&*name) == Some(&WriteOnce::AppendOnly)
+    }
+
+    /// checks if inserting `value` under `key` would get past the limit of the table (if it rejects new keys) \
+    /// and its write-once setting, without writing anything
+    pub fn would_fit<T: Serialize>(&self, table: &str, key: &str, value: &T) -> Result<bool> {
+        serialized_size_as(self.format, value).with_context(|| {
+            SerializeFailed(format!(
+                "failed to serialize value of type `{}` for key `{}`",
+                type_name::<T>(),
+                key
+            ))
+        })?;
+        let name = &*self.resolve(table);
+        let (max_entries, write_once) = {
+            let settings = self.settings();
+            let max_entries = settings
+                .limits
+                .get(name)
+                .filter(|limit| limit.policy == EvictionPolicy::RejectNew)
+                .map(|limit| limit.max_entries);
+            let write_once =
+                settings.write_once.contains_key(name) || settings.chained.contains(name);
+            (max_entries, write_once)
+        };
+        if max_entries.is_none() && !write_once {
+            return Ok(true);
+        }
+        let key = self.stored_key(name, key);
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, true);
+        if table.get(&*key)?.is_some() {
+            return Ok(!write_once);
+        }
+        match max_entries {
+            Some(max_entries) => Ok(table.len()? < max_entries as u64),
+            None => Ok(true),
+        }
+    }
+
+    pub fn insert_only(&mut self, table: &str) -> Result<()> {
+        self.set_write_once(table, WriteOnce::InsertOnly)
+    }
+
+    pub fn append_only(&mut self, table: &str) -> Result<()> {
+        self.set_write_once(table, WriteOnce::AppendOnly)
+    }
+
+    /// protects the keys of the table, a table that's already at least as protected is left as is
+    fn set_write_once(&mut self, table: &str, write_once: WriteOnce) -> Result<()> {
+        let name = self.resolve(table).to_string();
+        if self.settings().write_once.get(&name) >= Some(&write_once) {
+            return Ok(());
+        }
+        ensure!(
+            write_once != WriteOnce::AppendOnly || !self.settings().limits.contains_key(&name),
+            "table `{}` has a limit, it can't be made append-only",
+            name
+        );
+        let tnx = self.begin_write_to(&name)?;
+        {
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(
+                &self.reserved.write_once,
+            ))?;
+            table.insert(name.as_str(), serialize(&write_once)?.as_slice())?;
+        }
+        tnx.commit()?;
+        self.settings_mut().write_once.insert(name, write_once);
+        Ok(())
+    }
+
+    pub fn is_chained(&self, table: &str) -> bool {
+        let name = self.resolve(table);
+        self.settings().chained.contains(&*name)
+    }
+
+    pub fn append_only_chained(&mut self, table: &str) -> Result<()> {
+        let name = self.resolve(table).to_string();
+        if self.settings().chained.contains(&name) {
+            return Ok(());
+        }
+        ensure!(
+            !self.settings().limits.contains_key(&name),
+            "table `{}` has a limit, it can't be made append-only",
+            name
+        );
+        ensure!(
+            !self.settings().deduplicated.contains(&name),
+            "table `{}` is deduplicated, it can't be made append-only",
+            name
+        );
+        let tnx = self.begin_write_to(&name)?;
+        {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            ensure!(
+                table.is_empty()?,
+                "table `{}` can only be made append-only while it's empty",
+                name
+            );
+            let mut chained =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.chained))?;
+            chained.insert(name.as_str(), serialize(&())?.as_slice())?;
+        }
+        tnx.commit()?;
+        self.settings_mut().chained.insert(name);
+        Ok(())
+    }
+
+    pub fn verify_chain(&self, table: &str) -> Result<ChainReport> {
+        let name = &*self.resolve(table);
+        let mut report = ChainReport {
+            entries: 0,
+            head: [0; 32],
+            first_divergent_key: None,
+        };
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, report);
+        let chain = match tnx.open_table(TableDefinition::<u64, &[u8]>::new(
+            &self.chain_table_name(name),
+        )) {
+            Ok(chain) => Some(chain),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let mut chained_keys = HashSet::new();
+        if let Some(chain) = &chain {
+            for link in chain.iter()? {
+                let (_, link) = link?;
+                let (hash, key) = link.value().split_at(32);
+                let key = std::str::from_utf8(key)?;
+                let matches = match table.get(key)? {
+                    Some(value) => chain_hash(&report.head, key, value.value()) == hash,
+                    None => false,
+                };
+                if !matches {
+                    report.first_divergent_key = Some(self.user_key(name, key)?);
+                    return Ok(report);
+                }
+                report.head = hash.try_into()?;
+                report.entries += 1;
+                chained_keys.insert(key.to_string());
+            }
+        }
+        for entry in table.iter()? {
+            let (key, _) = entry?;
+            if !chained_keys.contains(key.value()) {
+                report.first_divergent_key = Some(self.user_key(name, key.value())?);
+                break;
+            }
+        }
+        Ok(report)
+    }
+
+    /// applies the limit of the table (if any) after `key` was written
+    fn enforce_limit(
+        &self,
+        tnx: &WriteTransaction,
+        table: &mut redb::Table<&'static str, &'static [u8]>,
+        name: &str,
+        key: &str,
+        existed: bool,
+    ) -> Result<()> {
+        let Some(limit) = self.settings().limits.get(name).copied() else {
+            return Ok(());
+        };
+        match limit.policy {
+            // checked by `write_value()` before the key was written
+            EvictionPolicy::RejectNew => return Ok(()),
+            EvictionPolicy::Fifo if existed => return Ok(()),
+            _ => {}
+        }
+        let (order_name, stamps_name) = self.order_table_names(name);
+        let mut order = tnx.open_table(TableDefinition::<u64, &str>::new(&order_name))?;
+        let mut stamps = tnx.open_table(TableDefinition::<&str, u64>::new(&stamps_name))?;
+        touch(&mut order, &mut stamps, key)?;
+        while table.len()? > limit.max_entries as u64 {
+            evict_oldest(table, &mut order, &mut stamps)?;
+        }
+        Ok(())
+    }
+
+    /// moves the key to the end of the order, if the table evicts the least recently used entries. \
+    /// reads of a frozen database don't count as a use, there's nothing they could write
+    fn touch_on_read(&self, name: &str, key: &str) -> Result<()> {
+        self.touch_all_on_read(name, &[key])
+    }
+
+    /// [`touch_on_read()`](#method.touch_on_read) for several keys read together, in a single write
+    fn touch_all_on_read(&self, name: &str, keys: &[&str]) -> Result<()> {
+        if keys.is_empty()
+            || self.frozen.load(Ordering::Acquire)
+            || self.settings().limits.get(name).map(|l| l.policy) != Some(EvictionPolicy::Lru)
+        {
+            return Ok(());
+        }
+        let tnx = self.begin_write()?;
+        self.touch_in(&tnx, name, keys)?;
+        tnx.commit()?;
+        Ok(())
+    }
+
+    /// [`touch_all_on_read()`](#method.touch_all_on_read) inside a write transaction
+    fn touch_in(&self, tnx: &WriteTransaction, name: &str, keys: &[&str]) -> Result<()> {
+        if keys.is_empty()
+            || self.settings().limits.get(name).map(|l| l.policy) != Some(EvictionPolicy::Lru)
+        {
+            return Ok(());
+        }
+        let (order_name, stamps_name) = self.order_table_names(name);
+        let mut order = tnx.open_table(TableDefinition::<u64, &str>::new(&order_name))?;
+        let mut stamps = tnx.open_table(TableDefinition::<&str, u64>::new(&stamps_name))?;
+        for key in keys {
+            touch(&mut order, &mut stamps, key)?;
+        }
+        Ok(())
+    }
+
+    pub fn escapes_keys(&self, table: &str) -> bool {
+        let name = self.resolve(table);
+        self.settings().escaped.contains(&*name)
+    }
+
+    pub fn set_escape_keys(&mut self, table: &str, enabled: bool) -> Result<()> {
+        let name = self.resolve(table).to_string();
+        if self.settings().escaped.contains(&name) == enabled {
+            return Ok(());
+        }
+        let tnx = self.begin_write_to(&name)?;
+        {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            ensure!(
+                table.is_empty()?,
+                "key escaping of table `{}` can only be changed while it's empty",
+                name
+            );
+            let mut escaped =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.escaped))?;
+            if enabled {
+                escaped.insert(name.as_str(), serialize(&())?.as_slice())?;
+            } else {
+                escaped.remove(name.as_str())?;
+            }
+        }
+        tnx.commit()?;
+        if enabled {
+            self.settings_mut().escaped.insert(name);
+        } else {
+            self.settings_mut().escaped.remove(&name);
+        }
+        Ok(())
+    }
+
+    pub fn is_deduplicated(&self, table: &str) -> bool {
+        let name = self.resolve(table);
+        self.settings().deduplicated.contains(&*name)
+    }
+
+    pub fn set_deduplicated(&mut self, table: &str, enabled: bool) -> Result<()> {
+        let name = self.resolve(table).to_string();
+        if self.settings().deduplicated.contains(&name) == enabled {
+            return Ok(());
+        }
+        ensure!(
+            !self.settings().limits.contains_key(&name),
+            "table `{}` has a limit, it can't be deduplicated",
+            name
+        );
+        ensure!(
+            !self.settings().chained.contains(&name),
+            "append-only table `{}` can't be deduplicated",
+            name
+        );
+        let tnx = self.begin_write_to(&name)?;
+        {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            ensure!(
+                table.is_empty()?,
+                "deduplication of table `{}` can only be changed while it's empty",
+                name
+            );
+            let mut deduplicated = tnx.open_table(TableDefinition::<&str, &[u8]>::new(
+                &self.reserved.deduplicated,
+            ))?;
+            if enabled {
+                deduplicated.insert(name.as_str(), serialize(&())?.as_slice())?;
+            } else {
+                deduplicated.remove(name.as_str())?;
+            }
+        }
+        tnx.commit()?;
+        if enabled {
+            self.settings_mut().deduplicated.insert(name);
+        } else {
+            self.settings_mut().deduplicated.remove(&name);
+        }
+        Ok(())
+    }
+
+    /// the form `key` is stored in, escaped if the table escapes its keys
+    fn stored_key<'k>(&self, name: &str, key: &'k str) -> Cow<'k, str> {
+        if self.settings().escaped.contains(name) {
+            Cow::Owned(escape_key(key))
+        } else {
+            Cow::Borrowed(key)
+        }
+    }
+
+    /// the form `key` is returned to the user in, unescaped if the table escapes its keys
+    fn user_key(&self, name: &str, key: &str) -> Result<String> {
+        if self.settings().escaped.contains(name) {
+            unescape_key(key)
+        } else {
+            Ok(key.to_string())
+        }
+    }
+
+    pub fn list_aliases(&self) -> BTreeMap<String, String> {
+        self.settings()
+            .aliases
+            .iter()
+            .map(|(alias, target)| (alias.clone(), target.clone()))
+            .collect()
+    }
+
+    /// serializes a value in the format of the database
+    pub fn serialize<T: Serialize>(&self, key: &str, value: &T) -> Result<Vec<u8>> {
+        serialize_for_key_as(self.format, key, value)
+    }
+
+    fn deserialize<T: DeserializeOwned>(&self, key: &str, bytes: &[u8]) -> Result<T> {
+        deserialize_as(
+            self.format,
+            key,
+            bytes,
+            self.options.strict_numbers,
+            &self.options.read_limits,
+        )
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, table: &str, key: &str) -> Result<Option<T>> {
+        let db = &self.db;
+        let name = &*self.resolve(table);
+        let value = self.get_at(&db.begin_read()?, name, key)?;
+        if value.is_some() {
+            self.touch_on_read(name, &self.stored_key(name, key))?;
+        }
+        Ok(value)
+    }
+
+    /// begins the read transaction of a [`ReadTransaction`](../struct.ReadTransaction.html), \
+    /// which reads through the `*_at()` methods. \
+    /// its error carries the user's context, like the ones of the transaction's tables
+    pub fn begin_snapshot(&self) -> Result<ReadTransaction, DblessError> {
+        self.context("", self.begin_read())
+    }
+
+    /// [`begin_snapshot()`](#method.begin_snapshot) without the user's context, for callers that add it themselves
+    pub fn begin_read(&self) -> Result<ReadTransaction> {
+        let db = &self.db;
+        Ok(db.begin_read()?)
+    }
+
+    /// [`get()`](#method.get) in the given read transaction, \
+    /// which doesn't count as a use of the key in an LRU table, a snapshot read can't write anything
+    pub fn get_at<T: DeserializeOwned>(
+        &self,
+        tnx: &ReadTransaction,
+        table: &str,
+        key: &str,
+    ) -> Result<Option<T>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = open_table_read_or!(tnx, name, None);
+        let bytes = match table.get(key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let blobs = self.read_blobs(tnx, name)?;
+        let bytes = resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value())?;
+        if is_expired(&bytes, now_millis())? {
+            return Ok(None);
+        }
+        Ok(Some(self.deserialize(key, &bytes)?))
+    }
+
+    pub fn get_many<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        keys: &[&str],
+    ) -> Result<Vec<Option<T>>> {
+        self.get_all(table, keys)?
+            .into_iter()
+            .map(|result| match result.value {
+                Lookup::Found(value) => Ok(Some(value)),
+                Lookup::Missing => Ok(None),
+                Lookup::Failed(e) => Err(e),
+            })
+            .collect()
+    }
+
+    pub fn get_all<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        keys: &[&str],
+    ) -> Result<Vec<KeyedResult<T>>> {
+        let name = &*self.resolve(table);
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let missing = keys
+            .iter()
+            .map(|key| KeyedResult {
+                key: key.to_string(),
+                value: Lookup::Missing,
+            })
+            .collect();
+        let table = open_table_read_or!(tnx, name, missing);
+        let blobs = self.read_blobs(&tnx, name)?;
+        let now = now_millis();
+        let mut results = Vec::with_capacity(keys.len());
+        let mut found = vec![];
+        for key in keys {
+            let stored_key = self.stored_key(name, key);
+            let value = match table.get(&*stored_key)? {
+                Some(bytes) => {
+                    match resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value()) {
+                        Ok(bytes) if is_expired(&bytes, now).unwrap_or(false) => Lookup::Missing,
+                        bytes => {
+                            let value =
+                                bytes.and_then(|bytes| self.deserialize(&stored_key, &bytes));
+                            found.push(stored_key);
+                            match value {
+                                Ok(value) => Lookup::Found(value),
+                                Err(e) => Lookup::Failed(e),
+                            }
+                        }
+                    }
+                }
+                None => Lookup::Missing,
+            };
+            results.push(KeyedResult {
+                key: key.to_string(),
+                value,
+            });
+        }
+        let found = found.iter().map(|key| &**key).collect::<Vec<_>>();
+        self.touch_all_on_read(name, &found)?;
+        Ok(results)
+    }
+
+    pub fn get_dynamic(&self, table: &str, key: &str) -> Result<Option<Value>> {
+        let Some(bytes) = self.get_resolved(table, key)? else {
+            return Ok(None);
+        };
+        let bytes = self.plain_msgpack(&bytes)?;
+        let value = Value::read(&bytes, &self.options.read_limits)
+            .with_context(|| format!("failed to read value of key `{}`", key))?;
+        Ok(Some(value))
+    }
+
+    /// the plain MessagePack form of a stored value, \
+    /// converted from the format of the database for the APIs that work on raw MessagePack. \
+    /// raw bytes come back as a MessagePack bin
+    fn plain_msgpack<'v>(&self, bytes: &'v [u8]) -> Result<Cow<'v, [u8]>> {
+        let value = Envelope::decode(bytes)?.value;
+        if decode_counter(bytes).is_some() || decode_raw(value).is_some() {
+            return plain_value(bytes);
+        }
+        to_msgpack(self.format, value)
+    }
+
+    /// the bytes written with `insert_raw()` as they were given, or the value as plain MessagePack
+    pub fn get_raw(&self, table: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        let Some(bytes) = self.get_resolved(table, key)? else {
+            return Ok(None);
+        };
+        if let Some(raw) = decode_raw(Envelope::decode(&bytes)?.value) {
+            return Ok(Some(raw.to_vec()));
+        }
+        Ok(Some(self.plain_msgpack(&bytes)?.into_owned()))
+    }
+
+    /// a stored value with its blob resolved and its transforms reversed, `None` if it's missing or expired
+    fn get_resolved(&self, table: &str, key: &str) -> Result<Option<Vec<u8>>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, None);
+        let bytes = match table.get(key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let blobs = self.read_blobs(&tnx, name)?;
+        let value = resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value())?;
+        if is_expired(&value, now_millis())? {
+            return Ok(None);
+        }
+        let value = value.into_owned();
+        self.touch_on_read(name, key)?;
+        Ok(Some(value))
+    }
+
+    pub fn get_tagged(&self, table: &str, key: &str) -> Result<Option<(String, Vec<u8>)>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, None);
+        let bytes = match table.get(key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let blobs = self.read_blobs(&tnx, name)?;
+        let bytes = resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value())?;
+        let envelope = Envelope::decode(&bytes)?;
+        if envelope.is_expired(now_millis()) {
+            return Ok(None);
+        }
+        let tag = envelope
+            .tag
+            .with_context(|| format!("value of key `{}` is not tagged", key))?;
+        let value = to_msgpack(self.format, envelope.value)?;
+        Ok(Some((tag.to_string(), value.into_owned())))
+    }
+
+    pub fn get_as<T: DeserializeOwned>(&self, table: &str, key: &str) -> Result<Option<T>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, None);
+        let bytes = match table.get(key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let blobs = self.read_blobs(&tnx, name)?;
+        let bytes = resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value())?;
+        let envelope = Envelope::decode(&bytes)?;
+        if envelope.is_expired(now_millis()) {
+            return Ok(None);
+        }
+        let expected = type_name::<T>();
+        match envelope.tag {
+            Some(tag) if tag == expected => {}
+            Some(tag) => bail!(
+                "value of key `{}` is tagged as `{}`, not `{}`",
+                key,
+                tag,
+                expected
+            ),
+            None => bail!("value of key `{}` is not tagged", key),
+        }
+        self.deserialize(key, &bytes).map(Some)
+    }
+
+    pub fn insert<T: Serialize>(&mut self, table: &str, key: &str, value: &T) -> Result<()> {
+        let bytes = self.serialize(key, value)?;
+        self.insert_bytes(table, key, &bytes)
+    }
+
+    /// inserts bytes as they are, to be read back with `get_raw()`
+    pub fn insert_raw(&mut self, table: &str, key: &str, bytes: &[u8]) -> Result<()> {
+        self.insert_bytes(table, key, &encode_raw(bytes))
+    }
+
+    /// inserts a value that is already serialized, after checking that it's a single MessagePack value
+    pub fn insert_msgpack(&mut self, table: &str, key: &str, bytes: &[u8]) -> Result<()> {
+        let bytes = self.checked_msgpack(key, bytes)?;
+        self.insert_bytes(table, key, &bytes)
+    }
+
+    /// checks that `bytes` is a single MessagePack value and converts it to the format of the database
+    fn checked_msgpack<'v>(&self, key: &str, bytes: &'v [u8]) -> Result<Cow<'v, [u8]>> {
+        let invalid = || format!("value of key `{}` isn't valid MessagePack", key);
+        if let Token::Ext(..) = Reader::new(bytes).read().with_context(invalid)? {
+            bail!(
+                "value of key `{}` is a MessagePack ext value, which dbless reserves for its own metadata",
+                key
+            );
+        }
+        let mut reader = Reader::new(bytes);
+        reader.skip().with_context(invalid)?;
+        ensure!(
+            reader.remaining().is_empty(),
+            "value of key `{}` has trailing bytes after its MessagePack value",
+            key
+        );
+        from_msgpack(self.format, bytes, &self.options.read_limits)
+    }
+
+    /// inserts already serialized entries in a single write transaction, \
+    /// as raw bytes, or as MessagePack values checked like `insert_msgpack()` does
+    #[cfg(feature = "lmdb-import")]
+    pub fn insert_serialized(
+        &mut self,
+        table: &str,
+        entries: &[(String, Vec<u8>)],
+        raw: bool,
+    ) -> Result<()> {
+        let tnx = self.begin_write_to(&self.resolve(table))?;
+        for (key, bytes) in entries {
+            let bytes = match raw {
+                true => Cow::Owned(encode_raw(bytes)),
+                false => self.checked_msgpack(key, bytes)?,
+            };
+            self.insert_in(&tnx, table, key, &bytes)?;
+        }
+        tnx.commit()?;
+        Ok(())
+    }
+
+    fn insert_bytes(&mut self, table: &str, key: &str, bytes: &[u8]) -> Result<()> {
+        let tnx = self.begin_write_to(&self.resolve(table))?;
+        self.insert_in(&tnx, table, key, bytes)?;
+        tnx.commit()?;
+        Ok(())
+    }
+
+    /// begins the write transaction of a [`Transaction`](../struct.Transaction.html), \
+    /// which reads and writes through the `*_in()` methods and is ended with \
+    /// [`commit_transaction()`](#method.commit_transaction) or [`abort_transaction()`](#method.abort_transaction). \
+    /// like them, its error (including [`Frozen`](../struct.Frozen.html)) carries the user's context
+    pub fn begin_transaction(&self) -> Result<WriteTransaction, DblessError> {
+        self.context("", self.begin_write())
+    }
+
+    /// commits the write transaction of a [`Transaction`](../struct.Transaction.html)
+    pub fn commit_transaction(&self, tnx: WriteTransaction) -> Result<(), DblessError> {
+        self.context("", tnx.commit().map_err(Into::into))
+    }
+
+    /// rolls back the write transaction of a [`Transaction`](../struct.Transaction.html)
+    pub fn abort_transaction(&self, tnx: WriteTransaction) -> Result<(), DblessError> {
+        self.context("", tnx.abort().map_err(Into::into))
+    }
+
+    /// inserts a serialized value inside a write transaction
+    pub fn insert_in(
+        &self,
+        tnx: &WriteTransaction,
+        table: &str,
+        key: &str,
+        bytes: &[u8],
+    ) -> Result<()> {
+        self.insert_envelope_in(tnx, table, key, Envelope::plain(bytes))
+    }
+
+    /// inserts a serialized value with its metadata inside a write transaction
+    fn insert_envelope_in(
+        &self,
+        tnx: &WriteTransaction,
+        table: &str,
+        key: &str,
+        envelope: Envelope,
+    ) -> Result<()> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))?;
+        let bytes = self.with_next_version(tnx, &table, name, key, envelope)?;
+        let existed = self.write_value(tnx, &mut table, name, key, &bytes)?;
+        self.enforce_limit(tnx, &mut table, name, key, existed)
+    }
+
+    /// inserts a value read from a JSON dump inside a write transaction, \
+    /// restoring its type tag, its expiry, or that it's a counter or raw bytes
+    #[cfg(feature = "json")]
+    pub fn insert_dump_in(
+        &self,
+        tnx: &WriteTransaction,
+        table: &str,
+        key: &str,
+        entry: DumpEntry,
+    ) -> Result<()> {
+        let bytes = match entry.value {
+            DumpValue::Counter(counter) => {
+                let name = &*self.resolve(table);
+                let key = self.stored_key(name, key);
+                let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))?;
+                let counter = encode_counter(counter);
+                let existed = self.write_value(tnx, &mut table, name, &key, &counter)?;
+                return self.enforce_limit(tnx, &mut table, name, &key, existed);
+            }
+            DumpValue::Json(value) => self.serialize(key, &value)?,
+            DumpValue::Binary(bytes) => {
+                from_msgpack(self.format, &msgpack_bin(&bytes), &self.options.read_limits)?
+                    .into_owned()
+            }
+            DumpValue::Raw(bytes) => encode_raw(&bytes),
+        };
+        if let Some(tag) = &entry.tag {
+            ensure!(
+                tag.len() <= u16::MAX as usize,
+                "type tag `{}` is too long",
+                tag
+            );
+        }
+        let envelope = Envelope {
+            tag: entry.tag.as_deref(),
+            expires_at: entry.expires_at,
+            ..Envelope::plain(&bytes)
+        };
+        self.insert_envelope_in(tnx, table, key, envelope)
+    }
+
+    /// gets a value inside a write transaction, seeing the writes made in it so far
+    pub fn get_in<T: DeserializeOwned>(
+        &self,
+        tnx: &WriteTransaction,
+        table: &str,
+        key: &str,
+    ) -> Result<Option<T>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        // opening a table in a write transaction creates it, a read shouldn't
+        if !tnx.list_tables()?.any(|t| t.name() == name) {
+            return Ok(None);
+        }
+        let value = {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))?;
+            let bytes = match table.get(key)? {
+                Some(bytes) => bytes,
+                None => return Ok(None),
+            };
+            let bytes = self.resolve_in(tnx, name, bytes.value())?;
+            if is_expired(&bytes, now_millis())? {
+                return Ok(None);
+            }
+            self.deserialize(key, &bytes)?
+        };
+        self.touch_in(tnx, name, &[key])?;
+        Ok(Some(value))
+    }
+
+    /// removes a key inside a write transaction
+    pub fn remove_in(&self, tnx: &WriteTransaction, table: &str, key: &str) -> Result<()> {
+        let name = &*self.resolve(table);
+        self.ensure_unlocked(name)?;
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        self.ensure_removable(name, Some(key))?;
+        let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))?;
+        if let Some(old) = table.remove(key)? {
+            self.release_blob(tnx, name, old.value())?;
+        };
+        Ok(())
+    }
+
+    pub fn insert_tagged<T: Serialize>(&mut self, table: &str, key: &str, value: &T) -> Result<()> {
+        let tag = type_name::<T>();
+        ensure!(
+            tag.len() <= u16::MAX as usize,
+            "type tag `{}` is too long",
+            tag
+        );
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let bytes = self.serialize(key, value)?;
+        let envelope = Envelope {
+            tag: Some(tag),
+            ..Envelope::plain(&bytes)
+        };
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(table)?;
+            let bytes = self.with_next_version(&tnx, &table, name, key, envelope)?;
+            let existed = self.write_value(&tnx, &mut table, name, key, &bytes)?;
+            self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+        }
+        tnx.commit()?;
+        Ok(())
+    }
+
+    pub fn insert_with_ttl<T: Serialize>(
+        &mut self,
+        table: &str,
+        key: &str,
+        value: &T,
+        ttl: Duration,
+    ) -> Result<()> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let bytes = self.serialize(key, value)?;
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(table)?;
+            self.write_with_ttl(&tnx, &mut table, name, key, &bytes, ttl)?;
+        }
+        tnx.commit()?;
+        Ok(())
+    }
+
+    /// gets the value of the key if it's there and hasn't expired, \
+    /// otherwise writes the value `f` returns with the given time to live, in a single write transaction
+    pub fn get_or_insert_with_ttl<T, F>(
+        &mut self,
+        table: &str,
+        key: &str,
+        ttl: Duration,
+        f: F,
+    ) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> T,
+    {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        let value = {
+            let mut table = tnx.open_table(table)?;
+            let current = match table.get(key)? {
+                Some(bytes) => {
+                    let bytes = self.resolve_in(&tnx, name, bytes.value())?;
+                    match is_expired(&bytes, now_millis())? {
+                        true => None,
+                        false => Some(self.deserialize::<T>(key, &bytes)?),
+                    }
+                }
+                None => None,
+            };
+            match current {
+                Some(value) => {
+                    self.touch_in(&tnx, name, &[key])?;
+                    value
+                }
+                None => {
+                    let value = f();
+                    let bytes = self.serialize(key, &value)?;
+                    self.write_with_ttl(&tnx, &mut table, name, key, &bytes, ttl)?;
+                    value
+                }
+            }
+        };
+        tnx.commit()?;
+        Ok(value)
+    }
+
+    /// writes a serialized value that expires after `ttl`
+    fn write_with_ttl(
+        &self,
+        tnx: &WriteTransaction,
+        table: &mut redb::Table<&'static str, &'static [u8]>,
+        name: &str,
+        key: &str,
+        bytes: &[u8],
+        ttl: Duration,
+    ) -> Result<()> {
+        let ttl = u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX);
+        let envelope = Envelope {
+            expires_at: Some(now_millis().saturating_add(ttl)),
+            ..Envelope::plain(bytes)
+        };
+        let bytes = self.with_next_version(tnx, table, name, key, envelope)?;
+        let existed = self.write_value(tnx, table, name, key, &bytes)?;
+        self.enforce_limit(tnx, table, name, key, existed)
+    }
+
+    /// removes the entries whose time to live has passed, `chunk_size` entries per write transaction
+    pub fn purge_expired(&mut self, table: &str, chunk_size: usize) -> Result<u64> {
+        let now = now_millis();
+        self.remove_matching(table, chunk_size, |store, tnx, name, value| {
+            is_expired(&store.resolve_in(tnx, name, value)?, now)
+        })
+    }
+
+    /// encodes the envelope with the key's next version, if versions are tracked. \
+    /// plain envelopes are borrowed as is when they aren't.
+    fn with_next_version<'b>(
+        &self,
+        tnx: &WriteTransaction,
+        table: &impl ReadableTable<&'static str, &'static [u8]>,
+        name: &str,
+        key: &str,
+        mut envelope: Envelope<'b>,
+    ) -> Result<Cow<'b, [u8]>> {
+        if self.options.track_versions {
+            envelope.version = Some(self.version_in(tnx, table, name, key)?.unwrap_or(0) + 1);
+        }
+        if envelope.is_plain() {
+            return Ok(Cow::Borrowed(envelope.value));
+        }
+        Ok(Cow::Owned(envelope.encode()))
+    }
+
+    pub fn apply_batch(&mut self, table: &str, ops: &[BatchOp]) -> Result<()> {
+        let name = &*self.resolve(table);
+        for op in ops {
+            match op {
+                BatchOp::Insert(..) => {}
+                BatchOp::Remove(key) => {
+                    self.ensure_removable(name, Some(&self.stored_key(name, key)))?
+                }
+                BatchOp::Clear => self.ensure_removable(name, None)?,
+            }
+        }
+        let definition = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(definition)?;
+            for op in ops {
+                match op {
+                    BatchOp::Insert(key, bytes) => {
+                        let bytes = from_msgpack(self.format, bytes, &self.options.read_limits)?;
+                        let key = &*self.stored_key(name, key);
+                        let bytes = self.with_next_version(
+                            &tnx,
+                            &table,
+                            name,
+                            key,
+                            Envelope::plain(&bytes),
+                        )?;
+                        let existed = self.write_value(&tnx, &mut table, name, key, &bytes)?;
+                        self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+                    }
+                    BatchOp::Remove(key) => {
+                        if let Some(old) = table.remove(&*self.stored_key(name, key))? {
+                            self.release_blob(&tnx, name, old.value())?;
+                        }
+                    }
+                    BatchOp::Clear => {
+                        drop(table);
+                        self.clear_in(&tnx, name)?;
+                        table = tnx.open_table(definition)?;
+                    }
+                }
+            }
+        }
+        tnx.commit()?;
+        Ok(())
+    }
+
+    pub fn set_if_version<T: Serialize>(
+        &mut self,
+        table: &str,
+        key: &str,
+        value: &T,
+        expected_version: u64,
+    ) -> Result<bool> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let bytes = self.serialize(key, value)?;
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(table)?;
+            if self.version_in(&tnx, &table, name, key)?.unwrap_or(0) != expected_version {
+                return Ok(false);
+            }
+            let bytes = Envelope {
+                version: Some(expected_version + 1),
+                ..Envelope::plain(&bytes)
+            }
+            .encode();
+            let existed = self.write_value(&tnx, &mut table, name, key, &bytes)?;
+            self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+        }
+        tnx.commit()?;
+        Ok(true)
+    }
+
+    pub fn update_versioned<T: Serialize + DeserializeOwned + Versioned + Clone>(
+        &mut self,
+        table: &str,
+        key: &str,
+        expected_version: u64,
+        new_value: &T,
+    ) -> Result<VersionedOutcome> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        let new_version = {
+            let mut table = tnx.open_table(table)?;
+            let current_version = match table.get(key)? {
+                Some(bytes) => {
+                    let bytes = self.resolve_in(&tnx, name, bytes.value())?;
+                    match is_expired(&bytes, now_millis())? {
+                        true => 0,
+                        false => self.deserialize::<T>(key, &bytes)?.version(),
+                    }
+                }
+                None => 0,
+            };
+            if current_version != expected_version {
+                return Ok(VersionedOutcome::Conflict { current_version });
+            }
+            let mut new_value = new_value.clone();
+            new_value.set_version(expected_version + 1);
+            let bytes = self.serialize(key, &new_value)?;
+            let bytes = self.with_next_version(&tnx, &table, name, key, Envelope::plain(&bytes))?;
+            let existed = self.write_value(&tnx, &mut table, name, key, &bytes)?;
+            self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+            expected_version + 1
+        };
+        tnx.commit()?;
+        Ok(VersionedOutcome::Updated { new_version })
+    }
+
+    pub fn update<T, F>(&mut self, table: &str, key: &str, f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<T>) -> T,
+    {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        let value = {
+            let mut table = tnx.open_table(table)?;
+            let current = match table.get(key)? {
+                Some(bytes) => {
+                    let bytes = self.resolve_in(&tnx, name, bytes.value())?;
+                    match is_expired(&bytes, now_millis())? {
+                        true => None,
+                        false => Some(self.deserialize::<T>(key, &bytes)?),
+                    }
+                }
+                None => None,
+            };
+            let value = f(current);
+            let bytes = self.serialize(key, &value)?;
+            let bytes = self.with_next_version(&tnx, &table, name, key, Envelope::plain(&bytes))?;
+            let existed = self.write_value(&tnx, &mut table, name, key, &bytes)?;
+            self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+            value
+        };
+        tnx.commit()?;
+        Ok(value)
+    }
+
+    pub fn version_of(&self, table: &str, key: &str) -> Result<Option<u64>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, None);
+        let bytes = match table.get(key)? {
+            Some(bytes) => bytes,
+            None => return Ok(None),
+        };
+        let blobs = self.read_blobs(&tnx, name)?;
+        let bytes = resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value())?;
+        if is_expired(&bytes, now_millis())? {
+            return Ok(None);
+        }
+        version_of_value(&bytes).map(Some)
+    }
+
+    pub fn get_u64(&self, table: &str, key: &str) -> Result<Option<u64>> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, name, None);
+        let value = match table.get(key)? {
+            Some(bytes) => counter_in(key, bytes.value())?,
+            None => return Ok(None),
+        };
+        self.touch_on_read(name, key)?;
+        Ok(Some(value))
+    }
+
+    pub fn set_u64(&mut self, table: &str, key: &str, value: u64) -> Result<()> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(table)?;
+            let existed = self.write_value(&tnx, &mut table, name, key, &encode_counter(value))?;
+            self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+        }
+        tnx.commit()?;
+        Ok(())
+    }
+
+    pub fn add_u64(&mut self, table: &str, key: &str, delta: u64) -> Result<u64> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        let value = {
+            let mut table = tnx.open_table(table)?;
+            let current = match table.get(key)? {
+                Some(bytes) => counter_in(key, bytes.value())?,
+                None => 0,
+            };
+            let value = current
+                .checked_add(delta)
+                .with_context(|| format!("adding {} to key `{}` overflows", delta, key))?;
+            let existed = self.write_value(&tnx, &mut table, name, key, &encode_counter(value))?;
+            self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+            value
+        };
+        tnx.commit()?;
+        Ok(value)
+    }
+
+    pub fn insert_batch<T: Serialize>(
+        &mut self,
+        table: &str,
+        entries: &[(&str, &T)],
+    ) -> Result<Vec<String>> {
+        self.insert_batch_timed(table, entries)
+            .map(|(skipped, _)| skipped)
+    }
+
+    /// inserts all entries in a single transaction, aborting on any serialization error whatever the policy
+    pub fn insert_many<T: Serialize>(&mut self, table: &str, entries: &[(&str, &T)]) -> Result<()> {
+        self.insert_batch_with_policy(table, entries, SerializeErrorPolicy::Abort)
+            .map(|_| ())
+    }
+
+    pub fn insert_batch_timed<T: Serialize>(
+        &mut self,
+        table: &str,
+        entries: &[(&str, &T)],
+    ) -> Result<(Vec<String>, LoadTiming)> {
+        let policy = self.options.serialize_error_policy;
+        self.insert_batch_with_policy(table, entries, policy)
+    }
+
+    fn insert_batch_with_policy<T: Serialize>(
+        &mut self,
+        table: &str,
+        entries: &[(&str, &T)],
+        policy: SerializeErrorPolicy,
+    ) -> Result<(Vec<String>, LoadTiming)> {
+        let start = Instant::now();
+        let name = &*self.resolve(table);
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let mut serialized = Vec::with_capacity(entries.len());
+        let mut skipped = vec![];
+        for (key, value) in entries {
+            match self.serialize(key, value) {
+                Ok(bytes) => serialized.push((self.stored_key(name, key), bytes)),
+                Err(e) => match policy {
+                    SerializeErrorPolicy::Abort => return Err(e),
+                    SerializeErrorPolicy::Skip => skipped.push(key.to_string()),
+                },
+            }
+        }
+        let serialized_at = Instant::now();
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(table)?;
+            for (key, bytes) in &serialized {
+                let key = &**key;
+                let bytes =
+                    self.with_next_version(&tnx, &table, name, key, Envelope::plain(bytes))?;
+                let existed = self.write_value(&tnx, &mut table, name, key, &bytes)?;
+                self.enforce_limit(&tnx, &mut table, name, key, existed)?;
+            }
+        }
+        tnx.commit()?;
+        let end = Instant::now();
+        let timing = LoadTiming {
+            serialize_ns: (serialized_at - start).as_nanos() as u64,
+            commit_ns: (end - serialized_at).as_nanos() as u64,
+            total_ns: (end - start).as_nanos() as u64,
+        };
+        Ok((skipped, timing))
+    }
+
+    pub fn archive<T, F>(&mut self, from: &str, to: &str, mut f: F) -> Result<usize>
+    where
+        T: DeserializeOwned,
+        F: FnMut(&str, &T) -> bool,
+    {
+        let (from, to) = (&*self.resolve(from), &*self.resolve(to));
+        ensure!(from != to, "can't archive table `{}` into itself", from);
+        self.ensure_removable(from, None)?;
+        self.ensure_unlocked(to)?;
+        let tnx = self.begin_write_to(from)?;
+        let mut moved = 0;
+        {
+            let mut source = tnx.open_table(TableDefinition::<&str, &[u8]>::new(from))?;
+            let mut target = tnx.open_table(TableDefinition::<&str, &[u8]>::new(to))?;
+            let mut matching = vec![];
+            for entry in source.iter()? {
+                let (key, value) = entry?;
+                let value = self.resolve_in(&tnx, from, value.value())?;
+                let Ok(decoded) = self.deserialize::<T>(key.value(), &value) else {
+                    continue;
+                };
+                let user_key = self.user_key(from, key.value())?;
+                if f(&user_key, &decoded) {
+                    matching.push((key.value().to_string(), user_key));
+                }
+            }
+            for (key, user_key) in matching {
+                let Some(bytes) = source.remove(key.as_str())? else {
+                    continue;
+                };
+                let stored = bytes.value().to_vec();
+                drop(bytes);
+                let bytes = self.resolve_in(&tnx, from, &stored)?.into_owned();
+                self.release_blob(&tnx, from, &stored)?;
+                let key = self.stored_key(to, &user_key);
+                let existed = self.write_value(&tnx, &mut target, to, &key, &bytes)?;
+                self.enforce_limit(&tnx, &mut target, to, &key, existed)?;
+                moved += 1;
+            }
+        }
+        tnx.commit()?;
+        Ok(moved)
+    }
+
+    pub fn remove(&mut self, table: &str, key: &str) -> Result<()> {
+        let tnx = self.begin_write_to(&self.resolve(table))?;
+        self.remove_in(&tnx, table, key)?;
+        tnx.commit()?;
+        Ok(())
+    }
+
+    pub fn remove_if<T: DeserializeOwned + PartialEq>(
+        &mut self,
+        table: &str,
+        key: &str,
+        expected: &T,
+    ) -> Result<bool> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        self.ensure_removable(name, Some(key))?;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        {
+            let mut table = tnx.open_table(table)?;
+            let matches = match table.get(key)? {
+                Some(bytes) => {
+                    let bytes = self.resolve_in(&tnx, name, bytes.value())?;
+                    !is_expired(&bytes, now_millis())?
+                        && self.deserialize::<T>(key, &bytes)? == *expected
+                }
+                None => false,
+            };
+            if !matches {
+                return Ok(false);
+            }
+            if let Some(old) = table.remove(key)? {
+                self.release_blob(&tnx, name, old.value())?;
+            };
+        }
+        tnx.commit()?;
+        Ok(true)
+    }
+
+    pub fn clear(&self, table: &str) -> Result<()> {
+        let name = &*self.resolve(table);
+        let _maintenance = self.lock_maintenance();
+        self.ensure_removable(name, None)?;
+        let tnx = self.begin_write_to(name)?;
+        self.clear_in(&tnx, name)?;
+        self.commit_shared(tnx)
+    }
+
+    /// deletes the table along with its eviction order and blobs, keeping its settings
+    fn clear_in(&self, tnx: &WriteTransaction, name: &str) -> Result<()> {
+        tnx.delete_table(TableDefinition::<&str, &[u8]>::new(name))?;
+        if self.settings().limits.contains_key(name) {
+            let (order_name, stamps_name) = self.order_table_names(name);
+            tnx.delete_table(TableDefinition::<u64, &str>::new(&order_name))?;
+            tnx.delete_table(TableDefinition::<&str, u64>::new(&stamps_name))?;
+        }
+        if self.settings().deduplicated.contains(name) {
+            let blobs_name = self.blobs_table_name(name);
+            tnx.delete_table(TableDefinition::<&[u8], &[u8]>::new(&blobs_name))?;
+        }
+        Ok(())
+    }
+
+    /// moves every entry of `from` into `to` as stored, in a single write transaction
+    pub fn move_all(&mut self, from: &str, to: &str) -> Result<usize> {
+        let (from, to) = (&*self.resolve(from), &*self.resolve(to));
+        ensure!(from != to, "can't move table `{}` into itself", from);
+        self.ensure_removable(from, None)?;
+        self.ensure_unlocked(to)?;
+        let tnx = self.begin_write_to(from)?;
+        let mut entries = vec![];
+        {
+            let source = tnx.open_table(TableDefinition::<&str, &[u8]>::new(from))?;
+            for entry in source.iter()? {
+                let (key, value) = entry?;
+                let key = self.user_key(from, key.value())?;
+                let key = self.stored_key(to, &key).into_owned();
+                let value = self.resolve_in(&tnx, from, value.value())?.into_owned();
+                entries.push((key, value));
+            }
+        }
+        self.clear_in(&tnx, from)?;
+        {
+            let mut target = tnx.open_table(TableDefinition::<&str, &[u8]>::new(to))?;
+            for (key, value) in &entries {
+                let existed = self.write_value(&tnx, &mut target, to, key, value)?;
+                self.enforce_limit(&tnx, &mut target, to, key, existed)?;
+            }
+        }
+        tnx.commit()?;
+        Ok(entries.len())
+    }
+
+    /// moves the entries of `from` that `route` sends somewhere, as stored, \
+    /// `route` maps a key to the table and key it moves to, or `None` to leave it where it is. \
+    /// each chunk of `chunk_size` moved entries is committed in its own write transaction, \
+    /// and `from` is cleared with the last one if `clear` is set. \
+    /// returns the number of entries moved into each table, and the number of entries left in place
+    pub fn move_routed(
+        &mut self,
+        from: &str,
+        chunk_size: usize,
+        clear: bool,
+        mut route: impl FnMut(&str) -> Option<(String, String)>,
+    ) -> Result<(BTreeMap<String, u64>, u64)> {
+        let from = self.resolve(from).to_string();
+        self.ensure_removable(&from, None)?;
+        let definition = TableDefinition::<&str, &[u8]>::new(&from);
+        let mut moved = BTreeMap::new();
+        let mut unmatched = 0;
+        let mut cursor: Option<String> = None;
+        loop {
+            let tnx = self.begin_write_to(&from)?;
+            let mut matching = vec![];
+            {
+                let mut source = match tnx.open_table(definition) {
+                    Ok(table) => table,
+                    Err(TableError::TableDoesNotExist(_)) => return Ok((moved, unmatched)),
+                    Err(e) => return Err(e.into()),
+                };
+                let start = match &cursor {
+                    Some(cursor) => Bound::Excluded(cursor.as_str()),
+                    None => Bound::Unbounded,
+                };
+                for entry in source.range::<&str>((start, Bound::Unbounded))? {
+                    let (key, _) = entry?;
+                    cursor = Some(key.value().to_string());
+                    match route(&self.user_key(&from, key.value())?) {
+                        Some((to, new_key)) => {
+                            let to = self.resolve(&to).to_string();
+                            ensure!(
+                                to != from,
+                                "can't move entries of table `{}` into itself",
+                                to
+                            );
+                            let new_key = self.stored_key(&to, &new_key).into_owned();
+                            matching.push((key.value().to_string(), to, new_key));
+                            if matching.len() == chunk_size {
+                                break;
+                            }
+                        }
+                        None => unmatched += 1,
+                    }
+                }
+                for (key, to, new_key) in &matching {
+                    let Some(bytes) = source.remove(key.as_str())? else {
+                        continue;
+                    };
+                    let stored = bytes.value().to_vec();
+                    drop(bytes);
+                    let bytes = self.resolve_in(&tnx, &from, &stored)?.into_owned();
+                    self.release_blob(&tnx, &from, &stored)?;
+                    let mut target = tnx.open_table(TableDefinition::<&str, &[u8]>::new(to))?;
+                    let existed = self.write_value(&tnx, &mut target, to, new_key, &bytes)?;
+                    self.enforce_limit(&tnx, &mut target, to, new_key, existed)?;
+                    *moved.entry(to.clone()).or_insert(0) += 1;
+                }
+            }
+            let done = matching.len() < chunk_size;
+            if done && clear {
+                self.clear_in(&tnx, &from)?;
+            }
+            tnx.commit()?;
+            if done {
+                return Ok((moved, unmatched));
+            }
+        }
+    }
+
+    /// the listed keys or entries of a table, in a deterministic random order if the database was opened with \
+    /// [`Database::in_memory_seeded()`](struct.Database.html#method.in_memory_seeded), sorted by key otherwise
+    fn shuffled<T>(&self, mut items: Vec<T>) -> Vec<T> {
+        let Some(mut state) = self.options.shuffle_seed else {
+            return items;
+        };
+        // splitmix64, enough to shuffle reproducibly
+        let mut next = move || {
+            state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        };
+        for i in (1..items.len()).rev() {
+            items.swap(i, (next() % (i as u64 + 1)) as usize);
+        }
+        items
+    }
+
+    pub fn keys(&self, table: &str) -> Result<Vec<String>> {
+        let db = &self.db;
+        self.keys_at(&db.begin_read()?, table)
+    }
+
+    /// [`keys()`](#method.keys) in the given read transaction
+    pub fn keys_at(&self, tnx: &ReadTransaction, table: &str) -> Result<Vec<String>> {
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let mut keys = Vec::with_capacity(table.len()? as usize);
+        for entry in table.iter()?.flatten() {
+            keys.push(self.user_key(name, entry.0.value())?);
+        }
+        Ok(self.shuffled(keys))
+    }
+
+    pub fn find_key<F: FnMut(&str) -> bool>(
+        &self,
+        table: &str,
+        mut f: F,
+    ) -> Result<Option<String>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, None);
+        for entry in table.iter()? {
+            let (key, _) = entry?;
+            if self.settings().escaped.contains(name) {
+                let key = self.user_key(name, key.value())?;
+                if f(&key) {
+                    return Ok(Some(key));
+                }
+            } else if f(key.value()) {
+                return Ok(Some(key.value().to_string()));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn keys_after(
+        &self,
+        table: &str,
+        after: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<String>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let after = after.map(|after| self.stored_key(name, after));
+        let start = match after.as_deref() {
+            Some(after) => Bound::Excluded(after),
+            None => Bound::Unbounded,
+        };
+        let mut keys = Vec::with_capacity(limit.min(table.len()? as usize));
+        for entry in table.range::<&str>((start, Bound::Unbounded))?.take(limit) {
+            keys.push(self.user_key(name, entry?.0.value())?);
+        }
+        Ok(keys)
+    }
+
+    pub fn keys_page(&self, table: &str, offset: usize, limit: usize) -> Result<Vec<String>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let mut keys = Vec::with_capacity(limit.min(table.len()? as usize));
+        for entry in table.iter()?.skip(offset).take(limit) {
+            keys.push(self.user_key(name, entry?.0.value())?);
+        }
+        Ok(keys)
+    }
+
+    /// the entries at the positions of [`keys_page()`](#method.keys_page), \
+    /// the skipped values are never resolved nor deserialized
+    pub fn entries_page<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(String, T)>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let blobs = self.read_blobs(&tnx, name)?;
+        let mut entries = Vec::new();
+        let now = now_millis();
+        for entry in table.iter()?.skip(offset).take(limit) {
+            let (k, v) = entry?;
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if let Ok(value) = self.deserialize(k.value(), &v) {
+                entries.push((self.user_key(name, k.value())?, value));
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn values<T: DeserializeOwned>(&self, table: &str) -> Result<Vec<T>> {
+        let db = &self.db;
+        self.values_at(&db.begin_read()?, table)
+    }
+
+    /// [`values()`](#method.values) in the given read transaction
+    pub fn values_at<T: DeserializeOwned>(
+        &self,
+        tnx: &ReadTransaction,
+        table: &str,
+    ) -> Result<Vec<T>> {
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let blobs = self.read_blobs(tnx, name)?;
+        let mut values = Vec::new();
+        // deserialize while iterating, each guard (and the raw value it borrows) is dropped
+        // before the next entry is read, so only the output is held
+        let now = now_millis();
+        for (k, v) in table.iter()?.flatten() {
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if let Ok(value) = self.deserialize(k.value(), &v) {
+                values.push(value);
+            }
+        }
+        Ok(self.shuffled(values))
+    }
+
+    /// the number of values that deserialize into a `T`, the entries [`values()`](#method.values) would return
+    pub fn count_values<T: DeserializeOwned>(&self, table: &str) -> Result<usize> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, 0);
+        let blobs = self.read_blobs(&tnx, name)?;
+        let mut count = 0;
+        let now = now_millis();
+        for (k, v) in table.iter()?.flatten() {
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if self.deserialize::<T>(k.value(), &v).is_ok() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn entries<T: DeserializeOwned>(&self, table: &str) -> Result<Vec<(String, T)>> {
+        let db = &self.db;
+        self.entries_at(&db.begin_read()?, table)
+    }
+
+    /// [`entries()`](#method.entries) in the given read transaction
+    pub fn entries_at<T: DeserializeOwned>(
+        &self,
+        tnx: &ReadTransaction,
+        table: &str,
+    ) -> Result<Vec<(String, T)>> {
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let blobs = self.read_blobs(tnx, name)?;
+        let mut entries = Vec::new();
+        let now = now_millis();
+        for (k, v) in table.iter()?.flatten() {
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if let Ok(value) = self.deserialize(k.value(), &v) {
+                entries.push((self.user_key(name, k.value())?, value));
+            }
+        }
+        Ok(self.shuffled(entries))
+    }
+
+    pub fn entries_by_key_filter<T: DeserializeOwned, F: FnMut(&str) -> bool>(
+        &self,
+        table: &str,
+        mut f: F,
+    ) -> Result<Vec<(String, T)>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let blobs = self.read_blobs(&tnx, name)?;
+        let mut entries = vec![];
+        let now = now_millis();
+        for (k, v) in table.iter()?.flatten() {
+            // the key is checked first, values of keys that don't match are never resolved or deserialized
+            let key = self.user_key(name, k.value())?;
+            if !f(&key) {
+                continue;
+            }
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if let Ok(value) = self.deserialize(k.value(), &v) {
+                entries.push((key, value));
+            }
+        }
+        Ok(self.shuffled(entries))
+    }
+
+    pub fn iter_entries<T: DeserializeOwned>(&self, table: &str) -> Result<EntriesIter<'_, T>> {
+        let name = &*self.resolve(table);
+        let tnx = self.db.begin_read()?;
+        let (range, blobs) = match tnx.open_table(TableDefinition::<&str, &[u8]>::new(name)) {
+            Ok(table) => (Some(table.range::<&str>(..)?), self.read_blobs(&tnx, name)?),
+            Err(TableError::TableDoesNotExist(_)) => (None, None),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(EntriesIter {
+            store: self,
+            name: name.to_string(),
+            range,
+            blobs,
+            _value: PhantomData,
+        })
+    }
+
+    pub fn iter_keys(&self, table: &str) -> Result<KeysIter<'_>> {
+        let name = &*self.resolve(table);
+        let tnx = self.db.begin_read()?;
+        let range = match tnx.open_table(TableDefinition::<&str, &[u8]>::new(name)) {
+            Ok(table) => Some(table.range::<&str>(..)?),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        Ok(KeysIter {
+            store: self,
+            name: name.to_string(),
+            range,
+        })
+    }
+
+    pub fn keys_with_prefix(&self, table: &str, prefix: &str) -> Result<Vec<String>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let prefix = self.stored_key(name, prefix);
+        let end = prefix_end(&prefix);
+        let mut keys = vec![];
+        // keys are sorted, so the matching ones are contiguous from the prefix on
+        for entry in table.range::<&str>(prefix_range(&prefix, &end))? {
+            let (key, _) = entry?;
+            keys.push(self.user_key(name, key.value())?);
+        }
+        Ok(self.shuffled(keys))
+    }
+
+    pub fn entries_with_prefix<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        prefix: &str,
+    ) -> Result<Vec<(String, T)>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let prefix = self.stored_key(name, prefix);
+        let end = prefix_end(&prefix);
+        let blobs = self.read_blobs(&tnx, name)?;
+        let mut entries = vec![];
+        let now = now_millis();
+        for entry in table.range::<&str>(prefix_range(&prefix, &end))? {
+            let (k, v) = entry?;
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if let Ok(value) = self.deserialize(k.value(), &v) {
+                entries.push((self.user_key(name, k.value())?, value));
+            }
         }
-    };
-}
+        Ok(self.shuffled(entries))
+    }
 
-impl Store {
-    pub fn file(path: impl AsRef<Path>) -> Result<Self> {
-        let db = Database::create(path)?;
-        Ok(Store(db))
+    /// the entries with a key in the given bounds, sorted by key. \
+    /// unlike the other listings, they're never shuffled, the order is the point of a range.
+    pub fn range_entries<T: DeserializeOwned>(
+        &self,
+        table: &str,
+        start: Bound<&str>,
+        end: Bound<&str>,
+    ) -> Result<Vec<(String, T)>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        let start = start.map(|start| self.stored_key(name, start));
+        let end = end.map(|end| self.stored_key(name, end));
+        let range = (start.as_ref().map(|s| &**s), end.as_ref().map(|e| &**e));
+        let blobs = self.read_blobs(&tnx, name)?;
+        let mut entries = vec![];
+        let now = now_millis();
+        for (k, v) in table.range::<&str>(range)?.flatten() {
+            let v = resolve_value(blobs.as_ref(), &self.options.transforms, v.value())?;
+            if is_expired(&v, now)? {
+                continue;
+            }
+            if let Ok(value) = self.deserialize(k.value(), &v) {
+                entries.push((self.user_key(name, k.value())?, value));
+            }
+        }
+        Ok(entries)
     }
 
-    pub fn in_memory() -> Result<Self> {
-        let backend = InMemoryBackend::new();
-        let db = Builder::new().create_with_backend(backend)?;
-        Ok(Store(db))
+    pub fn export_keys(
+        &self,
+        table: &str,
+        mut writer: impl Write,
+        delimiter: u8,
+        prefix: Option<&str>,
+    ) -> Result<u64> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, 0);
+        // escaping is prefix preserving, so the escaped prefix still bounds the escaped keys
+        let prefix = prefix.map(|prefix| self.stored_key(name, prefix));
+        let prefix = prefix.as_deref();
+        let mut count = 0;
+        for entry in table.range(prefix.unwrap_or("")..)? {
+            let (key, _) = entry?;
+            let key = key.value();
+            if prefix.is_some_and(|prefix| !key.starts_with(prefix)) {
+                break;
+            }
+            writer.write_all(self.user_key(name, key)?.as_bytes())?;
+            writer.write_all(&[delimiter])?;
+            count += 1;
+        }
+        writer.flush()?;
+        Ok(count)
     }
 
-    pub fn get<T: DeserializeOwned>(&self, table: &str, key: &str) -> Result<Option<T>> {
-        let db = &self.0;
+    #[cfg(feature = "csv")]
+    pub fn export_table_csv<T: Serialize + DeserializeOwned>(
+        &self,
+        table: &str,
+        mut writer: impl Write,
+    ) -> Result<()> {
+        let name = &*self.resolve(table);
+        let db = &self.db;
         let tnx = db.begin_read()?;
-        let table = open_table_read_or!(tnx, table, None);
-        let bytes = match table.get(key)? {
-            Some(bytes) => bytes,
-            None => return Ok(None),
+        let mut header: Option<Vec<String>> = None;
+        let table = match tnx.open_table(TableDefinition::<&str, &[u8]>::new(name)) {
+            Ok(table) => Some(table),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
         };
-        deserialize(bytes.value())
+        if let Some(table) = &table {
+            let blobs = self.read_blobs(&tnx, name)?;
+            for entry in table.iter()? {
+                let (stored_key, value) = entry?;
+                let stored_key = stored_key.value();
+                let key = self.user_key(name, stored_key)?;
+                let value = resolve_value(blobs.as_ref(), &self.options.transforms, value.value())?;
+                if is_expired(&value, now_millis())? {
+                    continue;
+                }
+                let value: T = self.deserialize(stored_key, &value).with_context(|| {
+                    format!("value of key `{}` isn't a `{}`", key, type_name::<T>())
+                })?;
+                let columns = csv_columns(&serialize_for_key(&key, &value)?)
+                    .with_context(|| format!("failed to convert key `{}` to CSV", key))?;
+                let names: Vec<String> = columns.iter().map(|(name, _)| name.clone()).collect();
+                match &header {
+                    Some(header) => ensure!(
+                        *header == names,
+                        "key `{}` has the columns {:?} instead of {:?}, \
+                         every value must serialize to the same fields (check #[serde(skip_serializing_if)])",
+                        key,
+                        names,
+                        header
+                    ),
+                    None => {
+                        write_csv_field(&mut writer, "key")?;
+                        for name in &names {
+                            writer.write_all(b",")?;
+                            write_csv_field(&mut writer, name)?;
+                        }
+                        writer.write_all(b"\n")?;
+                        header = Some(names);
+                    }
+                }
+                write_csv_field(&mut writer, &key)?;
+                for (_, cell) in &columns {
+                    writer.write_all(b",")?;
+                    write_csv_field(&mut writer, cell)?;
+                }
+                writer.write_all(b"\n")?;
+            }
+        }
+        if header.is_none() {
+            writer.write_all(b"key\n")?;
+        }
+        writer.flush()?;
+        Ok(())
     }
 
-    pub fn insert<T: Serialize>(&mut self, table: &str, key: &str, value: &T) -> Result<()> {
-        let table = TableDefinition::<&str, &[u8]>::new(table);
-        let bytes = serialize(value)?;
-        let db = &self.0;
-        let tnx = db.begin_write()?;
+    pub fn stream_table_to(
+        &self,
+        table: &str,
+        writer: impl Write,
+        format: ExportFormat,
+    ) -> Result<u64> {
+        let mut writer = CountingWriter::new(writer);
+        let name = &*self.resolve(table);
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        self.stream_table_at(&tnx, name, &mut writer, format, false)?;
+        writer.flush()?;
+        Ok(writer.count)
+    }
+
+    /// writes the entries of the table `name` under the snapshot, see [`stream_table_to()`](#method.stream_table_to)
+    fn stream_table_at(
+        &self,
+        tnx: &ReadTransaction,
+        name: &str,
+        mut writer: impl Write,
+        format: ExportFormat,
+        dump: bool,
+    ) -> Result<()> {
+        let table = match tnx.open_table(TableDefinition::<&str, &[u8]>::new(name)) {
+            Ok(table) => Some(table),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let len = match &table {
+            Some(table) => table.len()?,
+            None => 0,
+        };
+        match format {
+            ExportFormat::MessagePack => {
+                let len = u32::try_from(len).context("table has too many entries for a map")?;
+                write_msgpack_map_len(&mut writer, len)?;
+            }
+            ExportFormat::Json => writer.write_all(b"{")?,
+        }
+        if let Some(table) = &table {
+            let blobs = self.read_blobs(tnx, name)?;
+            for (i, entry) in table.iter()?.enumerate() {
+                let (key, value) = entry?;
+                let key = self.user_key(name, key.value())?;
+                let stored =
+                    resolve_value(blobs.as_ref(), &self.options.transforms, value.value())?;
+                let value = self.plain_msgpack(&stored)?;
+                let limits = &self.options.read_limits;
+                match format {
+                    ExportFormat::MessagePack => {
+                        write_msgpack_str(&mut writer, &key)?;
+                        writer.write_all(&value)?;
+                    }
+                    ExportFormat::Json => {
+                        if i > 0 {
+                            writer.write_all(b",")?;
+                        }
+                        write_json_str(&mut writer, &key)?;
+                        writer.write_all(b":")?;
+                        let written = match dump {
+                            true => write_dump_value(&mut writer, &stored, &value, limits),
+                            false => msgpack::check(&value, limits)
+                                .and_then(|_| write_json(&mut writer, &mut Reader::new(&value))),
+                        };
+                        written
+                            .with_context(|| format!("failed to convert key `{}` to JSON", key))?;
+                    }
+                }
+            }
+        }
+        if format == ExportFormat::Json {
+            writer.write_all(b"}")?;
+        }
+        Ok(())
+    }
+
+    /// writes the user tables and the default tables as a single JSON object under a single snapshot, \
+    /// mapping each table name to an object of its keys and values, \
+    /// with the values carrying metadata wrapped by `write_dump_value()`
+    pub fn export_json(&self, mut writer: impl Write, default_table: &str) -> Result<()> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        writer.write_all(b"{")?;
+        let tables = self
+            .table_kinds_at(&tnx, default_table)?
+            .into_iter()
+            .filter(|(_, kind)| {
+                matches!(
+                    kind,
+                    TableKind::User | TableKind::Reserved(ReservedKind::Default)
+                )
+            });
+        for (i, (name, _)) in tables.enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            write_json_str(&mut writer, &name)?;
+            writer.write_all(b":")?;
+            self.stream_table_at(&tnx, &name, &mut writer, ExportFormat::Json, true)
+                .with_context(|| format!("failed to export table `{}`", name))?;
+        }
+        writer.write_all(b"}")?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// converts every value of the user tables and the default tables to `to`, `chunk_size` values per write transaction, \
+    /// then saves `to` as the format of the database. \
+    /// each chunk saves the last entry it converted along with it, so an interrupted conversion resumes after it. \
+    /// `progress` is called after every chunk
+    pub fn convert_format(
+        &mut self,
+        to: SerializationFormat,
+        default_table: &str,
+        chunk_size: usize,
+        mut progress: impl FnMut(ConvertProgress),
+    ) -> Result<ConvertReport> {
+        self.ensure_none_locked()?;
+        let mut report = ConvertReport::default();
+        let mut cursor: Option<(String, String)> = None;
+        match self.metadata::<String>(CONVERT_TO_KEY)? {
+            Some(pending) => {
+                ensure!(
+                    pending == to.name(),
+                    "the conversion of the database to {} was interrupted, it must be finished first",
+                    pending
+                );
+                report.resumed = true;
+                cursor = self.metadata(CONVERT_CURSOR_KEY)?;
+            }
+            None if self.format == to => return Ok(report),
+            None => {}
+        }
+        let tables = {
+            let tnx = self.db.begin_read()?;
+            let mut tables: Vec<String> = self
+                .table_kinds_at(&tnx, default_table)?
+                .into_iter()
+                .filter(|(_, kind)| {
+                    matches!(
+                        kind,
+                        TableKind::User | TableKind::Reserved(ReservedKind::Default)
+                    )
+                })
+                .map(|(name, _)| name)
+                .collect();
+            tables.sort();
+            tables
+        };
+        if let Some(chained) = tables.iter().find(|t| self.settings().chained.contains(*t)) {
+            bail!(
+                "append-only table `{}` can't be converted, its hash chain covers the stored values",
+                chained
+            );
+        }
+        if !report.resumed {
+            let tnx = self.begin_write()?;
+            self.set_metadata_in(&tnx, CONVERT_TO_KEY, &to.name())?;
+            tnx.commit()?;
+        }
+
+        for name in &tables {
+            let mut after = match &cursor {
+                Some((table, _)) if table > name => continue,
+                Some((table, key)) if table == name => Some(key.clone()),
+                _ => None,
+            };
+            loop {
+                let tnx = self.begin_write()?;
+                let done = {
+                    let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))?;
+                    let start = match after.as_deref() {
+                        Some(after) => Bound::Excluded(after),
+                        None => Bound::Unbounded,
+                    };
+                    let mut entries = vec![];
+                    for entry in table
+                        .range::<&str>((start, Bound::Unbounded))?
+                        .take(chunk_size)
+                    {
+                        let (key, value) = entry?;
+                        entries.push((key.value().to_string(), value.value().to_vec()));
+                    }
+                    for (key, stored) in &entries {
+                        let bytes = self.resolve_in(&tnx, name, stored)?;
+                        let envelope = Envelope::decode(&bytes)?;
+                        if decode_counter(&bytes).is_some() || decode_raw(envelope.value).is_some()
+                        {
+                            report.unchanged += 1;
+                            continue;
+                        }
+                        let value = to_msgpack(self.format, envelope.value)
+                            .and_then(|value| {
+                                from_msgpack(to, &value, &self.options.read_limits)
+                                    .map(Cow::into_owned)
+                            })
+                            .with_context(|| {
+                                format!("failed to convert key `{}` of table `{}`", key, name)
+                            })?;
+                        let converted = Envelope {
+                            value: &value,
+                            ..envelope
+                        }
+                        .encode();
+                        let converted = self.options.transforms.apply(&converted);
+                        let converted = self.store_blob(&tnx, name, &converted)?;
+                        table.insert(key.as_str(), &*converted)?;
+                        self.release_blob(&tnx, name, stored)?;
+                        report.converted += 1;
+                    }
+                    let done = entries.len();
+                    if let Some((key, _)) = entries.pop() {
+                        self.set_metadata_in(&tnx, CONVERT_CURSOR_KEY, &(name, &key))?;
+                        after = Some(key);
+                    }
+                    done
+                };
+                tnx.commit()?;
+                if done > 0 {
+                    progress(ConvertProgress {
+                        table: name.clone(),
+                        converted: report.converted,
+                    });
+                }
+                if done < chunk_size {
+                    break;
+                }
+            }
+        }
+
+        let tnx = self.begin_write()?;
+        self.set_metadata_in(&tnx, FORMAT_KEY, &to.name())?;
         {
-            let mut table = tnx.open_table(table)?;
-            table.insert(key, bytes.as_slice())?;
+            let mut metadata =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.metadata))?;
+            metadata.remove(CONVERT_TO_KEY)?;
+            metadata.remove(CONVERT_CURSOR_KEY)?;
         }
         tnx.commit()?;
+        self.format = to;
+        Ok(report)
+    }
+
+    /// a value of the metadata table
+    fn metadata<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, &self.reserved.metadata, None);
+        let Some(value) = table.get(key)? else {
+            return Ok(None);
+        };
+        Ok(Some(deserialize(key, value.value(), false)?))
+    }
+
+    fn set_metadata_in(
+        &self,
+        tnx: &WriteTransaction,
+        key: &str,
+        value: &impl Serialize,
+    ) -> Result<()> {
+        let mut metadata =
+            tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.metadata))?;
+        metadata.insert(key, serialize(value)?.as_slice())?;
         Ok(())
     }
 
-    pub fn remove(&mut self, table: &str, key: &str) -> Result<()> {
-        let table = TableDefinition::<&str, &[u8]>::new(table);
-        let db = &self.0;
-        let tnx = db.begin_write()?;
+    /// what the table is, see [`TableKind`](../enum.TableKind.html)
+    #[cfg(feature = "json")]
+    pub fn table_kind_of(&self, name: &str, default_table: &str) -> Result<TableKind> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        self.table_kind(name, default_table, || {
+            tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))
+                .map(drop)
+        })
+    }
+
+    /// removes the entries matching `predicate`, `chunk_size` entries per write transaction
+    pub fn remove_where_field(
+        &mut self,
+        table: &str,
+        path: &str,
+        predicate: &FieldPredicate,
+        chunk_size: usize,
+    ) -> Result<u64> {
+        self.remove_matching(table, chunk_size, |store, tnx, name, value| {
+            predicate.matches_value(
+                &store.plain_msgpack(&store.resolve_in(tnx, name, value)?)?,
+                path,
+            )
+        })
+    }
+
+    /// removes the entries whose value is larger than `bytes` as stored, `chunk_size` entries per write transaction
+    pub fn remove_larger_than(
+        &mut self,
+        table: &str,
+        bytes: usize,
+        chunk_size: usize,
+    ) -> Result<u64> {
+        self.remove_matching(table, chunk_size, |store, tnx, name, value| {
+            Ok(store.stored_len_in(tnx, name, value)? > bytes)
+        })
+    }
+
+    /// removes the entries whose stored value `matches`, `chunk_size` entries per write transaction
+    fn remove_matching(
+        &mut self,
+        table: &str,
+        chunk_size: usize,
+        mut matches: impl FnMut(&Self, &WriteTransaction, &str, &[u8]) -> Result<bool>,
+    ) -> Result<u64> {
+        let name = &*self.resolve(table);
+        self.ensure_removable(name, None)?;
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let mut count = 0;
+        let mut cursor: Option<String> = None;
+        loop {
+            let tnx = self.begin_write_to(name)?;
+            let matching = {
+                let mut table = match tnx.open_table(table) {
+                    Ok(table) => table,
+                    Err(TableError::TableDoesNotExist(_)) => return Ok(count),
+                    Err(e) => return Err(e.into()),
+                };
+                let start = match &cursor {
+                    Some(cursor) => Bound::Excluded(cursor.as_str()),
+                    None => Bound::Unbounded,
+                };
+                let mut matching = vec![];
+                let mut last = None;
+                for entry in table.range::<&str>((start, Bound::Unbounded))? {
+                    let (key, value) = entry?;
+                    last = Some(key.value().to_string());
+                    if matches(self, &tnx, name, value.value())? {
+                        matching.push(key.value().to_string());
+                        if matching.len() == chunk_size {
+                            break;
+                        }
+                    }
+                }
+                for key in &matching {
+                    if let Some(old) = table.remove(key.as_str())? {
+                        self.release_blob(&tnx, name, old.value())?;
+                    }
+                }
+                cursor = last;
+                matching.len()
+            };
+            tnx.commit()?;
+            count += matching as u64;
+            if matching < chunk_size {
+                return Ok(count);
+            }
+        }
+    }
+
+    pub fn remove_keys<K: AsRef<str>>(&mut self, table: &str, keys: &[K]) -> Result<u64> {
+        let name = &*self.resolve(table);
+        if let Some(key) = keys.first() {
+            self.ensure_removable(name, Some(&self.stored_key(name, key.as_ref())))?;
+        }
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        let mut count = 0;
         {
             let mut table = tnx.open_table(table)?;
-            table.remove(key)?;
+            for key in keys {
+                if let Some(old) = table.remove(&*self.stored_key(name, key.as_ref()))? {
+                    self.release_blob(&tnx, name, old.value())?;
+                    count += 1;
+                }
+            }
         }
         tnx.commit()?;
-        Ok(())
+        Ok(count)
     }
 
-    pub fn clear(&mut self, table: &str) -> Result<()> {
-        let table = TableDefinition::<&str, &[u8]>::new(table);
-        let db = &self.0;
-        let tnx = db.begin_write()?;
-        tnx.delete_table(table)?;
+    pub fn remove_keys_with_report(
+        &mut self,
+        table: &str,
+        keys: Vec<String>,
+    ) -> Result<RemoveReport> {
+        let name = &*self.resolve(table);
+        if let Some(key) = keys.first() {
+            self.ensure_removable(name, Some(&self.stored_key(name, key)))?;
+        }
+        let table = TableDefinition::<&str, &[u8]>::new(name);
+        let tnx = self.begin_write_to(name)?;
+        let mut report = RemoveReport::default();
+        {
+            let mut table = tnx.open_table(table)?;
+            for key in keys {
+                if let Some(old) = table.remove(&*self.stored_key(name, &key))? {
+                    self.release_blob(&tnx, name, old.value())?;
+                    report.removed.push(key);
+                } else {
+                    report.not_found.push(key);
+                }
+            }
+        }
         tnx.commit()?;
+        Ok(report)
+    }
+
+    pub fn digest(&self, table: &str) -> Result<[u8; 32]> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, Sha256::digest([]).into());
+        digest_table(
+            &table,
+            self.read_blobs(&tnx, name)?.as_ref(),
+            &self.options.transforms,
+        )
+    }
+
+    pub fn digest_all(&self, default_table: &str) -> Result<BTreeMap<String, [u8; 32]>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let mut digests = BTreeMap::new();
+        for name in self.data_tables_at(&tnx, default_table)? {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            let blobs = self.read_blobs(&tnx, &name)?;
+            let digest = digest_table(&table, blobs.as_ref(), &self.options.transforms)?;
+            digests.insert(name, digest);
+        }
+        Ok(digests)
+    }
+
+    pub fn tables_equal(&self, a: &str, b: &str) -> Result<bool> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let (a, b) = (&*self.resolve(a), &*self.resolve(b));
+        let open = |name: &str| match tnx.open_table(TableDefinition::<&str, &[u8]>::new(name)) {
+            Ok(table) => Ok(Some(table)),
+            Err(TableError::TableDoesNotExist(_)) => Ok(None),
+            Err(e) => Err(e),
+        };
+        // a table that doesn't exist is equal to an empty one
+        let (table_a, table_b) = match (open(a)?, open(b)?) {
+            (Some(table_a), Some(table_b)) => (table_a, table_b),
+            (Some(table), None) | (None, Some(table)) => return Ok(table.is_empty()?),
+            (None, None) => return Ok(true),
+        };
+        if table_a.len()? != table_b.len()? {
+            return Ok(false);
+        }
+        let (blobs_a, blobs_b) = (self.read_blobs(&tnx, a)?, self.read_blobs(&tnx, b)?);
+        // both iterate in key order, so equal tables line up entry by entry
+        for (entry_a, entry_b) in table_a.iter()?.zip(table_b.iter()?) {
+            let ((key_a, value_a), (key_b, value_b)) = (entry_a?, entry_b?);
+            if key_a.value() != key_b.value()
+                || resolve_value(blobs_a.as_ref(), &self.options.transforms, value_a.value())?
+                    != resolve_value(blobs_b.as_ref(), &self.options.transforms, value_b.value())?
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    pub fn redb_stats(&self, table: &str) -> Result<Option<TableStats>> {
+        if self.in_memory {
+            return Ok(None);
+        }
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let table = open_table_read_or!(tnx, &self.resolve(table), None);
+        Ok(Some(table.stats()?))
+    }
+
+    /// reads every key and value of the table (and of its blobs table) once, discarding them
+    pub fn prewarm(&self, table: &str) -> Result<()> {
+        if self.in_memory {
+            return Ok(());
+        }
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, ());
+        let mut read = 0;
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            read += key.value().len() + value.value().len();
+        }
+        if let Some(blobs) = self.read_blobs(&tnx, name)? {
+            for entry in blobs.iter()? {
+                read += entry?.1.value().len();
+            }
+        }
+        std::hint::black_box(read);
         Ok(())
     }
 
-    pub fn keys(&self, table: &str) -> Result<Vec<String>> {
-        let db = &self.0;
+    pub fn keys_larger_than(&self, table: &str, bytes: usize) -> Result<Vec<(String, usize)>> {
+        let db = &self.db;
         let tnx = db.begin_read()?;
-        let table = open_table_read_or!(tnx, table, vec![]);
-        let entries = table.iter()?;
-        let keys = entries
-            .flatten()
-            .map(|(k, _)| k.value().to_string())
-            .collect();
-        Ok(keys)
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, vec![]);
+        self.larger_than_in(&tnx, &table, name, bytes)
     }
 
-    pub fn values<T: DeserializeOwned>(&self, table: &str) -> Result<Vec<T>> {
-        let db = &self.0;
+    pub fn large_values_report(
+        &self,
+        bytes: usize,
+        default_table: &str,
+    ) -> Result<Vec<(String, String, usize)>> {
+        let db = &self.db;
         let tnx = db.begin_read()?;
-        let table = open_table_read_or!(tnx, table, vec![]);
-        let entries = table.iter()?;
-        let values = entries
-            .flatten()
-            .flat_map(|(_, v)| deserialize(v.value()).ok())
-            .collect();
-        Ok(values)
+        let mut report = vec![];
+        for name in self.data_tables_at(&tnx, default_table)? {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            for (key, size) in self.larger_than_in(&tnx, &table, &name, bytes)? {
+                report.push((name.clone(), key, size));
+            }
+        }
+        Ok(report)
     }
 
-    pub fn entries<T: DeserializeOwned>(&self, table: &str) -> Result<Vec<(String, T)>> {
-        let db = &self.0;
+    fn larger_than_in(
+        &self,
+        tnx: &ReadTransaction,
+        table: &ReadOnlyTable<&'static str, &'static [u8]>,
+        name: &str,
+        bytes: usize,
+    ) -> Result<Vec<(String, usize)>> {
+        let blobs = self.read_blobs(tnx, name)?;
+        let mut larger = vec![];
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            let size = stored_len(blobs.as_ref(), value.value())?;
+            if size > bytes {
+                larger.push((self.user_key(name, key.value())?, size));
+            }
+        }
+        Ok(larger)
+    }
+
+    pub fn summarize_all(&self, default_table: &str) -> Result<BTreeMap<String, TableManifest>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        self.summarize_at(&tnx, default_table)
+    }
+
+    pub fn summarize_at(
+        &self,
+        tnx: &ReadTransaction,
+        default_table: &str,
+    ) -> Result<BTreeMap<String, TableManifest>> {
+        let mut summaries = BTreeMap::new();
+        for name in self.data_tables_at(tnx, default_table)? {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            let blobs = self.read_blobs(tnx, &name)?;
+            let mut summary = summarize_table(&table, blobs.as_ref(), &self.options.transforms)?;
+            let notes = self.notes_in(tnx, &name)?;
+            summary.description = notes.description;
+            summary.annotations = notes.annotations;
+            summaries.insert(name, summary);
+        }
+        Ok(summaries)
+    }
+
+    pub fn table_notes(&self, table: &str) -> Result<TableNotes> {
+        let db = &self.db;
         let tnx = db.begin_read()?;
-        let table = open_table_read_or!(tnx, table, vec![]);
-        let entries = table.iter()?;
-        let entries = entries
-            .flatten()
-            .flat_map(|(k, v)| Some((k.value().to_string(), deserialize(v.value()).ok()?)))
+        self.notes_in(&tnx, &self.resolve(table))
+    }
+
+    fn notes_in(&self, tnx: &ReadTransaction, name: &str) -> Result<TableNotes> {
+        if !self.settings().annotated.contains(name) {
+            return Ok(TableNotes::default());
+        }
+        let table = open_table_read_or!(tnx, &self.reserved.annotations, TableNotes::default());
+        let notes = match table.get(name)? {
+            Some(notes) => deserialize(name, notes.value(), false)?,
+            None => TableNotes::default(),
+        };
+        Ok(notes)
+    }
+
+    /// changes the description and annotations of a table with `f`, \
+    /// creating the table if it doesn't exist yet so they show up in the table listings
+    pub fn update_notes(&mut self, table: &str, f: impl FnOnce(&mut TableNotes)) -> Result<()> {
+        let name = self.resolve(table).to_string();
+        let tnx = self.begin_write_to(&name)?;
+        let notes = {
+            tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(
+                &self.reserved.annotations,
+            ))?;
+            let mut notes = match table.get(name.as_str())? {
+                Some(notes) => deserialize(&name, notes.value(), false)?,
+                None => TableNotes::default(),
+            };
+            f(&mut notes);
+            match notes.is_empty() {
+                true => table.remove(name.as_str())?,
+                false => table.insert(name.as_str(), serialize(&notes)?.as_slice())?,
+            };
+            notes
+        };
+        tnx.commit()?;
+        match notes.is_empty() {
+            true => self.settings_mut().annotated.remove(&name),
+            false => self.settings_mut().annotated.insert(name),
+        };
+        Ok(())
+    }
+
+    /// what the table is, with `default_table` (the current default table) counted as reserved. \
+    /// `open` opens it as a table of dbless, and is only called for the tables that aren't reserved
+    fn table_kind<T>(
+        &self,
+        name: &str,
+        default_table: &str,
+        open: impl FnOnce() -> Result<T, TableError>,
+    ) -> Result<TableKind> {
+        if name == default_table {
+            return Ok(TableKind::Reserved(ReservedKind::Default));
+        }
+        if let Some(kind) = self.reserved.kind(name) {
+            return Ok(TableKind::Reserved(kind));
+        }
+        match open() {
+            Ok(_) | Err(TableError::TableDoesNotExist(_)) => Ok(TableKind::User),
+            Err(TableError::TableTypeMismatch { .. } | TableError::TableIsMultimap(_)) => {
+                Ok(TableKind::Foreign)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// every table in the file with its kind, under the snapshot, \
+    /// multimap tables are always foreign since dbless never creates one
+    fn table_kinds_at(
+        &self,
+        tnx: &ReadTransaction,
+        default_table: &str,
+    ) -> Result<Vec<(String, TableKind)>> {
+        let mut kinds = vec![];
+        for t in tnx.list_tables()? {
+            let definition = TableDefinition::<&str, &[u8]>::new(t.name());
+            let kind = self.table_kind(t.name(), default_table, || tnx.open_table(definition))?;
+            kinds.push((t.name().to_string(), kind));
+        }
+        for t in tnx.list_multimap_tables()? {
+            kinds.push((t.name().to_string(), TableKind::Foreign));
+        }
+        Ok(kinds)
+    }
+
+    /// the tables whole-database reads go through, under the snapshot: \
+    /// the user tables and the default table, leaving out the internal and foreign ones
+    fn data_tables_at(&self, tnx: &ReadTransaction, default_table: &str) -> Result<Vec<String>> {
+        Ok(self
+            .table_kinds_at(tnx, default_table)?
+            .into_iter()
+            .filter(|(name, kind)| *kind != TableKind::Foreign && !self.reserved.is_reserved(name))
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    /// the tables created through dbless, the ones `Database::list_tables()` returns
+    pub fn user_tables(&self, default_table: &str) -> Result<Vec<String>> {
+        self.user_tables_with_prefix("", default_table)
+    }
+
+    /// the [`user_tables()`](#method.user_tables) whose name starts with `prefix`, \
+    /// only opening the tables whose name matches to classify them
+    pub fn user_tables_with_prefix(
+        &self,
+        prefix: &str,
+        default_table: &str,
+    ) -> Result<Vec<String>> {
+        let tnx = self.db.begin_read()?;
+        let mut tables = vec![];
+        for t in tnx.list_tables()?.filter(|t| t.name().starts_with(prefix)) {
+            let definition = TableDefinition::<&str, &[u8]>::new(t.name());
+            let kind = self.table_kind(t.name(), default_table, || tnx.open_table(definition))?;
+            if kind == TableKind::User {
+                tables.push(t.name().to_string());
+            }
+        }
+        Ok(tables)
+    }
+
+    /// every table in the file with its kind, entry count, description and annotations, under a single snapshot, \
+    /// sorted by name
+    pub fn list_tables_detailed(&self, default_table: &str) -> Result<Vec<TableInfo>> {
+        let tnx = self.db.begin_read()?;
+        let mut details = vec![];
+        for (name, kind) in self.table_kinds_at(&tnx, default_table)? {
+            let entries = match kind {
+                TableKind::Foreign => None,
+                _ => match tnx.open_untyped_table(TableDefinition::<&str, &[u8]>::new(&name)) {
+                    Ok(table) => Some(table.len()? as usize),
+                    Err(TableError::TableDoesNotExist(_)) => Some(0),
+                    Err(e) => return Err(e.into()),
+                },
+            };
+            let notes = match kind {
+                TableKind::Foreign => TableNotes::default(),
+                _ => self.notes_in(&tnx, &name)?,
+            };
+            details.push(TableInfo {
+                name,
+                kind,
+                entries,
+                description: notes.description,
+                annotations: notes.annotations,
+            });
+        }
+        details.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(details)
+    }
+
+    /// calls `f` with every entry of the table under the snapshot, \
+    /// keys as stored and values with their blob resolved and their transforms reversed
+    pub fn for_each_resolved_at(
+        &self,
+        tnx: &ReadTransaction,
+        name: &str,
+        mut f: impl FnMut(&str, &[u8]) -> Result<()>,
+    ) -> Result<()> {
+        let table = open_table_read_or!(tnx, name, ());
+        let blobs = self.read_blobs(tnx, name)?;
+        for entry in table.iter()? {
+            let (key, value) = entry?;
+            let value = resolve_value(blobs.as_ref(), &self.options.transforms, value.value())?;
+            f(key.value(), &value)?;
+        }
+        Ok(())
+    }
+
+    /// writes the entries of a backup in a single write transaction, table by table, \
+    /// `next` giving the `(key, value)` of the entries of `tables` in order, as many as each table holds. \
+    /// every table is created even if it holds no entries, and cleared first if `replace`
+    pub fn restore_entries(
+        &mut self,
+        tables: &[(String, u64)],
+        replace: bool,
+        mut next: impl FnMut() -> Result<(String, Vec<u8>)>,
+    ) -> Result<()> {
+        for (name, _) in tables {
+            ensure!(
+                !self.reserved.is_reserved(name),
+                "table `{}` is reserved by dbless, it can't be restored",
+                name
+            );
+            self.ensure_unlocked(name)?;
+            if replace {
+                self.ensure_removable(name, None)?;
+            }
+        }
+        let tnx = self.begin_write()?;
+        for (name, entries) in tables {
+            if replace {
+                self.clear_in(&tnx, name)?;
+            }
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))?;
+            for _ in 0..*entries {
+                let (key, value) = next()?;
+                let existed = self.write_value(&tnx, &mut table, name, &key, &value)?;
+                self.enforce_limit(&tnx, &mut table, name, &key, existed)?;
+            }
+        }
+        tnx.commit()?;
+        Ok(())
+    }
+
+    /// copies every table of a damaged database (but the reserved ones) into this one, see `dbless::salvage()`
+    #[cfg(feature = "recovery")]
+    pub fn salvage_from(&mut self, source: &Database) -> Result<Vec<SalvagedTable>> {
+        let tnx = source.begin_read()?;
+        let mut names: Vec<String> = tnx
+            .list_tables()?
+            .map(|t| t.name().to_string())
+            .filter(|name| !self.reserved.is_reserved(name))
             .collect();
-        Ok(entries)
+        names.sort_unstable();
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let mut salvaged = SalvagedTable {
+                name,
+                salvaged: 0,
+                expected: None,
+                error: None,
+            };
+            let copy = catch_unwind(AssertUnwindSafe(|| self.salvage_table(&tnx, &mut salvaged)));
+            salvaged.error = match copy {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(format!("{:#}", e)),
+                Err(_) => Some("redb panicked reading the table".to_string()),
+            };
+            tables.push(salvaged);
+        }
+        Ok(tables)
+    }
+
+    /// copies the entries of a table until one can't be read, \
+    /// the ones copied before that are committed
+    #[cfg(feature = "recovery")]
+    fn salvage_table(
+        &mut self,
+        source: &ReadTransaction,
+        salvaged: &mut SalvagedTable,
+    ) -> Result<()> {
+        let name = salvaged.name.clone();
+        let table = source.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+        salvaged.expected = table.len().ok();
+        let blobs_name = self.blobs_table_name(&name);
+        let blobs = match source.open_table(TableDefinition::<&[u8], &[u8]>::new(&blobs_name)) {
+            Ok(blobs) => Some(blobs),
+            Err(TableError::TableDoesNotExist(_)) => None,
+            Err(e) => return Err(e.into()),
+        };
+        let tnx = self.begin_write_to(&name)?;
+        let result = {
+            let mut dest = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            let mut copy = || -> Result<()> {
+                for entry in table.iter()? {
+                    let (key, value) = entry?;
+                    let (key, value) = (key.value(), value.value());
+                    ensure!(
+                        blobs.is_some() || decode_blob_ref(value).is_none(),
+                        "the value of key `{}` is in a blobs table that doesn't exist",
+                        key
+                    );
+                    // stored again with the transforms of this database, which should be the same
+                    let value = resolve_value(blobs.as_ref(), &self.options.transforms, value)?;
+                    let existed = self.write_value(&tnx, &mut dest, &name, key, &value)?;
+                    self.enforce_limit(&tnx, &mut dest, &name, key, existed)?;
+                    salvaged.salvaged += 1;
+                }
+                Ok(())
+            };
+            copy()
+        };
+        tnx.commit()?;
+        result
+    }
+
+    pub fn options(&self) -> &Options {
+        &self.options
+    }
+
+    /// the format the values are stored in
+    pub fn format(&self) -> SerializationFormat {
+        self.format
     }
 
     pub fn len(&self, table: &str) -> Result<usize> {
-        let db = &self.0;
-        let tnx = db.begin_read()?;
-        let table = open_table_read_or!(tnx, table, 0);
+        let name = &*self.resolve(table);
+        if !self.options.cache_table_lengths {
+            return self.count_entries(name);
+        }
+        let mut lengths = self
+            .table_lengths
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if let Some(&len) = lengths.get(name) {
+            return Ok(len);
+        }
+        let len = self.count_entries(name)?;
+        lengths.insert(name.to_string(), len);
+        Ok(len)
+    }
+
+    fn count_entries(&self, name: &str) -> Result<usize> {
+        let db = &self.db;
+        self.len_at(&db.begin_read()?, name)
+    }
+
+    /// [`len()`](#method.len) in the given read transaction, never cached
+    pub fn len_at(&self, tnx: &ReadTransaction, table: &str) -> Result<usize> {
+        let name = &*self.resolve(table);
+        let table = open_table_read_or!(tnx, name, 0);
         let len = table.len()?;
         Ok(len as usize)
     }
 
     pub fn contains_key(&self, table: &str, key: &str) -> Result<bool> {
-        let db = &self.0;
+        let db = &self.db;
+        self.contains_key_at(&db.begin_read()?, table, key)
+    }
+
+    /// [`contains_key()`](#method.contains_key) in the given read transaction
+    pub fn contains_key_at(&self, tnx: &ReadTransaction, table: &str, key: &str) -> Result<bool> {
+        let name = &*self.resolve(table);
+        let key = self.stored_key(name, key);
+        let key = &*key;
+        let table = open_table_read_or!(tnx, name, false);
+        let Some(bytes) = table.get(key)? else {
+            return Ok(false);
+        };
+        let blobs = self.read_blobs(tnx, name)?;
+        let bytes = resolve_value(blobs.as_ref(), &self.options.transforms, bytes.value())?;
+        Ok(!is_expired(&bytes, now_millis())?)
+    }
+
+    /// checks if the table is one of the [`user_tables()`](#method.user_tables)
+    pub fn contains_table(&self, name: &str, default_table: &str) -> Result<bool> {
+        if self.options.cache_table_names {
+            let mut table_names = self
+                .table_names
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            if table_names.is_none() {
+                *table_names = Some(self.user_tables(default_table)?.into_iter().collect());
+            }
+            return Ok(table_names
+                .as_ref()
+                .is_some_and(|names| names.contains(name)));
+        }
+        let db = &self.db;
         let tnx = db.begin_read()?;
-        let table = open_table_read_or!(tnx, table, false);
-        Ok(table.get(key)?.is_some())
+        if !tnx.list_tables()?.any(|t| t.name() == name) {
+            return Ok(false);
+        }
+        let kind = self.table_kind(name, default_table, || {
+            tnx.open_table(TableDefinition::<&str, &[u8]>::new(name))
+                .map(drop)
+        })?;
+        Ok(kind == TableKind::User)
     }
 
     pub fn list_tables(&self) -> Result<Vec<String>> {
-        let db = &self.0;
+        let db = &self.db;
         let tnx = db.begin_read()?;
         let tables = tnx.list_tables()?;
         Ok(tables.map(|t| t.name().to_string()).collect())
     }
 
-    pub fn len_all_tables(&self) -> Result<usize> {
-        let db = &self.0;
+    /// the tables [`len_all_tables()`](#method.len_all_tables) counts, as far as their names tell: \
+    /// the default table (unless it falls under a custom reserved prefix) and every table that isn't reserved. \
+    /// foreign tables are only told apart once opened, see [`counted_len_at()`](#method.counted_len_at).
+    fn counted_tables_at(&self, tnx: &ReadTransaction, default_table: &str) -> Result<Vec<String>> {
+        Ok(tnx
+            .list_tables()?
+            .map(|t| t.name().to_string())
+            .filter(|name| {
+                if name == default_table {
+                    !self.reserved.is_reserved(name)
+                } else {
+                    self.reserved.kind(name).is_none()
+                }
+            })
+            .collect())
+    }
+
+    /// the number of entries in a table listed by [`counted_tables_at()`](#method.counted_tables_at), \
+    /// opening it only once, and 0 for a foreign table
+    fn counted_len_at(tnx: &ReadTransaction, name: &str) -> Result<u64> {
+        match tnx.open_table(TableDefinition::<&str, &[u8]>::new(name)) {
+            Ok(table) => Ok(table.len()?),
+            Err(
+                TableError::TableDoesNotExist(_)
+                | TableError::TableTypeMismatch { .. }
+                | TableError::TableIsMultimap(_),
+            ) => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// the number of entries in the user tables and the default table, \
+    /// unless the default table falls under a custom reserved prefix. \
+    /// the tables are counted in parallel with the `rayon` feature.
+    pub fn len_all_tables(&self, default_table: &str) -> Result<usize> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let tables = self.counted_tables_at(&tnx, default_table)?;
+        #[cfg(feature = "rayon")]
+        let lens = {
+            use rayon::prelude::*;
+            tables
+                .par_iter()
+                .map(|name| Self::counted_len_at(&tnx, name))
+                .collect::<Result<Vec<_>>>()?
+        };
+        #[cfg(not(feature = "rayon"))]
+        let lens = tables
+            .iter()
+            .map(|name| Self::counted_len_at(&tnx, name))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(lens.into_iter().sum::<u64>() as usize)
+    }
+
+    /// [`len_all_tables()`](#method.len_all_tables), but stops opening tables once `cap` entries were counted
+    pub fn len_all_tables_capped(&self, default_table: &str, cap: usize) -> Result<CappedLen> {
+        let db = &self.db;
         let tnx = db.begin_read()?;
-        let tables = tnx.list_tables()?;
         let mut len = 0;
-        for t in tables {
-            let table_definition = TableDefinition::<&str, &[u8]>::new(t.name());
-            let table = tnx.open_table(table_definition)?;
-            len += table.len()?;
+        for name in self.counted_tables_at(&tnx, default_table)? {
+            if len >= cap {
+                break;
+            }
+            len += Self::counted_len_at(&tnx, &name)? as usize;
         }
-        Ok(len as usize)
+        if len >= cap {
+            return Ok(CappedLen::AtLeast(cap));
+        }
+        Ok(CappedLen::Exact(len))
     }
 
-    pub fn delete_all_tables(&mut self) -> Result<()> {
-        let db = &self.0;
-        let tnx = db.begin_write()?;
-        let tables = tnx.list_tables()?;
+    pub fn keys_all_tables(&self, default_table: &str) -> Result<Vec<(String, String)>> {
+        let db = &self.db;
+        let tnx = db.begin_read()?;
+        let mut keys = vec![];
+        for name in self.data_tables_at(&tnx, default_table)? {
+            let table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(&name))?;
+            for entry in table.iter()? {
+                let (key, _) = entry?;
+                keys.push((name.clone(), self.user_key(&name, key.value())?));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// deletes the user and reserved tables, leaving the foreign ones alone
+    pub fn delete_all_tables(&self, default_table: &str) -> Result<()> {
+        self.ensure_none_locked()?;
+        let _maintenance = self.lock_maintenance();
+        let tnx = self.begin_write()?;
+        let tables: Vec<_> = tnx.list_tables()?.collect();
         for table in tables {
-            tnx.delete_table(table)?;
+            let definition = TableDefinition::<&str, &[u8]>::new(table.name());
+            let kind = self.table_kind(table.name(), default_table, || {
+                tnx.open_table(definition).map(drop)
+            })?;
+            if kind != TableKind::Foreign {
+                tnx.delete_table(table)?;
+            }
         }
-        tnx.commit()?;
+        self.commit_shared(tnx)?;
+        let mut settings = self.settings_mut();
+        settings.aliases.clear();
+        settings.limits.clear();
+        settings.escaped.clear();
+        settings.chained.clear();
+        settings.deduplicated.clear();
+        settings.annotated.clear();
+        settings.write_once.clear();
         Ok(())
     }
 
-    pub fn delete_table(&mut self, name: &str) -> Result<()> {
-        let db = &self.0;
+    /// the share of the allocated space that's lost to fragmentation, from 0 to 1
+    pub fn fragmentation(&self) -> Result<f64> {
+        let db = &self.db;
         let tnx = db.begin_write()?;
-        tnx.delete_table(TableDefinition::<&str, &[u8]>::new(name))?;
+        let stats = tnx.stats()?;
+        tnx.abort()?;
+        let allocated = stats.allocated_pages() * stats.page_size() as u64;
+        if allocated == 0 {
+            return Ok(0.0);
+        }
+        Ok(stats.fragmented_bytes() as f64 / allocated as f64)
+    }
+
+    pub fn compact(&mut self) -> Result<bool> {
+        self.ensure_not_frozen()?;
+        Ok(self.db.compact()?)
+    }
+
+    /// compacts the database if it's opened with `Options::auto_compact_on_close()` and fragmented enough
+    pub fn compact_on_close(&mut self) -> Result<()> {
+        let Some(threshold) = self.options.auto_compact_on_close else {
+            return Ok(());
+        };
+        if self.is_frozen() {
+            return Ok(());
+        }
+        if self.fragmentation()? >= threshold {
+            self.compact()?;
+        }
+        Ok(())
+    }
+
+    pub fn health_check(&mut self) -> Result<()> {
+        const KEY: &str = "health_check";
+        let sentinel = SystemTime::now()
+            .duration_since(UNIX_EPOCH)?
+            .as_nanos()
+            .to_be_bytes();
+        let definition = TableDefinition::<&str, &[u8]>::new(&self.reserved.health);
+        let db = &self.db;
+
+        let tnx = self.begin_write()?;
+        {
+            let mut table = tnx.open_table(definition)?;
+            table.insert(KEY, sentinel.as_slice())?;
+        }
+        tnx.commit()?;
+
+        let read_back = || -> Result<bool> {
+            let tnx = db.begin_read()?;
+            let table = tnx.open_table(definition)?;
+            let value = table.get(KEY)?;
+            Ok(value.is_some_and(|v| v.value() == sentinel))
+        };
+        let matches = read_back();
+
+        // the sentinel is removed whether it was read back or not
+        let remove = || -> Result<()> {
+            let tnx = self.begin_write()?;
+            tnx.delete_table(definition)?;
+            tnx.commit()?;
+            Ok(())
+        };
+        let removed = remove();
+        let matches = matches?;
+        removed?;
+
+        ensure!(
+            matches,
+            "health check failed: sentinel value didn't round trip"
+        );
+        Ok(())
+    }
+
+    /// renames a table along with its eviction order, chain, blobs, settings, description and annotations, \
+    /// in a single write transaction, replacing `to` if `overwrite` is set
+    pub fn rename_table(&mut self, from: &str, to: &str, overwrite: bool) -> Result<()> {
+        if from == to {
+            return Ok(());
+        }
+        for name in [from, to] {
+            ensure!(
+                !self.reserved.is_reserved(name),
+                "table `{}` is reserved by dbless, it can't be renamed",
+                name
+            );
+            ensure!(
+                !self.settings().aliases.contains_key(name),
+                "`{}` is an alias, aliases can't be renamed",
+                name
+            );
+            self.ensure_no_aliases(name)?;
+        }
+        let tables = self.list_tables()?;
+        if !tables.iter().any(|table| table == from) {
+            return Err(Missing(format!("table `{}` doesn't exist", from)).into());
+        }
+        let replaced = tables.iter().any(|table| table == to);
+        ensure!(overwrite || !replaced, "table `{}` already exists", to);
+
+        self.ensure_unlocked(from)?;
+        self.ensure_unlocked(to)?;
+        let tnx = self.begin_write()?;
+        if replaced {
+            self.delete_table_in(&tnx, to)?;
+        }
+        rename_in(
+            &tnx,
+            TableDefinition::<&str, &[u8]>::new(from),
+            TableDefinition::<&str, &[u8]>::new(to),
+        )?;
+        let ((from_order, from_stamps), (to_order, to_stamps)) =
+            (self.order_table_names(from), self.order_table_names(to));
+        rename_in(
+            &tnx,
+            TableDefinition::<u64, &str>::new(&from_order),
+            TableDefinition::<u64, &str>::new(&to_order),
+        )?;
+        rename_in(
+            &tnx,
+            TableDefinition::<&str, u64>::new(&from_stamps),
+            TableDefinition::<&str, u64>::new(&to_stamps),
+        )?;
+        rename_in(
+            &tnx,
+            TableDefinition::<u64, &[u8]>::new(&self.chain_table_name(from)),
+            TableDefinition::<u64, &[u8]>::new(&self.chain_table_name(to)),
+        )?;
+        rename_in(
+            &tnx,
+            TableDefinition::<&[u8], &[u8]>::new(&self.blobs_table_name(from)),
+            TableDefinition::<&[u8], &[u8]>::new(&self.blobs_table_name(to)),
+        )?;
+        // the settings of the table are saved under its name in these metadata tables
+        for metadata in [
+            &self.reserved.limits,
+            &self.reserved.escaped,
+            &self.reserved.chained,
+            &self.reserved.deduplicated,
+            &self.reserved.annotations,
+            &self.reserved.write_once,
+        ] {
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(metadata))?;
+            let setting = table.remove(from)?.map(|setting| setting.value().to_vec());
+            if let Some(setting) = setting {
+                table.insert(to, setting.as_slice())?;
+            }
+        }
         tnx.commit()?;
+        let mut settings = self.settings_mut();
+        settings.forget(to);
+        settings.rename(from, to);
+        Ok(())
+    }
+
+    pub fn delete_table(&self, name: &str) -> Result<()> {
+        let _maintenance = self.lock_maintenance();
+        self.ensure_no_aliases(name)?;
+        let tnx = self.begin_write_to(name)?;
+        if self.settings().aliases.contains_key(name) {
+            {
+                let mut table =
+                    tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.aliases))?;
+                table.remove(name)?;
+            }
+            self.commit_shared(tnx)?;
+            self.settings_mut().aliases.remove(name);
+            return Ok(());
+        }
+        self.delete_table_in(&tnx, name)?;
+        self.commit_shared(tnx)?;
+        self.settings_mut().forget(name);
+        Ok(())
+    }
+
+    /// deletes all the given tables (not aliases) in a single write transaction
+    pub fn delete_tables(&self, names: &[String]) -> Result<()> {
+        let _maintenance = self.lock_maintenance();
+        for name in names {
+            self.ensure_unlocked(name)?;
+            self.ensure_no_aliases(name)?;
+        }
+        let tnx = self.begin_write()?;
+        for name in names {
+            self.delete_table_in(&tnx, name)?;
+        }
+        self.commit_shared(tnx)?;
+        let mut settings = self.settings_mut();
+        for name in names {
+            settings.forget(name);
+        }
+        Ok(())
+    }
+
+    fn ensure_no_aliases(&self, name: &str) -> Result<()> {
+        let settings = self.settings();
+        let mut aliases: Vec<&str> = settings
+            .aliases
+            .iter()
+            .filter(|(_, target)| *target == name)
+            .map(|(alias, _)| alias.as_str())
+            .collect();
+        aliases.sort_unstable();
+        ensure!(
+            aliases.is_empty(),
+            "table `{}` can't be deleted while it has aliases: {}",
+            name,
+            aliases.join(", ")
+        );
+        Ok(())
+    }
+
+    /// deletes the table along with its limit, escaping, chain and deduplication settings, \
+    /// and its description and annotations
+    fn delete_table_in(&self, tnx: &WriteTransaction, name: &str) -> Result<()> {
+        tnx.delete_table(TableDefinition::<&str, &[u8]>::new(name))?;
+        if self.settings().annotated.contains(name) {
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(
+                &self.reserved.annotations,
+            ))?;
+            table.remove(name)?;
+        }
+        if self.settings().limits.contains_key(name) {
+            self.delete_limit(tnx, name)?;
+        }
+        if self.settings().escaped.contains(name) {
+            let mut table =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.escaped))?;
+            table.remove(name)?;
+        }
+        if self.settings().chained.contains(name) {
+            let mut table =
+                tnx.open_table(TableDefinition::<&str, &[u8]>::new(&self.reserved.chained))?;
+            table.remove(name)?;
+            tnx.delete_table(TableDefinition::<u64, &[u8]>::new(
+                &self.chain_table_name(name),
+            ))?;
+        }
+        if self.settings().deduplicated.contains(name) {
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(
+                &self.reserved.deduplicated,
+            ))?;
+            table.remove(name)?;
+            tnx.delete_table(TableDefinition::<&[u8], &[u8]>::new(
+                &self.blobs_table_name(name),
+            ))?;
+        }
+        if self.settings().write_once.contains_key(name) {
+            let mut table = tnx.open_table(TableDefinition::<&str, &[u8]>::new(
+                &self.reserved.write_once,
+            ))?;
+            table.remove(name)?;
+        }
         Ok(())
     }
 }
@@ -1,7 +1,17 @@
-use anyhow::Result;
-use serde::{de::DeserializeOwned, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::ops::RangeBounds;
+use std::time::Duration;
 
-use crate::store::Store;
+use anyhow::ensure;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::escape::escape_key;
+use crate::store::{EntriesIter, KeysIter, Store};
+use crate::{ChainReport, FieldPredicate, Result, TableStats, Value, WriteBatch};
+
+const REMOVE_KEYS_CHUNK_SIZE: usize = 10_000;
 
 /// A trait for reading from a table
 pub trait TableReadInterface {
@@ -17,7 +27,57 @@ pub trait TableReadInterface {
     /// ```
     fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
 
-    /// Gets a list of all keys in the table.
+    /// Gets the values of several keys at once, in a single read transaction, \
+    /// returned in the order of `keys`, with `None` for the keys that don't exist.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let values = db.get_many::<String>(&["config", "session", "theme"])?;
+    /// if let [config, session, theme] = &values[..] {
+    ///     println!("{:?} {:?} {:?}", config, session, theme);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_many<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<Option<T>>>;
+
+    /// Gets the bytes written with [`set_raw()`](trait.TableWriteInterface.html#method.set_raw) as they were given, \
+    /// or `None` if the key doesn't exist. \
+    /// any other value comes back as plain MessagePack, without the metadata dbless stores alongside it \
+    /// (versions, type tags), and counters come back as MessagePack integers.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// if let Some(bytes) = db.table("users").get_raw("alice")? {
+    ///     println!("{} bytes", bytes.len());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>>;
+
+    /// Gets the values of several keys at once, in a single read transaction, \
+    /// returned in the order of `keys` along with what was found under each of them: \
+    /// the value, nothing, or the error that kept it from being read. \
+    /// unlike [`get_many()`](#method.get_many), a value that fails to read doesn't fail the others, \
+    /// only errors reading the table itself are returned as an `Err`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{Lookup, TableReadInterface};
+    /// let db = Database::open("my_database.db")?;
+    /// for result in db.table("users").get_all::<String>(&["alice", "bob"])? {
+    ///     match result.value {
+    ///         Lookup::Found(name) => println!("{}: {}", result.key, name),
+    ///         Lookup::Missing => println!("{}: not found", result.key),
+    ///         Lookup::Failed(e) => println!("{}: {:#}", result.key, e),
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_all<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<KeyedResult<T>>>;
+
+    /// Gets a list of all keys in the table, sorted byte by byte. \
+    /// like every key listing, it includes expired keys until they're purged, see [`set_with_ttl()`](trait.TableWriteInterface.html#method.set_with_ttl).
     /// ```no_run
     /// # use dbless::Database;
     /// # use dbless::TableReadInterface;
@@ -30,7 +90,58 @@ pub trait TableReadInterface {
     /// ```
     fn keys(&self) -> Result<Vec<String>>;
 
-    /// Gets a list of all values in the table (that can be deserialized into the given type).
+    /// Gets the keys of the table one at a time, sorted byte by byte, \
+    /// reading them only as the iterator advances, so stopping early (with `take()` or `find()` for example) \
+    /// doesn't walk the rest of the table, and nothing is collected. \
+    /// unlike [`keys()`](#method.keys), they're always sorted, even in a database opened with \
+    /// [`Database::in_memory_seeded()`](struct.Database.html#method.in_memory_seeded). \
+    /// the iterator holds a read transaction, so it sees the table as it was when it was created, \
+    /// and it borrows the database, which can't be written through until the iterator is dropped.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// for key in db.table("events").iter_keys()?.take(10) {
+    ///     println!("{}", key?);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn iter_keys(&self) -> Result<KeysIter<'_>>;
+
+    /// Gets the entries of the table one at a time, sorted by key, \
+    /// so a table of any size can be walked in bounded memory. \
+    /// unlike [`entries()`](#method.entries), a value that can't be deserialized into the given type is returned as an error \
+    /// (skip them with `.filter_map(Result::ok)`). \
+    /// the iterator holds a read transaction, so it sees the table as it was when it was created, \
+    /// and it borrows the database, which can't be written through until the iterator is dropped.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// for entry in db.table("events").iter_entries::<String>()? {
+    ///     let (key, value) = entry?;
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn iter_entries<T: DeserializeOwned>(&self) -> Result<EntriesIter<'_, T>>;
+
+    /// Walks the keys of the table in sorted order and returns the first one for which `f` returns `true`, \
+    /// or `None` if there is none. \
+    /// stops as soon as a key matches, without collecting the keys or reading any value.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let draft = db.find_key(|key| key.ends_with(".draft"))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn find_key<F: FnMut(&str) -> bool>(&self, f: F) -> Result<Option<String>>;
+
+    /// Gets a list of all values in the table (that can be deserialized into the given type), in the order of their keys. \
+    /// values are deserialized one at a time while walking the table, \
+    /// so apart from the returned list only a single raw value is held in memory at once \
+    /// (redb's page cache may still keep the pages it read, see the [crate docs](index.html#performance)).
     /// ```no_run
     /// # use dbless::Database;
     /// # use dbless::TableReadInterface;
@@ -43,7 +154,10 @@ pub trait TableReadInterface {
     /// ```
     fn values<T: DeserializeOwned>(&self) -> Result<Vec<T>>;
 
-    /// Gets a list of all entries in the table (that can be deserialized into the given type).
+    /// Gets a list of all entries in the table (that can be deserialized into the given type), sorted by key. \
+    /// values are deserialized one at a time while walking the table, \
+    /// so apart from the returned list only a single raw value is held in memory at once \
+    /// (redb's page cache may still keep the pages it read, see the [crate docs](index.html#performance)).
     /// ```no_run
     /// # use dbless::Database;
     /// # use dbless::TableReadInterface;
@@ -56,7 +170,74 @@ pub trait TableReadInterface {
     /// ```
     fn entries<T: DeserializeOwned>(&self) -> Result<Vec<(String, T)>>;
 
+    /// Gets the entries whose key matches `f` (and whose value can be deserialized into the given type), sorted by key. \
+    /// `f` is called on every key before its value is read, \
+    /// so entries that don't match are skipped without deserializing their value, \
+    /// unlike filtering the result of [`entries()`](#method.entries).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let even = db.entries_by_key_filter::<String, _>(|key| {
+    ///     key.rsplit('-').next().and_then(|n| n.parse::<u32>().ok()).is_some_and(|n| n % 2 == 0)
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn entries_by_key_filter<T: DeserializeOwned, F: FnMut(&str) -> bool>(
+        &self,
+        f: F,
+    ) -> Result<Vec<(String, T)>>;
+
+    /// Gets the keys starting with `prefix`, sorted byte by byte. \
+    /// only the keys starting with it are read, the rest of the table isn't walked.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// for key in db.keys_with_prefix("user:123:")? {
+    ///     println!("{}", key);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>>;
+
+    /// Gets the entries whose keys start with `prefix` (and that can be deserialized into the given type), sorted by key. \
+    /// only the entries starting with it are read, the rest of the table isn't walked.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// for (key, value) in db.entries_with_prefix::<String>("user:123:")? {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn entries_with_prefix<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<(String, T)>>;
+
+    /// Gets the entries whose keys are within `range` (and that can be deserialized into the given type), sorted by key. \
+    /// both ends can be inclusive, exclusive or unbounded, and only the entries within them are read. \
+    /// keys compare as strings, byte by byte.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let april = db.table("events").get_range::<String>("2024-04-01".."2024-05-01")?;
+    /// for (date, event) in april {
+    ///     println!("{}: {}", date, event);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_range<'r, T: DeserializeOwned>(
+        &self,
+        range: impl RangeBounds<&'r str>,
+    ) -> Result<Vec<(String, T)>>;
+
     /// Gets the number of entries in the table. \
+    /// every stored key is counted, whatever the type of its value, \
+    /// so it can be more than the number of values [`values()`](#method.values) returns for a given type, \
+    /// see [`count_values()`](#method.count_values) to count those. \
+    /// expired keys are counted too, until they're purged, see [`set_with_ttl()`](trait.TableWriteInterface.html#method.set_with_ttl). \
+    /// see [`Options::cache_table_lengths()`](struct.Options.html#method.cache_table_lengths) to answer repeated counts from memory. \
     /// aliases: [`size()`](#method.size)
     /// ```no_run
     /// # use dbless::Database;
@@ -68,6 +249,19 @@ pub trait TableReadInterface {
     /// ```
     fn len(&self) -> Result<usize>;
 
+    /// Gets the number of values in the table that can be deserialized into the given type, \
+    /// the length of the list [`values()`](#method.values) would return, without collecting it. \
+    /// every value is read, unlike [`len()`](#method.len) which counts keys.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let skipped = db.len()? - db.count_values::<String>()?;
+    /// println!("{} values aren't strings", skipped);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn count_values<T: DeserializeOwned>(&self) -> Result<usize>;
+
     /// Checks if the table contains the given key. \
     /// aliases: [`contains()`](#method.contains), [`has()`](#method.has)
     /// ```no_run
@@ -186,6 +380,141 @@ pub trait TableReadInterface {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     fn get_or_default<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T>;
+
+    /// Gets the version of the value associated with the given key, \
+    /// or `None` if the key doesn't exist. \
+    /// values written without a version (see [`Options::track_versions()`](struct.Options.html#method.track_versions)) are at version 0.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let version = db.version_of("key")?;
+    /// println!("key is at version {:?}", version);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn version_of(&self, key: &str) -> Result<Option<u64>>;
+
+    /// Gets up to `limit` keys that come strictly after `after` in sorted order, \
+    /// or the first `limit` keys if `after` is `None`. \
+    /// passing the last key of a page as `after` gets the next page, \
+    /// which stays correct even if entries are inserted or removed between pages.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let mut cursor = None;
+    /// loop {
+    ///     let page = db.keys_after(cursor.as_deref(), 50)?;
+    ///     println!("{:?}", page);
+    ///     match page.last() {
+    ///         Some(last) => cursor = Some(last.clone()),
+    ///         None => break,
+    ///     }
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn keys_after(&self, after: Option<&str>, limit: usize) -> Result<Vec<String>>;
+
+    /// Gets up to `limit` keys starting at position `offset` in sorted order, \
+    /// an `offset` past the end gets an empty page. \
+    /// the keys before `offset` are skipped without being read, but still walked, \
+    /// see [`keys_after()`](#method.keys_after) for pages that don't shift when entries are inserted or removed between them.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let page = 3;
+    /// let keys = db.table("records").keys_page(page * 50, 50)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn keys_page(&self, offset: usize, limit: usize) -> Result<Vec<String>>;
+
+    /// Gets the entries of the keys [`keys_page()`](#method.keys_page) returns for the same `offset` and `limit`, \
+    /// without deserializing the skipped values. \
+    /// the values that can't be deserialized into the given type are left out, \
+    /// so a page can hold fewer than `limit` entries even before the end of the table.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// for (key, value) in db.table("records").entries_page::<String>(100, 50)? {
+    ///     println!("{}: {}", key, value);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn entries_page<T: DeserializeOwned>(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(String, T)>>;
+
+    /// Gets the type tag and the raw serialized bytes of a value \
+    /// inserted with [`set_tagged()`](trait.TableWriteInterface.html#method.set_tagged), \
+    /// or `None` if the key doesn't exist. \
+    /// errors if the value isn't tagged.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// if let Some((tag, _bytes)) = db.get_tagged("key")? {
+    ///     println!("key holds a {}", tag);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_tagged(&self, key: &str) -> Result<Option<(String, Vec<u8>)>>;
+
+    /// Gets a value inserted with [`set_tagged()`](trait.TableWriteInterface.html#method.set_tagged), \
+    /// or `None` if the key doesn't exist. \
+    /// errors if the value isn't tagged, or was tagged with a type other than `T`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let value = db.get_as::<String>("key")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_as<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
+
+    /// Gets a value inserted with [`insert_escaped()`](trait.TableWriteInterface.html#method.insert_escaped), \
+    /// the key is escaped with [`escape_key()`](fn.escape_key.html) before looking it up. \
+    /// not needed on tables that escape their keys on their own, \
+    /// see [`TableMut::set_escape_keys()`](struct.TableMut.html#method.set_escape_keys).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let value: Option<String> = db.get_escaped("multi\nline")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_escaped<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
+
+    /// Gets a counter set with [`set_u64()`](trait.TableWriteInterface.html#method.set_u64) \
+    /// or [`add_u64()`](trait.TableWriteInterface.html#method.add_u64), \
+    /// or `None` if the key doesn't exist. \
+    /// errors if the value isn't a counter, even if it's a serialized integer.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableReadInterface;
+    /// let db = Database::open("my_database.db")?;
+    /// let visits = db.get_u64("visits")?.unwrap_or(0);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_u64(&self, key: &str) -> Result<Option<u64>>;
+
+    /// Gets the value associated with the given key, \
+    /// treating a stored `None` (MessagePack nil) the same as a missing key. \
+    /// the counterpart of [`set_opt()`](trait.TableWriteInterface.html#method.set_opt), \
+    /// where [`get()`](#method.get) would error on a stored `None` unless `T` is itself an `Option`.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set("nickname", &None::<String>)?;
+    /// assert_eq!(db.get_opt::<String>("nickname")?, None);
+    /// assert_eq!(db.get::<Option<String>>("nickname")?, Some(None));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_opt<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
 }
 
 /// A trait for writing to a table
@@ -255,6 +584,30 @@ pub trait TableWriteInterface {
     /// ```
     fn delete(&mut self, key: &str) -> Result<()>;
 
+    /// Removes the given keys in a single write transaction, \
+    /// keys that don't exist are skipped, like with [`remove()`](#method.remove). \
+    /// aliases: [`delete_many()`](#method.delete_many)
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("sessions").remove_many(&["a1b2", "c3d4"])?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn remove_many(&mut self, keys: &[&str]) -> Result<()>;
+
+    /// Removes the given keys in a single write transaction, \
+    /// keys that don't exist are skipped, like with [`delete()`](#method.delete). \
+    /// aliases: [`remove_many()`](#method.remove_many)
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("sessions").delete_many(&["a1b2", "c3d4"])?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn delete_many(&mut self, keys: &[&str]) -> Result<()>;
+
     /// Clears the table. \
     /// aliases: [`clear()`](#method.clear)
     /// ```no_run
@@ -323,48 +676,560 @@ pub trait TableWriteInterface {
         &mut self,
         key: &str,
     ) -> Result<T>;
-}
 
-/// A read-only handle to a table
-pub struct Table<'a> {
-    pub(crate) store: &'a Store,
-    pub(crate) name: &'a str,
-}
+    /// Inserts all the given entries into the table in a single transaction. \
+    /// if a value fails to serialize, the whole batch is aborted or the entry is skipped, \
+    /// depending on the [`SerializeErrorPolicy`](enum.SerializeErrorPolicy.html) the database was opened with. \
+    /// returns the keys of the skipped entries.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let skipped = db.insert_batch(&[("key1", &1), ("key2", &2)])?;
+    /// assert!(skipped.is_empty());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn insert_batch<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<Vec<String>>;
 
-/// A read-write handle to a table
-pub struct TableMut<'a> {
-    pub(crate) store: &'a mut Store,
-    pub(crate) name: &'a str,
-}
+    /// Inserts all the given entries into the table in a single transaction, \
+    /// which is much faster than inserting them one by one. \
+    /// unlike [`insert_batch()`](trait.TableWriteInterface.html#method.insert_batch), \
+    /// a value failing to serialize always aborts the whole batch, whatever the [`SerializeErrorPolicy`](enum.SerializeErrorPolicy.html), \
+    /// and nothing is written. \
+    /// aliases: [`set_many()`](#method.set_many)
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("config").insert_many(&[("theme", &"dark"), ("language", &"en")])?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn insert_many<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<()>;
 
-impl<'a> TableReadInterface for Table<'a> {
-    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
-        self.store.get(self.name, key)
-    }
+    /// Inserts all the given entries into the table in a single transaction, \
+    /// aborting the whole batch if a value fails to serialize. \
+    /// aliases: [`insert_many()`](#method.insert_many)
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_many(&[("key1", &1), ("key2", &2)])?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_many<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<()>;
 
-    fn keys(&self) -> Result<Vec<String>> {
-        self.store.keys(self.name)
-    }
+    /// Inserts bytes as they are, without serializing them, like an encoded protobuf or an image. \
+    /// read them back with [`get_raw()`](trait.TableReadInterface.html#method.get_raw), \
+    /// [`get()`](trait.TableReadInterface.html#method.get) refuses them since they aren't in the format of the database. \
+    /// they're kept the same way in every format, and still get a version and an expiry like any other value. \
+    /// to insert a value that is already serialized as MessagePack, use [`TableMut::set_msgpack()`](struct.TableMut.html#method.set_msgpack).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_raw("avatar", &[0x89, b'P', b'N', b'G'])?;
+    /// assert_eq!(db.get_raw("avatar")?.as_deref(), Some(&[0x89, b'P', b'N', b'G'][..]));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_raw(&mut self, key: &str, bytes: &[u8]) -> Result<()>;
 
-    fn values<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
-        self.store.values(self.name)
-    }
+    /// Reads the value of `key` (`None` if it doesn't exist), passes it to `f`, and writes back what `f` returns, \
+    /// all within a single write transaction, so no other write can happen in between. \
+    /// returns the written value. \
+    /// `f` is called exactly once, while the database is locked for writing: \
+    /// writes don't conflict in redb, they wait for each other, so there's nothing to retry, \
+    /// but other writers wait for `f` to return, so it should be quick. \
+    /// nothing is written if reading, deserializing or serializing fails.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let count = db.update("count", |count: Option<u64>| count.unwrap_or(0) + 1)?;
+    /// println!("called {} times", count);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn update<T, F>(&mut self, key: &str, f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<T>) -> T;
 
-    fn entries<T: DeserializeOwned>(&self) -> Result<Vec<(String, T)>> {
-        self.store.entries(self.name)
-    }
+    /// Inserts a value into the table with the given key, \
+    /// only if the key is currently at `expected_version` (a missing key is at version 0), \
+    /// and bumps its version. returns whether the value was written. \
+    /// see [`version_of()`](trait.TableReadInterface.html#method.version_of)
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let version = db.version_of("key")?.unwrap_or(0);
+    /// if !db.set_if_version("key", &"value", version)? {
+    ///     println!("key was modified concurrently");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+        expected_version: u64,
+    ) -> Result<bool>;
 
-    fn len(&self) -> Result<usize> {
-        self.store.len(self.name)
-    }
+    /// Inserts a value into the table with the given key, \
+    /// tagged with the name of its type (as given by [`std::any::type_name()`]). \
+    /// tagged values can still be read with [`get()`](trait.TableReadInterface.html#method.get), \
+    /// see [`get_tagged()`](trait.TableReadInterface.html#method.get_tagged) and [`get_as()`](trait.TableReadInterface.html#method.get_as). \
+    /// type names aren't guaranteed to be stable between compiler versions, \
+    /// so tags are best used to tell apart types of the same program.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_tagged("key", &1u32)?;
+    /// assert!(db.get_as::<String>("key").is_err());
+    /// assert_eq!(db.get_as::<u32>("key")?, Some(1));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_tagged<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()>;
 
-    fn is_empty(&self) -> Result<bool> {
-        Ok(self.store.len(self.name)? == 0)
-    }
+    /// Inserts a value into the table with the given key, expiring after `ttl`. \
+    /// once expired, the key reads as missing: [`get()`](trait.TableReadInterface.html#method.get) returns `None`, \
+    /// [`contains_key()`](trait.TableReadInterface.html#method.contains_key) returns `false`, \
+    /// and every method reading values skips it, like [`entries()`](trait.TableReadInterface.html#method.entries), \
+    /// [`iter_entries()`](trait.TableReadInterface.html#method.iter_entries) or [`count_values()`](trait.TableReadInterface.html#method.count_values). \
+    /// but it's still stored, and counted by [`len()`](trait.TableReadInterface.html#method.len) and listed by the methods reading only keys, \
+    /// like [`keys()`](trait.TableReadInterface.html#method.keys) or [`iter_keys()`](trait.TableReadInterface.html#method.iter_keys), \
+    /// until [`purge_expired()`](#method.purge_expired) removes it. \
+    /// overwriting the key with any other method removes its expiry.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// # use std::time::Duration;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("sessions").set_with_ttl("a1b2", &"alice", Duration::from_secs(3600))?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_with_ttl<T: Serialize>(&mut self, key: &str, value: &T, ttl: Duration) -> Result<()>;
 
-    fn contains_key(&self, key: &str) -> Result<bool> {
-        self.store.contains_key(self.name, key)
-    }
+    /// Gets the value associated with the given key if it's there and hasn't expired, \
+    /// otherwise inserts the result of the given closure, expiring after `ttl`, and returns it, \
+    /// all in a single write transaction. see [`set_with_ttl()`](#method.set_with_ttl)
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// # use std::time::Duration;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let rate = db.table_mut("cache").get_or_insert_with_ttl("usd-eur", Duration::from_secs(60), || {
+    ///     // fetch the rate from somewhere slow
+    ///     0.92
+    /// })?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn get_or_insert_with_ttl<T: Serialize + DeserializeOwned, F: FnOnce() -> T>(
+        &mut self,
+        key: &str,
+        ttl: Duration,
+        f: F,
+    ) -> Result<T>;
+
+    /// Removes the entries whose time to live has passed, see [`set_with_ttl()`](#method.set_with_ttl), \
+    /// and returns the number of removed entries. \
+    /// the table is walked in chunks, each chunk removed in its own write transaction.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let purged = db.table_mut("sessions").purge_expired()?;
+    /// println!("purged {} expired sessions", purged);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn purge_expired(&mut self) -> Result<usize>;
+
+    /// Same as [`insert_batch()`](trait.TableWriteInterface.html#method.insert_batch), \
+    /// but also returns how long serializing and committing took, next to the skipped keys. \
+    /// useful for finding out where the time goes in a bulk load.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let (skipped, timing) = db.set_many_timed(&[("key1", &1), ("key2", &2)])?;
+    /// println!("serialize: {}ns, commit: {}ns", timing.serialize_ns, timing.commit_ns);
+    /// println!("skipped: {:?}", skipped);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_many_timed<T: Serialize>(
+        &mut self,
+        entries: &[(&str, &T)],
+    ) -> Result<(Vec<String>, LoadTiming)>;
+
+    /// Inserts a value with its key escaped by [`escape_key()`](fn.escape_key.html), \
+    /// so keys with newlines or other control characters are stored in a form that's safe to export. \
+    /// read it back with [`get_escaped()`](trait.TableReadInterface.html#method.get_escaped).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.insert_escaped("multi\nline", &"value")?;
+    /// assert!(db.keys()?.contains(&"multi%0Aline".to_string()));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn insert_escaped<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()>;
+
+    /// Sets a counter, stored as 8 big-endian bytes instead of MessagePack, \
+    /// which is smaller and faster than [`set()`](trait.TableWriteInterface.html#method.set) for plain counters. \
+    /// read it with [`get_u64()`](trait.TableReadInterface.html#method.get_u64), not [`get()`](trait.TableReadInterface.html#method.get). \
+    /// counters don't carry a version, even if versions are tracked.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_u64("visits", 0)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_u64(&mut self, key: &str, value: u64) -> Result<()>;
+
+    /// Adds `delta` to a counter (a missing key counts as 0) and returns the new value, \
+    /// the read and the write happen in a single write transaction. \
+    /// errors if the value isn't a counter, or if the addition overflows.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let visits = db.add_u64("visits", 1)?;
+    /// println!("visit number {}", visits);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn add_u64(&mut self, key: &str, delta: u64) -> Result<u64>;
+
+    /// Inserts a value that carries its own version (see [`Versioned`](trait.Versioned.html)) \
+    /// only if the stored value is at `expected_version` (a missing key is at version 0), \
+    /// the value is written with its version set to `expected_version + 1`. \
+    /// the check and the write happen in a single write transaction.
+    /// ```no_run
+    /// # use dbless::{Database, Versioned, VersionedOutcome};
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// # #[derive(Clone, serde::Serialize, serde::Deserialize)]
+    /// # struct Document { version: u64, body: String }
+    /// # impl Versioned for Document {
+    /// #     fn version(&self) -> u64 { self.version }
+    /// #     fn set_version(&mut self, version: u64) { self.version = version; }
+    /// # }
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut doc: Document = db.get("doc")?.unwrap();
+    /// doc.body.push_str(" edited");
+    /// match db.update_versioned("doc", doc.version, &doc)? {
+    ///     VersionedOutcome::Updated { new_version } => println!("saved as version {}", new_version),
+    ///     VersionedOutcome::Conflict { current_version } => println!("someone else saved version {}", current_version),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn update_versioned<T: Serialize + DeserializeOwned + Versioned + Clone>(
+        &mut self,
+        key: &str,
+        expected_version: u64,
+        new_value: &T,
+    ) -> Result<VersionedOutcome>;
+
+    /// Removes the value associated with the given key, \
+    /// only if it's currently equal to `expected`. returns whether the value was removed. \
+    /// the check and the removal happen in a single write transaction.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// if !db.remove_if("status", &"done".to_string())? {
+    ///     println!("status changed, leaving it");
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn remove_if<T: DeserializeOwned + PartialEq>(
+        &mut self,
+        key: &str,
+        expected: &T,
+    ) -> Result<bool>;
+
+    /// Sets the value of the given key if `value` is `Some`, and removes the key if it's `None`, \
+    /// instead of storing a `None` that [`contains_key()`](trait.TableReadInterface.html#method.contains_key) would still see. \
+    /// read it back with [`get_opt()`](trait.TableReadInterface.html#method.get_opt).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.set_opt("nickname", &Some("bob"))?;
+    /// db.set_opt("nickname", &None::<&str>)?;
+    /// assert!(!db.contains_key("nickname")?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    fn set_opt<T: Serialize>(&mut self, key: &str, value: &Option<T>) -> Result<()>;
+}
+
+/// The value of a single key read by [`get_all()`](trait.TableReadInterface.html#method.get_all)
+#[derive(Debug)]
+pub struct KeyedResult<T> {
+    /// The key, as it was given
+    pub key: String,
+    /// What was found under it
+    pub value: Lookup<T>,
+}
+
+/// What [`get_all()`](trait.TableReadInterface.html#method.get_all) found under a key
+#[derive(Debug)]
+pub enum Lookup<T> {
+    /// The value of the key
+    Found(T),
+    /// The key doesn't exist
+    Missing,
+    /// The key exists but its value couldn't be read, \
+    /// like a value of another type, or one that fails the checks of [`Options::max_depth()`](struct.Options.html#method.max_depth)
+    Failed(anyhow::Error),
+}
+
+impl<T> Lookup<T> {
+    /// The value, if it was found
+    pub fn found(self) -> Option<T> {
+        match self {
+            Lookup::Found(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+/// The outcome of [`TableMut::remove_keys()`](struct.TableMut.html#method.remove_keys)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RemoveReport {
+    /// The keys that were removed
+    pub removed: Vec<String>,
+    /// The keys that didn't exist
+    pub not_found: Vec<String>,
+}
+
+/// The outcome of [`Database::split_default_table_by_prefix()`](struct.Database.html#method.split_default_table_by_prefix)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SplitReport {
+    /// The number of entries moved into each table
+    pub moved: BTreeMap<String, u64>,
+    /// The number of entries that matched no rule, and stayed in the default table
+    pub unmatched: u64,
+}
+
+/// The number of entries counted by [`Database::len_all_tables_capped()`](struct.Database.html#method.len_all_tables_capped)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CappedLen {
+    /// The database has exactly this many entries, fewer than the cap
+    Exact(usize),
+    /// The database has at least the cap entries, counting stopped there
+    AtLeast(usize),
+}
+
+/// How far [`Database::convert_codec()`](struct.Database.html#method.convert_codec) got, \
+/// reported after every chunk of converted values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertProgress {
+    /// The table being converted
+    pub table: String,
+    /// The number of values converted so far, across every table
+    pub converted: u64,
+}
+
+/// What [`Database::convert_codec()`](struct.Database.html#method.convert_codec) did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConvertReport {
+    /// The number of values converted
+    pub converted: u64,
+    /// The number of counters and raw bytes left as they are, they're stored the same way in every format
+    pub unchanged: u64,
+    /// Whether an interrupted conversion was resumed
+    pub resumed: bool,
+}
+
+/// A breakdown of where the time went in [`set_many_timed()`](trait.TableWriteInterface.html#method.set_many_timed), in nanoseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LoadTiming {
+    /// Time spent serializing the values
+    pub serialize_ns: u64,
+    /// Time spent writing the values and committing the transaction (including I/O)
+    pub commit_ns: u64,
+    /// Total time of the call
+    pub total_ns: u64,
+}
+
+/// A value that carries its own version, \
+/// see [`update_versioned()`](trait.TableWriteInterface.html#method.update_versioned)
+/// ```
+/// # use dbless::Versioned;
+/// struct Document {
+///     version: u64,
+///     body: String,
+/// }
+///
+/// impl Versioned for Document {
+///     fn version(&self) -> u64 {
+///         self.version
+///     }
+///
+///     fn set_version(&mut self, version: u64) {
+///         self.version = version;
+///     }
+/// }
+/// ```
+pub trait Versioned {
+    /// Gets the version of the value
+    fn version(&self) -> u64;
+
+    /// Sets the version of the value
+    fn set_version(&mut self, version: u64);
+}
+
+/// The outcome of [`update_versioned()`](trait.TableWriteInterface.html#method.update_versioned)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionedOutcome {
+    /// The value was written
+    Updated {
+        /// The version the value was written with
+        new_version: u64,
+    },
+    /// The stored value wasn't at the expected version, nothing was written
+    Conflict {
+        /// The version of the stored value
+        current_version: u64,
+    },
+}
+
+/// What happens when inserting a new key into a table that reached its limit, \
+/// see [`TableMut::set_limit()`](struct.TableMut.html#method.set_limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EvictionPolicy {
+    /// Evict the least recently used entry, both reads and writes count as a use. \
+    /// every read of a key (`get()`, `get_many()`, `get_u64()`...) commits a write transaction to record it, \
+    /// which costs as much as a `set()` on a durable database, can't run alongside other writes, \
+    /// and drops the cached table names and lengths (see [`Options::cache_table_names()`](struct.Options.html#method.cache_table_names)). \
+    /// the reads made in a [`Database::read_transaction()`](struct.Database.html#method.read_transaction) don't count as a use. \
+    /// prefer [`Fifo`](#variant.Fifo) for tables that are read far more often than written
+    Lru,
+    /// Evict the entry that was inserted first, overwriting a key doesn't move it
+    Fifo,
+    /// Reject the new key with an error, existing keys can still be overwritten
+    RejectNew,
+}
+
+/// The maximum number of entries of a table, \
+/// see [`TableMut::set_limit()`](struct.TableMut.html#method.set_limit)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TableLimit {
+    /// The maximum number of entries
+    pub max_entries: usize,
+    /// What to do when inserting past the limit
+    pub policy: EvictionPolicy,
+}
+
+/// A read-only handle to a table
+pub struct Table<'a> {
+    pub(crate) store: &'a Store,
+    pub(crate) name: Cow<'a, str>,
+}
+
+/// A read-write handle to a table
+pub struct TableMut<'a> {
+    pub(crate) store: &'a mut Store,
+    pub(crate) name: Cow<'a, str>,
+}
+
+impl<'a> TableReadInterface for Table<'a> {
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.store
+            .context(&self.name, self.store.get(&self.name, key))
+    }
+
+    fn get_many<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<Option<T>>> {
+        self.store
+            .context(&self.name, self.store.get_many(&self.name, keys))
+    }
+
+    fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.store
+            .context(&self.name, self.store.get_raw(&self.name, key))
+    }
+
+    fn get_all<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<KeyedResult<T>>> {
+        self.store
+            .context(&self.name, self.store.get_all(&self.name, keys))
+    }
+
+    fn keys(&self) -> Result<Vec<String>> {
+        self.store.context(&self.name, self.store.keys(&self.name))
+    }
+
+    fn iter_keys(&self) -> Result<KeysIter<'_>> {
+        self.store
+            .context(&self.name, self.store.iter_keys(&self.name))
+    }
+
+    fn iter_entries<T: DeserializeOwned>(&self) -> Result<EntriesIter<'_, T>> {
+        self.store
+            .context(&self.name, self.store.iter_entries(&self.name))
+    }
+
+    fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> {
+        self.store
+            .context(&self.name, self.store.keys_with_prefix(&self.name, prefix))
+    }
+
+    fn entries_with_prefix<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<(String, T)>> {
+        self.store.context(
+            &self.name,
+            self.store.entries_with_prefix(&self.name, prefix),
+        )
+    }
+
+    fn get_range<'r, T: DeserializeOwned>(
+        &self,
+        range: impl RangeBounds<&'r str>,
+    ) -> Result<Vec<(String, T)>> {
+        let start = range.start_bound().map(|start| *start);
+        let end = range.end_bound().map(|end| *end);
+        self.store
+            .context(&self.name, self.store.range_entries(&self.name, start, end))
+    }
+
+    fn find_key<F: FnMut(&str) -> bool>(&self, f: F) -> Result<Option<String>> {
+        self.store
+            .context(&self.name, self.store.find_key(&self.name, f))
+    }
+
+    fn values<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        self.store
+            .context(&self.name, self.store.values(&self.name))
+    }
+
+    fn entries<T: DeserializeOwned>(&self) -> Result<Vec<(String, T)>> {
+        self.store
+            .context(&self.name, self.store.entries(&self.name))
+    }
+
+    fn entries_by_key_filter<T: DeserializeOwned, F: FnMut(&str) -> bool>(
+        &self,
+        f: F,
+    ) -> Result<Vec<(String, T)>> {
+        self.store
+            .context(&self.name, self.store.entries_by_key_filter(&self.name, f))
+    }
+
+    fn len(&self) -> Result<usize> {
+        self.store.context(&self.name, self.store.len(&self.name))
+    }
+
+    fn count_values<T: DeserializeOwned>(&self) -> Result<usize> {
+        self.store
+            .context(&self.name, self.store.count_values::<T>(&self.name))
+    }
+
+    fn is_empty(&self) -> Result<bool> {
+        Ok(self.store.context(&self.name, self.store.len(&self.name))? == 0)
+    }
+
+    fn contains_key(&self, key: &str) -> Result<bool> {
+        self.store
+            .context(&self.name, self.store.contains_key(&self.name, key))
+    }
 
     fn size(&self) -> Result<usize> {
         self.len()
@@ -393,13 +1258,62 @@ impl<'a> TableReadInterface for Table<'a> {
     fn get_or_default<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T> {
         self.get_or_else(key, T::default)
     }
+
+    fn version_of(&self, key: &str) -> Result<Option<u64>> {
+        self.store
+            .context(&self.name, self.store.version_of(&self.name, key))
+    }
+
+    fn keys_after(&self, after: Option<&str>, limit: usize) -> Result<Vec<String>> {
+        self.store
+            .context(&self.name, self.store.keys_after(&self.name, after, limit))
+    }
+
+    fn keys_page(&self, offset: usize, limit: usize) -> Result<Vec<String>> {
+        self.store
+            .context(&self.name, self.store.keys_page(&self.name, offset, limit))
+    }
+
+    fn entries_page<T: DeserializeOwned>(
+        &self,
+        offset: usize,
+        limit: usize,
+    ) -> Result<Vec<(String, T)>> {
+        self.store.context(
+            &self.name,
+            self.store.entries_page(&self.name, offset, limit),
+        )
+    }
+
+    fn get_tagged(&self, key: &str) -> Result<Option<(String, Vec<u8>)>> {
+        self.store
+            .context(&self.name, self.store.get_tagged(&self.name, key))
+    }
+
+    fn get_as<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.store
+            .context(&self.name, self.store.get_as(&self.name, key))
+    }
+
+    fn get_escaped<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        self.get(&escape_key(key))
+    }
+
+    fn get_u64(&self, key: &str) -> Result<Option<u64>> {
+        self.store
+            .context(&self.name, self.store.get_u64(&self.name, key))
+    }
+
+    fn get_opt<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        Ok(self.get::<Option<T>>(key)?.flatten())
+    }
 }
 
 macro_rules! mirror_methods_with_into {
     {$into:ident; $(fn $name:ident$(<$($gname:ident: $gty1:ident $(+$gtyr:ident)*),+>)?(&self $(,$pname:ident: $pty:ty)*) -> $ret:ty;)*} => {
         $(
             fn $name$(<$($gname: $gty1$(+$gtyr)*),+>)?(&self, $($pname: $pty),*) -> $ret {
-                Into::<$into>::into(self).$name($($pname),*)
+                Into::<$into>::into(self).$name$(::<$($gname),+>)?($($pname),*)
             }
         )*
     }
@@ -409,10 +1323,16 @@ impl<'a> TableReadInterface for TableMut<'a> {
     mirror_methods_with_into! {
         Table;
         fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>>;
+        fn get_many<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<Option<T>>> ;
+        fn get_raw(&self, key: &str) -> Result<Option<Vec<u8>>> ;
+        fn get_all<T: DeserializeOwned>(&self, keys: &[&str]) -> Result<Vec<KeyedResult<T>>> ;
         fn keys(&self) -> Result<Vec<String>> ;
+        fn keys_with_prefix(&self, prefix: &str) -> Result<Vec<String>> ;
+        fn entries_with_prefix<T: DeserializeOwned>(&self, prefix: &str) -> Result<Vec<(String, T)>> ;
         fn values<T: DeserializeOwned>(&self) -> Result<Vec<T>> ;
         fn entries<T: DeserializeOwned>(&self) -> Result<Vec<(String, T)>> ;
         fn len(&self) -> Result<usize> ;
+        fn count_values<T: DeserializeOwned>(&self) -> Result<usize> ;
         fn is_empty(&self) -> Result<bool> ;
         fn contains_key(&self, key: &str) -> Result<bool> ;
         fn size(&self) -> Result<usize> ;
@@ -420,6 +1340,15 @@ impl<'a> TableReadInterface for TableMut<'a> {
         fn has(&self, key: &str) -> Result<bool> ;
         fn get_or<T: DeserializeOwned>(&self, key: &str, default: T) -> Result<T> ;
         fn get_or_default<T: DeserializeOwned + Default>(&self, key: &str) -> Result<T> ;
+        fn version_of(&self, key: &str) -> Result<Option<u64>> ;
+        fn keys_after(&self, after: Option<&str>, limit: usize) -> Result<Vec<String>> ;
+        fn keys_page(&self, offset: usize, limit: usize) -> Result<Vec<String>> ;
+        fn entries_page<T: DeserializeOwned>(&self, offset: usize, limit: usize) -> Result<Vec<(String, T)>> ;
+        fn get_tagged(&self, key: &str) -> Result<Option<(String, Vec<u8>)>> ;
+        fn get_as<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> ;
+        fn get_escaped<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> ;
+        fn get_u64(&self, key: &str) -> Result<Option<u64>> ;
+        fn get_opt<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> ;
     }
 
     // current macro can't handle FnOnce() -> T
@@ -430,19 +1359,52 @@ impl<'a> TableReadInterface for TableMut<'a> {
     ) -> Result<T> {
         Into::<Table>::into(self).get_or_else(key, default)
     }
+
+    fn find_key<F: FnMut(&str) -> bool>(&self, f: F) -> Result<Option<String>> {
+        Into::<Table>::into(self).find_key(f)
+    }
+
+    // nor a lifetime parameter
+    fn get_range<'r, T: DeserializeOwned>(
+        &self,
+        range: impl RangeBounds<&'r str>,
+    ) -> Result<Vec<(String, T)>> {
+        Into::<Table>::into(self).get_range(range)
+    }
+
+    // the iterators borrow the store, not the temporary `Table`
+    fn iter_keys(&self) -> Result<KeysIter<'_>> {
+        self.store
+            .context(&self.name, self.store.iter_keys(&self.name))
+    }
+
+    fn iter_entries<T: DeserializeOwned>(&self) -> Result<EntriesIter<'_, T>> {
+        self.store
+            .context(&self.name, self.store.iter_entries(&self.name))
+    }
+
+    fn entries_by_key_filter<T: DeserializeOwned, F: FnMut(&str) -> bool>(
+        &self,
+        f: F,
+    ) -> Result<Vec<(String, T)>> {
+        Into::<Table>::into(self).entries_by_key_filter(f)
+    }
 }
 
 impl<'a> TableWriteInterface for TableMut<'a> {
     fn insert<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
-        self.store.insert(self.name, key, value)
+        let result = self.store.insert(&self.name, key, value);
+        self.store.context(&self.name, result)
     }
 
     fn remove(&mut self, key: &str) -> Result<()> {
-        self.store.remove(self.name, key)
+        let result = self.store.remove(&self.name, key);
+        self.store.context(&self.name, result)
     }
 
     fn clear(&mut self) -> Result<()> {
-        self.store.clear(self.name)
+        let result = self.store.clear(&self.name);
+        self.store.context(&self.name, result)
     }
 
     fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
@@ -453,6 +1415,15 @@ impl<'a> TableWriteInterface for TableMut<'a> {
         self.remove(key)
     }
 
+    fn remove_many(&mut self, keys: &[&str]) -> Result<()> {
+        let result = self.store.remove_keys(&self.name, keys);
+        self.store.context(&self.name, result).map(|_| ())
+    }
+
+    fn delete_many(&mut self, keys: &[&str]) -> Result<()> {
+        self.remove_many(keys)
+    }
+
     fn reset(&mut self) -> Result<()> {
         self.clear()
     }
@@ -486,6 +1457,121 @@ impl<'a> TableWriteInterface for TableMut<'a> {
     ) -> Result<T> {
         self.get_or_insert_with(key, T::default)
     }
+
+    fn insert_batch<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<Vec<String>> {
+        let result = self.store.insert_batch(&self.name, entries);
+        self.store.context(&self.name, result)
+    }
+
+    fn insert_many<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<()> {
+        let result = self.store.insert_many(&self.name, entries);
+        self.store.context(&self.name, result)
+    }
+
+    fn set_many<T: Serialize>(&mut self, entries: &[(&str, &T)]) -> Result<()> {
+        self.insert_many(entries)
+    }
+
+    fn set_raw(&mut self, key: &str, bytes: &[u8]) -> Result<()> {
+        let result = self.store.insert_raw(&self.name, key, bytes);
+        self.store.context(&self.name, result)
+    }
+
+    fn update<T, F>(&mut self, key: &str, f: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce(Option<T>) -> T,
+    {
+        let result = self.store.update(&self.name, key, f);
+        self.store.context(&self.name, result)
+    }
+
+    fn set_if_version<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+        expected_version: u64,
+    ) -> Result<bool> {
+        let result = self
+            .store
+            .set_if_version(&self.name, key, value, expected_version);
+        self.store.context(&self.name, result)
+    }
+
+    fn set_tagged<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        let result = self.store.insert_tagged(&self.name, key, value);
+        self.store.context(&self.name, result)
+    }
+
+    fn set_with_ttl<T: Serialize>(&mut self, key: &str, value: &T, ttl: Duration) -> Result<()> {
+        let result = self.store.insert_with_ttl(&self.name, key, value, ttl);
+        self.store.context(&self.name, result)
+    }
+
+    fn get_or_insert_with_ttl<T: Serialize + DeserializeOwned, F: FnOnce() -> T>(
+        &mut self,
+        key: &str,
+        ttl: Duration,
+        f: F,
+    ) -> Result<T> {
+        let result = self.store.get_or_insert_with_ttl(&self.name, key, ttl, f);
+        self.store.context(&self.name, result)
+    }
+
+    fn purge_expired(&mut self) -> Result<usize> {
+        let result = self.store.purge_expired(&self.name, REMOVE_KEYS_CHUNK_SIZE);
+        self.store.context(&self.name, result).map(|n| n as usize)
+    }
+
+    fn set_many_timed<T: Serialize>(
+        &mut self,
+        entries: &[(&str, &T)],
+    ) -> Result<(Vec<String>, LoadTiming)> {
+        let result = self.store.insert_batch_timed(&self.name, entries);
+        self.store.context(&self.name, result)
+    }
+
+    fn insert_escaped<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        self.insert(&escape_key(key), value)
+    }
+
+    fn set_u64(&mut self, key: &str, value: u64) -> Result<()> {
+        let result = self.store.set_u64(&self.name, key, value);
+        self.store.context(&self.name, result)
+    }
+
+    fn add_u64(&mut self, key: &str, delta: u64) -> Result<u64> {
+        let result = self.store.add_u64(&self.name, key, delta);
+        self.store.context(&self.name, result)
+    }
+
+    fn update_versioned<T: Serialize + DeserializeOwned + Versioned + Clone>(
+        &mut self,
+        key: &str,
+        expected_version: u64,
+        new_value: &T,
+    ) -> Result<VersionedOutcome> {
+        let result = self
+            .store
+            .update_versioned(&self.name, key, expected_version, new_value);
+        self.store.context(&self.name, result)
+    }
+
+    fn remove_if<T: DeserializeOwned + PartialEq>(
+        &mut self,
+        key: &str,
+        expected: &T,
+    ) -> Result<bool> {
+        let result = self.store.remove_if(&self.name, key, expected);
+        self.store.context(&self.name, result)
+    }
+
+    fn set_opt<T: Serialize>(&mut self, key: &str, value: &Option<T>) -> Result<()> {
+        match value {
+            Some(value) => self.set(key, value),
+            None => self.remove(key),
+        }
+    }
 }
 
 impl<'a> From<TableMut<'a>> for Table<'a> {
@@ -501,7 +1587,7 @@ impl<'a> From<&'a TableMut<'a>> for Table<'a> {
     fn from(table: &'a TableMut<'a>) -> Self {
         Self {
             store: table.store,
-            name: table.name,
+            name: Cow::Borrowed(&table.name),
         }
     }
 }
@@ -509,13 +1595,501 @@ impl<'a> From<&'a TableMut<'a>> for Table<'a> {
 impl<'a> Table<'a> {
     /// Gets the name of the table
     pub fn name(&self) -> &str {
-        self.name
+        &self.name
+    }
+
+    /// Writes all keys in the table (optionally only the ones starting with `prefix`) to `writer`, \
+    /// each followed by `delimiter` (usually `b'\n'`), and returns the number of keys written. \
+    /// keys are streamed in sorted order without being collected in memory, and are unique.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let file = std::fs::File::create("keys.txt")?;
+    /// let count = db.table("my_table").export_keys(std::io::BufWriter::new(file), b'\n', None)?;
+    /// println!("exported {} keys", count);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn export_keys(
+        &self,
+        writer: impl Write,
+        delimiter: u8,
+        prefix: Option<&str>,
+    ) -> Result<u64> {
+        self.store.context(
+            &self.name,
+            self.store
+                .export_keys(&self.name, writer, delimiter, prefix),
+        )
+    }
+
+    /// Computes a SHA-256 digest over all keys and raw values in the table, in key order. \
+    /// two tables with the same digest hold byte-identical entries, \
+    /// a table that doesn't exist has the same digest as an empty one.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let backup = Database::open("my_backup.db")?;
+    /// assert_eq!(db.table("my_table").digest()?, backup.table("my_table").digest()?);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn digest(&self) -> Result<[u8; 32]> {
+        self.store
+            .context(&self.name, self.store.digest(&self.name))
+    }
+
+    /// Gets the limit set on the table with [`TableMut::set_limit()`](struct.TableMut.html#method.set_limit), if any
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// if let Some(limit) = db.table("cache").limit() {
+    ///     println!("cache holds at most {} entries", limit.max_entries);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn limit(&self) -> Option<TableLimit> {
+        self.store.limit(&self.name)
+    }
+
+    /// Re-walks an append-only table (see [`TableMut::append_only_chained()`](struct.TableMut.html#method.append_only_chained)) \
+    /// in insertion order, recomputing the hash chain from the stored values, \
+    /// and reports the first key that doesn't match it. \
+    /// a table that isn't chained reports its first key as divergent, and an empty table is intact.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// let report = db.table("audit").verify_chain()?;
+    /// match report.first_divergent_key {
+    ///     Some(key) => println!("tampered with at `{}`", key),
+    ///     None => println!("{} entries intact", report.entries),
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn verify_chain(&self) -> Result<ChainReport> {
+        self.store
+            .context(&self.name, self.store.verify_chain(&self.name))
+    }
+
+    /// Checks if the table is append-only, \
+    /// see [`TableMut::append_only_chained()`](struct.TableMut.html#method.append_only_chained)
+    pub fn is_chained(&self) -> bool {
+        self.store.is_chained(&self.name)
+    }
+
+    /// Checks if the table escapes its keys on its own, \
+    /// see [`TableMut::set_escape_keys()`](struct.TableMut.html#method.set_escape_keys)
+    pub fn escapes_keys(&self) -> bool {
+        self.store.escapes_keys(&self.name)
+    }
+
+    /// Checks if the table stores each distinct value once, \
+    /// see [`TableMut::set_deduplicated()`](struct.TableMut.html#method.set_deduplicated)
+    pub fn is_deduplicated(&self) -> bool {
+        self.store.is_deduplicated(&self.name)
+    }
+
+    /// Checks if the keys of the table can't be overwritten, \
+    /// see [`TableMut::insert_only()`](struct.TableMut.html#method.insert_only) and [`TableMut::append_only()`](struct.TableMut.html#method.append_only)
+    pub fn is_insert_only(&self) -> bool {
+        self.store.is_insert_only(&self.name)
+    }
+
+    /// Checks if the keys of the table can't be overwritten or removed, \
+    /// see [`TableMut::append_only()`](struct.TableMut.html#method.append_only)
+    pub fn is_append_only(&self) -> bool {
+        self.store.is_append_only(&self.name)
+    }
+
+    /// Gets the value associated with the given key as a [`Value`](enum.Value.html) tree, \
+    /// for reading values without a rust type for them, like in a generic editor. \
+    /// write it back with [`TableMut::set_dynamic()`](struct.TableMut.html#method.set_dynamic).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// if let Some(user) = db.table("users").get_dynamic("alice")? {
+    ///     println!("{:?}", user.pointer("/profile/name"));
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn get_dynamic(&self, key: &str) -> Result<Option<Value>> {
+        self.store
+            .context(&self.name, self.store.get_dynamic(&self.name, key))
+    }
+
+    /// Reads the whole table once and discards it, \
+    /// so its pages are in the OS page cache and redb's cache before the first real read, \
+    /// smoothing out the latency of a cold start on a hot table. \
+    /// does nothing on a database opened with [`Database::in_memory()`](struct.Database.html#method.in_memory), \
+    /// or for a table that doesn't exist.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// db.table("sessions").prewarm()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn prewarm(&self) -> Result<()> {
+        self.store
+            .context(&self.name, self.store.prewarm(&self.name))
+    }
+
+    /// Returns redb's storage statistics for the table, like the height of its B-tree, \
+    /// its page counts, and how many bytes are lost to fragmentation. \
+    /// returns `None` for a database opened with [`Database::in_memory()`](struct.Database.html#method.in_memory), \
+    /// and for a table that doesn't exist.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// if let Some(stats) = db.table("logs").redb_stats()? {
+    ///     println!("{} of {} bytes are fragmented", stats.fragmented_bytes(), stats.stored_bytes());
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn redb_stats(&self) -> Result<Option<TableStats>> {
+        self.store
+            .context(&self.name, self.store.redb_stats(&self.name))
+    }
+
+    /// Returns the key and size of every value larger than `bytes`, in key order, \
+    /// with sizes read from the stored values without deserializing them, all under a single snapshot. \
+    /// sizes are those of the values as stored, after any [transform](trait.ValueTransform.html), \
+    /// and a value of a deduplicated table counts the full size of its shared copy.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let db = Database::open("my_database.db")?;
+    /// for (key, size) in db.table("uploads").keys_larger_than(1 << 20)? {
+    ///     println!("{}: {} bytes", key, size);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn keys_larger_than(&self, bytes: usize) -> Result<Vec<(String, usize)>> {
+        self.store
+            .context(&self.name, self.store.keys_larger_than(&self.name, bytes))
     }
 }
 
 impl<'a> TableMut<'a> {
     /// Gets the name of the table
     pub fn name(&self) -> &str {
-        self.name
+        &self.name
+    }
+
+    /// Removes the given keys in a single write transaction, \
+    /// and reports which of them were removed and which didn't exist. \
+    /// refuses to remove more than `cap` keys at once, \
+    /// use [`remove_keys_from_reader()`](struct.TableMut.html#method.remove_keys_from_reader) to remove large sets of keys in chunks.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let keys = vec!["key1".to_string(), "key2".to_string()];
+    /// let report = db.table_mut("my_table").remove_keys(keys, 1000)?;
+    /// if !report.not_found.is_empty() {
+    ///     println!("already gone: {:?}", report.not_found);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_keys(
+        &mut self,
+        keys: impl IntoIterator<Item = String>,
+        cap: usize,
+    ) -> Result<RemoveReport> {
+        let keys: Vec<String> = keys.into_iter().collect();
+        let check = || -> anyhow::Result<()> {
+            ensure!(
+                keys.len() <= cap,
+                "refusing to remove {} keys in a single transaction (cap is {}), \
+                 use remove_keys_from_reader() to remove them in chunks",
+                keys.len(),
+                cap
+            );
+            Ok(())
+        };
+        let result = check().and_then(|_| self.store.remove_keys_with_report(&self.name, keys));
+        self.store.context(&self.name, result)
+    }
+
+    /// Caps the number of entries in the table, enforced on every insert according to `policy`. \
+    /// the limit is saved in the database, and removed along with the table or by [`remove_limit()`](#method.remove_limit). \
+    /// existing entries are ordered by key when the limit is first set, \
+    /// and if there are already more than `max_entries`, the extra entries are evicted right away (unless the policy is [`RejectNew`](enum.EvictionPolicy.html#variant.RejectNew)). \
+    /// tracking the order of entries costs an extra write per insert, \
+    /// and with [`Lru`](enum.EvictionPolicy.html#variant.Lru), an extra write transaction per read.
+    /// ```no_run
+    /// # use dbless::{Database, EvictionPolicy};
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut cache = db.table_mut("cache");
+    /// cache.set_limit(1000, EvictionPolicy::Lru)?;
+    /// cache.set("key", &"value")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_limit(&mut self, max_entries: usize, policy: EvictionPolicy) -> Result<()> {
+        let limit = TableLimit {
+            max_entries,
+            policy,
+        };
+        let result = self.store.set_limit(&self.name, limit);
+        self.store.context(&self.name, result)
+    }
+
+    /// Removes the limit set with [`set_limit()`](#method.set_limit), if any
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("cache").remove_limit()?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_limit(&mut self) -> Result<()> {
+        let result = self.store.remove_limit(&self.name);
+        self.store.context(&self.name, result)
+    }
+
+    /// Makes the table append-only and tamper evident: \
+    /// inserting a new key appends it to a hash chain, each link hashing the previous link, the key and the value, \
+    /// and overwriting or removing entries (or clearing the table) fails with an [`AppendOnlyViolation`](struct.AppendOnlyViolation.html). \
+    /// the chain is checked with [`Table::verify_chain()`](struct.Table.html#method.verify_chain). \
+    /// the setting is saved in the database and can't be undone, short of deleting the table, \
+    /// and it can only be enabled while the table is empty and has no limit.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut audit = db.table_mut("audit");
+    /// audit.append_only_chained()?;
+    /// audit.set("2024-05-01T12:00:00Z", &"user 42 logged in")?;
+    /// assert!(audit.remove("2024-05-01T12:00:00Z").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn append_only_chained(&mut self) -> Result<()> {
+        let result = self.store.append_only_chained(&self.name);
+        self.store.context(&self.name, result)
+    }
+
+    /// Makes the keys of the table write-once: \
+    /// writing a key that already exists fails with an [`ImmutableKey`](struct.ImmutableKey.html) instead of overwriting it, \
+    /// whichever method writes it (including batches, transactions, moves and restores). \
+    /// keys can still be removed, see [`append_only()`](#method.append_only) to prevent that too. \
+    /// the setting is saved in the database and can't be undone, short of deleting the table.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut blobs = db.table_mut("blobs");
+    /// blobs.insert_only()?;
+    /// blobs.set("9f86d081", &"test")?;
+    /// assert!(blobs.set("9f86d081", &"something else").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn insert_only(&mut self) -> Result<()> {
+        let result = self.store.insert_only(&self.name);
+        self.store.context(&self.name, result)
+    }
+
+    /// Makes the table [insert-only](#method.insert_only), \
+    /// and makes removing its keys (or clearing it) fail with an [`AppendOnlyViolation`](struct.AppendOnlyViolation.html). \
+    /// unlike [`append_only_chained()`](#method.append_only_chained) it keeps no hash chain, \
+    /// so it can be enabled on a table that already has entries. \
+    /// the setting is saved in the database and can't be undone, short of deleting the table, \
+    /// and it can't be combined with a [limit](#method.set_limit).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::TableWriteInterface;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut events = db.table_mut("events");
+    /// events.append_only()?;
+    /// events.set("0001", &"created")?;
+    /// assert!(events.remove("0001").is_err());
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn append_only(&mut self) -> Result<()> {
+        let result = self.store.append_only(&self.name);
+        self.store.context(&self.name, result)
+    }
+
+    /// Checks if inserting `value` under `key` would succeed, without writing it: \
+    /// that the value serializes, that a table [limited](#method.set_limit) with [`EvictionPolicy::RejectNew`](enum.EvictionPolicy.html#variant.RejectNew) has room for a new key, \
+    /// and that the key isn't already taken in an [insert-only](#method.insert_only) table. \
+    /// see [`serialized_size()`](fn.serialized_size.html) to check the size of the value.
+    /// ```no_run
+    /// # use dbless::{Database, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut uploads = db.table_mut("uploads");
+    /// if uploads.would_fit("report.pdf", &vec![0u8; 1024])? {
+    ///     uploads.set("report.pdf", &vec![0u8; 1024])?;
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn would_fit<T: Serialize>(&self, key: &str, value: &T) -> Result<bool> {
+        let result = self.store.would_fit(&self.name, key, value);
+        self.store.context(&self.name, result)
+    }
+
+    /// Makes the table escape every key with [`escape_key()`](fn.escape_key.html) when storing it, \
+    /// and unescape it when returning it (from [`keys()`](trait.TableReadInterface.html#method.keys), [`entries()`](trait.TableReadInterface.html#method.entries), and the like), \
+    /// so keys with newlines or other control characters never reach the database as is. \
+    /// the setting is saved in the database, and can only be changed while the table is empty. \
+    /// since escaping is prefix preserving, prefix scans like [`Table::export_keys()`](struct.Table.html#method.export_keys) keep working.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut notes = db.table_mut("notes");
+    /// notes.set_escape_keys(true)?;
+    /// notes.set("line 1\nline 2", &"value")?;
+    /// assert_eq!(notes.keys()?, vec!["line 1\nline 2"]);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_escape_keys(&mut self, enabled: bool) -> Result<()> {
+        let result = self.store.set_escape_keys(&self.name, enabled);
+        self.store.context(&self.name, result)
+    }
+
+    /// Makes the table store each distinct value once: \
+    /// a written value is stored under the SHA-256 of its bytes, along with a count of the keys referencing it, \
+    /// and the key only holds that reference, so many keys with the same large value take the space of one. \
+    /// reads resolve the reference transparently, at the cost of an extra lookup, \
+    /// and removing or overwriting the last key referencing a value removes the value too. \
+    /// values no longer than a reference (35 bytes) are stored under their key as usual. \
+    /// the setting is saved in the database, and can only be changed while the table is empty, \
+    /// it can't be combined with a [limit](#method.set_limit) or [`append_only_chained()`](#method.append_only_chained).
+    /// ```no_run
+    /// # use dbless::Database;
+    /// # use dbless::{TableReadInterface, TableWriteInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut pages = db.table_mut("pages");
+    /// pages.set_deduplicated(true)?;
+    /// let body = "<html>...</html>".repeat(1000);
+    /// pages.set("/index.html", &body)?;
+    /// pages.set("/", &body)?; // stored once
+    /// assert_eq!(pages.get::<String>("/")?, Some(body));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_deduplicated(&mut self, enabled: bool) -> Result<()> {
+        let result = self.store.set_deduplicated(&self.name, enabled);
+        self.store.context(&self.name, result)
+    }
+
+    /// Removes every entry whose value has a field at `path` matching `predicate`, \
+    /// and returns the number of removed entries. \
+    /// `path` is a dot separated list of map keys (struct fields) or array indices, like `meta.ts` or `tags.0`, \
+    /// and an empty path checks the value itself. \
+    /// fields are read straight from the stored MessagePack, without deserializing whole values, \
+    /// and matching entries are removed in chunks, each chunk in its own write transaction.
+    /// ```no_run
+    /// # use dbless::{Database, FieldPredicate};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let cutoff = 1_700_000_000u64;
+    /// let removed = db.table_mut("logs").remove_where_field("ts", FieldPredicate::LessThan(cutoff.into()))?;
+    /// println!("removed {} old log entries", removed);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_where_field(&mut self, path: &str, predicate: FieldPredicate) -> Result<u64> {
+        let result =
+            self.store
+                .remove_where_field(&self.name, path, &predicate, REMOVE_KEYS_CHUNK_SIZE);
+        self.store.context(&self.name, result)
+    }
+
+    /// Applies the operations staged in `batch` to the table, in order, in a single write transaction: \
+    /// either they all take effect, or none does.
+    /// ```no_run
+    /// # use dbless::{Database, WriteBatch};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut batch = WriteBatch::new();
+    /// batch.clear_table();
+    /// batch.insert("theme", &"dark")?;
+    /// db.table_mut("config").apply(batch)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn apply(&mut self, batch: WriteBatch) -> Result<()> {
+        let result = self.store.apply_batch(&self.name, &batch.ops);
+        self.store.context(&self.name, result)
+    }
+
+    /// Inserts a value that is already serialized as MessagePack, \
+    /// like one read with [`get_raw()`](trait.TableReadInterface.html#method.get_raw) or written by another language, \
+    /// converted to the format of the database so [`get()`](trait.TableReadInterface.html#method.get) can read it. \
+    /// errors if `bytes` isn't exactly one MessagePack value, \
+    /// or is a MessagePack ext value, which dbless reserves for its own metadata. \
+    /// to store bytes as they are, use [`set_raw()`](trait.TableWriteInterface.html#method.set_raw).
+    /// ```no_run
+    /// # use dbless::{Database, TableReadInterface};
+    /// let mut db = Database::open("my_database.db")?;
+    /// db.table_mut("users").set_msgpack("alice", &[0xa5, b'A', b'l', b'i', b'c', b'e'])?;
+    /// assert_eq!(db.table("users").get::<String>("alice")?.as_deref(), Some("Alice"));
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_msgpack(&mut self, key: &str, bytes: &[u8]) -> Result<()> {
+        let result = self.store.insert_msgpack(&self.name, key, bytes);
+        self.store.context(&self.name, result)
+    }
+
+    /// Inserts a [`Value`](enum.Value.html) tree, like one read with [`Table::get_dynamic()`](struct.Table.html#method.get_dynamic) and edited. \
+    /// errors if the value holds a [`Value::Ext`](enum.Value.html#variant.Ext), which dbless reserves for its own metadata.
+    /// ```no_run
+    /// # use dbless::{Database, Value};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let mut user = db.table("users").get_dynamic("alice")?.unwrap_or(Value::Map(vec![]));
+    /// if let Some(name) = user.pointer_mut("/profile/name") {
+    ///     *name = "Alice".into();
+    /// }
+    /// db.table_mut("users").set_dynamic("alice", &user)?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn set_dynamic(&mut self, key: &str, value: &Value) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    /// Removes every entry whose value is larger than `bytes`, with sizes measured like \
+    /// [`Table::keys_larger_than()`](struct.Table.html#method.keys_larger_than), \
+    /// and returns the number of removed entries. \
+    /// matching entries are removed in chunks, each chunk in its own write transaction.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let removed = db.table_mut("uploads").remove_larger_than(1 << 20)?;
+    /// println!("removed {} large uploads", removed);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_larger_than(&mut self, bytes: usize) -> Result<u64> {
+        let result = self
+            .store
+            .remove_larger_than(&self.name, bytes, REMOVE_KEYS_CHUNK_SIZE);
+        self.store.context(&self.name, result)
+    }
+
+    /// Removes every key read from `reader`, each followed by `delimiter` (empty keys are ignored), \
+    /// and returns the number of keys that were actually removed. \
+    /// reads the output of [`Table::export_keys()`](struct.Table.html#method.export_keys) given the same delimiter. \
+    /// keys are removed in chunks, each chunk in its own write transaction.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// let mut db = Database::open("my_database.db")?;
+    /// let file = std::fs::File::open("keys.txt")?;
+    /// let count = db.table_mut("my_table").remove_keys_from_reader(std::io::BufReader::new(file), b'\n')?;
+    /// println!("removed {} keys", count);
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn remove_keys_from_reader(&mut self, reader: impl BufRead, delimiter: u8) -> Result<u64> {
+        let mut count = 0;
+        let mut chunk = Vec::with_capacity(REMOVE_KEYS_CHUNK_SIZE);
+        for key in reader.split(delimiter) {
+            let key = key
+                .map_err(Into::into)
+                .and_then(|key| String::from_utf8(key).map_err(Into::into));
+            let key = self.store.context(&self.name, key)?;
+            if key.is_empty() {
+                continue;
+            }
+            chunk.push(key);
+            if chunk.len() == REMOVE_KEYS_CHUNK_SIZE {
+                let result = self.store.remove_keys(&self.name, &chunk);
+                count += self.store.context(&self.name, result)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            let result = self.store.remove_keys(&self.name, &chunk);
+            count += self.store.context(&self.name, result)?;
+        }
+        Ok(count)
     }
 }
@@ -1,25 +1,34 @@
-use crate::{Database, TableReadInterface, TableWriteInterface};
+use crate::{
+    escape_key, unescape_key, AppendOnlyViolation, BackupOpts, CappedLen, Database, EvictionPolicy,
+    ExportFormat, FieldPredicate, ImmutableKey, LossyNumberError, ReservedKind, RestoreMode,
+    SerializeErrorPolicy, Table, TableKind, TableReadInterface, TableWriteInterface, Value,
+    Versioned, VersionedOutcome, WriteBatch,
+};
 use anyhow::Result;
 use ctor::{ctor as run_before_tests, dtor as run_after_tests};
 use serde::{Deserialize, Serialize};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
 
 type TestResult = Result<()>;
 
-const TEST_DB_NAME: &str = "test.db";
 static mut DB: Option<Database> = None;
 
 #[run_before_tests]
 fn init_tests() {
-    delete_test_db();
     unsafe {
-        DB = Some(Database::open(TEST_DB_NAME).unwrap());
+        DB = Some(Database::open_temp().unwrap());
     }
 }
 
+fn test_db() -> &'static mut Database {
+    unsafe { (*std::ptr::addr_of_mut!(DB)).as_mut().unwrap() }
+}
+
 macro_rules! test_db_and_tables {
     (|$db:ident| $block:block) => {{
         #[allow(unused_mut)]
-        let mut $db = unsafe { DB.as_mut().unwrap() };
+        let mut $db = test_db();
         $db.delete_all_tables()?;
         $block
 
@@ -293,7 +302,7 @@ fn get_or_insert() -> TestResult {
 
 #[test]
 fn default_table() -> TestResult {
-    let db = unsafe { DB.as_mut().unwrap() };
+    let db = test_db();
     db.delete_all_tables()?;
     db.set_default_table("t1");
     db.set("key", &"value")?;
@@ -310,7 +319,3928 @@ fn default_table() -> TestResult {
     Ok(())
 }
 
-#[run_after_tests]
-fn delete_test_db() {
-    let _ = std::fs::remove_file(TEST_DB_NAME);
+#[test]
+fn export_and_remove_keys() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("t");
+    for i in 0..25 {
+        table.set(&format!("key{:02}", i), &i)?;
+    }
+    table.set("other", &"value")?;
+
+    let mut all = vec![];
+    assert_eq!(db.table("t").export_keys(&mut all, b'\n', None)?, 26);
+    assert_eq!(String::from_utf8(all)?.lines().count(), 26);
+
+    let mut exported = vec![];
+    assert_eq!(
+        db.table("t")
+            .export_keys(&mut exported, b'\n', Some("key"))?,
+        25
+    );
+    let exported = String::from_utf8(exported)?;
+    assert!(exported.lines().all(|k| k.starts_with("key")));
+
+    let mut table = db.table_mut("t");
+    let removed = table.remove_keys_from_reader(exported.as_bytes(), b'\n')?;
+    assert_eq!(removed, 25);
+    assert_eq!(table.keys()?, vec!["other".to_owned()]);
+    assert_eq!(
+        table.remove_keys_from_reader("key00\n\nother\n".as_bytes(), b'\n')?,
+        1
+    );
+    assert!(table.is_empty()?);
+
+    table.set("multi\nline", &1)?;
+    table.set("carriage\rreturn", &2)?;
+    table.set("kept", &3)?;
+    let mut exported = vec![];
+    db.table("t").export_keys(&mut exported, 0, Some("m"))?;
+    db.table("t").export_keys(&mut exported, 0, Some("c"))?;
+    let removed = db
+        .table_mut("t")
+        .remove_keys_from_reader(exported.as_slice(), 0)?;
+    assert_eq!(removed, 2);
+    assert_eq!(db.table("t").keys()?, ["kept"]);
+
+    Ok(())
+}
+
+struct FailsOnZero(i32);
+
+impl Serialize for FailsOnZero {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.0 == 0 {
+            return Err(serde::ser::Error::custom("zero is not allowed"));
+        }
+        self.0.serialize(serializer)
+    }
+}
+
+#[test]
+fn serialize_error_policy() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let err = db.set("key", &FailsOnZero(0)).unwrap_err().to_string();
+    assert!(err.contains("key"));
+    assert!(err.contains("FailsOnZero"));
+
+    let entries = [
+        ("a", &FailsOnZero(1)),
+        ("b", &FailsOnZero(0)),
+        ("c", &FailsOnZero(3)),
+    ];
+    assert!(db.insert_batch(&entries).is_err());
+    assert!(db.is_empty()?);
+
+    let mut db = Database::options()
+        .serialize_error_policy(SerializeErrorPolicy::Skip)
+        .in_memory()?;
+    assert_eq!(db.insert_batch(&entries)?, vec!["b".to_owned()]);
+    assert_eq!(db.keys()?, vec!["a".to_owned(), "c".to_owned()]);
+
+    let (skipped, _) = db.table_mut("timed").set_many_timed(&entries)?;
+    assert_eq!(skipped, vec!["b".to_owned()]);
+    assert_eq!(
+        db.table("timed").keys()?,
+        vec!["a".to_owned(), "c".to_owned()]
+    );
+
+    Ok(())
+}
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    static LIVE_BYTES: Cell<isize> = const { Cell::new(0) };
+    static PEAK_BYTES: Cell<isize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        let _ = LIVE_BYTES.try_with(|live| {
+            live.set(live.get() + layout.size() as isize);
+            let _ = PEAK_BYTES.try_with(|peak| peak.set(peak.get().max(live.get())));
+        });
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let _ = LIVE_BYTES.try_with(|live| live.set(live.get() - layout.size() as isize));
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce() -> crate::Result<()>) -> Result<usize> {
+    let before = ALLOCATIONS.with(Cell::get);
+    f()?;
+    Ok(ALLOCATIONS.with(Cell::get) - before)
+}
+
+/// the most memory allocated on this thread at once while running `f`, \
+/// on top of what was allocated before it
+fn peak_allocated<T>(f: impl FnOnce() -> crate::Result<T>) -> Result<(T, usize)> {
+    let before = LIVE_BYTES.with(Cell::get);
+    PEAK_BYTES.with(|peak| peak.set(before));
+    let output = f()?;
+    Ok((output, (PEAK_BYTES.with(Cell::get) - before) as usize))
+}
+
+#[test]
+fn default_table_methods_dont_allocate_the_name() -> TestResult {
+    let mut db = Database::in_memory()?;
+    for _ in 0..10 {
+        db.set("key", &"value")?;
+    }
+    let mut through_database = 0;
+    let mut through_table = 0;
+    for _ in 0..100 {
+        through_database += count_allocations(|| db.set("key", &"value"))?;
+        through_table += count_allocations(|| db.default_table_mut().set("key", &"value"))?;
+    }
+    assert_eq!(through_database, through_table);
+
+    Ok(())
+}
+
+#[test]
+fn health_check() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.health_check()?;
+    db.health_check()?;
+    assert!(db.list_tables()?.is_empty());
+    assert_eq!(db.len_all_tables()?, 0);
+    Ok(())
+}
+
+#[test]
+fn digest() -> TestResult {
+    let mut a = Database::in_memory()?;
+    let mut b = Database::in_memory()?;
+    assert_eq!(a.table("t").digest()?, b.table("t").digest()?);
+
+    a.table_mut("t").set("key1", &1)?;
+    a.table_mut("t").set("key2", &"two")?;
+    b.table_mut("t").set("key2", &"two")?;
+    assert_ne!(a.table("t").digest()?, b.table("t").digest()?);
+    b.table_mut("t").set("key1", &1)?;
+    assert_eq!(a.table("t").digest()?, b.table("t").digest()?);
+
+    a.set("key", &true)?;
+    assert_ne!(a.digest_all()?, b.digest_all()?);
+    b.set("key", &true)?;
+    assert_eq!(a.digest_all()?, b.digest_all()?);
+    assert_eq!(a.digest_all()?.len(), 2);
+    assert_eq!(a.digest_all()?["t"], a.table("t").digest()?);
+
+    Ok(())
+}
+
+#[test]
+fn versions() -> TestResult {
+    let mut db = Database::in_memory()?;
+    assert_eq!(db.version_of("key")?, None);
+    db.set("key", &"value")?;
+    assert_eq!(db.version_of("key")?, Some(0));
+
+    assert!(!db.set_if_version("key", &"new", 1)?);
+    assert!(db.set_if_version("key", &"new", 0)?);
+    assert_eq!(db.version_of("key")?, Some(1));
+    assert_eq!(db.get::<String>("key")?, Some("new".to_owned()));
+    assert_eq!(db.values::<String>()?, vec!["new".to_owned()]);
+    assert!(db.set_if_version("other", &1, 0)?);
+    assert_eq!(db.version_of("other")?, Some(1));
+
+    let mut db = Database::options().track_versions(true).in_memory()?;
+    db.set("key", &1)?;
+    db.set("key", &2)?;
+    db.insert_batch(&[("key", &3)])?;
+    assert_eq!(db.version_of("key")?, Some(3));
+    assert_eq!(db.get::<i32>("key")?, Some(3));
+    assert_eq!(db.entries::<i32>()?, vec![("key".to_owned(), 3)]);
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+struct Measurement {
+    value: f32,
+}
+
+#[derive(Serialize)]
+struct PreciseMeasurement {
+    value: f64,
+}
+
+#[test]
+fn strict_numbers() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set("big", &u64::MAX)?;
+    db.set("precise", &PreciseMeasurement { value: 0.1 })?;
+    db.set("fine", &PreciseMeasurement { value: 0.5 })?;
+    assert!(db.get::<i64>("big").is_err());
+    assert_eq!(db.get::<f64>("big")?, Some(u64::MAX as f64));
+    assert!(db.get::<Measurement>("precise")?.is_some());
+
+    let mut db = Database::options().strict_numbers(true).in_memory()?;
+    db.set("big", &u64::MAX)?;
+    db.set("small", &42u64)?;
+    db.set("precise", &PreciseMeasurement { value: 0.1 })?;
+    db.set("fine", &PreciseMeasurement { value: 0.5 })?;
+    db.set("list", &vec![Some(1.0f64), None, Some(1e300)])?;
+
+    let err = db.get::<f64>("big").unwrap_err();
+    let err = err.downcast_ref::<LossyNumberError>().unwrap();
+    assert_eq!(err.key, "big");
+    assert_eq!(err.value, u64::MAX.to_string());
+    assert_eq!(err.target, "f64");
+    assert_eq!(db.get::<f64>("small")?, Some(42.0));
+
+    let err = db.get::<Measurement>("precise").unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<LossyNumberError>().unwrap().target,
+        "f32"
+    );
+    assert_eq!(
+        db.get::<Measurement>("fine")?,
+        Some(Measurement { value: 0.5 })
+    );
+    assert!(db.get::<Vec<Option<f32>>>("list").is_err());
+    assert_eq!(db.get::<Vec<Option<f64>>>("list")?.unwrap().len(), 3);
+    assert_eq!(db.values::<Measurement>()?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn custom_backend() -> TestResult {
+    let mut db = Database::open_with_backend(redb::backends::InMemoryBackend::new())?;
+    db.set("key", &"value")?;
+    assert_eq!(db.get::<String>("key")?, Some("value".to_owned()));
+
+    let db = Database::options()
+        .strict_numbers(true)
+        .open_with_backend(redb::backends::InMemoryBackend::new())?;
+    assert!(db.is_empty()?);
+    Ok(())
+}
+
+#[test]
+fn keys_after() -> TestResult {
+    test_db_and_tables!(|db| {
+        assert!(db.keys_after(None, 10)?.is_empty());
+        for i in 0..10 {
+            db.set(&format!("key{}", i), &i)?;
+        }
+        assert_eq!(db.keys_after(None, 3)?, vec!["key0", "key1", "key2"]);
+        assert_eq!(
+            db.keys_after(Some("key2"), 3)?,
+            vec!["key3", "key4", "key5"]
+        );
+
+        // writes between pages don't shift the cursor
+        db.remove("key0")?;
+        db.set("key3a", &0)?;
+        assert_eq!(db.keys_after(Some("key3"), 2)?, vec!["key3a", "key4"]);
+        assert_eq!(db.keys_after(Some("key8"), 5)?, vec!["key9"]);
+        assert!(db.keys_after(Some("key9"), 5)?.is_empty());
+        assert!(db.keys_after(None, 0)?.is_empty());
+        db.clear()?;
+    })
+}
+
+#[test]
+fn tagged_values() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set_tagged("measurement", &Measurement { value: 1.5 })?;
+    db.set_tagged("count", &3u32)?;
+    db.set("plain", &3u32)?;
+
+    let (tag, bytes) = db.get_tagged("count")?.unwrap();
+    assert_eq!(tag, std::any::type_name::<u32>());
+    assert_eq!(rmp_serde::from_slice::<u32>(&bytes)?, 3);
+    assert_eq!(db.get_tagged("missing")?, None);
+    assert!(db.get_tagged("plain").is_err());
+
+    assert_eq!(db.get_as::<u32>("count")?, Some(3));
+    assert_eq!(
+        db.get_as::<Measurement>("measurement")?,
+        Some(Measurement { value: 1.5 })
+    );
+    assert!(db.get_as::<u64>("count").is_err());
+    assert!(db.get_as::<u32>("plain").is_err());
+    assert_eq!(db.get_as::<u32>("missing")?, None);
+    assert_eq!(db.get::<u32>("count")?, Some(3));
+
+    let mut db = Database::options().track_versions(true).in_memory()?;
+    db.set_tagged("count", &1u32)?;
+    db.set_tagged("count", &2u32)?;
+    assert_eq!(db.version_of("count")?, Some(2));
+    assert_eq!(db.get_as::<u32>("count")?, Some(2));
+
+    Ok(())
+}
+
+#[test]
+fn remove_keys_report() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("table");
+    table.set("a", &1)?;
+    table.set("b", &2)?;
+    table.set("c", &3)?;
+
+    let keys = ["a", "missing", "c"].map(String::from);
+    assert!(table.remove_keys(keys.clone(), 2).is_err());
+    assert_eq!(table.len()?, 3);
+
+    let report = table.remove_keys(keys, 3)?;
+    assert_eq!(report.removed, vec!["a", "c"]);
+    assert_eq!(report.not_found, vec!["missing"]);
+    assert_eq!(table.keys()?, vec!["b"]);
+
+    Ok(())
+}
+
+#[test]
+fn set_many_timed() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let values: Vec<String> = (0..100).map(|i| i.to_string()).collect();
+    let entries: Vec<(&str, &String)> = values.iter().map(|v| (v.as_str(), v)).collect();
+    let (skipped, timing) = db.set_many_timed(&entries)?;
+    assert!(skipped.is_empty());
+    assert!(timing.total_ns >= timing.serialize_ns + timing.commit_ns);
+    assert!(timing.commit_ns > 0);
+    assert_eq!(db.len()?, 100);
+    assert_eq!(db.get::<String>("42")?, Some("42".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn table_aliases() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("users").set("a", &1)?;
+    db.alias_table("old_users", "users")?;
+    db.alias_table("older_users", "old_users")?;
+
+    db.table_mut("older_users").set("b", &2)?;
+    assert_eq!(db.table("users").keys()?, vec!["a", "b"]);
+    assert_eq!(db.table("old_users").get::<i32>("a")?, Some(1));
+    assert_eq!(db.list_tables()?, vec!["users"]);
+    assert_eq!(db.len_all_tables()?, 2);
+    assert_eq!(
+        db.list_aliases()?.into_iter().collect::<Vec<_>>(),
+        vec![
+            ("old_users".to_owned(), "users".to_owned()),
+            ("older_users".to_owned(), "old_users".to_owned()),
+        ]
+    );
+
+    assert!(db.alias_table("users", "older_users").is_err());
+    assert!(db.alias_table("users", "users").is_err());
+    db.table_mut("other").set("c", &3)?;
+    assert!(db.alias_table("other", "users").is_err());
+
+    assert!(db.delete_table("users").is_err());
+    assert!(db.delete_table("old_users").is_err());
+    db.delete_table("older_users")?;
+    db.delete_table("old_users")?;
+    assert!(db.list_aliases()?.is_empty());
+    assert_eq!(db.table("users").len()?, 2);
+    db.delete_table("users")?;
+
+    Ok(())
+}
+
+#[test]
+fn table_aliases_persist() -> TestResult {
+    let dir = std::env::temp_dir().join(format!("dbless_aliases_{}", std::process::id()));
+    {
+        let mut db = Database::open(&dir)?;
+        db.table_mut("users").set("a", &1)?;
+        db.alias_table("old_users", "users")?;
+    }
+    let db = Database::open(&dir)?;
+    assert_eq!(db.table("old_users").get::<i32>("a")?, Some(1));
+    drop(db);
+    std::fs::remove_file(&dir)?;
+    Ok(())
+}
+
+#[test]
+fn open_temp() -> TestResult {
+    let mut db = Database::open_temp()?;
+    let path = db.temp_file.as_ref().unwrap().0.clone();
+    assert!(path.exists());
+    db.set("key", &"value")?;
+    assert_eq!(db.get::<String>("key")?, Some("value".to_owned()));
+
+    let other = Database::options().track_versions(true).open_temp()?;
+    assert_ne!(other.temp_file.as_ref().unwrap().0, path);
+
+    drop(db);
+    assert!(!path.exists());
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+struct Document {
+    version: u64,
+    body: String,
+}
+
+impl Versioned for Document {
+    fn version(&self) -> u64 {
+        self.version
+    }
+
+    fn set_version(&mut self, version: u64) {
+        self.version = version;
+    }
+}
+
+#[test]
+fn update_versioned() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut doc = Document {
+        version: 0,
+        body: "draft".to_owned(),
+    };
+    assert_eq!(
+        db.update_versioned("doc", 0, &doc)?,
+        VersionedOutcome::Updated { new_version: 1 }
+    );
+    doc = db.get("doc")?.unwrap();
+    assert_eq!(doc.version, 1);
+
+    let stale = doc.clone();
+    doc.body = "edited".to_owned();
+    assert_eq!(
+        db.update_versioned("doc", doc.version, &doc)?,
+        VersionedOutcome::Updated { new_version: 2 }
+    );
+    assert_eq!(
+        db.update_versioned("doc", stale.version, &stale)?,
+        VersionedOutcome::Conflict { current_version: 2 }
+    );
+    assert_eq!(
+        db.get::<Document>("doc")?,
+        Some(Document {
+            version: 2,
+            body: "edited".to_owned()
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn remove_if() -> TestResult {
+    test_db_and_tables!(|db| {
+        db.set("status", &"pending")?;
+        assert!(!db.remove_if("status", &"done".to_owned())?);
+        assert!(!db.remove_if("missing", &"done".to_owned())?);
+        assert!(db.contains_key("status")?);
+        db.set("status", &"done".to_owned())?;
+        assert!(db.remove_if("status", &"done".to_owned())?);
+        assert!(!db.contains_key("status")?);
+        assert!(db.remove_if::<i32>("status", &1).is_ok());
+        db.set("status", &"done".to_owned())?;
+        assert!(db.remove_if::<i32>("status", &1).is_err());
+        db.clear()?;
+    })
+}
+
+#[test]
+fn manifest() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set("key", &"value")?;
+    db.table_mut("users").set("a", &1)?;
+    db.health_check()?;
+
+    let manifest = db.manifest()?;
+    assert_eq!(manifest.codec, "messagepack");
+    assert!(manifest.features.is_empty());
+    assert_eq!(
+        manifest.tables.keys().collect::<Vec<_>>(),
+        vec![crate::DEFAULT_DEFAULT_TABLE, "users"]
+    );
+    let users = &manifest.tables["users"];
+    assert_eq!(users.entries, 1);
+    assert_eq!(users.bytes, 2);
+    assert_eq!(users.digest.len(), 64);
+    assert!(manifest.diff(&db.manifest()?).is_empty());
+
+    let mut other = Database::options().track_versions(true).in_memory()?;
+    other.set("key", &"value")?;
+    other.table_mut("users").set("a", &2)?;
+    other.table_mut("groups").set("g", &1)?;
+    let diff = manifest.diff(&other.manifest()?);
+    assert_eq!(diff.changed_fields, vec!["features"]);
+    assert_eq!(diff.added_tables, vec!["groups"]);
+    assert!(diff.removed_tables.is_empty());
+    // versioned values are stored differently, so the default table changed too
+    let changed: Vec<_> = diff.changed_tables.iter().map(|t| t.0.as_str()).collect();
+    assert_eq!(changed, vec![crate::DEFAULT_DEFAULT_TABLE, "users"]);
+
+    let encoded = rmp_serde::to_vec(&manifest)?;
+    assert_eq!(
+        rmp_serde::from_slice::<crate::Manifest>(&encoded)?,
+        manifest
+    );
+    Ok(())
+}
+
+#[test]
+fn table_limits() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("fifo");
+    for key in ["c", "a", "b"] {
+        table.set(key, &0)?;
+    }
+    // existing entries are ordered by key
+    table.set_limit(2, EvictionPolicy::Fifo)?;
+    assert_eq!(table.keys()?, vec!["b", "c"]);
+    table.set("b", &1)?;
+    table.set("d", &0)?;
+    assert_eq!(table.keys()?, vec!["c", "d"]);
+    table.remove("c")?;
+    table.set("e", &0)?;
+    table.set("f", &0)?;
+    assert_eq!(table.keys()?, vec!["e", "f"]);
+
+    let mut table = db.table_mut("lru");
+    table.set_limit(2, EvictionPolicy::Lru)?;
+    table.set("a", &0)?;
+    table.set("b", &0)?;
+    assert_eq!(table.get::<i32>("a")?, Some(0));
+    table.set("c", &0)?;
+    assert_eq!(table.keys()?, vec!["a", "c"]);
+    table.set("a", &1)?;
+    table.set("d", &0)?;
+    assert_eq!(table.keys()?, vec!["a", "d"]);
+    // reads from a snapshot don't write, so they don't count as a use
+    let read = db.read_transaction(|snapshot| snapshot.table("lru").get::<i32>("a"))?;
+    assert_eq!(read, Some(1));
+    db.table_mut("lru").set("e", &0)?;
+    assert_eq!(db.table("lru").keys()?, vec!["d", "e"]);
+
+    let mut table = db.table_mut("reject");
+    table.set_limit(1, EvictionPolicy::RejectNew)?;
+    table.set("a", &0)?;
+    assert!(table.set("b", &0).is_err());
+    assert!(table.insert_batch(&[("a", &1), ("b", &1)]).is_err());
+    table.set("a", &2)?;
+    assert_eq!(table.entries::<i32>()?, vec![("a".to_owned(), 2)]);
+    // a refused key isn't written, even if the error is ignored
+    let result = db.transaction(|tx| {
+        assert!(tx.table_mut("reject").set("b", &0).is_err());
+        Ok(())
+    });
+    assert!(result.is_err());
+    let mut table = db.table_mut("reject");
+    assert_eq!(table.len()?, 1);
+    table.remove_limit()?;
+    table.set("b", &0)?;
+    assert_eq!(table.len()?, 2);
+
+    assert!(db
+        .table_mut("zero")
+        .set_limit(0, EvictionPolicy::Lru)
+        .is_err());
+    assert_eq!(db.table("lru").limit().map(|l| l.max_entries), Some(2));
+    assert_eq!(db.table("reject").limit(), None);
+    assert_eq!(db.list_tables()?, vec!["fifo", "lru", "reject"]);
+    db.digest_all()?;
+    db.delete_table("lru")?;
+    assert_eq!(db.table("lru").limit(), None);
+    Ok(())
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn timestamps() -> TestResult {
+    use crate::TimestampKey;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    let mut db = Database::in_memory()?;
+    let start = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    db.set("time", &start)?;
+    assert_eq!(db.get::<DateTime<Utc>>("time")?, Some(start));
+    assert_eq!(db.get::<String>("time")?.unwrap(), "2024-05-01T12:00:00Z");
+
+    let early = Utc.with_ymd_and_hms(999, 1, 1, 0, 0, 0).unwrap();
+    assert!(TimestampKey::from(early) < TimestampKey::from(start));
+    assert!(TimestampKey::from(start) < TimestampKey::from(start + Duration::nanoseconds(1)));
+
+    let mut events = db.table_mut("events");
+    for minutes in [30, 0, 10, 20] {
+        events.insert_at(start + Duration::minutes(minutes), &minutes)?;
+    }
+    let range = events.range_between::<i64>(start, start + Duration::minutes(30))?;
+    assert_eq!(
+        range,
+        vec![
+            (start, 0),
+            (start + Duration::minutes(10), 10),
+            (start + Duration::minutes(20), 20),
+        ]
+    );
+    assert!(db
+        .table("events")
+        .range_between::<i64>(early, start)?
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn archive() -> TestResult {
+    let mut db = Database::in_memory()?;
+    for i in 0..10u32 {
+        db.table_mut("hot").set(&format!("key{}", i), &i)?;
+    }
+    db.table_mut("hot").set("text", &"not a number")?;
+    db.table_mut("cold").set("key0", &100u32)?;
+
+    let moved = db.archive::<u32, _>("hot", "cold", |_, i| i % 2 == 0)?;
+    assert_eq!(moved, 5);
+    assert_eq!(db.table("hot").len()?, 6);
+    assert_eq!(db.table("cold").len()?, 5);
+    assert_eq!(db.table("cold").get::<u32>("key0")?, Some(0));
+    assert!(db.table("hot").contains_key("text")?);
+
+    assert!(db.archive::<u32, _>("hot", "hot", |_, _| true).is_err());
+
+    // the predicate sees the keys as they were set, and they're stored as the target expects
+    db.table_mut("escaped").set_escape_keys(true)?;
+    db.table_mut("escaped").set("a\nb", &1u32)?;
+    let mut seen = vec![];
+    db.archive::<u32, _>("escaped", "plain", |key, _| {
+        seen.push(key.to_owned());
+        true
+    })?;
+    assert_eq!(seen, ["a\nb"]);
+    assert_eq!(db.table("plain").get::<u32>("a\nb")?, Some(1));
+    db.archive::<u32, _>("plain", "escaped", |_, _| true)?;
+    assert_eq!(db.table("escaped").get::<u32>("a\nb")?, Some(1));
+    assert_eq!(db.table("escaped").keys()?, ["a\nb"]);
+    Ok(())
+}
+
+#[test]
+fn error_context() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("users").set("key", &"value")?;
+    let error = db.table("users").get::<u32>("key").unwrap_err();
+    assert_eq!(error.inner().chain().count(), 1);
+
+    db.with_error_context(|table| format!("while accessing user-db (table `{}`)", table));
+    let error = db.table("users").get::<u32>("key").unwrap_err();
+    assert_eq!(error.to_string(), "while accessing user-db (table `users`)");
+    assert_eq!(error.inner().chain().count(), 2);
+    let error = db.alias_table("users", "users").unwrap_err();
+    assert_eq!(error.to_string(), "while accessing user-db (table `users`)");
+
+    db.freeze();
+    let error = db
+        .transaction(|tx| tx.table_mut("users").set("key", &1))
+        .unwrap_err();
+    assert_eq!(error.to_string(), "while accessing user-db (table ``)");
+    assert!(error.is::<crate::Frozen>());
+    Ok(())
+}
+
+#[test]
+fn escaped_keys() -> TestResult {
+    let keys = ["plain", "", "50%", "a\nb", "\t\u{7f}%\r", "naïve\u{0}"];
+    for key in keys {
+        assert_eq!(unescape_key(&escape_key(key))?, key);
+    }
+    assert_eq!(escape_key("a\nb%c"), "a%0Ab%25c");
+    assert!(escape_key("user\nname").starts_with(&escape_key("user\n")));
+    for bad in ["%", "%0", "%0a", "%41", "%zz", "a\nb"] {
+        assert!(unescape_key(bad).is_err(), "{:?}", bad);
+    }
+
+    let mut db = Database::in_memory()?;
+    db.insert_escaped("a\nb", &1)?;
+    assert_eq!(db.keys()?, vec!["a%0Ab"]);
+    assert_eq!(db.get_escaped("a\nb")?, Some(1));
+
+    let mut notes = db.table_mut("notes");
+    notes.set_escape_keys(true)?;
+    notes.insert_batch(&[("x\ny", &1), ("x\nz", &2), ("x%", &3), ("w", &4)])?;
+    assert_eq!(notes.keys()?, vec!["w", "x\ny", "x\nz", "x%"]);
+    assert_eq!(notes.get("x\ny")?, Some(1));
+    assert_eq!(notes.keys_after(Some("x\ny"), 10)?, vec!["x\nz", "x%"]);
+    let mut exported = vec![];
+    Table::from(&notes).export_keys(&mut exported, 0, Some("x\n"))?;
+    assert_eq!(exported, b"x\ny\0x\nz\0");
+    notes.remove("x%")?;
+    assert!(!notes.contains_key("x%")?);
+    assert!(notes.set_escape_keys(false).is_err());
+    notes.clear()?;
+    notes.set_escape_keys(false)?;
+    assert!(!db.table("notes").escapes_keys());
+    Ok(())
+}
+
+#[test]
+fn counters() -> TestResult {
+    let mut db = Database::in_memory()?;
+    assert_eq!(db.get_u64("visits")?, None);
+    assert_eq!(db.add_u64("visits", 5)?, 5);
+    assert_eq!(db.add_u64("visits", 2)?, 7);
+    assert_eq!(db.get_u64("visits")?, Some(7));
+    assert!(db.get::<u64>("visits").is_err());
+    db.set_u64("visits", u64::MAX)?;
+    assert!(db.add_u64("visits", 1).is_err());
+    assert_eq!(db.get_u64("visits")?, Some(u64::MAX));
+
+    db.set("serialized", &7u64)?;
+    assert!(db.get_u64("serialized").is_err());
+    assert!(db.add_u64("serialized", 1).is_err());
+    Ok(())
+}
+
+#[test]
+fn list_tables_with_prefix() -> TestResult {
+    let mut db = Database::in_memory()?;
+    for table in ["cache:a", "cache:b", "session:a", "cache"] {
+        db.table_mut(table).set("key", &1)?;
+    }
+    assert_eq!(
+        db.list_tables_with_prefix("cache:", false)?,
+        vec!["cache:a", "cache:b"]
+    );
+    assert_eq!(db.list_tables_with_prefix("cache:", true)?, vec!["a", "b"]);
+    assert!(db.list_tables_with_prefix("index:", false)?.is_empty());
+    assert!(db.list_tables_with_prefix("#_#", false)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn table_groups() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut user = db.group_mut("user.42.");
+    user.table_mut("inbox")
+        .insert_batch(&[("a", &1), ("b", &2)])?;
+    user.table_mut("outbox").set("c", &3)?;
+    user.table_mut("outbox")
+        .set_limit(10, EvictionPolicy::Fifo)?;
+    db.group_mut("user.7.").table_mut("inbox").set("d", &4)?;
+
+    assert_eq!(db.table("user.42.inbox").get("a")?, Some(1));
+    assert_eq!(db.group("user.42.").table("outbox").get("c")?, Some(3));
+    assert_eq!(
+        db.list_tables_with_prefix("user.42.", true)?,
+        vec!["inbox", "outbox"]
+    );
+    assert_eq!(db.len_tables_with_prefix("user.42.")?, 3);
+
+    db.alias_table("mail", "user.42.inbox")?;
+    assert!(db.delete_tables_with_prefix("user.42.").is_err());
+    assert_eq!(db.len_tables_with_prefix("user.42.")?, 3);
+    db.delete_table("mail")?;
+    assert_eq!(db.delete_tables_with_prefix("user.42.")?, 2);
+    assert_eq!(db.list_tables()?, vec!["user.7.inbox"]);
+    assert_eq!(db.table("user.42.outbox").limit(), None);
+    Ok(())
+}
+
+#[test]
+fn delete_tables_with_prefix_spares_reserved() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set("key", &1)?;
+    db.table_mut("cache").set_limit(5, EvictionPolicy::Lru)?;
+    db.table_mut("cache").set("key", &2)?;
+    db.table_mut("#_#_mine").set("key", &3)?;
+    assert_eq!(db.delete_tables_with_prefix("#_#_")?, 1);
+    assert_eq!(db.get("key")?, Some(1));
+    assert_eq!(db.table("cache").limit().map(|l| l.max_entries), Some(5));
+
+    db.set_default_table("tenant.main");
+    db.set("key", &4)?;
+    db.table_mut("tenant.data").set("key", &5)?;
+    assert_eq!(db.delete_tables_with_prefix("")?, 2);
+    assert_eq!(db.get("key")?, Some(4));
+    assert_eq!(db.list_tables()?, vec![crate::DEFAULT_DEFAULT_TABLE]);
+    assert_eq!(db.table(crate::DEFAULT_DEFAULT_TABLE).get("key")?, Some(1));
+    Ok(())
+}
+
+#[test]
+fn stream_table_to() -> TestResult {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct User {
+        name: String,
+        tags: Vec<String>,
+        score: f64,
+    }
+
+    #[derive(Deserialize)]
+    struct Export {
+        alice: User,
+        count: i32,
+        visits: u64,
+    }
+
+    let mut db = Database::options().track_versions(true).in_memory()?;
+    let alice = User {
+        name: "alice \"a\"\n".to_string(),
+        tags: vec!["admin".to_string()],
+        score: 1.5,
+    };
+    let mut users = db.table_mut("users");
+    users.set("alice", &alice)?;
+    users.set_tagged("count", &-3i32)?;
+    users.set_u64("visits", 7)?;
+
+    let mut json = vec![];
+    let written = db.stream_table_to("users", &mut json, ExportFormat::Json)?;
+    assert_eq!(written, json.len() as u64);
+    assert_eq!(
+        String::from_utf8(json)?,
+        r#"{"alice":{"name":"alice \"a\"\n","tags":["admin"],"score":1.5},"count":-3,"visits":7}"#
+    );
+
+    let mut msgpack = vec![];
+    db.stream_table_to("users", &mut msgpack, ExportFormat::MessagePack)?;
+    let export: Export = rmp_serde::from_slice(&msgpack)?;
+    assert_eq!(export.alice, alice);
+    assert_eq!((export.count, export.visits), (-3, 7));
+
+    let mut empty = vec![];
+    db.stream_table_to("missing", &mut empty, ExportFormat::Json)?;
+    assert_eq!(empty, b"{}");
+    Ok(())
+}
+
+#[test]
+fn remove_where_field() -> TestResult {
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Meta {
+        ts: u64,
+        level: String,
+    }
+    #[derive(Serialize, Deserialize, PartialEq, Debug)]
+    struct Log {
+        meta: Meta,
+        tags: Vec<f64>,
+    }
+
+    let mut db = Database::in_memory()?;
+    let mut logs = db.table_mut("logs");
+    for i in 0..10u64 {
+        let log = Log {
+            meta: Meta {
+                ts: i * 100,
+                level: if i % 2 == 0 { "info" } else { "warn" }.to_string(),
+            },
+            tags: vec![i as f64 / 2.0],
+        };
+        logs.set(&format!("log{}", i), &log)?;
+    }
+    logs.set("plain", &250)?;
+
+    let old = FieldPredicate::LessThan(300u64.into());
+    assert_eq!(logs.remove_where_field("meta.ts", old)?, 3);
+    assert_eq!(logs.len()?, 8);
+    let warn = FieldPredicate::Equals("warn".into());
+    assert_eq!(logs.remove_where_field("meta.level", warn)?, 4);
+    let high = FieldPredicate::GreaterThan(3.9.into());
+    assert_eq!(logs.remove_where_field("tags.0", high)?, 1);
+    assert_eq!(logs.keys()?, vec!["log4", "log6", "plain"]);
+    let missing = FieldPredicate::Equals(0.into());
+    assert_eq!(logs.remove_where_field("meta.nope", missing.clone())?, 0);
+    assert_eq!(logs.remove_where_field("tags.5", missing)?, 0);
+    assert_eq!(
+        logs.remove_where_field("", FieldPredicate::Equals(250.0.into()))?,
+        1
+    );
+
+    for i in 0..5 {
+        db.table_mut("chunked").set(&i.to_string(), &i)?;
+    }
+    let predicate = FieldPredicate::GreaterThan(0.into());
+    assert_eq!(
+        db.store.remove_where_field("chunked", "", &predicate, 2)?,
+        4
+    );
+    assert_eq!(db.table("chunked").keys()?, vec!["0"]);
+    assert_eq!(
+        db.table_mut("missing").remove_where_field("", predicate)?,
+        0
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_writes() -> TestResult {
+    let mut db = Database::options()
+        .verify_writes(true)
+        .track_versions(true)
+        .in_memory()?;
+    db.set("key", &"value")?;
+    db.set_tagged("tagged", &1u8)?;
+    db.insert_batch(&[("a", &1), ("b", &2)])?;
+    assert_eq!(db.add_u64("counter", 3)?, 3);
+    assert!(db.set_if_version("key", &"new", 1)?);
+    assert_eq!(db.get("key")?, Some("new".to_string()));
+    assert_eq!(db.version_of("key")?, Some(2));
+    Ok(())
+}
+
+#[test]
+fn append_only_chained() -> TestResult {
+    let path = std::env::temp_dir().join(format!("dbless_chain_{}", std::process::id()));
+    {
+        let mut db = Database::open(&path)?;
+        let mut audit = db.table_mut("audit");
+        audit.append_only_chained()?;
+        audit.set("b", &"first")?;
+        audit.set("a", &"second")?;
+        audit.insert_batch(&[("c", &"third")])?;
+
+        let error = audit.set("a", &"changed").unwrap_err();
+        let violation = error.downcast_ref::<AppendOnlyViolation>().unwrap();
+        assert_eq!(violation.key.as_deref(), Some("a"));
+        assert!(audit.remove("a").is_err());
+        assert!(audit.clear().is_err());
+        assert!(audit.set_limit(10, EvictionPolicy::Fifo).is_err());
+        assert_eq!(audit.get("a")?, Some("second".to_string()));
+
+        // a refused overwrite changes nothing, even if the error is ignored
+        let result = db.transaction(|tx| {
+            assert!(tx.table_mut("audit").set("a", &"changed").is_err());
+            Ok(())
+        });
+        assert!(result.is_err());
+        let audit = db.table("audit");
+        assert_eq!(audit.get("a")?, Some("second".to_string()));
+
+        let report = audit.verify_chain()?;
+        assert!(report.is_intact());
+        assert_eq!(report.entries, 3);
+        assert_ne!(report.head, [0; 32]);
+        db.table_mut("plain").set("key", &1)?;
+        assert!(db.table_mut("plain").append_only_chained().is_err());
+    }
+    {
+        let raw = redb::Database::create(&path)?;
+        let tnx = raw.begin_write()?;
+        {
+            let mut table = tnx.open_table(redb::TableDefinition::<&str, &[u8]>::new("audit"))?;
+            let forged = rmp_serde::to_vec("forged")?;
+            table.insert("a", forged.as_slice())?;
+        }
+        tnx.commit()?;
+    }
+    {
+        let db = Database::open(&path)?;
+        assert!(db.table("audit").is_chained());
+        let report = db.table("audit").verify_chain()?;
+        assert_eq!(report.first_divergent_key.as_deref(), Some("a"));
+        assert_eq!(report.entries, 1);
+        db.delete_table("audit")?;
+        assert!(!db.table("audit").is_chained());
+        assert!(db.list_tables()?.iter().all(|t| t == "plain"));
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn find_key() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.insert_batch(&[("b.draft", &1), ("a", &2), ("c.draft", &3)])?;
+    assert_eq!(
+        db.find_key(|key| key.ends_with(".draft"))?,
+        Some("b.draft".to_string())
+    );
+    let mut visited = 0;
+    db.find_key(|_| {
+        visited += 1;
+        true
+    })?;
+    assert_eq!(visited, 1);
+    assert_eq!(db.find_key(|key| key.is_empty())?, None);
+    assert_eq!(db.table("missing").find_key(|_| true)?, None);
+
+    let mut notes = db.table_mut("notes");
+    notes.set_escape_keys(true)?;
+    notes.set("x\ny", &1)?;
+    assert_eq!(
+        notes.find_key(|key| key.contains('\n'))?,
+        Some("x\ny".to_string())
+    );
+    Ok(())
+}
+
+#[test]
+fn entries_peak_memory() -> TestResult {
+    const VALUES: usize = 16;
+    const VALUE_SIZE: usize = 2 * 1024 * 1024;
+    let mut db = Database::in_memory()?;
+    for i in 0..VALUES {
+        db.set(&format!("key-{:02}", i), &"x".repeat(VALUE_SIZE))?;
+    }
+    let output_size = VALUES * VALUE_SIZE;
+    // the first walk fills redb's page cache, which isn't what's being measured
+    db.keys()?;
+
+    let (entries, peak) = peak_allocated(|| db.entries::<String>())?;
+    assert_eq!(entries.len(), VALUES);
+    assert!(
+        peak < output_size + VALUE_SIZE,
+        "peak {} for {}",
+        peak,
+        output_size
+    );
+    drop(entries);
+    let (values, peak) = peak_allocated(|| db.values::<String>())?;
+    assert!(
+        peak < output_size + VALUE_SIZE,
+        "peak {} for {}",
+        peak,
+        output_size
+    );
+    assert_eq!(values.len(), VALUES);
+
+    Ok(())
+}
+
+#[cfg(feature = "service")]
+#[test]
+fn db_handle() -> TestResult {
+    use crate::{DbHandle, HandleShutDown};
+
+    let handle = DbHandle::new(Database::in_memory()?);
+    let clone = handle.clone();
+    clone.write(|db| db.set("key", &1))?;
+    assert_eq!(handle.read(|db| db.get::<i32>("key"))?, Some(1));
+    handle.ping()?;
+    handle.health_check()?;
+    assert!(!handle.is_shut_down());
+
+    handle.shutdown();
+    handle.shutdown();
+    assert!(clone.is_shut_down());
+    let is_shut_down = |e: crate::DblessError| e.downcast_ref::<HandleShutDown>().is_some();
+    assert!(is_shut_down(clone.ping().unwrap_err()));
+    assert!(is_shut_down(clone.read(|db| db.len()).unwrap_err()));
+    assert!(is_shut_down(
+        clone.write(|db| db.set("key", &2)).unwrap_err()
+    ));
+
+    Ok(())
+}
+
+#[cfg(feature = "service")]
+#[test]
+fn db_handle_shutdown_while_writing() -> TestResult {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use crate::{DbHandle, HandleShutDown};
+
+    let path = std::env::temp_dir().join(format!("dbless_service_{}", std::process::id()));
+    let handle = DbHandle::new(Database::open(&path)?);
+    let written = Arc::new(AtomicUsize::new(0));
+    let writers = (0..4)
+        .map(|thread| {
+            let handle = handle.clone();
+            let written = written.clone();
+            std::thread::spawn(move || -> TestResult {
+                for i in 0.. {
+                    let key = format!("{}-{}", thread, i);
+                    match handle.write(|db| db.set(&key, &i)) {
+                        Ok(()) => written.fetch_add(1, Ordering::SeqCst),
+                        Err(e) if e.downcast_ref::<HandleShutDown>().is_some() => return Ok(()),
+                        Err(e) => return Err(e.into()),
+                    };
+                    handle.read(|db| db.get::<i32>(&key)).ok();
+                }
+                Ok(())
+            })
+        })
+        .collect::<Vec<_>>();
+    while written.load(Ordering::SeqCst) < 20 {
+        std::thread::yield_now();
+    }
+    handle.shutdown();
+    for writer in writers {
+        writer.join().unwrap()?;
+    }
+
+    // every write that was accepted finished before the database was closed
+    let db = Database::open(&path)?;
+    assert_eq!(db.len()?, written.load(Ordering::SeqCst));
+    db.close();
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[test]
+fn reserved_prefix() -> TestResult {
+    let backend = redb::backends::InMemoryBackend::new();
+    let mut db = Database::options()
+        .reserved_prefix("dbless/")
+        .open_with_backend(backend)?;
+    db.set("key", &1)?;
+    db.table_mut("users").set("alice", &2)?;
+    db.table_mut("limited").set_limit(1, EvictionPolicy::Fifo)?;
+    db.table_mut("limited").set("a", &3)?;
+    db.alias_table("people", "users")?;
+    db.health_check()?;
+    assert_eq!(db.list_tables()?, vec!["limited", "users"]);
+    assert_eq!(db.len_all_tables()?, 2);
+    assert_eq!(db.table("people").get::<i32>("alice")?, Some(2));
+
+    let tables = db.store.list_tables()?;
+    assert!(tables.contains(&"dbless/main".to_owned()));
+    assert!(tables.contains(&"dbless/aliases".to_owned()));
+    assert!(tables.contains(&"dbless/order:limited".to_owned()));
+    assert!(tables.iter().all(|t| !t.starts_with("#_#_")));
+
+    assert!(Database::options().reserved_prefix("").in_memory().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn database_mirrors_default_table() -> TestResult {
+    for mut db in [Database::in_memory()?, Database::open_temp()?] {
+        for (key, value) in [("b", 2), ("a", 1), ("B", 0), ("é", 4), ("ab", 3)] {
+            db.set(key, &value)?;
+        }
+        let sorted = vec!["B", "a", "ab", "b", "é"];
+        assert_eq!(db.keys()?, sorted);
+        assert_eq!(db.default_table().keys()?, sorted);
+        assert_eq!(db.default_table_mut().keys()?, sorted);
+
+        assert_eq!(db.values::<i32>()?, vec![0, 1, 3, 2, 4]);
+        assert_eq!(db.default_table().values::<i32>()?, db.values::<i32>()?);
+        assert_eq!(db.default_table_mut().values::<i32>()?, db.values::<i32>()?);
+
+        let entries = db.entries::<i32>()?;
+        assert_eq!(entries.iter().map(|(k, _)| k).collect::<Vec<_>>(), sorted);
+        assert_eq!(db.default_table().entries::<i32>()?, entries);
+        assert_eq!(db.default_table_mut().entries::<i32>()?, entries);
+
+        assert_eq!(db.find_key(|k| k.starts_with('a'))?.as_deref(), Some("a"));
+        assert_eq!(
+            db.default_table()
+                .find_key(|k| k.starts_with('a'))?
+                .as_deref(),
+            Some("a")
+        );
+        assert_eq!(
+            db.default_table_mut()
+                .find_key(|k| k.starts_with('a'))?
+                .as_deref(),
+            Some("a")
+        );
+
+        // get_or_else never inserts, and only calls the closure for a missing key
+        assert_eq!(db.get_or_else::<i32, _>("a", || unreachable!())?, 1);
+        assert_eq!(db.get_or_else("missing", || 5)?, 5);
+        assert_eq!(db.default_table().get_or_else("missing", || 6)?, 6);
+        assert_eq!(db.default_table_mut().get_or_else("missing", || 7)?, 7);
+        assert!(!db.contains_key("missing")?);
+
+        // get_or_insert_with inserts into the default table, and only calls the closure once
+        let mut calls = 0;
+        assert_eq!(
+            db.get_or_insert_with("c", || {
+                calls += 1;
+                8
+            })?,
+            8
+        );
+        assert_eq!(
+            db.get_or_insert_with("c", || {
+                calls += 1;
+                9
+            })?,
+            8
+        );
+        assert_eq!(
+            db.default_table_mut().get_or_insert_with("c", || {
+                calls += 1;
+                9
+            })?,
+            8
+        );
+        assert_eq!(
+            db.default_table_mut().get_or_insert_with("d", || {
+                calls += 1;
+                10
+            })?,
+            10
+        );
+        assert_eq!(calls, 2);
+        assert_eq!(db.default_table().get::<i32>("c")?, Some(8));
+        assert_eq!(db.get::<i32>("d")?, Some(10));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn deduplicated_tables() -> TestResult {
+    use redb::{ReadableTable, ReadableTableMetadata};
+
+    let path = std::env::temp_dir().join(format!("dbless_dedup_{}", std::process::id()));
+    let big = "x".repeat(1000);
+    let other = "y".repeat(1000);
+    {
+        let mut db = Database::options().track_versions(true).open(&path)?;
+        let mut pages = db.table_mut("pages");
+        pages.set_deduplicated(true)?;
+        assert!(pages.set_limit(10, EvictionPolicy::Fifo).is_err());
+        assert!(pages.append_only_chained().is_err());
+        for key in ["a", "b", "c", "d"] {
+            pages.set(key, &big)?;
+        }
+        pages.set("small", &"tiny")?;
+        pages.set("d", &other)?;
+        pages.set("e", &other)?;
+        pages.remove("c")?;
+        assert_eq!(pages.remove_keys(["e".to_owned()], 10)?.removed, vec!["e"]);
+        assert!(pages.set_deduplicated(false).is_err());
+
+        assert_eq!(pages.get::<String>("a")?, Some(big.clone()));
+        assert_eq!(pages.get::<String>("d")?, Some(other.clone()));
+        assert_eq!(pages.get::<String>("small")?.as_deref(), Some("tiny"));
+        assert_eq!(pages.version_of("a")?, Some(1));
+        assert_eq!(pages.version_of("d")?, Some(2));
+        assert_eq!(pages.values::<String>()?.len(), 4);
+        assert!(pages.remove_if("b", &big)?);
+
+        let mut plain = db.table_mut("plain");
+        let tiny = "tiny".to_owned();
+        for (key, value) in [("a", &big), ("d", &big), ("d", &other), ("small", &tiny)] {
+            plain.set(key, value)?;
+        }
+        let digests = db.digest_all()?;
+        assert_eq!(digests["pages"], digests["plain"]);
+    }
+    {
+        let raw = redb::Database::create(&path)?;
+        let tnx = raw.begin_read()?;
+        let name = format!("{}pages", crate::BLOBS_TABLE_PREFIX);
+        let blobs = tnx.open_table(redb::TableDefinition::<&[u8], &[u8]>::new(&name))?;
+        let mut refs = vec![];
+        for blob in blobs.iter()? {
+            let (_, blob) = blob?;
+            refs.push(u64::from_be_bytes(blob.value()[..8].try_into()?));
+        }
+        refs.sort_unstable();
+        assert_eq!(refs, vec![1, 1]);
+        let pages = tnx.open_table(redb::TableDefinition::<&str, &[u8]>::new("pages"))?;
+        assert_eq!(pages.len()?, 3);
+        assert!(pages.get("a")?.unwrap().value().len() < 100);
+    }
+    {
+        let mut db = Database::open(&path)?;
+        assert!(db.table("pages").is_deduplicated());
+        db.table_mut("pages").clear()?;
+        db.table_mut("pages").set_deduplicated(false)?;
+        db.table_mut("pages").set("a", &big)?;
+        assert_eq!(db.table("pages").get::<String>("a")?, Some(big));
+    }
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[test]
+fn contains_table() -> TestResult {
+    for cache in [false, true] {
+        let mut db = Database::options().cache_table_names(cache).in_memory()?;
+        assert!(!db.contains_table("users")?);
+        // reads don't create tables
+        assert_eq!(db.table("users").get::<i32>("alice")?, None);
+        assert!(!db.contains_table("users")?);
+
+        // tables are created by their first write, which drops the cached names
+        db.table_mut("users").set("alice", &1)?;
+        assert!(db.contains_table("users")?);
+        assert_eq!(
+            db.contains_table("users")?,
+            db.list_tables()?.contains(&"users".to_owned())
+        );
+        db.alias_table("people", "users")?;
+        assert!(!db.contains_table("people")?);
+
+        db.set("key", &1)?;
+        assert!(!db.contains_table(crate::DEFAULT_DEFAULT_TABLE)?);
+        db.health_check()?;
+        assert!(!db.contains_table(crate::HEALTH_TABLE)?);
+
+        db.delete_table("people")?;
+        db.delete_table("users")?;
+        assert!(!db.contains_table("users")?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn tables_equal() -> TestResult {
+    let mut db = Database::in_memory()?;
+    assert!(db.tables_equal("a", "b")?);
+    db.table_mut("a").set("x", &1)?;
+    assert!(!db.tables_equal("a", "b")?);
+    assert!(!db.tables_equal("b", "a")?);
+
+    db.table_mut("b").set("x", &1)?;
+    assert!(db.tables_equal("a", "b")?);
+    db.table_mut("b").set("x", &1.0)?;
+    assert!(!db.tables_equal("a", "b")?);
+    db.table_mut("b").set("x", &1)?;
+    db.table_mut("b").set("y", &2)?;
+    assert!(!db.tables_equal("a", "b")?);
+    db.table_mut("a").set("z", &2)?;
+    assert!(!db.tables_equal("a", "b")?);
+
+    // deduplicated values are compared by what they stand for
+    let value = "a value long enough to be stored once".repeat(2);
+    db.table_mut("c").set_deduplicated(true)?;
+    db.table_mut("c").set("x", &value)?;
+    db.table_mut("d").set("x", &value)?;
+    assert!(db.tables_equal("c", "d")?);
+    db.alias_table("e", "c")?;
+    assert!(db.tables_equal("d", "e")?);
+
+    db.table_mut("a").clear()?;
+    assert!(db.tables_equal("a", "missing")?);
+    Ok(())
+}
+
+#[test]
+fn migrate_default_table() -> TestResult {
+    let mut db = Database::in_memory()?;
+    assert_eq!(Database::DEFAULT_TABLE_NAME, crate::DEFAULT_DEFAULT_TABLE);
+    assert!(!db.has_default_table_data()?);
+    assert_eq!(db.migrate_default_table_to("settings")?, 0);
+
+    db.set("theme", &"dark")?;
+    db.set("volume", &7)?;
+    db.table_mut("settings").set("volume", &3)?;
+    db.table_mut("settings").set("language", &"en")?;
+    db.set_default_table("other");
+    assert!(db.has_default_table_data()?);
+
+    assert!(db
+        .migrate_default_table_to(Database::DEFAULT_TABLE_NAME)
+        .is_err());
+    assert!(db.migrate_default_table_to(crate::HEALTH_TABLE).is_err());
+    assert_eq!(db.migrate_default_table_to("settings")?, 2);
+    assert!(!db.has_default_table_data()?);
+    let settings = db.table("settings");
+    assert_eq!(settings.get::<String>("theme")?, Some("dark".to_owned()));
+    assert_eq!(settings.get::<i32>("volume")?, Some(7));
+    assert_eq!(settings.get::<String>("language")?, Some("en".to_owned()));
+
+    // keys end up in the form the target table stores them in
+    let mut db = Database::in_memory()?;
+    db.set("multi\nline", &1)?;
+    db.table_mut("escaped").set_escape_keys(true)?;
+    assert_eq!(db.migrate_default_table_to("escaped")?, 1);
+    assert_eq!(db.table("escaped").get::<i32>("multi\nline")?, Some(1));
+
+    let mut db = Database::options().reserved_prefix("_sys.").in_memory()?;
+    db.set("key", &1)?;
+    assert!(db.has_default_table_data()?);
+    assert_eq!(db.migrate_default_table_to("named")?, 1);
+    assert_eq!(db.table("named").len()?, 1);
+    Ok(())
+}
+
+#[test]
+fn value_transforms() -> TestResult {
+    use crate::ValueTransform;
+
+    struct Xor(u8);
+    impl ValueTransform for Xor {
+        fn id(&self) -> u8 {
+            1
+        }
+        fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+            bytes.iter().map(|b| b ^ self.0).collect()
+        }
+        fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+            Ok(self.encode(bytes))
+        }
+    }
+
+    struct Reverse;
+    impl ValueTransform for Reverse {
+        fn id(&self) -> u8 {
+            2
+        }
+        fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+            bytes.iter().rev().copied().collect()
+        }
+        fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+            Ok(self.encode(bytes))
+        }
+    }
+
+    // appends a keyed checksum, and refuses values whose checksum doesn't match
+    struct Sign(u8);
+    impl ValueTransform for Sign {
+        fn id(&self) -> u8 {
+            3
+        }
+        fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+            let sum = bytes.iter().fold(self.0, |sum, b| sum.wrapping_add(*b));
+            [bytes, &[sum]].concat()
+        }
+        fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>> {
+            let (_, value) = bytes.split_last().unwrap();
+            anyhow::ensure!(self.encode(value) == bytes, "bad signature");
+            Ok(value.to_vec())
+        }
+    }
+
+    let mut plain = Database::options().track_versions(true).in_memory()?;
+    let long = "a long value ".repeat(10);
+    let fill = |db: &mut Database| -> TestResult {
+        db.set("a", &1)?;
+        db.set("b", &vec!["x", "y"])?;
+        db.set_tagged("c", &2.5)?;
+        db.set_u64("counter", 7)?;
+        db.add_u64("counter", 1)?;
+        db.table_mut("dedup").set_deduplicated(true)?;
+        db.table_mut("dedup").set("a", &long)?;
+        db.table_mut("dedup").set("b", &long)?;
+        Ok(())
+    };
+    fill(&mut plain)?;
+
+    let orders = [
+        [0, 1, 2],
+        [0, 2, 1],
+        [1, 0, 2],
+        [1, 2, 0],
+        [2, 0, 1],
+        [2, 1, 0],
+    ];
+    for order in orders {
+        let mut options = Database::options().track_versions(true);
+        for stage in order {
+            options = match stage {
+                0 => options.transform(Xor(0x5a)),
+                1 => options.transform(Reverse),
+                _ => options.transform(Sign(42)),
+            };
+        }
+        let mut db = options.in_memory()?;
+        fill(&mut db)?;
+        assert_eq!(db.get::<i32>("a")?, Some(1));
+        assert_eq!(
+            db.get::<Vec<String>>("b")?,
+            Some(vec!["x".to_owned(), "y".to_owned()])
+        );
+        assert_eq!(
+            db.get_tagged("c")?.map(|(tag, _)| tag),
+            Some("f64".to_owned())
+        );
+        assert_eq!(db.get_u64("counter")?, Some(8));
+        assert_eq!(db.version_of("a")?, Some(1));
+        assert_eq!(db.table("dedup").get::<String>("b")?, Some(long.clone()));
+        assert_eq!(db.entries::<f64>()?.len(), 2);
+        assert!(db.remove_if("a", &1)?);
+        db.set("a", &1)?;
+        assert_eq!(db.digest_all()?, plain.digest_all()?);
+        let features: Vec<_> = order.map(|stage| format!("transform:{}", stage + 1)).into();
+        assert_eq!(db.manifest()?.features[1..], features);
+    }
+
+    let duplicated = Database::options().transform(Xor(1)).transform(Xor(2));
+    assert!(duplicated.in_memory().is_err());
+
+    // values written with different chains stay readable as long as their stages are there
+    let path = std::env::temp_dir().join(format!("dbless_transforms_{}", std::process::id()));
+    Database::open(&path)?.set("old", &"old")?;
+    Database::options()
+        .transform(Xor(7))
+        .open(&path)?
+        .set("new", &"new")?;
+    {
+        let mut db = Database::options()
+            .transform(Xor(7))
+            .transform(Sign(1))
+            .open(&path)?;
+        db.set("newer", &"newer")?;
+        for key in ["old", "new", "newer"] {
+            assert_eq!(db.get::<String>(key)?.as_deref(), Some(key));
+        }
+    }
+    {
+        let db = Database::open(&path)?;
+        assert_eq!(db.get::<String>("old")?.as_deref(), Some("old"));
+        assert!(db.get::<String>("new").is_err());
+        drop(db);
+        let db = Database::options()
+            .transform(Xor(7))
+            .transform(Sign(2))
+            .open(&path)?;
+        assert_eq!(db.get::<String>("new")?.as_deref(), Some("new"));
+        assert!(db.get::<String>("newer").is_err());
+    }
+    {
+        // required transforms refuse values that skipped a stage, and counters
+        let mut db = Database::options()
+            .transform(Xor(7))
+            .transform(Sign(1))
+            .require_transforms(true)
+            .open(&path)?;
+        assert_eq!(db.get::<String>("newer")?.as_deref(), Some("newer"));
+        assert!(db.get::<String>("old").is_err());
+        assert!(db.get::<String>("new").is_err());
+        assert!(db.set_u64("counter", 1).is_err());
+        db.set("newest", &"newest")?;
+        assert_eq!(db.get::<String>("newest")?.as_deref(), Some("newest"));
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn large_values() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let big = "x".repeat(1000);
+    db.set("small", &"tiny")?;
+    db.set("big", &big)?;
+    let mut uploads = db.table_mut("uploads");
+    uploads.set_deduplicated(true)?;
+    uploads.set("a", &big)?;
+    uploads.set("b", &big)?;
+    uploads.set("c", &"tiny")?;
+    let mut notes = db.table_mut("notes");
+    notes.set_escape_keys(true)?;
+    notes.set("multi\nline", &big)?;
+
+    // a 1000 byte string is stored with a 3 byte MessagePack header
+    assert_eq!(
+        db.table("uploads").keys_larger_than(100)?,
+        vec![("a".to_owned(), 1003), ("b".to_owned(), 1003)]
+    );
+    assert_eq!(db.table("uploads").keys_larger_than(1003)?, vec![]);
+    assert_eq!(db.table("missing").keys_larger_than(0)?, vec![]);
+    let report = db.large_values_report(100)?;
+    let report: Vec<_> = report
+        .iter()
+        .map(|(t, k, s)| (t.as_str(), k.as_str(), *s))
+        .collect();
+    assert_eq!(
+        report,
+        vec![
+            (crate::DEFAULT_DEFAULT_TABLE, "big", 1003),
+            ("notes", "multi\nline", 1003),
+            ("uploads", "a", 1003),
+            ("uploads", "b", 1003),
+        ]
+    );
+
+    assert_eq!(db.table_mut("uploads").remove_larger_than(100)?, 2);
+    assert_eq!(db.table("uploads").keys()?, vec!["c"]);
+    assert_eq!(db.default_table_mut().remove_larger_than(100)?, 1);
+    assert_eq!(db.keys()?, vec!["small"]);
+    db.table_mut("audit").append_only_chained()?;
+    assert!(db.table_mut("audit").remove_larger_than(100).is_err());
+    Ok(())
+}
+
+#[test]
+fn redb_stats() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("logs").set("a", &1)?;
+    assert!(db.table("logs").redb_stats()?.is_none());
+
+    let mut db = Database::open_temp()?;
+    assert!(db.table("logs").redb_stats()?.is_none());
+    for i in 0..1000 {
+        db.table_mut("logs")
+            .set(&format!("{:04}", i), &"x".repeat(100))?;
+    }
+    let stats = db.table("logs").redb_stats()?.unwrap();
+    assert!(stats.tree_height() > 1);
+    assert!(stats.leaf_pages() > 1);
+    assert!(stats.stored_bytes() >= 1000 * 100);
+    Ok(())
+}
+
+#[test]
+fn optional_values() -> TestResult {
+    test_db_and_tables!(|db| {
+        // a stored None is a stored MessagePack nil, not a missing key
+        db.set("nil", &None::<String>)?;
+        assert!(db.contains_key("nil")?);
+        assert!(db.get::<String>("nil").is_err());
+        assert_eq!(db.get::<Option<String>>("nil")?, Some(None));
+        assert_eq!(db.get::<()>("nil")?, Some(()));
+        assert_eq!(db.get_opt::<String>("nil")?, None);
+
+        db.set("some", &Some("value"))?;
+        assert_eq!(db.get::<String>("some")?.as_deref(), Some("value"));
+        assert_eq!(db.get_opt::<String>("some")?.as_deref(), Some("value"));
+        assert_eq!(db.get_opt::<String>("missing")?, None);
+
+        db.set_opt("opt", &Some(1))?;
+        assert_eq!(db.get::<i32>("opt")?, Some(1));
+        db.set_opt("opt", &None::<i32>)?;
+        assert!(!db.contains_key("opt")?);
+        db.set_opt("opt", &None::<i32>)?;
+        assert_eq!(db.get_opt::<i32>("opt")?, None);
+        db.remove("nil")?;
+        db.remove("some")?;
+    })
+}
+
+#[test]
+fn compact_on_close() -> TestResult {
+    let path = std::env::temp_dir().join(format!("dbless_compact_{}", std::process::id()));
+    let churn = |db: &mut Database| -> TestResult {
+        for i in 0..200 {
+            db.set(&i.to_string(), &"x".repeat(10_000))?;
+        }
+        for i in 0..200 {
+            db.remove(&i.to_string())?;
+        }
+        db.set("kept", &1)?;
+        Ok(())
+    };
+
+    let mut db = Database::open(&path)?;
+    churn(&mut db)?;
+    assert!(db.fragmentation()? > 0.5);
+    db.close_checked()?;
+    let uncompacted = std::fs::metadata(&path)?.len();
+    std::fs::remove_file(&path)?;
+
+    let mut db = Database::options().auto_compact_on_close(0.5).open(&path)?;
+    churn(&mut db)?;
+    let fragmented = db.fragmentation()?;
+    db.close_checked()?;
+    let compacted = std::fs::metadata(&path)?.len();
+    assert!(compacted < uncompacted / 2);
+
+    let mut db = Database::open(&path)?;
+    assert_eq!(db.get::<i32>("kept")?, Some(1));
+    // what's left is the unused space of the few pages still in use
+    assert!(db.fragmentation()? < fragmented);
+    db.compact()?;
+    assert_eq!(db.get::<i32>("kept")?, Some(1));
+    drop(db);
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn keys_all_tables() -> TestResult {
+    let mut db = Database::in_memory()?;
+    assert_eq!(db.keys_all_tables()?, vec![]);
+    db.set("b", &1)?;
+    db.table_mut("users").set("bob", &1)?;
+    db.table_mut("users").set("alice", &1)?;
+    db.table_mut("cache").set_limit(10, EvictionPolicy::Lru)?;
+    db.table_mut("cache").set("x", &1)?;
+    db.table_mut("notes").set_escape_keys(true)?;
+    db.table_mut("notes").set("multi\nline", &1)?;
+    db.alias_table("people", "users")?;
+    db.health_check()?;
+
+    let pair = |table: &str, key: &str| (table.to_owned(), key.to_owned());
+    assert_eq!(
+        db.keys_all_tables()?,
+        vec![
+            pair(crate::DEFAULT_DEFAULT_TABLE, "b"),
+            pair("cache", "x"),
+            pair("notes", "multi\nline"),
+            pair("users", "alice"),
+            pair("users", "bob"),
+        ]
+    );
+    assert_eq!(db.keys_all_tables()?.len(), db.len_all_tables()?);
+    Ok(())
+}
+
+#[test]
+fn entries_by_key_filter() -> TestResult {
+    thread_local! {
+        static DESERIALIZED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(u32);
+
+    impl<'de> Deserialize<'de> for Counted {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            DESERIALIZED.with(|count| count.set(count.get() + 1));
+            u32::deserialize(deserializer).map(Counted)
+        }
+    }
+
+    let mut db = Database::in_memory()?;
+    for i in 0..10u32 {
+        db.set(&format!("item-{}", i), &i)?;
+    }
+    let even = db.entries_by_key_filter::<Counted, _>(|key| {
+        key.rsplit('-').next().unwrap().parse::<u32>().unwrap() % 2 == 0
+    })?;
+    assert_eq!(
+        even.iter().map(|(key, _)| key.as_str()).collect::<Vec<_>>(),
+        ["item-0", "item-2", "item-4", "item-6", "item-8"]
+    );
+    assert_eq!(even[1].1, Counted(2));
+    assert_eq!(DESERIALIZED.with(Cell::get), 5);
+
+    assert!(db.entries_by_key_filter::<u32, _>(|_| false)?.is_empty());
+    assert!(db
+        .table("missing")
+        .entries_by_key_filter::<u32, _>(|_| true)?
+        .is_empty());
+
+    let mut notes = db.table_mut("notes");
+    notes.set_escape_keys(true)?;
+    notes.set("x\ny", &1)?;
+    notes.set("z", &2)?;
+    assert_eq!(
+        notes.entries_by_key_filter::<u32, _>(|key| key.contains('\n'))?,
+        [("x\ny".to_string(), 1)]
+    );
+    Ok(())
+}
+
+#[test]
+fn table_annotations() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("misc2").set("key", &1)?;
+    assert_eq!(db.table_description("misc2")?, None);
+    assert!(db.table_annotations("misc2")?.is_empty());
+
+    db.set_table_description("misc2", "leftover imports")?;
+    db.set_table_annotation("misc2", "owner", "ingest-service")?;
+    db.set_table_annotation("misc2", "ticket", "OPS-1")?;
+    db.remove_table_annotation("misc2", "ticket")?;
+    db.set_table_description("empty", "created by its description")?;
+    assert_eq!(
+        db.table_description("misc2")?.as_deref(),
+        Some("leftover imports")
+    );
+
+    let details: Vec<_> = db
+        .list_tables_detailed()?
+        .into_iter()
+        .filter(|t| t.kind == TableKind::User)
+        .collect();
+    assert_eq!(details.len(), 2);
+    assert_eq!(details[0].name, "empty");
+    assert_eq!(details[0].entries, Some(0));
+    assert_eq!(details[1].name, "misc2");
+    assert_eq!(details[1].entries, Some(1));
+    assert_eq!(details[1].description.as_deref(), Some("leftover imports"));
+    assert_eq!(
+        details[1].annotations.get("owner").map(String::as_str),
+        Some("ingest-service")
+    );
+    assert_eq!(details[1].annotations.len(), 1);
+
+    let manifest = db.manifest()?;
+    assert_eq!(
+        manifest.tables["misc2"].description.as_deref(),
+        Some("leftover imports")
+    );
+    assert_eq!(manifest.tables["misc2"].annotations.len(), 1);
+
+    db.alias_table("old_misc", "misc2")?;
+    assert_eq!(
+        db.table_description("old_misc")?.as_deref(),
+        Some("leftover imports")
+    );
+    db.delete_table("old_misc")?;
+
+    db.delete_table("misc2")?;
+    db.table_mut("misc2").set("key", &1)?;
+    assert_eq!(db.table_description("misc2")?, None);
+    assert!(db.table_annotations("misc2")?.is_empty());
+
+    db.remove_table_description("empty")?;
+    let empty = db
+        .list_tables_detailed()?
+        .into_iter()
+        .find(|t| t.name == "empty");
+    assert_eq!(empty.unwrap().description, None);
+    assert!(db
+        .set_table_description("#_#_limits_dbless_table_#_#", "no")
+        .is_err());
+    db.set_table_description(Database::DEFAULT_TABLE_NAME, "the default table")?;
+    assert!(!db.list_tables()?.iter().any(|t| t.contains("annotations")));
+    Ok(())
+}
+
+#[cfg(feature = "recovery")]
+#[test]
+fn salvage() -> TestResult {
+    use crate::{salvage, SalvageStrategy};
+
+    let path = std::env::temp_dir().join(format!("dbless_salvage_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let mut db = Database::open(&path)?;
+    db.set("motd", &"hello")?;
+    let mut users = db.table_mut("users");
+    for i in 0..2000u32 {
+        users.set(
+            &format!("user-{}", i),
+            &format!("payload {} {}", i, "x".repeat(200)),
+        )?;
+    }
+    let mut docs = db.table_mut("docs");
+    docs.set_deduplicated(true)?;
+    docs.set("a", &"same")?;
+    docs.set("b", &"same")?;
+    drop(db);
+
+    let mut rescued = Database::in_memory()?;
+    let report = salvage(&path, &mut rescued)?;
+    assert_eq!(report.strategy, SalvageStrategy::Normal);
+    assert!(report.is_complete(), "{:?}", report);
+    assert_eq!(report.salvaged(), 2003);
+    assert_eq!(rescued.get::<String>("motd")?.as_deref(), Some("hello"));
+    assert_eq!(rescued.table("users").len()?, 2000);
+    assert_eq!(
+        rescued.table("docs").get::<String>("b")?.as_deref(),
+        Some("same")
+    );
+
+    let len = std::fs::metadata(&path)?.len();
+    let file = std::fs::OpenOptions::new().write(true).open(&path)?;
+    file.set_len(len / 2)?;
+    drop(file);
+    let mut rescued = Database::in_memory()?;
+    let error = salvage(&path, &mut rescued).unwrap_err();
+    assert!(error.to_string().contains("truncated"), "{}", error);
+    assert_eq!(std::fs::metadata(&path)?.len(), len / 2);
+    std::fs::remove_file(&path)?;
+
+    // never closed, so the file is left waiting for a repair
+    let mut db = Database::open(&path)?;
+    db.table_mut("users").set("alice", &"Alice")?;
+    std::mem::forget(db);
+    let mut rescued = Database::in_memory()?;
+    let report = salvage(&path, &mut rescued)?;
+    assert_eq!(report.strategy, SalvageStrategy::Repaired);
+    assert!(report.is_complete(), "{:?}", report);
+    assert_eq!(
+        rescued.table("users").get::<String>("alice")?.as_deref(),
+        Some("Alice")
+    );
+    std::fs::remove_file(&path)?;
+
+    Ok(())
+}
+
+#[test]
+fn prewarm() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("hot").set("a", &1)?;
+    db.table("hot").prewarm()?;
+
+    let mut db = Database::open_temp()?;
+    db.table("missing").prewarm()?;
+    let mut docs = db.table_mut("docs");
+    docs.set_deduplicated(true)?;
+    for i in 0..100 {
+        docs.set(&format!("{:03}", i), &"x".repeat(100))?;
+    }
+    db.table("docs").prewarm()?;
+    assert_eq!(db.table("docs").len()?, 100);
+    Ok(())
+}
+
+#[test]
+fn split_default_table_by_prefix() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set("cfg:theme", &"dark")?;
+    db.set("cfg:lang", &"en")?;
+    db.set("user:alice", &1)?;
+    db.set("user:bob", &2)?;
+    db.set("cache:x", &3)?;
+    db.set("motd", &"hello")?;
+
+    let report =
+        db.split_default_table_by_prefix(&[("cfg:", "config"), ("user:", "users")], true)?;
+    assert_eq!(report.moved["config"], 2);
+    assert_eq!(report.moved["users"], 2);
+    assert_eq!(report.unmatched, 2);
+    assert_eq!(db.keys()?, ["cache:x", "motd"]);
+    assert_eq!(
+        db.table("config").get::<String>("theme")?.as_deref(),
+        Some("dark")
+    );
+    assert_eq!(db.table("users").keys()?, ["alice", "bob"]);
+
+    let report = db.split_default_table_by_prefix(&[("cache:", "cache")], false)?;
+    assert_eq!(report.moved["cache"], 1);
+    assert_eq!(db.table("cache").keys()?, ["cache:x"]);
+    assert!(db
+        .split_default_table_by_prefix(&[("m", "#_#_limits_dbless_table_#_#")], false)
+        .is_err());
+
+    assert_eq!(db.flatten_table_into_default("config", "cfg:")?, 2);
+    assert_eq!(db.get::<String>("cfg:lang")?.as_deref(), Some("en"));
+    assert!(!db.contains_table("config")?);
+    assert_eq!(db.flatten_table_into_default("missing", "x:")?, 0);
+    assert!(db
+        .flatten_table_into_default(Database::DEFAULT_TABLE_NAME, "x:")
+        .is_err());
+    Ok(())
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn export_table_csv() -> TestResult {
+    #[derive(Serialize, Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+        email: Option<String>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct Nested {
+        tags: Vec<String>,
+    }
+
+    let mut db = Database::in_memory()?;
+    let mut users = db.table_mut("users");
+    users.set(
+        "alice",
+        &User {
+            name: "Alice, \"Al\"".into(),
+            age: 30,
+            email: None,
+        },
+    )?;
+    users.set(
+        "bob",
+        &User {
+            name: "Bob".into(),
+            age: 25,
+            email: Some("bob@example.com".into()),
+        },
+    )?;
+    let mut csv = vec![];
+    db.export_table_csv::<User, _>("users", &mut csv)?;
+    assert_eq!(
+        String::from_utf8(csv)?,
+        "key,name,age,email\nalice,\"Alice, \"\"Al\"\"\",30,\nbob,Bob,25,bob@example.com\n"
+    );
+
+    db.table_mut("scores").set("a", &1.5)?;
+    let mut csv = vec![];
+    db.export_table_csv::<f64, _>("scores", &mut csv)?;
+    assert_eq!(String::from_utf8(csv)?, "key,value\na,1.5\n");
+
+    let mut csv = vec![];
+    db.export_table_csv::<User, _>("missing", &mut csv)?;
+    assert_eq!(String::from_utf8(csv)?, "key\n");
+
+    db.table_mut("nested").set(
+        "a",
+        &Nested {
+            tags: vec!["x".into()],
+        },
+    )?;
+    let error = db
+        .export_table_csv::<Nested, _>("nested", vec![])
+        .unwrap_err();
+    assert!(format!("{:#}", error).contains("serde(flatten)"));
+    assert!(db.export_table_csv::<User, _>("scores", vec![]).is_err());
+    Ok(())
+}
+
+#[test]
+fn untrusted_values() -> TestResult {
+    use crate::fuzzing::{deserialize, inspect};
+    use std::collections::BTreeMap;
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct Tree(Vec<Tree>);
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Record {
+        name: String,
+        scores: Vec<f32>,
+        extra: BTreeMap<String, i64>,
+        next: Option<Box<Record>>,
+    }
+
+    // a valid value nested far deeper than the stack can recurse
+    let mut deep = vec![0x91; 100_000];
+    deep.push(0xc0);
+    let mut db = Database::in_memory()?;
+    db.table_mut("hostile").set_msgpack("deep", &deep)?;
+    let error = db.table("hostile").get::<Tree>("deep").unwrap_err();
+    assert!(format!("{:#}", error).contains("limit of 128"));
+    assert!(db
+        .stream_table_to("hostile", vec![], ExportFormat::Json)
+        .is_err());
+    assert!(db.table("hostile").get_raw("deep")?.is_some());
+    inspect(&deep, "0.0.0");
+
+    // collections claiming far more elements than there are bytes
+    for bytes in [
+        &[0xdd, 0xff, 0xff, 0xff, 0xff, 0xc0][..],
+        &[0xdf, 0xff, 0xff, 0xff, 0xff, 0xa1, b'a', 0xc0],
+        &[0x91, 0xdc, 0xff, 0xff, 0x01],
+        &[0xdb, 0xff, 0xff, 0xff, 0xff, b'a'],
+    ] {
+        assert!(deserialize::<Vec<Tree>>(bytes, false).is_err());
+        assert!(deserialize::<BTreeMap<String, ()>>(bytes, true).is_err());
+        assert!(db.table_mut("hostile").set_msgpack("huge", bytes).is_err());
+        inspect(bytes, "a.0");
+    }
+
+    let mut db = Database::options()
+        .max_depth(1)
+        .max_collection_len(2)
+        .in_memory()?;
+    db.set("flat", &vec![1, 2])?;
+    db.set("long", &vec![1, 2, 3])?;
+    db.set("nested", &vec![vec![1]])?;
+    assert_eq!(db.get::<Vec<i32>>("flat")?, Some(vec![1, 2]));
+    assert!(db.get::<Vec<i32>>("long").is_err());
+    assert!(db.get::<Vec<Vec<i32>>>("nested").is_err());
+
+    // random and mutated bytes must fail cleanly, never panic
+    let record = crate::serde::serialize(&Record {
+        name: "alice".into(),
+        scores: vec![1.5, 2.0],
+        extra: BTreeMap::from([("a".into(), -1)]),
+        next: Some(Box::new(Record {
+            name: "bob".into(),
+            scores: vec![],
+            extra: BTreeMap::new(),
+            next: None,
+        })),
+    })?;
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        state
+    };
+    for i in 0..20_000 {
+        let bytes = match i % 2 {
+            0 => (0..next() % 48).map(|_| next() as u8).collect::<Vec<_>>(),
+            _ => {
+                let mut bytes = record.clone();
+                for _ in 0..1 + next() % 4 {
+                    let at = next() as usize % bytes.len();
+                    bytes[at] = next() as u8;
+                }
+                bytes.truncate(next() as usize % (bytes.len() + 1));
+                bytes
+            }
+        };
+        for strict in [false, true] {
+            let _ = deserialize::<Record>(&bytes, strict);
+            let _ = deserialize::<Tree>(&bytes, strict);
+            let _ = deserialize::<(u8, String, Vec<u8>)>(&bytes, strict);
+        }
+        inspect(&bytes, ["name", "scores.1", "next.extra.a"][i % 3]);
+    }
+    Ok(())
+}
+
+#[test]
+fn get_many() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set("config", &"dark")?;
+    db.set("theme", &"blue")?;
+    db.table_mut("users").set("alice", &30)?;
+
+    assert_eq!(
+        db.get_many::<String>(&["config", "session", "theme", "config"])?,
+        vec![
+            Some("dark".to_string()),
+            None,
+            Some("blue".to_string()),
+            Some("dark".to_string())
+        ]
+    );
+    assert_eq!(db.get_many::<String>(&[])?, vec![]);
+    assert_eq!(
+        db.table("users").get_many::<u32>(&["bob", "alice"])?,
+        vec![None, Some(30)]
+    );
+    assert_eq!(
+        db.table_mut("missing").get_many::<u32>(&["a", "b"])?,
+        vec![None, None]
+    );
+    assert!(db.get_many::<u32>(&["config"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn insert_many() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let keys = (0..10_000).map(|i| format!("key{}", i)).collect::<Vec<_>>();
+    let values = (0..10_000).collect::<Vec<usize>>();
+    let entries = keys
+        .iter()
+        .map(String::as_str)
+        .zip(&values)
+        .collect::<Vec<_>>();
+    db.table_mut("seed").insert_many(&entries)?;
+    assert_eq!(db.table("seed").len()?, 10_000);
+    assert_eq!(db.table("seed").get::<usize>("key1234")?, Some(1234));
+
+    // a failing value aborts the batch even with the skip policy
+    let mut db = Database::options()
+        .serialize_error_policy(SerializeErrorPolicy::Skip)
+        .in_memory()?;
+    let (one, zero, two) = (FailsOnZero(1), FailsOnZero(0), FailsOnZero(2));
+    let error = db
+        .insert_many(&[("a", &one), ("b", &zero), ("c", &two)])
+        .unwrap_err();
+    assert!(format!("{:#}", error).contains("zero is not allowed"));
+    assert!(db.is_empty()?);
+    assert_eq!(db.insert_batch(&[("a", &one), ("b", &zero)])?, vec!["b"]);
+    Ok(())
+}
+
+#[test]
+fn cache_table_lengths() -> TestResult {
+    for cache in [false, true] {
+        let mut db = Database::options().cache_table_lengths(cache).in_memory()?;
+        assert_eq!(db.table("users").len()?, 0);
+        assert!(db.table("users").is_empty()?);
+
+        // every write drops the cached lengths
+        db.table_mut("users").set("alice", &1)?;
+        db.table_mut("users").set("bob", &2)?;
+        assert_eq!(db.table("users").len()?, 2);
+        assert_eq!(db.table("users").len()?, 2);
+        db.table_mut("users").set("bob", &3)?;
+        assert_eq!(db.table("users").len()?, 2);
+        db.table_mut("users").remove("alice")?;
+        assert_eq!(db.table("users").len()?, 1);
+
+        // writes to another table drop them too
+        db.set("key", &1)?;
+        assert_eq!(db.len()?, 1);
+        db.table_mut("users")
+            .insert_many(&[("carol", &4), ("dave", &5)])?;
+        assert_eq!(db.table("users").len()?, 3);
+        assert!(!db.table("users").is_empty()?);
+
+        db.alias_table("people", "users")?;
+        assert_eq!(db.table("people").len()?, 3);
+        db.table_mut("people").clear()?;
+        assert_eq!(db.table("users").len()?, 0);
+        assert!(db.table("people").is_empty()?);
+
+        db.table_mut("users").set("erin", &6)?;
+        assert_eq!(db.table("users").len()?, 1);
+        db.delete_table("people")?;
+        db.delete_table("users")?;
+        assert_eq!(db.table("users").len()?, 0);
+
+        db.drop_caches();
+        assert_eq!(db.len()?, 1);
+    }
+    Ok(())
+}
+
+#[test]
+fn set_many() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("seed").set("existing", &FailsOnZero(7))?;
+    let values = (1..=10_000).map(FailsOnZero).collect::<Vec<_>>();
+    let keys = values
+        .iter()
+        .map(|value| format!("key{}", value.0))
+        .collect::<Vec<_>>();
+    let entries = keys
+        .iter()
+        .map(String::as_str)
+        .zip(&values)
+        .collect::<Vec<_>>();
+    db.table_mut("seed").set_many(&entries)?;
+    assert_eq!(db.table("seed").len()?, 10_001);
+    assert_eq!(db.table("seed").get::<i32>("key10000")?, Some(10_000));
+
+    // a failure in the middle of the batch leaves the table untouched
+    let zero = FailsOnZero(0);
+    let keys = keys
+        .iter()
+        .map(|key| format!("new_{}", key))
+        .collect::<Vec<_>>();
+    let mut entries = keys
+        .iter()
+        .map(String::as_str)
+        .zip(&values)
+        .collect::<Vec<_>>();
+    entries[5_000].1 = &zero;
+    let before = db.table("seed").entries::<i32>()?;
+    assert!(db.table_mut("seed").set_many(&entries).is_err());
+    assert_eq!(db.table("seed").entries::<i32>()?, before);
+    Ok(())
+}
+
+#[test]
+fn update() -> TestResult {
+    let mut db = Database::options().track_versions(true).in_memory()?;
+    for i in 1..=100u64 {
+        assert_eq!(db.update("count", |v: Option<u64>| v.unwrap_or(0) + 1)?, i);
+    }
+    assert_eq!(db.get::<u64>("count")?, Some(100));
+    assert_eq!(db.version_of("count")?, Some(100));
+
+    let mut calls = 0;
+    let tags = db
+        .table_mut("posts")
+        .update("tags", |tags: Option<Vec<String>>| {
+            calls += 1;
+            assert_eq!(tags, None);
+            vec!["rust".to_string()]
+        })?;
+    assert_eq!(calls, 1);
+    assert_eq!(tags, vec!["rust"]);
+
+    // a value of another type fails without calling `f` or writing anything
+    db.set("name", &"alice")?;
+    assert!(db.update("name", |_: Option<u64>| unreachable!()).is_err());
+    assert_eq!(db.get::<String>("name")?, Some("alice".to_string()));
+    Ok(())
+}
+
+#[test]
+fn dynamic_values() -> TestResult {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Profile {
+        name: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct User {
+        age: i32,
+        profile: Profile,
+    }
+
+    let mut db = Database::in_memory()?;
+    db.table_mut("users").set(
+        "alice",
+        &User {
+            age: -1,
+            profile: Profile {
+                name: "alice".into(),
+                tags: vec!["a/b".into(), "c".into()],
+            },
+        },
+    )?;
+    let mut user = db.table("users").get_dynamic("alice")?.unwrap();
+    assert_eq!(user.pointer("/age"), Some(&Value::Int(-1)));
+    assert_eq!(
+        user.pointer("/profile/name").and_then(Value::as_str),
+        Some("alice")
+    );
+    assert_eq!(
+        user.pointer("/profile/tags/0").and_then(Value::as_str),
+        Some("a/b")
+    );
+    assert_eq!(user.pointer("/profile/tags/2"), None);
+    assert_eq!(user.pointer("/profile/missing"), None);
+    assert_eq!(user.pointer("profile"), None);
+    assert_eq!(user.pointer(""), Some(&user));
+
+    *user.pointer_mut("/profile/name").unwrap() = "Alice".into();
+    *user.pointer_mut("/age").unwrap() = 30.into();
+    db.table_mut("users").set_dynamic("alice", &user)?;
+    assert_eq!(
+        db.table("users").get::<User>("alice")?,
+        Some(User {
+            age: 30,
+            profile: Profile {
+                name: "Alice".into(),
+                tags: vec!["a/b".into(), "c".into()],
+            },
+        })
+    );
+    assert_eq!(db.table("users").get::<Value>("alice")?, Some(user));
+
+    let escaped = Value::Map(vec![("a/b~c".into(), Value::Nil)]);
+    assert_eq!(escaped.pointer("/a~1b~0c"), Some(&Value::Nil));
+
+    db.set_u64("visits", 3)?;
+    assert_eq!(
+        db.default_table().get_dynamic("visits")?,
+        Some(Value::UInt(3))
+    );
+    assert_eq!(db.default_table().get_dynamic("missing")?, None);
+    assert!(db
+        .default_table_mut()
+        .set_dynamic("ext", &Value::Array(vec![Value::Ext(1, vec![])]))
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn prefix_scans() -> TestResult {
+    thread_local! {
+        static DESERIALIZED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(u32);
+
+    impl<'de> Deserialize<'de> for Counted {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            DESERIALIZED.with(|count| count.set(count.get() + 1));
+            u32::deserialize(deserializer).map(Counted)
+        }
+    }
+
+    let mut db = Database::in_memory()?;
+    let keys = (0..3_000u32)
+        .map(|i| format!("{}:{}", ["post", "user", "user:1"][i as usize % 3], i))
+        .collect::<Vec<_>>();
+    let values = (0..3_000u32).collect::<Vec<_>>();
+    let entries = keys
+        .iter()
+        .map(String::as_str)
+        .zip(&values)
+        .collect::<Vec<_>>();
+    db.insert_many(&entries)?;
+
+    let users = db.keys_with_prefix("user:")?;
+    assert_eq!(users.len(), 2_000);
+    assert!(users.iter().all(|key| key.starts_with("user:")));
+    assert!(users.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(db.keys_with_prefix("user:1:")?.len(), 1_000);
+    assert_eq!(db.keys_with_prefix("")?.len(), 3_000);
+    assert!(db.keys_with_prefix("users")?.is_empty());
+
+    let posts = db.entries_with_prefix::<Counted>("post:")?;
+    assert_eq!(posts.len(), 1_000);
+    assert_eq!(posts[0], ("post:0".to_string(), Counted(0)));
+    assert_eq!(DESERIALIZED.with(Cell::get), 1_000);
+    assert!(db.entries_with_prefix::<String>("post:")?.is_empty());
+    assert!(db
+        .table("missing")
+        .entries_with_prefix::<u32>("a")?
+        .is_empty());
+
+    let mut notes = db.table_mut("notes");
+    notes.set_escape_keys(true)?;
+    notes.set("a%\nb", &1)?;
+    notes.set("a%c", &2)?;
+    notes.set("a&", &3)?;
+    assert_eq!(notes.keys_with_prefix("a%")?, ["a%\nb", "a%c"]);
+    assert_eq!(
+        notes.entries_with_prefix::<u32>("a%\n")?,
+        [("a%\nb".to_string(), 1)]
+    );
+    Ok(())
+}
+
+#[test]
+fn in_memory_seeded() -> TestResult {
+    let keys = (0..50).map(|i| format!("key{:02}", i)).collect::<Vec<_>>();
+    let values = (0..50).collect::<Vec<u32>>();
+    let entries = keys
+        .iter()
+        .map(String::as_str)
+        .zip(&values)
+        .collect::<Vec<_>>();
+    let open = |seed| -> Result<Database> {
+        let mut db = Database::in_memory_seeded(seed)?;
+        db.insert_many(&entries)?;
+        Ok(db)
+    };
+
+    let db = open(7)?;
+    let shuffled = db.keys()?;
+    assert_ne!(shuffled, keys);
+    let mut sorted = shuffled.clone();
+    sorted.sort();
+    assert_eq!(sorted, keys);
+    // reproducible for a seed, different across seeds
+    assert_eq!(open(7)?.keys()?, shuffled);
+    assert_ne!(open(8)?.keys()?, shuffled);
+
+    let listed = db.entries::<u32>()?;
+    assert_ne!(listed.windows(2).filter(|w| w[0] < w[1]).count(), 49);
+    assert!(listed
+        .iter()
+        .all(|(key, value)| key[3..].parse() == Ok(*value)));
+    assert_eq!(db.values::<u32>()?.len(), 50);
+    assert_eq!(db.keys_with_prefix("key1")?.len(), 10);
+    assert_eq!(db.keys_after(None, 3)?, ["key00", "key01", "key02"]);
+
+    // the default stays sorted
+    let mut db = Database::in_memory()?;
+    db.insert_many(&entries)?;
+    assert_eq!(db.keys()?, keys);
+    Ok(())
+}
+
+#[test]
+fn remove_many() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set_many(&[("a", &1), ("b", &2), ("c", &3), ("d", &4)])?;
+    db.remove_many(&["a", "missing", "c", "a"])?;
+    assert_eq!(db.len()?, 2);
+    assert_eq!(db.keys()?, ["b", "d"]);
+    db.delete_many(&[])?;
+    db.delete_many(&["gone", "d"])?;
+    assert_eq!(db.len()?, 1);
+
+    let mut sessions = db.table_mut("sessions");
+    sessions.set_escape_keys(true)?;
+    sessions.set_many(&[("x\ny", &1), ("z", &2)])?;
+    sessions.remove_many(&["x\ny"])?;
+    assert_eq!(sessions.keys()?, ["z"]);
+    db.table_mut("missing").remove_many(&["a"])?;
+    Ok(())
+}
+
+#[test]
+fn get_all() -> TestResult {
+    use crate::Lookup;
+
+    let mut db = Database::in_memory()?;
+    let mut users = db.table_mut("users");
+    users.set("alice", &"Alice")?;
+    users.set("bob", &42)?;
+    let mut deep = vec![0x91; 200];
+    deep.push(0xc0);
+    users.set_msgpack("deep", &deep)?;
+
+    let results = db
+        .table("users")
+        .get_all::<String>(&["alice", "carol", "bob", "deep"])?;
+    assert_eq!(
+        results.iter().map(|r| r.key.as_str()).collect::<Vec<_>>(),
+        ["alice", "carol", "bob", "deep"]
+    );
+    let mut results = results.into_iter().map(|r| r.value);
+    assert!(matches!(results.next(), Some(Lookup::Found(name)) if name == "Alice"));
+    assert!(matches!(results.next(), Some(Lookup::Missing)));
+    assert!(matches!(results.next(), Some(Lookup::Failed(_))));
+    let Some(Lookup::Failed(e)) = results.next() else {
+        panic!("expected the nested value to fail");
+    };
+    assert!(format!("{:#}", e).contains("limit of 128"));
+
+    // get_many fails as a whole on the same keys
+    assert!(db
+        .table("users")
+        .get_many::<String>(&["alice", "bob"])
+        .is_err());
+    let missing = db.table("missing").get_all::<u32>(&["a"])?;
+    assert_eq!(missing.len(), 1);
+    assert!(matches!(missing[0].value, Lookup::Missing));
+    Ok(())
+}
+
+#[test]
+fn iter_entries() -> TestResult {
+    thread_local! {
+        static DESERIALIZED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Counted(u32);
+
+    impl<'de> Deserialize<'de> for Counted {
+        fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            DESERIALIZED.with(|count| count.set(count.get() + 1));
+            u32::deserialize(deserializer).map(Counted)
+        }
+    }
+
+    let mut db = Database::in_memory()?;
+    let keys = (0..1_000).map(|i| format!("{:04}", i)).collect::<Vec<_>>();
+    let values = (0..1_000).collect::<Vec<u32>>();
+    let entries = keys
+        .iter()
+        .map(String::as_str)
+        .zip(&values)
+        .collect::<Vec<_>>();
+    db.insert_many(&entries)?;
+
+    // entries are only read as the iterator is advanced
+    let first = db
+        .iter_entries::<Counted>()?
+        .take(2)
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        first,
+        [
+            ("0000".to_string(), Counted(0)),
+            ("0001".to_string(), Counted(1))
+        ]
+    );
+    assert_eq!(DESERIALIZED.with(Cell::get), 2);
+    assert_eq!(db.iter_entries::<u32>()?.count(), 1_000);
+
+    db.set("9999", &"not a number")?;
+    let results = db.iter_entries::<u32>()?.collect::<Vec<_>>();
+    assert_eq!(results.len(), 1_001);
+    assert!(results[1_000].is_err());
+    assert_eq!(
+        db.iter_entries::<u32>()?.filter_map(Result::ok).count(),
+        1_000
+    );
+
+    assert_eq!(db.table("missing").iter_entries::<u32>()?.count(), 0);
+    let mut notes = db.table_mut("notes");
+    notes.set_escape_keys(true)?;
+    notes.set("a\nb", &1)?;
+    assert_eq!(
+        notes.iter_entries::<u32>()?.collect::<Result<Vec<_>>>()?,
+        [("a\nb".to_string(), 1)]
+    );
+
+    let mut pages = db.table_mut("pages");
+    pages.set_deduplicated(true)?;
+    let body = "<p>".repeat(100);
+    pages.set_many(&[("a", &body), ("b", &body)])?;
+    let listed = pages
+        .iter_entries::<String>()?
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        listed,
+        [("a".to_string(), body.clone()), ("b".to_string(), body)]
+    );
+    Ok(())
+}
+
+#[test]
+fn write_batch() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.set_many(&[("a", &1), ("b", &2)])?;
+
+    let mut batch = WriteBatch::new();
+    batch.insert("c", &3)?;
+    batch.remove("a");
+    batch.insert("d", &4)?;
+    batch.remove("d");
+    batch.remove("missing");
+    assert!(batch.insert("bad", &FailsOnZero(0)).is_err());
+    assert_eq!(batch.len(), 5);
+    db.apply(batch)?;
+    assert_eq!(db.entries::<i32>()?, [("b".into(), 2), ("c".into(), 3)]);
+
+    let mut batch = WriteBatch::new();
+    batch.insert("x", &1)?;
+    batch.clear_table();
+    batch.insert("y", &2)?;
+    db.table_mut("other").set("z", &0)?;
+    db.table_mut("other").apply(batch)?;
+    assert_eq!(db.table("other").entries::<i32>()?, [("y".into(), 2)]);
+    db.table_mut("other").apply(WriteBatch::new())?;
+
+    // nothing is applied if any operation fails
+    let mut chained = db.table_mut("ledger");
+    chained.append_only_chained()?;
+    chained.set("first", &1)?;
+    let mut batch = WriteBatch::new();
+    batch.insert("second", &2)?;
+    batch.remove("first");
+    assert!(chained.apply(batch).is_err());
+    assert_eq!(chained.keys()?, ["first"]);
+    Ok(())
+}
+
+#[test]
+fn delete_through_shared_reference() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("users").set("alice", &1)?;
+    db.table_mut("cache").set_many(&[("a", &1), ("b", &2)])?;
+    for i in 0..20 {
+        let name = format!("stale.{}", i);
+        let mut table = db.table_mut(&name);
+        table.set_deduplicated(true)?;
+        table.set("key", &i)?;
+    }
+
+    let db = &db;
+    std::thread::scope(|s| -> TestResult {
+        let maintenance = s.spawn(|| -> crate::Result<usize> {
+            db.clear_table("cache")?;
+            db.delete_tables_with_prefix("stale.")
+        });
+        while !maintenance.is_finished() {
+            assert_eq!(db.table("users").get::<i32>("alice")?, Some(1));
+        }
+        assert_eq!(maintenance.join().unwrap()?, 20);
+        Ok(())
+    })?;
+
+    assert_eq!(db.list_tables()?, ["users"]);
+    assert!(db.table("cache").is_empty()?);
+    // the settings of deleted tables are forgotten
+    assert!(!db.table("stale.0").is_deduplicated());
+
+    db.delete_all_tables()?;
+    assert!(db.list_tables()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn transaction() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("counters").set("orders", &1u64)?;
+
+    // reads see the writes made earlier in the transaction
+    let order = db.transaction(|tx| {
+        let mut counters = tx.table_mut("counters");
+        let order = counters.get::<u64>("orders")?.unwrap_or(0) + 1;
+        counters.set("orders", &order)?;
+        assert_eq!(counters.get::<u64>("orders")?, Some(order));
+        tx.table_mut("log")
+            .set(&format!("order-{}", order), &"created")?;
+        assert_eq!(
+            tx.table_mut("log").get::<String>("order-2")?.as_deref(),
+            Some("created")
+        );
+        assert_eq!(tx.table_mut("missing").get::<u64>("key")?, None);
+        Ok(order)
+    })?;
+    assert_eq!(order, 2);
+    assert_eq!(db.table("counters").get::<u64>("orders")?, Some(2));
+    assert_eq!(db.table("log").keys()?, ["order-2"]);
+    assert!(!db.list_tables()?.contains(&"missing".to_string()));
+
+    // an error rolls back every table
+    let result = db.transaction(|tx| -> crate::Result<()> {
+        tx.table_mut("counters").set("orders", &3u64)?;
+        tx.table_mut("log").remove("order-2")?;
+        tx.table_mut("new").set("key", &1)?;
+        Err(anyhow::anyhow!("out of stock").into())
+    });
+    assert_eq!(result.unwrap_err().to_string(), "out of stock");
+    assert_eq!(db.table("counters").get::<u64>("orders")?, Some(2));
+    assert_eq!(db.table("log").keys()?, ["order-2"]);
+    assert_eq!(db.list_tables()?, ["counters", "log"]);
+
+    // so does a write the table refuses
+    db.table_mut("ledger").append_only_chained()?;
+    db.table_mut("ledger").set("first", &1)?;
+    let result = db.transaction(|tx| {
+        tx.table_mut("counters").set("orders", &3u64)?;
+        tx.table_mut("ledger").remove("first")
+    });
+    assert!(result.is_err());
+    assert_eq!(db.table("counters").get::<u64>("orders")?, Some(2));
+    assert_eq!(db.table("ledger").keys()?, ["first"]);
+
+    // even if the closure ignores the error
+    let result = db.transaction(|tx| {
+        tx.table_mut("counters").set("orders", &3u64)?;
+        tx.table_mut("ledger").remove("first").ok();
+        tx.table_mut("ledger").set("first", &2).ok();
+        Ok(())
+    });
+    assert!(result.is_err());
+    assert_eq!(db.table("counters").get::<u64>("orders")?, Some(2));
+    assert_eq!(db.table("ledger").get::<i32>("first")?, Some(1));
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn json_format() -> TestResult {
+    use crate::SerializationFormat;
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+        tags: Vec<String>,
+    }
+    let alice = User {
+        name: "alice".into(),
+        age: 30,
+        tags: vec!["admin".into()],
+    };
+
+    let path = std::env::temp_dir().join(format!("dbless_json_{}", std::process::id()));
+    {
+        let mut db = Database::options()
+            .format(SerializationFormat::Json)
+            .open(&path)?;
+        db.table_mut("users").set("alice", &alice)?;
+        assert_eq!(db.table("users").get::<User>("alice")?, Some(alice.clone()));
+        assert!(db.table("users").get::<u32>("alice").is_err());
+        assert_eq!(db.manifest()?.codec, "json");
+
+        // the raw APIs, field filters and exports still speak MessagePack
+        let raw = db.table("users").get_raw("alice")?.unwrap();
+        assert_eq!(
+            db.table("users")
+                .get_dynamic("alice")?
+                .unwrap()
+                .pointer("/tags/0"),
+            Some(&Value::from("admin"))
+        );
+        let mut users = db.table_mut("users");
+        users.set_msgpack("copy", &raw)?;
+        users.set_dynamic("bob", &Value::Map(vec![("age".into(), 25.into())]))?;
+        let mut batch = WriteBatch::new();
+        batch.insert("carol", &("carol", 41))?;
+        users.apply(batch)?;
+        assert_eq!(
+            users.remove_where_field("age", FieldPredicate::LessThan(26.into()))?,
+            1
+        );
+        assert_eq!(users.keys()?, ["alice", "carol", "copy"]);
+        let mut json = vec![];
+        db.stream_table_to("users", &mut json, ExportFormat::Json)?;
+        assert!(String::from_utf8(json)?.contains(r#""carol":["carol",41]"#));
+    }
+    {
+        let raw = redb::Database::create(&path)?;
+        let tnx = raw.begin_read()?;
+        let users = tnx.open_table(redb::TableDefinition::<&str, &[u8]>::new("users"))?;
+        let stored = users.get("alice")?.unwrap();
+        assert_eq!(
+            std::str::from_utf8(stored.value())?,
+            r#"{"name":"alice","age":30,"tags":["admin"]}"#
+        );
+        let stored = users.get("copy")?.unwrap();
+        assert_eq!(
+            std::str::from_utf8(stored.value())?,
+            r#"{"name":"alice","age":30,"tags":["admin"]}"#
+        );
+    }
+    {
+        // the format is saved in the database, and used when none is set
+        let db = Database::open(&path)?;
+        assert_eq!(db.table("users").get::<User>("copy")?, Some(alice));
+        assert!(db.table("users").get::<u32>("copy").is_err());
+        assert_eq!(db.manifest()?.codec, "json");
+        drop(db);
+        let e = Database::options()
+            .format(SerializationFormat::MessagePack)
+            .open(&path)
+            .err()
+            .unwrap();
+        assert_eq!(
+            e.to_string(),
+            "the database stores its values as json, it can't be opened as messagepack"
+        );
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+fn typed_tables() -> TestResult {
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+
+    let mut db = Database::in_memory()?;
+    let mut users = db.typed_table_mut::<User>("users");
+    users.set(
+        "alice",
+        &User {
+            name: "Alice".into(),
+            age: 30,
+        },
+    )?;
+    users.insert_many(&[(
+        "bob",
+        &User {
+            name: "Bob".into(),
+            age: 25,
+        },
+    )])?;
+    let older = users.update("bob", |bob| {
+        let bob = bob.unwrap();
+        User {
+            age: bob.age + 1,
+            ..bob
+        }
+    })?;
+    assert_eq!(older.age, 26);
+    users.remove("missing")?;
+
+    let users = db.typed_table::<User>("users");
+    let alice = users.get("alice")?.unwrap();
+    assert_eq!(alice.name, "Alice");
+    assert_eq!(users.values()?.iter().map(|user| user.age).sum::<u32>(), 56);
+    assert_eq!(users.entries()?[1].1.name, "Bob");
+    assert_eq!(users.iter_entries()?.count(), 2);
+    assert!(users.get("carol")?.is_none());
+    assert_eq!(users.len()?, 2);
+    // the value type isn't checked, reading other values fails like `get::<User>()` would
+    db.table_mut("users").set("carol", &"not a user")?;
+    assert!(db.typed_table::<User>("users").get("carol").is_err());
+    Ok(())
+}
+
+#[test]
+fn read_transaction() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("sessions")
+        .set_many(&[("a", &1), ("b", &2), ("c", &3)])?;
+
+    let db = &db;
+    let sum = db.read_transaction(|snapshot| {
+        let sessions = snapshot.table("sessions");
+        let keys = sessions.keys()?;
+        // a write landing between the reads isn't seen by the snapshot
+        db.clear_table("sessions")?;
+        assert!(db.table("sessions").is_empty()?);
+        let mut sum = 0;
+        for key in &keys {
+            sum += sessions.get::<i32>(key)?.unwrap();
+        }
+        assert_eq!(sessions.len()?, 3);
+        assert!(sessions.contains_key("a")?);
+        assert_eq!(sessions.entries::<i32>()?.len(), 3);
+        assert_eq!(sessions.values::<i32>()?, [1, 2, 3]);
+        assert!(snapshot.table("missing").is_empty()?);
+        Ok(sum)
+    })?;
+    assert_eq!(sum, 6);
+    assert!(db.table("sessions").is_empty()?);
+    Ok(())
+}
+
+#[test]
+fn backup_stream() -> TestResult {
+    let mut source = Database::in_memory()?;
+    source
+        .table_mut("users")
+        .set_many(&[("alice", &30), ("bob", &25)])?;
+    // large enough to be split over several sections
+    let big = vec![7u8; 600_000];
+    for key in ["a", "b", "c"] {
+        source.table_mut("blobs").set(key, &big)?;
+    }
+    source.table_mut("empty").set("key", &0)?;
+    source.table_mut("empty").remove("key")?;
+
+    let mut stream = vec![];
+    let summary = source.backup_stream(&mut stream, BackupOpts::new())?;
+    assert_eq!((summary.tables, summary.entries), (3, 5));
+    assert_eq!(summary.bytes, stream.len() as u64);
+    assert_eq!(summary.manifest, source.manifest()?);
+
+    let mut db = Database::in_memory()?;
+    db.table_mut("users")
+        .set_many(&[("alice", &1), ("carol", &40)])?;
+    db.restore_stream(stream.as_slice(), RestoreMode::Merge)?;
+    assert_eq!(db.table("users").get::<i32>("alice")?, Some(30));
+    assert_eq!(db.table("users").get::<i32>("carol")?, Some(40));
+    assert_eq!(db.table("blobs").get::<Vec<u8>>("c")?, Some(big));
+    assert!(db.contains_table("empty")?);
+
+    let summary = db.restore_stream(stream.as_slice(), RestoreMode::Replace)?;
+    assert_eq!(summary.entries, 5);
+    assert_eq!(db.table("users").len()?, 2);
+    assert_eq!(db.digest_all()?, source.digest_all()?);
+    Ok(())
+}
+
+#[test]
+fn backup_stream_corrupted() -> TestResult {
+    let mut source = Database::in_memory()?;
+    source
+        .table_mut("users")
+        .set_many(&[("alice", &30), ("bob", &25)])?;
+    let mut stream = vec![];
+    source.backup_stream(&mut stream, BackupOpts::new())?;
+
+    let mut db = Database::in_memory()?;
+    db.table_mut("users").set("alice", &1)?;
+    let before = db.digest_all()?;
+
+    for len in [
+        0,
+        8,
+        20,
+        stream.len() / 2,
+        stream.len() - 33,
+        stream.len() - 1,
+    ] {
+        let result = db.restore_stream(&stream[..len], RestoreMode::Replace);
+        assert!(
+            result.is_err(),
+            "a stream truncated to {} bytes restored",
+            len
+        );
+    }
+    for at in [stream.len() / 2, stream.len() - 40, stream.len() - 1] {
+        let mut corrupted = stream.clone();
+        corrupted[at] ^= 1;
+        let result = db.restore_stream(corrupted.as_slice(), RestoreMode::Replace);
+        assert!(
+            result.is_err(),
+            "a stream corrupted at byte {} restored",
+            at
+        );
+    }
+    let mut extended = stream.clone();
+    extended.push(0);
+    assert!(db
+        .restore_stream(extended.as_slice(), RestoreMode::Replace)
+        .is_err());
+    assert_eq!(db.digest_all()?, before);
+
+    db.restore_stream(stream.as_slice(), RestoreMode::Replace)?;
+    assert_eq!(db.table("users").get::<i32>("alice")?, Some(30));
+    Ok(())
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn backup_stream_zstd() -> TestResult {
+    use crate::BackupCompression;
+
+    let mut source = Database::in_memory()?;
+    let value = "a very repetitive value ".repeat(1000);
+    for i in 0..100 {
+        source.table_mut("logs").set(&i.to_string(), &value)?;
+    }
+    let (mut plain, mut compressed) = (vec![], vec![]);
+    source.backup_stream(&mut plain, BackupOpts::new())?;
+    let opts = BackupOpts::new().compression(BackupCompression::Zstd(3));
+    source.backup_stream(&mut compressed, opts)?;
+    assert!(compressed.len() * 10 < plain.len());
+
+    let mut db = Database::in_memory()?;
+    db.restore_stream(compressed.as_slice(), RestoreMode::Merge)?;
+    assert_eq!(db.digest_all()?, source.digest_all()?);
+    Ok(())
+}
+
+#[test]
+fn freeze() -> TestResult {
+    use crate::Frozen;
+
+    let mut db = Database::in_memory()?;
+    db.table_mut("users").set("alice", &30)?;
+    db.freeze_with_reason("maintenance in progress");
+    assert!(db.is_frozen());
+
+    let e = db.table_mut("users").set("bob", &25).unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<Frozen>(),
+        Some(&Frozen {
+            reason: Some("maintenance in progress".to_string())
+        })
+    );
+    assert_eq!(
+        e.to_string(),
+        "the database is read-only: maintenance in progress"
+    );
+    assert!(db.table_mut("users").remove("alice").is_err());
+    assert!(db.delete_table("users").is_err());
+    assert!(db
+        .transaction(|tx| tx.table_mut("users").set("bob", &25))
+        .is_err());
+    assert!(db.compact().is_err());
+    assert_eq!(db.table("users").get::<i32>("alice")?, Some(30));
+    assert_eq!(db.table("users").keys()?, ["alice"]);
+
+    db.freeze();
+    let e = db.set("key", &"value").unwrap_err();
+    assert_eq!(e.downcast_ref::<Frozen>(), Some(&Frozen { reason: None }));
+
+    db.unfreeze();
+    assert!(!db.is_frozen());
+    db.table_mut("users").set("bob", &25)?;
+    assert_eq!(db.table("users").len()?, 2);
+    Ok(())
+}
+
+#[cfg(feature = "service")]
+#[test]
+fn db_handle_freeze() -> TestResult {
+    use crate::{DbHandle, Frozen};
+
+    let handle = DbHandle::new(Database::in_memory()?);
+    let other = handle.clone();
+    handle.write(|db| db.set("visits", &1))?;
+
+    other.freeze_with_reason("maintenance in progress")?;
+    assert!(handle.is_frozen()?);
+    let e = std::thread::scope(|s| s.spawn(|| handle.write(|db| db.set("visits", &2))).join())
+        .unwrap()
+        .unwrap_err();
+    assert!(e.downcast_ref::<Frozen>().is_some());
+    assert_eq!(handle.read(|db| db.get::<i32>("visits"))?, Some(1));
+
+    handle.unfreeze()?;
+    assert!(!other.is_frozen()?);
+    other.write(|db| db.set("visits", &2))?;
+    assert_eq!(handle.read(|db| db.get::<i32>("visits"))?, Some(2));
+    handle.shutdown();
+    Ok(())
+}
+
+#[cfg(feature = "service")]
+#[test]
+fn db_handle_lock_table() -> TestResult {
+    use crate::{DbHandle, TableLocked};
+
+    let mut db = Database::in_memory()?;
+    db.alias_table("balances", "accounts")?;
+    let handle = DbHandle::new(db);
+    let other = handle.clone();
+
+    let guard = handle.lock_table("balances")?;
+    assert_eq!(guard.table(), "accounts");
+    let e = other.try_lock_table("accounts").unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<TableLocked>().map(|e| e.table.as_str()),
+        Some("accounts")
+    );
+
+    // the other writers are refused before writing anything, the guard's writes go through
+    let e = other
+        .write(|db| db.table_mut("accounts").set("alice", &10))
+        .unwrap_err();
+    assert!(e.is::<TableLocked>());
+    let result = other.write(|db| {
+        db.transaction(|tx| {
+            tx.table_mut("log").set("deposit", &10)?;
+            tx.table_mut("balances").set("alice", &10)
+        })
+    });
+    assert!(result.unwrap_err().is::<TableLocked>());
+    assert!(other
+        .read(|db| db.table("log").get::<i32>("deposit"))?
+        .is_none());
+    assert!(other.write(|db| db.delete_all_tables()).is_err());
+    assert!(other.read(|db| db.clear_table("accounts")).is_err());
+    other.write(|db| db.table_mut("log").set("deposit", &10))?;
+    guard.write(|db| db.table_mut("balances").set("alice", &10))?;
+    assert_eq!(
+        other.read(|db| db.table("accounts").get::<i32>("alice"))?,
+        Some(10)
+    );
+
+    // a writer waiting for the lock gets it once the guard is dropped
+    std::thread::scope(|s| {
+        let waiter = s.spawn(|| -> TestResult {
+            let guard = other.lock_table("accounts")?;
+            guard.write(|db| db.table_mut("accounts").set("alice", &20))?;
+            Ok(())
+        });
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        guard.write(|db| db.table_mut("accounts").set("alice", &15))?;
+        drop(guard);
+        waiter.join().unwrap()
+    })?;
+    assert_eq!(
+        handle.read(|db| db.table("accounts").get::<i32>("alice"))?,
+        Some(20)
+    );
+    handle.write(|db| db.table_mut("accounts").set("alice", &30))?;
+    handle.shutdown();
+    Ok(())
+}
+
+#[test]
+fn count_values() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("mixed");
+    table.set_many(&[("a", &"one"), ("b", &"two")])?;
+    table.set_many(&[("c", &3), ("d", &4), ("e", &5)])?;
+
+    assert_eq!(table.len()?, 5);
+    assert_eq!(table.count_values::<String>()?, 2);
+    assert_eq!(table.count_values::<i32>()?, 3);
+    assert_eq!(table.values::<String>()?.len(), 2);
+    assert_eq!(table.values::<i32>()?.len(), 3);
+    assert_eq!(db.count_values::<i32>()?, 0);
+    assert_eq!(db.table("missing").count_values::<i32>()?, 0);
+    Ok(())
+}
+
+#[test]
+fn iter_keys() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let entries: Vec<(String, u32)> = (0..100).map(|i| (format!("key-{:03}", i), i)).collect();
+    let entries: Vec<(&str, &u32)> = entries.iter().map(|(k, v)| (k.as_str(), v)).collect();
+    db.table_mut("numbers").set_many(&entries)?;
+
+    let first: Vec<String> = db
+        .table("numbers")
+        .iter_keys()?
+        .take(3)
+        .collect::<Result<_>>()?;
+    assert_eq!(first, ["key-000", "key-001", "key-002"]);
+    let all: Vec<String> = db.table("numbers").iter_keys()?.collect::<Result<_>>()?;
+    assert_eq!(all, db.table("numbers").keys()?);
+    assert_eq!(db.table("missing").iter_keys()?.count(), 0);
+
+    db.table_mut("escaped").set_escape_keys(true)?;
+    db.table_mut("escaped").set("a/b", &1)?;
+    let keys: Vec<String> = db
+        .table_mut("escaped")
+        .iter_keys()?
+        .collect::<Result<_>>()?;
+    assert_eq!(keys, ["a/b"]);
+    db.set("default", &1)?;
+    assert_eq!(
+        db.iter_keys()?.next().transpose()?.as_deref(),
+        Some("default")
+    );
+    Ok(())
+}
+
+#[test]
+fn rename_table() -> TestResult {
+    let mut db = Database::in_memory()?;
+    db.table_mut("users_tmp").set_escape_keys(true)?;
+    db.table_mut("users_tmp")
+        .set_many(&[("alice", &30), ("bob", &25), ("a/b", &1)])?;
+    db.set_table_description("users_tmp", "every user")?;
+
+    db.rename_table("users_tmp", "users")?;
+    let tables = db.list_tables_with_prefix("", false)?;
+    assert!(tables.contains(&"users".to_string()));
+    assert!(!tables.contains(&"users_tmp".to_string()));
+    let mut keys = db.table("users").keys()?;
+    keys.sort();
+    assert_eq!(keys, ["a/b", "alice", "bob"]);
+    assert_eq!(db.table("users").get::<i32>("alice")?, Some(30));
+    assert_eq!(
+        db.table_description("users")?.as_deref(),
+        Some("every user")
+    );
+    assert!(db.table("users_tmp").is_empty()?);
+
+    db.table_mut("users_tmp").set("carol", &40)?;
+    assert!(db.rename_table("users_tmp", "users").is_err());
+    assert!(db.rename_table("missing", "other").is_err());
+    assert_eq!(db.table("users").len()?, 3);
+
+    db.rename_table_overwriting("users_tmp", "users")?;
+    assert_eq!(db.table("users").keys()?, ["carol"]);
+    assert_eq!(db.table_description("users")?, None);
+    assert!(!db.contains_table("users_tmp")?);
+    Ok(())
+}
+
+#[test]
+fn iter_entries_mixed_types() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("mixed");
+    table.set_many(&[("a", &"one"), ("c", &"three")])?;
+    table.set_many(&[("b", &2), ("d", &4)])?;
+
+    let strings = table.iter_entries::<String>()?.collect::<Vec<_>>();
+    assert_eq!(strings.len(), 4);
+    assert_eq!(
+        strings[0].as_ref().unwrap(),
+        &("a".to_string(), "one".to_string())
+    );
+    assert!(strings[1].as_ref().unwrap_err().to_string().contains("`b`"));
+    assert!(strings[2].is_ok());
+    assert!(strings[3].as_ref().unwrap_err().to_string().contains("`d`"));
+
+    let numbers = table.iter_entries::<i32>()?.collect::<Vec<_>>();
+    let failed: Vec<bool> = numbers.iter().map(Result::is_err).collect();
+    assert_eq!(failed, [true, false, true, false]);
+    // entries() skips what iter_entries() reports
+    assert_eq!(table.entries::<i32>()?.len(), 2);
+    Ok(())
+}
+
+#[test]
+fn list_tables_detailed_kinds() -> TestResult {
+    let path = std::env::temp_dir().join(format!("dbless_kinds_{}", std::process::id()));
+    {
+        let db = redb::Database::create(&path)?;
+        let tnx = db.begin_write()?;
+        tnx.open_table(redb::TableDefinition::<u64, u64>::new("counters"))?
+            .insert(1, 2)?;
+        tnx.commit()?;
+    }
+    {
+        let mut db = Database::open(&path)?;
+        db.set("key", &1)?;
+        db.table_mut("users").set("alice", &2)?;
+        db.table_mut("limited").set_limit(1, EvictionPolicy::Fifo)?;
+        assert_eq!(db.list_tables()?, vec!["limited", "users"]);
+        assert_eq!(db.len_all_tables()?, 2);
+        assert_eq!(db.len_all_tables_capped(1)?, CappedLen::AtLeast(1));
+        assert_eq!(db.len_all_tables_capped(2)?, CappedLen::AtLeast(2));
+        assert_eq!(db.len_all_tables_capped(3)?, CappedLen::Exact(2));
+        assert!(db.contains_table("users")?);
+        assert!(!db.contains_table("counters")?);
+
+        // whole-database reads leave the foreign table out
+        let keys = db.keys_all_tables()?;
+        assert!(keys.contains(&("users".to_string(), "alice".to_string())));
+        assert!(keys.iter().all(|(table, _)| table != "counters"));
+        let digests = db.digest_all()?;
+        assert!(digests.contains_key("users") && !digests.contains_key("counters"));
+        let large = db.large_values_report(0)?;
+        assert!(large.iter().any(|(table, _, _)| table == "users"));
+        assert!(large.iter().all(|(table, _, _)| table != "counters"));
+        let manifest = db.manifest()?;
+        assert!(manifest.tables.contains_key("users"));
+        assert!(!manifest.tables.contains_key("counters"));
+        let mut backup = vec![];
+        let summary = db.backup_stream(&mut backup, BackupOpts::new())?;
+        assert_eq!(summary.entries, 2);
+
+        let details = db.list_tables_detailed()?;
+        let kind = |name: &str| details.iter().find(|t| t.name == name).map(|t| t.kind);
+        assert_eq!(kind("users"), Some(TableKind::User));
+        assert_eq!(kind("counters"), Some(TableKind::Foreign));
+        assert_eq!(
+            kind(Database::DEFAULT_TABLE_NAME),
+            Some(TableKind::Reserved(ReservedKind::Default))
+        );
+        assert!(details
+            .iter()
+            .any(|t| t.kind == TableKind::Reserved(ReservedKind::Limits)));
+        assert!(details
+            .iter()
+            .any(|t| t.kind == TableKind::Reserved(ReservedKind::EvictionOrder)));
+        let counters = details.iter().find(|t| t.name == "counters").unwrap();
+        assert_eq!(counters.entries, None);
+
+        db.delete_all_tables()?;
+        let details = db.list_tables_detailed()?;
+        assert_eq!(details.len(), 1);
+        assert_eq!(details[0].name, "counters");
+        assert_eq!(db.len_all_tables()?, 0);
+        assert_eq!(db.len_all_tables_capped(1)?, CappedLen::Exact(0));
+        assert_eq!(db.len_all_tables_capped(0)?, CappedLen::AtLeast(0));
+    }
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn export_import_json() -> TestResult {
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct User {
+        name: String,
+        age: u32,
+    }
+    let mut db = Database::in_memory()?;
+    db.set("greeting", &"hello")?;
+    db.table_mut("users").set(
+        "alice",
+        &User {
+            name: "Alice".into(),
+            age: 30,
+        },
+    )?;
+    db.table_mut("users").set(
+        "bob",
+        &User {
+            name: "Bob".into(),
+            age: 25,
+        },
+    )?;
+    db.table_mut("scores").set("alice", &vec![1.5, 2.0])?;
+    db.table_mut("flags").set("beta", &true)?;
+
+    let mut dump = vec![];
+    db.export_json(&mut dump)?;
+    let dump = String::from_utf8(dump)?;
+    let parsed: serde_json::Value = serde_json::from_str(&dump)?;
+    assert_eq!(parsed["users"]["bob"]["age"], 25);
+    assert_eq!(parsed[Database::DEFAULT_TABLE_NAME]["greeting"], "hello");
+
+    let before = db.keys_all_tables()?;
+    db.delete_all_tables()?;
+    assert!(db.keys_all_tables()?.is_empty());
+    db.import_json(dump.as_bytes())?;
+    assert_eq!(db.keys_all_tables()?, before);
+    assert_eq!(db.get::<String>("greeting")?.as_deref(), Some("hello"));
+    assert_eq!(
+        db.table("users").get::<User>("alice")?,
+        Some(User {
+            name: "Alice".into(),
+            age: 30,
+        })
+    );
+    assert_eq!(
+        db.table("scores").get::<Vec<f64>>("alice")?,
+        Some(vec![1.5, 2.0])
+    );
+    assert_eq!(db.table("flags").get::<bool>("beta")?, Some(true));
+
+    assert!(db
+        .import_json(r##"{"#_#_limits_dbless_table_#_#": {}}"##.as_bytes())
+        .is_err());
+    assert!(db.import_json("[1, 2]".as_bytes()).is_err());
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "json")]
+fn json_dump_keeps_metadata() -> TestResult {
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("meta");
+    table.set_with_ttl("session", &"token", Duration::from_millis(300))?;
+    table.set_with_ttl("cache", &vec![1u8, 2], Duration::from_secs(3600))?;
+    table.set_tagged("tagged", &42u32)?;
+    table.set_u64("visits", 7)?;
+    table.set_dynamic("bin", &Value::Binary(vec![0x00, 0xff]))?;
+    table.set_raw("raw", &[0xc1, 0x02])?;
+    table.set("lookalike", &BTreeMap::from([("$counter", 1)]))?;
+    table.set("plain", &"text")?;
+
+    let mut dump = vec![];
+    db.export_json(&mut dump)?;
+    let parsed: serde_json::Value = serde_json::from_slice(&dump)?;
+    let meta = &parsed["meta"];
+    assert_eq!(meta["visits"], serde_json::json!({"$counter": 7}));
+    assert_eq!(meta["bin"], serde_json::json!({"$binary": "00ff"}));
+    assert_eq!(meta["raw"], serde_json::json!({"$raw": "c102"}));
+    assert_eq!(meta["tagged"]["$value"], 42);
+    assert_eq!(meta["tagged"]["$tag"], "u32");
+    assert!(meta["cache"]["$expires_at"].as_u64().is_some());
+    assert_eq!(
+        meta["lookalike"],
+        serde_json::json!({"$value": {"$counter": 1}})
+    );
+    assert_eq!(meta["plain"], "text");
+
+    db.delete_all_tables()?;
+    db.import_json(&dump[..])?;
+    let table = db.table("meta");
+    assert_eq!(table.get_u64("visits")?, Some(7));
+    assert_eq!(
+        table.get_dynamic("bin")?,
+        Some(Value::Binary(vec![0x00, 0xff]))
+    );
+    assert_eq!(table.get_raw("raw")?, Some(vec![0xc1, 0x02]));
+    let (tag, _) = table.get_tagged("tagged")?.unwrap();
+    assert_eq!(tag, "u32");
+    assert_eq!(table.get::<u32>("tagged")?, Some(42));
+    assert_eq!(table.get::<Vec<u8>>("cache")?, Some(vec![1, 2]));
+    assert_eq!(
+        table.get::<BTreeMap<String, u32>>("lookalike")?,
+        Some(BTreeMap::from([("$counter".to_string(), 1)]))
+    );
+    assert_eq!(table.get::<String>("plain")?.as_deref(), Some("text"));
+    assert_eq!(table.get::<String>("session")?.as_deref(), Some("token"));
+    std::thread::sleep(Duration::from_millis(400));
+    assert_eq!(table.get::<String>("session")?, None);
+
+    for invalid in [
+        r#"{"meta": {"k": {"$counter": 1, "$tag": "u64"}}}"#,
+        r#"{"meta": {"k": {"$raw": "zz"}}}"#,
+        r#"{"meta": {"k": {"$tag": "u32"}}}"#,
+        r#"{"meta": {"k": {"$value": 1, "$raw": "00"}}}"#,
+    ] {
+        assert!(db.import_json(invalid.as_bytes()).is_err(), "{}", invalid);
+    }
+    Ok(())
+}
+
+#[test]
+fn prefix_scan_range_end() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let keys = [
+        "a\u{7F}",
+        "a\u{80}",
+        "a\u{FF}",
+        "a\u{FF}x",
+        "a\u{100}",
+        "b",
+        "\u{D7FF}x",
+        "\u{E000}",
+        "\u{10FFFF}",
+        "\u{10FFFF}a",
+        "\u{10FFFF}\u{10FFFF}",
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        db.set(key, &i)?;
+    }
+    assert_eq!(db.keys_with_prefix("a\u{7F}")?, ["a\u{7F}"]);
+    assert_eq!(db.keys_with_prefix("a\u{FF}")?, ["a\u{FF}", "a\u{FF}x"]);
+    assert_eq!(db.keys_with_prefix("a")?.len(), 5);
+    assert_eq!(db.keys_with_prefix("\u{D7FF}")?, ["\u{D7FF}x"]);
+    assert_eq!(
+        db.keys_with_prefix("\u{10FFFF}")?,
+        ["\u{10FFFF}", "\u{10FFFF}a", "\u{10FFFF}\u{10FFFF}"]
+    );
+    assert_eq!(
+        db.entries_with_prefix::<usize>("\u{10FFFF}\u{10FFFF}")?,
+        [("\u{10FFFF}\u{10FFFF}".to_string(), 10)]
+    );
+    assert_eq!(db.keys_with_prefix("")?, db.keys()?);
+    assert_eq!(db.entries_with_prefix::<usize>("")?, db.entries::<usize>()?);
+    Ok(())
+}
+
+#[test]
+fn insert_only_and_append_only() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut blobs = db.table_mut("blobs");
+    blobs.set("old", &0)?;
+    blobs.insert_only()?;
+    blobs.set("a", &1)?;
+    let e = blobs.set("a", &2).unwrap_err();
+    assert_eq!(
+        e.downcast_ref::<ImmutableKey>(),
+        Some(&ImmutableKey {
+            table: "blobs".into(),
+            key: "a".into(),
+        })
+    );
+    assert!(blobs.insert_many(&[("b", &3), ("a", &4)]).is_err());
+    blobs.add_u64("counter", 1)?;
+    assert!(blobs.add_u64("counter", 1).is_err());
+    assert_eq!(blobs.get::<i32>("a")?, Some(1));
+    assert_eq!(blobs.get::<i32>("b")?, None);
+    assert!(db
+        .transaction(|tx| tx.table_mut("blobs").set("old", &5))
+        .is_err());
+    assert_eq!(db.table("blobs").get::<i32>("old")?, Some(0));
+    assert!(db.table("blobs").is_insert_only() && !db.table("blobs").is_append_only());
+
+    let mut blobs = db.table_mut("blobs");
+    blobs.remove("old")?;
+    blobs.set("old", &6)?;
+    blobs.append_only()?;
+    blobs.insert_only()?;
+    assert!(db.table("blobs").is_append_only());
+    let mut blobs = db.table_mut("blobs");
+    let e = blobs.remove("old").unwrap_err();
+    assert!(e.downcast_ref::<AppendOnlyViolation>().is_some());
+    assert!(blobs.clear().is_err());
+    assert!(blobs.set_limit(10, EvictionPolicy::Fifo).is_err());
+    blobs.set("c", &7)?;
+    assert_eq!(blobs.len()?, 4);
+
+    db.rename_table("blobs", "hashes")?;
+    assert!(db.table("hashes").is_append_only());
+    db.delete_table("hashes")?;
+    db.table_mut("hashes").set("a", &1)?;
+    db.table_mut("hashes").set("a", &2)?;
+    assert!(!db.table("hashes").is_insert_only());
+    Ok(())
+}
+
+#[test]
+fn ttl() -> TestResult {
+    use std::time::Duration;
+
+    let mut db = Database::in_memory()?;
+    let mut cache = db.table_mut("cache");
+    cache.set_with_ttl("short", &1, Duration::from_millis(50))?;
+    cache.set_with_ttl("long", &2, Duration::from_secs(3600))?;
+    cache.set_with_ttl("kept", &3, Duration::from_millis(50))?;
+    cache.set("kept", &4)?;
+    assert_eq!(cache.get::<i32>("short")?, Some(1));
+    assert!(cache.contains_key("short")?);
+
+    std::thread::sleep(Duration::from_millis(100));
+    assert_eq!(cache.get::<i32>("short")?, None);
+    assert!(!cache.contains_key("short")?);
+    assert_eq!(cache.get::<i32>("long")?, Some(2));
+    assert_eq!(cache.get::<i32>("kept")?, Some(4));
+    assert_eq!(cache.values::<i32>()?.len(), 2);
+    // every read of values skips it, the key is listed until it's purged
+    let entries = cache
+        .iter_entries::<i32>()?
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(entries, [("kept".to_string(), 4), ("long".to_string(), 2)]);
+    assert!(cache.entries_with_prefix::<i32>("s")?.is_empty());
+    assert!(cache
+        .entries_by_key_filter::<i32, _>(|k| k == "short")?
+        .is_empty());
+    assert_eq!(cache.count_values::<i32>()?, 2);
+    assert_eq!(cache.version_of("short")?, None);
+    assert_eq!(cache.len()?, 3);
+    assert_eq!(cache.keys()?, ["kept", "long", "short"]);
+    assert_eq!(
+        cache.update("short", |old: Option<i32>| old.unwrap_or(0))?,
+        0
+    );
+    cache.remove("short")?;
+
+    let computed = cache.get_or_insert_with_ttl("short", Duration::from_secs(3600), || 5)?;
+    assert_eq!(computed, 5);
+    let cached = cache.get_or_insert_with_ttl("short", Duration::from_secs(3600), || 6)?;
+    assert_eq!(cached, 5);
+
+    cache.set_with_ttl("gone", &7, Duration::from_millis(10))?;
+    std::thread::sleep(Duration::from_millis(50));
+    assert_eq!(cache.len()?, 4);
+    assert_eq!(cache.purge_expired()?, 1);
+    assert_eq!(cache.len()?, 3);
+    assert_eq!(cache.purge_expired()?, 0);
+    Ok(())
+}
+
+#[cfg(feature = "service")]
+#[test]
+fn expiry_sweeper() -> TestResult {
+    use std::time::{Duration, Instant};
+
+    use crate::DbHandle;
+
+    let handle = DbHandle::new(Database::in_memory()?);
+    handle.write(|db| {
+        db.set_with_ttl("token", &1, Duration::from_millis(10))?;
+        db.table_mut("sessions")
+            .set_with_ttl("abc", &"alice", Duration::from_millis(10))?;
+        db.table_mut("sessions").set("def", &"bob")
+    })?;
+    let sweeper = handle.start_expiry_sweeper(Duration::from_millis(20));
+    let started = Instant::now();
+    while sweeper.purged() < 2 && started.elapsed() < Duration::from_secs(5) {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(sweeper.purged(), 2);
+    assert_eq!(sweeper.last_error(), None);
+    assert_eq!(handle.read(|db| db.len())?, 0);
+    assert_eq!(handle.read(|db| db.table("sessions").keys())?, ["def"]);
+    sweeper.stop();
+
+    let sweeper = handle.start_expiry_sweeper(Duration::from_millis(10));
+    handle.shutdown();
+    drop(sweeper);
+    Ok(())
+}
+
+#[test]
+fn serialized_size_and_would_fit() -> TestResult {
+    use crate::{serialized_size, EvictionPolicy};
+
+    let value = vec!["a".to_string(); 100];
+    assert_eq!(
+        serialized_size(&value)?,
+        crate::serde::serialize(&value)?.len() as u64
+    );
+    assert_eq!(serialized_size(&1u8)?, 1);
+
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("table");
+    assert!(table.would_fit("key", &value)?);
+    table.set_limit(1, EvictionPolicy::RejectNew)?;
+    assert!(table.would_fit("key", &value)?);
+    table.set("key", &value)?;
+    assert!(table.would_fit("key", &1)?);
+    assert!(!table.would_fit("other", &1)?);
+    assert!(table.set("other", &1).is_err());
+
+    let mut once = db.table_mut("once");
+    once.insert_only()?;
+    once.set("key", &1)?;
+    assert!(!once.would_fit("key", &2)?);
+    assert!(once.would_fit("other", &2)?);
+    assert_eq!(once.len()?, 1);
+    Ok(())
+}
+
+#[test]
+fn get_range() -> TestResult {
+    use std::ops::Bound;
+
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("table");
+    for key in ["z", "m", "b", "a", "mm", "c"] {
+        table.set(key, &key.to_uppercase())?;
+    }
+    table.set("n", &1)?;
+    let keys = |entries: Vec<(String, String)>| -> Vec<String> {
+        entries.into_iter().map(|(key, _)| key).collect()
+    };
+    assert_eq!(keys(table.get_range(..)?), ["a", "b", "c", "m", "mm", "z"]);
+    assert_eq!(keys(table.get_range("a".."m")?), ["a", "b", "c"]);
+    assert_eq!(keys(table.get_range("a"..="m")?), ["a", "b", "c", "m"]);
+    assert_eq!(keys(table.get_range("c"..)?), ["c", "m", "mm", "z"]);
+    assert_eq!(keys(table.get_range(.."b")?), ["a"]);
+    let after_m = (Bound::Excluded("m"), Bound::Unbounded);
+    assert_eq!(keys(table.get_range(after_m)?), ["mm", "z"]);
+    assert_eq!(table.get_range::<i32>("m"..)?, [("n".to_string(), 1)]);
+    assert!(table.get_range::<String>("x".."y")?.is_empty());
+    assert!(db.table("missing").get_range::<String>(..)?.is_empty());
+
+    let mut db = Database::in_memory_seeded(7)?;
+    for key in ["d", "a", "c", "b"] {
+        db.set(key, &key)?;
+    }
+    assert_eq!(keys(db.get_range(..)?), ["a", "b", "c", "d"]);
+    Ok(())
+}
+
+#[test]
+fn keys_and_entries_pages() -> TestResult {
+    let mut db = Database::in_memory_seeded(3)?;
+    let mut table = db.table_mut("table");
+    for i in 0..10 {
+        table.set(&format!("key{}", i), &i)?;
+    }
+    table.set("key5", &"five")?;
+    assert_eq!(table.keys_page(0, 3)?, ["key0", "key1", "key2"]);
+    assert_eq!(table.keys_page(8, 5)?, ["key8", "key9"]);
+    assert!(table.keys_page(10, 5)?.is_empty());
+    assert!(table.keys_page(100, 5)?.is_empty());
+    assert!(table.keys_page(0, 0)?.is_empty());
+
+    let page = table.entries_page::<i32>(3, 4)?;
+    assert_eq!(
+        page,
+        [
+            ("key3".to_string(), 3),
+            ("key4".to_string(), 4),
+            ("key6".to_string(), 6)
+        ]
+    );
+    assert!(table.entries_page::<i32>(10, 5)?.is_empty());
+    assert!(db.table("missing").keys_page(0, 5)?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn error_kinds() -> TestResult {
+    use crate::DblessError;
+
+    #[derive(Debug)]
+    struct FailsToSerialize;
+
+    impl serde::Serialize for FailsToSerialize {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("nope"))
+        }
+    }
+
+    let mut db = Database::in_memory()?;
+    db.set("name", &"alice")?;
+    let e = db.get::<u32>("name").unwrap_err();
+    assert!(matches!(e, DblessError::Deserialize(_)), "{:?}", e);
+
+    let e = db.set("key", &FailsToSerialize).unwrap_err();
+    assert!(matches!(e, DblessError::Serialize(_)), "{:?}", e);
+
+    let e = db.rename_table("missing", "other").unwrap_err();
+    assert!(matches!(e, DblessError::NotFound(_)), "{:?}", e);
+
+    db.freeze();
+    let e = db.set("key", &1).unwrap_err();
+    assert!(matches!(e, DblessError::Other(_)));
+    assert!(e.is::<crate::Frozen>());
+    db.unfreeze();
+
+    // an error going through a closure, or through anyhow and back, keeps its kind
+    let e = db
+        .transaction(|tx| {
+            tx.table_mut(Database::DEFAULT_TABLE_NAME)
+                .get::<u32>("name")
+        })
+        .unwrap_err();
+    assert!(matches!(e, DblessError::Deserialize(_)), "{:?}", e);
+    let e = anyhow::Error::from(e).context("loading the name");
+    assert!(matches!(DblessError::from(e), DblessError::Deserialize(_)));
+
+    let e = DblessError::from(anyhow::Error::from(std::io::Error::other("disk")));
+    assert!(matches!(e, DblessError::Backend(_)));
+    Ok(())
+}
+
+#[test]
+fn raw_bytes_round_trip() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut table = db.table_mut("raw");
+    table.set("name", &"Alice")?;
+    assert_eq!(
+        table.get_raw("name")?,
+        Some(vec![0xa5, b'A', b'l', b'i', b'c', b'e'])
+    );
+    table.set("list", &vec![1u8, 2])?;
+    assert_eq!(table.get_raw("list")?, Some(vec![0x92, 0x01, 0x02]));
+
+    table.set_msgpack("number", &[0xcd, 0x01, 0x00])?;
+    assert_eq!(table.get::<u32>("number")?, Some(256));
+    assert_eq!(table.get_raw("number")?, Some(vec![0xcd, 0x01, 0x00]));
+    assert!(table.set_msgpack("invalid", &[0xc1]).is_err());
+    assert!(table.set_msgpack("trailing", &[0x01, 0x02]).is_err());
+
+    let payload = vec![0x08, 0x96, 0x01];
+    table.set_dynamic("proto", &Value::Binary(payload.clone()))?;
+    assert_eq!(
+        table.get_raw("proto")?,
+        Some(vec![0xc4, 0x03, 0x08, 0x96, 0x01])
+    );
+    assert_eq!(table.get_raw("missing")?, None);
+    assert_eq!(
+        db.table("raw").get_dynamic("proto")?,
+        Some(Value::Binary(payload))
+    );
+
+    let opaque = vec![0xc1, 0x00, 0xff];
+    db.set_raw("opaque", &opaque)?;
+    assert_eq!(db.get_raw("opaque")?, Some(opaque.clone()));
+    assert!(db.get::<Vec<u8>>("opaque").is_err());
+    assert_eq!(
+        db.default_table().get_dynamic("opaque")?,
+        Some(Value::Binary(opaque.clone()))
+    );
+    db.set_raw("empty", &[])?;
+    assert_eq!(db.get_raw("empty")?, Some(vec![]));
+
+    let mut table = db.table_mut("raw");
+    table.set_raw("opaque", &opaque)?;
+    table.set_raw("opaque", &[0x01])?;
+    assert_eq!(table.get_raw("opaque")?, Some(vec![0x01]));
+    Ok(())
+}
+
+#[test]
+fn lru_reads_while_frozen() -> TestResult {
+    let mut db = Database::in_memory()?;
+    let mut cache = db.table_mut("cache");
+    cache.set_limit(2, EvictionPolicy::Lru)?;
+    cache.set("a", &1)?;
+    cache.set("b", &2)?;
+
+    db.freeze();
+    assert_eq!(db.table("cache").get::<i32>("a")?, Some(1));
+    assert_eq!(db.table_mut("cache").get::<i32>("a")?, Some(1));
+    assert_eq!(
+        db.table("cache").get_many::<i32>(&["a", "b"])?,
+        vec![Some(1), Some(2)]
+    );
+
+    // the frozen reads didn't count as a use, "a" is still the oldest
+    db.unfreeze();
+    db.table_mut("cache").set("c", &3)?;
+    assert_eq!(db.table("cache").keys()?, ["b", "c"]);
+    Ok(())
+}
+
+#[cfg(feature = "json")]
+#[test]
+fn convert_codec() -> TestResult {
+    use crate::{ConvertReport, SerializationFormat, DEFAULT_DEFAULT_TABLE};
+    use redb::ReadableTableMetadata;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    let path = std::env::temp_dir().join(format!("dbless_convert_{}", std::process::id()));
+    let mut db = Database::options().track_versions(true).open(&path)?;
+    db.set("number", &1)?;
+    db.set_tagged("tagged", &"text")?;
+    db.set_with_ttl("ttl", &vec![1, 2], Duration::from_secs(3600))?;
+    db.set_with_ttl("expired", &1, Duration::from_millis(1))?;
+    db.set_u64("counter", 7)?;
+    db.table_mut("dedup").set_deduplicated(true)?;
+    let long = "a long value ".repeat(10);
+    for key in ["a", "b", "c"] {
+        db.table_mut("dedup").set(key, &long)?;
+    }
+    // a map with a tuple key has no JSON form, so the conversion stops there
+    let bad = BTreeMap::from([((1, 2), 3)]);
+    db.table_mut("last").set("bad", &bad)?;
+    db.table_mut("last").set("good", &"good")?;
+
+    let mut seen = vec![];
+    let e = db
+        .store
+        .convert_format(SerializationFormat::Json, DEFAULT_DEFAULT_TABLE, 2, |p| {
+            seen.push((p.table, p.converted))
+        })
+        .unwrap_err();
+    assert!(format!("{:#}", e).contains("failed to convert key `bad` of table `last`"));
+    assert_eq!(seen.last(), Some(&("dedup".to_owned(), 7)));
+    assert!(db
+        .convert_codec(SerializationFormat::MessagePack, None)
+        .is_err());
+
+    db.table_mut("last").remove("bad")?;
+    let report = db.convert_codec(SerializationFormat::Json, None)?;
+    assert!(report.resumed);
+    assert_eq!(report.converted, 1);
+    drop(db);
+
+    let raw = redb::Database::create(&path)?;
+    {
+        let tnx = raw.begin_read()?;
+        let table = tnx.open_table(redb::TableDefinition::<&str, &[u8]>::new("last"))?;
+        // after the envelope holding its version
+        assert!(table.get("good")?.unwrap().value().ends_with(br#""good""#));
+        let blobs = redb::TableDefinition::<&[u8], &[u8]>::new("#_#_blobs_dbless_table_#_#:dedup");
+        assert_eq!(tnx.open_table(blobs)?.len()?, 1);
+    }
+    drop(raw);
+
+    let mut db = Database::open(&path)?;
+    assert_eq!(db.manifest()?.codec, "json");
+    assert_eq!(db.get::<u32>("number")?, Some(1));
+    assert_eq!(db.version_of("number")?, Some(1));
+    assert_eq!(
+        db.get_tagged("tagged")?.map(|(tag, _)| tag),
+        Some("&str".to_owned())
+    );
+    assert_eq!(db.get::<String>("tagged")?.as_deref(), Some("text"));
+    assert_eq!(db.get::<Vec<u32>>("ttl")?, Some(vec![1, 2]));
+    assert_eq!(db.get::<u32>("expired")?, None);
+    assert_eq!(db.get_u64("counter")?, Some(7));
+    assert_eq!(db.table("dedup").get::<String>("c")?, Some(long.clone()));
+
+    let report = db.convert_codec(SerializationFormat::Json, None)?;
+    assert_eq!(report, ConvertReport::default());
+    let report = db.convert_codec(SerializationFormat::MessagePack, None)?;
+    assert_eq!((report.converted, report.unchanged), (8, 1));
+    assert_eq!(db.manifest()?.codec, "messagepack");
+    assert_eq!(
+        db.table("last").get::<String>("good")?.as_deref(),
+        Some("good")
+    );
+    drop(db);
+    std::fs::remove_file(&path)?;
+    Ok(())
+}
+
+#[cfg(feature = "lmdb-import")]
+#[test]
+fn import_lmdb() -> TestResult {
+    use crate::{DblessError, ValueMode};
+    use heed::types::Bytes;
+
+    let dir = std::env::temp_dir().join(format!("dbless_lmdb_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    {
+        let mut options = heed::EnvOpenOptions::new();
+        options.max_dbs(2);
+        let env = unsafe { options.open(&dir)? };
+        let mut wtxn = env.write_txn()?;
+        let users = env.create_database::<Bytes, Bytes>(&mut wtxn, Some("users"))?;
+        users.put(&mut wtxn, b"alice", &[0xa5, b'A', b'l', b'i', b'c', b'e'])?;
+        users.put(&mut wtxn, b"bob", &[0x03])?;
+        let blobs = env.create_database::<Bytes, Bytes>(&mut wtxn, Some("blobs"))?;
+        for i in 0..25u8 {
+            blobs.put(&mut wtxn, format!("blob-{:02}", i).as_bytes(), &[0xc1, i])?;
+        }
+        blobs.put(&mut wtxn, b"bad\xffkey%", b"\x00")?;
+        wtxn.commit()?;
+        env.prepare_for_closing().wait();
+    }
+
+    let mut db = Database::in_memory()?;
+    let report = db.import_lmdb(&dir, &["users"], ValueMode::MsgpackPassthrough)?;
+    assert_eq!(report.databases[0].imported, 2);
+    assert_eq!(
+        db.table("users").get::<String>("alice")?.as_deref(),
+        Some("Alice")
+    );
+    assert_eq!(db.table("users").get::<u8>("bob")?, Some(3));
+
+    let report = crate::lmdb::import(&mut db, &dir, &["blobs"], ValueMode::RawBytes, 10)?;
+    assert_eq!(report.imported(), 26);
+    assert_eq!(report.databases[0].escaped_keys, ["bad%FFkey%25"]);
+    let blobs = db.table("blobs");
+    assert_eq!(blobs.len()?, 26);
+    assert_eq!(blobs.get_raw("blob-07")?, Some(vec![0xc1, 7]));
+    assert_eq!(blobs.get_raw("bad%FFkey%25")?, Some(vec![0x00]));
+    assert!(blobs.get::<u8>("blob-07").is_err());
+
+    let e = db
+        .import_lmdb(&dir, &["blobs"], ValueMode::MsgpackPassthrough)
+        .unwrap_err();
+    assert!(format!("{:#}", e).contains("blob-00"), "{:#}", e);
+    let e = db
+        .import_lmdb(&dir, &["missing"], ValueMode::RawBytes)
+        .unwrap_err();
+    assert!(matches!(e, DblessError::NotFound(_)), "{:?}", e);
+    let _ = std::fs::remove_dir_all(&dir);
+    Ok(())
+}
+
+#[run_after_tests]
+fn delete_test_db() {
+    unsafe {
+        (*std::ptr::addr_of_mut!(DB)).take();
+    }
 }
@@ -0,0 +1,110 @@
+use std::fmt;
+use std::ops::Bound;
+
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{Result, Table, TableMut, TableWriteInterface};
+
+/// A key made from a timestamp, which sorts in the same order as the timestamps themselves. \
+/// it's the RFC 3339 form of the timestamp in UTC with nanosecond precision, \
+/// like `2024-05-01T12:30:00.000000000Z`, \
+/// so the order holds for every timestamp between the years 0 and 9999.
+/// ```
+/// # use dbless::TimestampKey;
+/// use chrono::{TimeZone, Utc};
+/// let time = Utc.with_ymd_and_hms(2024, 5, 1, 12, 30, 0).unwrap();
+/// let key = TimestampKey::from(time);
+/// assert_eq!(key.as_str(), "2024-05-01T12:30:00.000000000Z");
+/// assert_eq!(TimestampKey::parse(key.as_str())?, time);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TimestampKey(String);
+
+impl TimestampKey {
+    /// Gets the key as a string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Parses a key back into the timestamp it was made from
+    pub fn parse(key: &str) -> Result<DateTime<Utc>> {
+        let time = DateTime::parse_from_rfc3339(key)
+            .with_context(|| format!("key `{}` is not a timestamp", key))?;
+        Ok(time.with_timezone(&Utc))
+    }
+}
+
+impl From<DateTime<Utc>> for TimestampKey {
+    fn from(time: DateTime<Utc>) -> Self {
+        TimestampKey(time.to_rfc3339_opts(SecondsFormat::Nanos, true))
+    }
+}
+
+impl fmt::Display for TimestampKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a> Table<'a> {
+    /// Gets all entries with a [`TimestampKey`](struct.TimestampKey.html) between `from` (inclusive) and `to` (exclusive), \
+    /// ordered by time. \
+    /// values that fail to deserialize are skipped (like in [`entries()`](trait.TableReadInterface.html#method.entries)), \
+    /// and a key in the range that isn't a timestamp is an error.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// use chrono::{Duration, TimeZone, Utc};
+    /// let db = Database::open("my_database.db")?;
+    /// let now = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    /// let last_hour = db.table("events").range_between::<String>(now - Duration::hours(1), now)?;
+    /// for (time, event) in last_hour {
+    ///     println!("{}: {}", time, event);
+    /// }
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn range_between<T: DeserializeOwned>(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, T)>> {
+        let (from, to) = (TimestampKey::from(from), TimestampKey::from(to));
+        let start = Bound::Included(from.as_str());
+        let end = Bound::Excluded(to.as_str());
+        let entries = self
+            .store
+            .context(&self.name, self.store.range_entries(&self.name, start, end))?;
+        entries
+            .into_iter()
+            .map(|(key, value)| Ok((TimestampKey::parse(&key)?, value)))
+            .collect()
+    }
+}
+
+impl<'a> TableMut<'a> {
+    /// Inserts a value into the table, keyed by the [`TimestampKey`](struct.TimestampKey.html) of `time`. \
+    /// a value inserted at the exact same time replaces the previous one.
+    /// ```no_run
+    /// # use dbless::Database;
+    /// use chrono::{TimeZone, Utc};
+    /// let mut db = Database::open("my_database.db")?;
+    /// let time = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+    /// db.table_mut("events").insert_at(time, &"started")?;
+    /// # Ok::<(), Box<dyn std::error::Error>>(())
+    /// ```
+    pub fn insert_at<T: Serialize>(&mut self, time: DateTime<Utc>, value: &T) -> Result<()> {
+        self.insert(TimestampKey::from(time).as_str(), value)
+    }
+
+    /// Gets all entries with a [`TimestampKey`](struct.TimestampKey.html) between `from` (inclusive) and `to` (exclusive). \
+    /// see [`Table::range_between()`](struct.Table.html#method.range_between)
+    pub fn range_between<T: DeserializeOwned>(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, T)>> {
+        Table::from(self).range_between(from, to)
+    }
+}
@@ -0,0 +1,165 @@
+use redb::WriteTransaction;
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::store::Store;
+use crate::Result;
+
+/// A write transaction spanning any number of tables, \
+/// passed to the closure given to [`Database::transaction()`](struct.Database.html#method.transaction). \
+/// reads through its tables see the writes made before them in the same transaction, \
+/// and nothing is visible outside of it until the closure returns `Ok` and it's committed. \
+/// a write that fails may be partly applied, so once one does the transaction is rolled back, \
+/// even if the closure ignores the error and returns `Ok`.
+pub struct Transaction<'a> {
+    pub(crate) store: &'a Store,
+    pub(crate) tnx: WriteTransaction,
+    pub(crate) failed: bool,
+}
+
+/// A handle to a table inside a [`Transaction`](struct.Transaction.html), \
+/// created with [`Transaction::table_mut()`](struct.Transaction.html#method.table_mut)
+pub struct TransactionTable<'t> {
+    store: &'t Store,
+    tnx: &'t WriteTransaction,
+    failed: &'t mut bool,
+    name: &'t str,
+}
+
+/// A consistent snapshot of the whole database, \
+/// passed to the closure given to [`Database::read_transaction()`](struct.Database.html#method.read_transaction). \
+/// every read through its tables sees the database as it was when the snapshot was taken, \
+/// writes committed in the meantime aren't seen.
+pub struct ReadTransaction<'a> {
+    pub(crate) store: &'a Store,
+    pub(crate) tnx: redb::ReadTransaction,
+}
+
+/// A handle to a table inside a [`ReadTransaction`](struct.ReadTransaction.html), \
+/// created with [`ReadTransaction::table()`](struct.ReadTransaction.html#method.table)
+pub struct ReadTransactionTable<'t> {
+    store: &'t Store,
+    tnx: &'t redb::ReadTransaction,
+    name: &'t str,
+}
+
+impl<'a> Transaction<'a> {
+    /// Get a handle to the table named `name`, inside the transaction
+    pub fn table_mut<'t>(&'t mut self, name: &'t str) -> TransactionTable<'t> {
+        TransactionTable {
+            store: self.store,
+            tnx: &self.tnx,
+            failed: &mut self.failed,
+            name,
+        }
+    }
+}
+
+impl TransactionTable<'_> {
+    /// marks the transaction as failed if a write did, it can't be committed anymore
+    fn check_write<T>(&mut self, result: anyhow::Result<T>) -> Result<T> {
+        *self.failed |= result.is_err();
+        self.store.context(self.name, result)
+    }
+
+    /// Gets the name of the table
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Gets a value from the table, \
+    /// including one inserted earlier in the transaction
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let result = self.store.get_in(self.tnx, self.name, key);
+        self.store.context(self.name, result)
+    }
+
+    /// Inserts a value into the table. \
+    /// aliases: [`set()`](#method.set)
+    pub fn insert<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        let result = self
+            .store
+            .serialize(key, value)
+            .and_then(|bytes| self.store.insert_in(self.tnx, self.name, key, &bytes));
+        self.check_write(result)
+    }
+
+    /// Inserts a value into the table. \
+    /// aliases: [`insert()`](#method.insert)
+    pub fn set<T: Serialize>(&mut self, key: &str, value: &T) -> Result<()> {
+        self.insert(key, value)
+    }
+
+    /// Removes a key from the table, removing a key that doesn't exist does nothing. \
+    /// aliases: [`delete()`](#method.delete)
+    pub fn remove(&mut self, key: &str) -> Result<()> {
+        let result = self.store.remove_in(self.tnx, self.name, key);
+        self.check_write(result)
+    }
+
+    /// Removes a key from the table, removing a key that doesn't exist does nothing. \
+    /// aliases: [`remove()`](#method.remove)
+    pub fn delete(&mut self, key: &str) -> Result<()> {
+        self.remove(key)
+    }
+}
+
+impl ReadTransaction<'_> {
+    /// Get a handle to the table named `name`, reading from the snapshot
+    pub fn table<'t>(&'t self, name: &'t str) -> ReadTransactionTable<'t> {
+        ReadTransactionTable {
+            store: self.store,
+            tnx: &self.tnx,
+            name,
+        }
+    }
+}
+
+impl ReadTransactionTable<'_> {
+    /// Gets the name of the table
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Gets the value associated with the given key. \
+    /// it doesn't count as a use of the key if the table evicts the least recently used entries, \
+    /// see [`Database::read_transaction()`](struct.Database.html#method.read_transaction)
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let result = self.store.get_at(self.tnx, self.name, key);
+        self.store.context(self.name, result)
+    }
+
+    /// Gets all the keys in the table
+    pub fn keys(&self) -> Result<Vec<String>> {
+        let result = self.store.keys_at(self.tnx, self.name);
+        self.store.context(self.name, result)
+    }
+
+    /// Gets all the values in the table, skipping the ones that fail to deserialize
+    pub fn values<T: DeserializeOwned>(&self) -> Result<Vec<T>> {
+        let result = self.store.values_at(self.tnx, self.name);
+        self.store.context(self.name, result)
+    }
+
+    /// Gets all the entries in the table, skipping the ones whose value fails to deserialize
+    pub fn entries<T: DeserializeOwned>(&self) -> Result<Vec<(String, T)>> {
+        let result = self.store.entries_at(self.tnx, self.name);
+        self.store.context(self.name, result)
+    }
+
+    /// Gets the number of entries in the table
+    pub fn len(&self) -> Result<usize> {
+        let result = self.store.len_at(self.tnx, self.name);
+        self.store.context(self.name, result)
+    }
+
+    /// Checks if the table is empty
+    pub fn is_empty(&self) -> Result<bool> {
+        Ok(self.len()? == 0)
+    }
+
+    /// Checks if the table contains the given key
+    pub fn contains_key(&self, key: &str) -> Result<bool> {
+        let result = self.store.contains_key_at(self.tnx, self.name, key);
+        self.store.context(self.name, result)
+    }
+}
@@ -0,0 +1,142 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::Arc;
+
+use anyhow::{bail, ensure, Result};
+
+use crate::envelope::{decode_transformed, encode_transformed};
+
+/// A stage of the transform chain values go through before they're stored, \
+/// like compression, encryption, or signing. \
+/// stages are added with [`Options::transform()`](struct.Options.html#method.transform), \
+/// applied in that order on write, and reversed in the opposite order on read.
+///
+/// every stored value records the ids of the stages that were applied to it, \
+/// so values written before a stage was added (or with a different chain) are still read correctly, \
+/// as long as every stage they went through is still in the chain. \
+/// a signing stage only protects values that went through it, \
+/// see [`Options::require_transforms()`](struct.Options.html#method.require_transforms) to refuse the ones that didn't.
+/// ```no_run
+/// # use dbless::{Database, ValueTransform};
+/// struct Xor(u8);
+///
+/// impl ValueTransform for Xor {
+///     fn id(&self) -> u8 {
+///         1
+///     }
+///
+///     fn encode(&self, bytes: &[u8]) -> Vec<u8> {
+///         bytes.iter().map(|b| b ^ self.0).collect()
+///     }
+///
+///     fn decode(&self, bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+///         Ok(self.encode(bytes))
+///     }
+/// }
+///
+/// let db = Database::options().transform(Xor(0x5a)).open("my_database.db")?;
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub trait ValueTransform: Send + Sync {
+    /// Identifies the stage in the header of every value it was applied to, \
+    /// must be unique within the chain, and must never change once values were written with it.
+    fn id(&self) -> u8;
+
+    /// Transforms the bytes of a value before it's stored
+    fn encode(&self, bytes: &[u8]) -> Vec<u8>;
+
+    /// Reverses [`encode()`](#tymethod.encode) when the value is read, \
+    /// an error fails the read (a signature that doesn't match for example).
+    fn decode(&self, bytes: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// the stages set with `Options::transform()`, in the order they're applied
+#[derive(Clone, Default)]
+pub struct TransformChain {
+    stages: Vec<Arc<dyn ValueTransform>>,
+    // set with `Options::require_transforms()`
+    required: bool,
+}
+
+impl fmt::Debug for TransformChain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.ids()).finish()
+    }
+}
+
+impl TransformChain {
+    pub fn push(&mut self, stage: impl ValueTransform + 'static) {
+        self.stages.push(Arc::new(stage));
+    }
+
+    pub fn set_required(&mut self, required: bool) {
+        self.required = required;
+    }
+
+    /// whether every value has to go through every stage, \
+    /// which also keeps counters (stored as they are) from being written or read
+    pub fn is_required(&self) -> bool {
+        self.required && !self.stages.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = u8> + '_ {
+        self.stages.iter().map(|stage| stage.id())
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for (i, stage) in self.stages.iter().enumerate() {
+            ensure!(
+                self.stages[..i]
+                    .iter()
+                    .all(|other| other.id() != stage.id()),
+                "transform stage id {} is used more than once",
+                stage.id()
+            );
+        }
+        Ok(())
+    }
+
+    /// runs the bytes through every stage, and wraps the result with the ids of the stages
+    pub fn apply<'v>(&self, bytes: &'v [u8]) -> Cow<'v, [u8]> {
+        if self.stages.is_empty() {
+            return Cow::Borrowed(bytes);
+        }
+        let mut transformed = bytes.to_vec();
+        for stage in &self.stages {
+            transformed = stage.encode(&transformed);
+        }
+        let ids: Vec<u8> = self.ids().collect();
+        Cow::Owned(encode_transformed(&ids, &transformed))
+    }
+
+    /// reverses the stages recorded in the bytes, last applied first, \
+    /// bytes that were never transformed are returned as they are, unless transforms are required
+    pub fn reverse<'v>(&self, bytes: &'v [u8]) -> Result<Cow<'v, [u8]>> {
+        let Some((ids, transformed)) = decode_transformed(bytes)? else {
+            ensure!(
+                !self.is_required(),
+                "value didn't go through the transform chain, which is required"
+            );
+            return Ok(Cow::Borrowed(bytes));
+        };
+        if self.is_required() {
+            if let Some(missing) = self.ids().find(|id| !ids.contains(id)) {
+                bail!(
+                    "value didn't go through transform stage {}, which is required",
+                    missing
+                );
+            }
+        }
+        let mut value = transformed.to_vec();
+        for &id in ids.iter().rev() {
+            let Some(stage) = self.stages.iter().find(|stage| stage.id() == id) else {
+                bail!(
+                    "value was written with transform stage {}, which isn't in the transform chain",
+                    id
+                );
+            };
+            value = stage.decode(&value)?;
+        }
+        Ok(Cow::Owned(value))
+    }
+}
@@ -0,0 +1,242 @@
+use std::marker::PhantomData;
+use std::ops::RangeBounds;
+use std::time::Duration;
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::store::{EntriesIter, KeysIter};
+use crate::{KeyedResult, Result, Table, TableMut, TableReadInterface, TableWriteInterface};
+
+/// A read-only handle to a table whose values are all of type `T`, \
+/// created with [`Database::typed_table()`](struct.Database.html#method.typed_table). \
+/// it has the methods of [`TableReadInterface`](trait.TableReadInterface.html) that read values, \
+/// without a type to annotate on every call.
+/// ```no_run
+/// # use dbless::Database;
+/// # #[derive(serde::Deserialize)]
+/// # struct User { name: String }
+/// let db = Database::open("my_database.db")?;
+/// let users = db.typed_table::<User>("users");
+/// if let Some(alice) = users.get("alice")? {
+///     println!("{}", alice.name);
+/// }
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TypedTable<'a, T> {
+    table: Table<'a>,
+    _value: PhantomData<fn() -> T>,
+}
+
+/// A read-write handle to a table whose values are all of type `T`, \
+/// created with [`Database::typed_table_mut()`](struct.Database.html#method.typed_table_mut). \
+/// see [`TypedTable`](struct.TypedTable.html)
+/// ```no_run
+/// # use dbless::Database;
+/// # #[derive(serde::Serialize, serde::Deserialize)]
+/// # struct User { name: String }
+/// let mut db = Database::open("my_database.db")?;
+/// let mut users = db.typed_table_mut::<User>("users");
+/// users.set("alice", &User { name: "Alice".into() })?;
+/// let names: Vec<String> = users.values()?.into_iter().map(|user| user.name).collect();
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+pub struct TypedTableMut<'a, T> {
+    table: TableMut<'a>,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> TypedTable<'a, T> {
+    pub(crate) fn new(table: Table<'a>) -> Self {
+        TypedTable {
+            table,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<'a, T> TypedTableMut<'a, T> {
+    pub(crate) fn new(table: TableMut<'a>) -> Self {
+        TypedTableMut {
+            table,
+            _value: PhantomData,
+        }
+    }
+}
+
+macro_rules! typed_read_methods {
+    ($($typed:ident),*) => {
+        $(
+            impl<T: DeserializeOwned> $typed<'_, T> {
+                /// Gets the name of the table
+                pub fn name(&self) -> &str {
+                    self.table.name()
+                }
+
+                /// Gets the value associated with the given key, \
+                /// see [`TableReadInterface::get()`](trait.TableReadInterface.html#method.get)
+                pub fn get(&self, key: &str) -> Result<Option<T>> {
+                    self.table.get(key)
+                }
+
+                /// Gets the value associated with the given key, or `default` if it doesn't exist
+                pub fn get_or(&self, key: &str, default: T) -> Result<T> {
+                    self.table.get_or(key, default)
+                }
+
+                /// Gets the values of several keys at once, \
+                /// see [`TableReadInterface::get_many()`](trait.TableReadInterface.html#method.get_many)
+                pub fn get_many(&self, keys: &[&str]) -> Result<Vec<Option<T>>> {
+                    self.table.get_many(keys)
+                }
+
+                /// Gets the values of several keys at once, along with what was found under each of them, \
+                /// see [`TableReadInterface::get_all()`](trait.TableReadInterface.html#method.get_all)
+                pub fn get_all(&self, keys: &[&str]) -> Result<Vec<KeyedResult<T>>> {
+                    self.table.get_all(keys)
+                }
+
+                /// Gets all the keys in the table
+                pub fn keys(&self) -> Result<Vec<String>> {
+                    self.table.keys()
+                }
+
+                /// Gets all the values in the table
+                pub fn values(&self) -> Result<Vec<T>> {
+                    self.table.values()
+                }
+
+                /// Gets all the entries in the table
+                pub fn entries(&self) -> Result<Vec<(String, T)>> {
+                    self.table.entries()
+                }
+
+                /// Gets the entries whose key starts with `prefix`
+                pub fn entries_with_prefix(&self, prefix: &str) -> Result<Vec<(String, T)>> {
+                    self.table.entries_with_prefix(prefix)
+                }
+
+                /// Gets the entries whose keys are within `range`, sorted by key, \
+                /// see [`TableReadInterface::get_range()`](trait.TableReadInterface.html#method.get_range)
+                pub fn get_range<'r>(&self, range: impl RangeBounds<&'r str>) -> Result<Vec<(String, T)>> {
+                    self.table.get_range(range)
+                }
+
+                /// Gets up to `limit` entries starting at position `offset` in sorted order, \
+                /// see [`TableReadInterface::entries_page()`](trait.TableReadInterface.html#method.entries_page)
+                pub fn entries_page(&self, offset: usize, limit: usize) -> Result<Vec<(String, T)>> {
+                    self.table.entries_page(offset, limit)
+                }
+
+                /// Iterates over the keys of the table without collecting them, \
+                /// see [`TableReadInterface::iter_keys()`](trait.TableReadInterface.html#method.iter_keys)
+                pub fn iter_keys(&self) -> Result<KeysIter<'_>> {
+                    self.table.iter_keys()
+                }
+
+                /// Iterates over the entries of the table without collecting them, \
+                /// see [`TableReadInterface::iter_entries()`](trait.TableReadInterface.html#method.iter_entries)
+                pub fn iter_entries(&self) -> Result<EntriesIter<'_, T>> {
+                    self.table.iter_entries()
+                }
+
+                /// Gets the number of entries in the table
+                pub fn len(&self) -> Result<usize> {
+                    self.table.len()
+                }
+
+                /// Gets the number of values in the table that can be deserialized into a `T`, \
+                /// see [`TableReadInterface::count_values()`](trait.TableReadInterface.html#method.count_values)
+                pub fn count_values(&self) -> Result<usize> {
+                    self.table.count_values::<T>()
+                }
+
+                /// Checks if the table is empty
+                pub fn is_empty(&self) -> Result<bool> {
+                    self.table.is_empty()
+                }
+
+                /// Checks if the table contains the given key
+                pub fn contains_key(&self, key: &str) -> Result<bool> {
+                    self.table.contains_key(key)
+                }
+            }
+        )*
+    };
+}
+
+typed_read_methods!(TypedTable, TypedTableMut);
+
+impl<T: Serialize + DeserializeOwned> TypedTableMut<'_, T> {
+    /// Inserts a value into the table. \
+    /// aliases: [`set()`](#method.set)
+    pub fn insert(&mut self, key: &str, value: &T) -> Result<()> {
+        self.table.insert(key, value)
+    }
+
+    /// Inserts a value into the table. \
+    /// aliases: [`insert()`](#method.insert)
+    pub fn set(&mut self, key: &str, value: &T) -> Result<()> {
+        self.table.set(key, value)
+    }
+
+    /// Inserts several values in a single write transaction, \
+    /// see [`TableWriteInterface::insert_many()`](trait.TableWriteInterface.html#method.insert_many)
+    pub fn insert_many(&mut self, entries: &[(&str, &T)]) -> Result<()> {
+        self.table.insert_many(entries)
+    }
+
+    /// Inserts a value into the table, expiring after `ttl`, \
+    /// see [`TableWriteInterface::set_with_ttl()`](trait.TableWriteInterface.html#method.set_with_ttl)
+    pub fn set_with_ttl(&mut self, key: &str, value: &T, ttl: Duration) -> Result<()> {
+        self.table.set_with_ttl(key, value, ttl)
+    }
+
+    /// Gets the value of the key if it hasn't expired, otherwise inserts what `f` returns, expiring after `ttl`, \
+    /// see [`TableWriteInterface::get_or_insert_with_ttl()`](trait.TableWriteInterface.html#method.get_or_insert_with_ttl)
+    pub fn get_or_insert_with_ttl(
+        &mut self,
+        key: &str,
+        ttl: Duration,
+        f: impl FnOnce() -> T,
+    ) -> Result<T> {
+        self.table.get_or_insert_with_ttl(key, ttl, f)
+    }
+
+    /// Removes the entries whose time to live has passed, and returns how many were removed
+    pub fn purge_expired(&mut self) -> Result<usize> {
+        self.table.purge_expired()
+    }
+
+    /// Gets the value of the key, inserting `default` first if it doesn't exist
+    pub fn get_or_insert(&mut self, key: &str, default: T) -> Result<T> {
+        self.table.get_or_insert(key, default)
+    }
+
+    /// Replaces the value of the key with what `f` returns, in a single write transaction, \
+    /// see [`TableWriteInterface::update()`](trait.TableWriteInterface.html#method.update)
+    pub fn update(&mut self, key: &str, f: impl FnOnce(Option<T>) -> T) -> Result<T> {
+        self.table.update(key, f)
+    }
+
+    /// Removes a key from the table. \
+    /// aliases: [`delete()`](#method.delete)
+    pub fn remove(&mut self, key: &str) -> Result<()> {
+        self.table.remove(key)
+    }
+
+    /// Removes a key from the table. \
+    /// aliases: [`remove()`](#method.remove)
+    pub fn delete(&mut self, key: &str) -> Result<()> {
+        self.table.delete(key)
+    }
+
+    /// Removes several keys in a single write transaction
+    pub fn remove_many(&mut self, keys: &[&str]) -> Result<()> {
+        self.table.remove_many(keys)
+    }
+
+    /// Clears the table
+    pub fn clear(&mut self) -> Result<()> {
+        self.table.clear()
+    }
+}
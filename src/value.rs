@@ -0,0 +1,351 @@
+use std::fmt;
+
+use anyhow::Result;
+use serde::de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer};
+
+use crate::msgpack::{self, Limits, Reader, Token};
+
+/// Any MessagePack value, for reading and editing stored values without a rust type for them, \
+/// see [`Table::get_dynamic()`](struct.Table.html#method.get_dynamic) and [`TableMut::set_dynamic()`](struct.TableMut.html#method.set_dynamic). \
+/// structs are stored as maps from field name to value, and enums as their variant name (or a map from it to their content). \
+/// non-negative integers are always read as [`UInt`](#variant.UInt), negative ones as [`Int`](#variant.Int).
+/// ```
+/// # use dbless::Value;
+/// let value = Value::Map(vec![(
+///     "profile".into(),
+///     Value::Map(vec![("name".into(), "alice".into())]),
+/// )]);
+/// assert_eq!(value.pointer("/profile/name").and_then(Value::as_str), Some("alice"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Nil, what `None` and `()` are stored as
+    Nil,
+    /// A boolean
+    Bool(bool),
+    /// A negative integer
+    Int(i64),
+    /// A non-negative integer
+    UInt(u64),
+    /// A single precision float
+    F32(f32),
+    /// A double precision float
+    F64(f64),
+    /// A string
+    String(String),
+    /// Binary data
+    Binary(Vec<u8>),
+    /// A list of values
+    Array(Vec<Value>),
+    /// Key-value pairs, in the order they're stored
+    Map(Vec<(Value, Value)>),
+    /// A MessagePack ext value, which dbless reserves for its own metadata, \
+    /// only read from values written by other programs, and never written
+    Ext(i8, Vec<u8>),
+}
+
+impl Value {
+    /// Reads a single MessagePack value, \
+    /// after checking it against `limits` so a deeply nested value can't overflow the stack
+    pub(crate) fn read(bytes: &[u8], limits: &Limits) -> Result<Self> {
+        msgpack::check(bytes, limits)?;
+        Self::read_checked(&mut Reader::new(bytes))
+    }
+
+    fn read_checked(reader: &mut Reader) -> Result<Self> {
+        Ok(match reader.read()? {
+            Token::Nil => Value::Nil,
+            Token::Bool(b) => Value::Bool(b),
+            Token::Int(n) if n >= 0 => Value::UInt(n as u64),
+            Token::Int(n) => Value::Int(n),
+            Token::UInt(n) => Value::UInt(n),
+            Token::F32(f) => Value::F32(f),
+            Token::F64(f) => Value::F64(f),
+            Token::Str(s) => Value::String(s.to_string()),
+            Token::Bin(bytes) => Value::Binary(bytes.to_vec()),
+            Token::Array(len) => Value::Array(
+                (0..len)
+                    .map(|_| Self::read_checked(reader))
+                    .collect::<Result<_>>()?,
+            ),
+            Token::Map(len) => Value::Map(
+                (0..len)
+                    .map(|_| Ok((Self::read_checked(reader)?, Self::read_checked(reader)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Token::Ext(ext_type, bytes) => Value::Ext(ext_type, bytes.to_vec()),
+        })
+    }
+
+    /// Looks up a value by a JSON pointer (RFC 6901) like `/profile/name` or `/tags/0`, \
+    /// where each segment selects a map entry by its string key, or an array element by index, \
+    /// and `~1` and `~0` stand for `/` and `~` in keys. \
+    /// the empty pointer is the value itself, and `None` is returned if the value isn't found.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        let mut value = self;
+        for segment in pointer_segments(pointer)? {
+            value = match value {
+                Value::Map(entries) => entries
+                    .iter()
+                    .find(|(key, _)| key.as_str() == Some(&segment))
+                    .map(|(_, value)| value)?,
+                Value::Array(values) => values.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Same as [`pointer()`](#method.pointer), but returns a mutable reference to edit the value in place.
+    /// ```
+    /// # use dbless::Value;
+    /// let mut value = Value::Map(vec![("name".into(), "alice".into())]);
+    /// *value.pointer_mut("/name").unwrap() = "bob".into();
+    /// assert_eq!(value.pointer("/name"), Some(&Value::from("bob")));
+    /// ```
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        let mut value = self;
+        for segment in pointer_segments(pointer)? {
+            value = match value {
+                Value::Map(entries) => entries
+                    .iter_mut()
+                    .find(|(key, _)| key.as_str() == Some(&segment))
+                    .map(|(_, value)| value)?,
+                Value::Array(values) => values.get_mut(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// Whether the value is nil
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    /// The boolean, if the value is one
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The integer, if the value is one that fits in an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Int(n) => Some(*n),
+            Value::UInt(n) => i64::try_from(*n).ok(),
+            _ => None,
+        }
+    }
+
+    /// The integer, if the value is a non-negative one
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            Value::UInt(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number as an `f64`, if the value is a float or an integer
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::F32(f) => Some(*f as f64),
+            Value::F64(f) => Some(*f),
+            Value::Int(n) => Some(*n as f64),
+            Value::UInt(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
+    /// The string, if the value is one
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The values, if the value is an array
+    pub fn as_array(&self) -> Option<&Vec<Value>> {
+        match self {
+            Value::Array(values) => Some(values),
+            _ => None,
+        }
+    }
+
+    /// The key-value pairs, if the value is a map
+    pub fn as_map(&self) -> Option<&Vec<(Value, Value)>> {
+        match self {
+            Value::Map(entries) => Some(entries),
+            _ => None,
+        }
+    }
+}
+
+/// the unescaped segments of a JSON pointer, `None` if it doesn't start with `/`
+fn pointer_segments(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(vec![]);
+    }
+    let segments = pointer.strip_prefix('/')?.split('/');
+    Some(
+        segments
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .collect(),
+    )
+}
+
+macro_rules! value_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    Value::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+value_from!(
+    bool => Bool,
+    u8 => UInt, u16 => UInt, u32 => UInt, u64 => UInt,
+    f32 => F32, f64 => F64,
+    &str => String, String => String,
+    Vec<u8> => Binary,
+    Vec<Value> => Array,
+    Vec<(Value, Value)> => Map,
+);
+
+macro_rules! value_from_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl From<$ty> for Value {
+                fn from(value: $ty) -> Self {
+                    match u64::try_from(value) {
+                        Ok(n) => Value::UInt(n),
+                        Err(_) => Value::Int(value.into()),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+value_from_signed!(i8, i16, i32, i64);
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Nil => serializer.serialize_unit(),
+            Value::Bool(b) => serializer.serialize_bool(*b),
+            Value::Int(n) => serializer.serialize_i64(*n),
+            Value::UInt(n) => serializer.serialize_u64(*n),
+            Value::F32(f) => serializer.serialize_f32(*f),
+            Value::F64(f) => serializer.serialize_f64(*f),
+            Value::String(s) => serializer.serialize_str(s),
+            Value::Binary(bytes) => serializer.serialize_bytes(bytes),
+            Value::Array(values) => {
+                let mut seq = serializer.serialize_seq(Some(values.len()))?;
+                for value in values {
+                    seq.serialize_element(value)?;
+                }
+                seq.end()
+            }
+            Value::Map(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+            Value::Ext(ext_type, _) => Err(ser::Error::custom(format!(
+                "MessagePack ext type {} is reserved by dbless for its own metadata",
+                ext_type
+            ))),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, b: bool) -> Result<Value, E> {
+        Ok(Value::Bool(b))
+    }
+
+    fn visit_i64<E: de::Error>(self, n: i64) -> Result<Value, E> {
+        Ok(n.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, n: u64) -> Result<Value, E> {
+        Ok(Value::UInt(n))
+    }
+
+    fn visit_f32<E: de::Error>(self, f: f32) -> Result<Value, E> {
+        Ok(Value::F32(f))
+    }
+
+    fn visit_f64<E: de::Error>(self, f: f64) -> Result<Value, E> {
+        Ok(Value::F64(f))
+    }
+
+    fn visit_str<E: de::Error>(self, s: &str) -> Result<Value, E> {
+        Ok(Value::String(s.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, s: String) -> Result<Value, E> {
+        Ok(Value::String(s))
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Value, E> {
+        Ok(Value::Binary(bytes.to_vec()))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, bytes: Vec<u8>) -> Result<Value, E> {
+        Ok(Value::Binary(bytes))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+        Ok(Value::Nil)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        Value::deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut values = vec![];
+        while let Some(value) = seq.next_element()? {
+            values.push(value);
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut entries = vec![];
+        while let Some(entry) = map.next_entry()? {
+            entries.push(entry);
+        }
+        Ok(Value::Map(entries))
+    }
+}